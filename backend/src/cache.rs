@@ -1,6 +1,8 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::sync::RwLock;
 use lru::LruCache;
+use metrics::{counter, gauge};
 use std::num::NonZeroUsize;
 use std::time::{Duration, Instant};
 
@@ -9,6 +11,18 @@ use std::time::{Duration, Instant};
 pub struct SmartCache<K, V> {
     cache: Arc<RwLock<LruCache<K, CachedItem<V>>>>,
     ttl: Duration,
+    /// `cache` label on the hit/miss metrics
+    name: &'static str,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Lookup counts and current size of a cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
 }
 
 struct CachedItem<V> {
@@ -16,30 +30,53 @@ struct CachedItem<V> {
     expires_at: Instant,
 }
 
-impl<K: std::hash::Hash + Eq, V: Clone> SmartCache<K, V> {
+impl<K: std::hash::Hash + Eq + Clone, V: Clone> SmartCache<K, V> {
     pub fn new(capacity: usize, ttl_seconds: u64) -> Self {
         let cache = LruCache::new(NonZeroUsize::new(capacity).unwrap());
         Self {
             cache: Arc::new(RwLock::new(cache)),
             ttl: Duration::from_secs(ttl_seconds),
+            name: "smart",
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
         }
     }
 
+    /// Name reported in the `cache` label of the cache metrics
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
+
     /// Get item from cache if not expired
     pub async fn get(&self, key: &K) -> Option<V> {
         let mut cache = self.cache.write().await;
         
         if let Some(item) = cache.get_mut(key) {
             if item.expires_at > Instant::now() {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                counter!("cache_hits_total", "cache" => self.name).increment(1);
                 return Some(item.value.clone());
             } else {
                 // Remove expired item
                 cache.pop(key);
             }
         }
+        
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        counter!("cache_misses_total", "cache" => self.name).increment(1);
         None
     }
 
+    /// Hit/miss counts since creation and the current number of entries
+    pub async fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.cache.read().await.len(),
+        }
+    }
+
     /// Insert item with TTL
     pub async fn insert(&self, key: K, value: V) {
         let expires_at = Instant::now() + self.ttl;
@@ -65,6 +102,8 @@ impl<K: std::hash::Hash + Eq, V: Clone> SmartCache<K, V> {
         for key in expired {
             cache.pop(&key);
         }
+        
+        gauge!("cache_entries", "cache" => self.name).set(cache.len() as f64);
     }
 }
 
@@ -85,7 +124,7 @@ impl SearchCache {
     pub fn new() -> Self {
         // Cache up to 1000 search results for 5 minutes
         Self {
-            cache: SmartCache::new(1000, 300),
+            cache: SmartCache::new(1000, 300).with_name("search"),
         }
     }
 
@@ -96,6 +135,10 @@ impl SearchCache {
     pub async fn cache_results(&self, query: &str, results: Vec<SearchResult>) {
         self.cache.insert(query.to_lowercase(), results).await;
     }
+
+    pub async fn stats(&self) -> CacheStats {
+        self.cache.stats().await
+    }
 }
 
 /// Specialized cache for conversation data
@@ -123,7 +166,7 @@ impl ConversationCache {
     pub fn new() -> Self {
         // Cache up to 500 conversations for 10 minutes
         Self {
-            cache: SmartCache::new(500, 600),
+            cache: SmartCache::new(500, 600).with_name("conversation"),
         }
     }
 
@@ -134,6 +177,10 @@ impl ConversationCache {
     pub async fn insert(&self, conversation: CachedConversation) {
         self.cache.insert(conversation.id, conversation).await;
     }
+
+    pub async fn stats(&self) -> CacheStats {
+        self.cache.stats().await
+    }
 }
 
 /// Background task to periodically evict expired entries
@@ -184,4 +231,16 @@ mod tests {
         assert_eq!(cache.get(&2).await, Some(2));
         assert_eq!(cache.get(&3).await, Some(3));
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_stats_count_misses_and_hits() {
+        let cache = SearchCache::new();
+        
+        assert!(cache.get_results("rust").await.is_none());
+        assert_eq!(cache.stats().await, CacheStats { hits: 0, misses: 1, entries: 0 });
+        
+        cache.cache_results("rust", Vec::new()).await;
+        assert!(cache.get_results("Rust").await.is_some());
+        assert_eq!(cache.stats().await, CacheStats { hits: 1, misses: 1, entries: 1 });
+    }
+}
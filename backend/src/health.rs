@@ -0,0 +1,60 @@
+use axum::{http::StatusCode, response::Json};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::time::Instant;
+
+/// Body of `/health`, the same shape the main server answers with
+#[derive(Debug, Serialize)]
+pub struct Health {
+    /// `healthy`, or `unhealthy` when the database can't be queried
+    pub status: &'static str,
+    pub database: bool,
+    /// Round trip of the probe query
+    pub latency_ms: f64,
+    pub version: &'static str,
+}
+
+/// Run a trivial query against the pool, answering 503 if it fails
+pub async fn check(pool: &SqlitePool) -> (StatusCode, Json<Health>) {
+    let start = Instant::now();
+    let database = sqlx::query("SELECT 1").execute(pool).await.is_ok();
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    let (status_code, status) = if database {
+        (StatusCode::OK, "healthy")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unhealthy")
+    };
+
+    (
+        status_code,
+        Json(Health {
+            status,
+            database,
+            latency_ms,
+            version: env!("CARGO_PKG_VERSION"),
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_health_fails_once_the_pool_is_closed() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+
+        let (status_code, Json(health)) = check(&pool).await;
+        assert_eq!(status_code, StatusCode::OK);
+        assert_eq!(health.status, "healthy");
+        assert!(health.database);
+        assert_eq!(health.version, env!("CARGO_PKG_VERSION"));
+
+        pool.close().await;
+        let (status_code, Json(health)) = check(&pool).await;
+        assert_eq!(status_code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(health.status, "unhealthy");
+        assert!(!health.database);
+    }
+}
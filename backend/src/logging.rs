@@ -0,0 +1,51 @@
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+/// Directives used when `RUST_LOG` isn't set
+const DEFAULT_FILTER: &str = "llm_archive_backend=debug,tower_http=debug";
+
+/// How log events are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines
+    Pretty,
+    /// One JSON object per line with `timestamp`, `level`, and `fields`
+    Json,
+}
+
+impl LogFormat {
+    /// Format named by `LOG_FORMAT`, pretty unless it is `json`
+    pub fn from_env() -> Self {
+        Self::parse(std::env::var("LOG_FORMAT").ok().as_deref())
+    }
+
+    fn parse(value: Option<&str>) -> Self {
+        match value {
+            Some(value) if value.eq_ignore_ascii_case("json") => LogFormat::Json,
+            _ => LogFormat::Pretty,
+        }
+    }
+}
+
+/// Install the global subscriber, filtered by `RUST_LOG` in either format
+pub fn init(format: LogFormat) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| DEFAULT_FILTER.into());
+    let registry = tracing_subscriber::registry().with(filter);
+
+    match format {
+        LogFormat::Pretty => registry.with(tracing_subscriber::fmt::layer()).init(),
+        LogFormat::Json => registry.with(tracing_subscriber::fmt::layer().json()).init(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_format_defaults_to_pretty() {
+        assert_eq!(LogFormat::parse(None), LogFormat::Pretty);
+        assert_eq!(LogFormat::parse(Some("pretty")), LogFormat::Pretty);
+        assert_eq!(LogFormat::parse(Some("JSON")), LogFormat::Json);
+        assert_eq!(LogFormat::parse(Some("json")), LogFormat::Json);
+    }
+}
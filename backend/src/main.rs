@@ -2,7 +2,7 @@ use anyhow::Result;
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json},
     routing::{get, post},
     Router,
 };
@@ -12,7 +12,13 @@ use sqlx::{sqlite::SqlitePool, Row};
 use std::sync::Arc;
 use tower_http::cors::CorsLayer;
 use tracing::{error, info};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+use llm_archive_v2::search_enhanced::fts_term;
+
+mod health;
+mod logging;
+mod search_limit;
+mod shutdown;
 
 // Data structures
 #[derive(Debug, Serialize, Deserialize)]
@@ -73,14 +79,8 @@ struct AppState {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "llm_archive_backend=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    // Initialize tracing; LOG_FORMAT=json for log pipelines
+    logging::init(logging::LogFormat::from_env());
 
     // Database connection
     let database_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:llm_archive.db".to_string());
@@ -93,10 +93,11 @@ async fn main() -> Result<()> {
     info!("Database migrations completed");
 
     // Create app state
-    let state = Arc::new(AppState { pool });
+    let state = Arc::new(AppState { pool: pool.clone() });
 
     // Build router
     let app = Router::new()
+        .route("/health", get(health_check))
         .route("/api/search", get(search))
         .route("/api/conversations", get(list_conversations))
         .route("/api/conversations/:id", get(get_conversation))
@@ -110,11 +111,23 @@ async fn main() -> Result<()> {
     info!("Starting server on {}", addr);
     
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown::signal())
+        .await?;
+    
+    // In-flight requests have finished; fold the WAL back into the database
+    info!("Shutting down");
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&pool).await?;
+    pool.close().await;
     
     Ok(())
 }
 
+// Health check - fails with 503 when the database can't be queried
+async fn health_check(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    health::check(&state.pool).await
+}
+
 // Search endpoint - <100ms response time target
 async fn search(
     State(state): State<Arc<AppState>>,
@@ -122,11 +135,14 @@ async fn search(
 ) -> Result<Json<Vec<SearchResult>>, StatusCode> {
     let start = std::time::Instant::now();
     
-    let limit = params.limit.unwrap_or(50).min(100);
+    let limit = search_limit::clamp(params.limit)?;
     let offset = params.offset.unwrap_or(0);
     
     // Use FTS5 for fast full-text search
-    let query = format!("%{}%", params.q);
+    let query = sanitize_fts_query(&params.q);
+    if query.is_empty() {
+        return Ok(Json(Vec::new()));
+    }
     
     let results = sqlx::query!(
         r#"
@@ -141,10 +157,10 @@ async fn search(
         JOIN messages m ON messages_fts.rowid = m.id
         JOIN conversations c ON m.conversation_id = c.id
         WHERE messages_fts MATCH ?1
-        ORDER BY rank
+        ORDER BY rank, m.conversation_id DESC, m.id ASC
         LIMIT ?2 OFFSET ?3
         "#,
-        params.q,
+        query,
         limit,
         offset
     )
@@ -175,6 +191,47 @@ async fn search(
     Ok(Json(search_results))
 }
 
+/// Turn user input into an FTS5 query that can't be a syntax error
+///
+/// Words made of letters, digits and `_` pass through; anything else, like
+/// `c++` or `error:`, is quoted, as are `"phrases"` (an unclosed quote runs
+/// to the end). Operators and column filters are never interpreted.
+fn sanitize_fts_query(input: &str) -> String {
+    let mut terms = Vec::new();
+    let mut chars = input.chars().peekable();
+    
+    while let Some(&ch) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        
+        let (term, phrase) = if ch == '"' {
+            chars.next();
+            (chars.by_ref().take_while(|&c| c != '"').collect::<String>(), true)
+        } else {
+            let mut term = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '"' {
+                    break;
+                }
+                term.push(c);
+                chars.next();
+            }
+            (term, false)
+        };
+        
+        let term = term.split_whitespace().collect::<Vec<_>>().join(" ");
+        if term.is_empty() {
+            continue;
+        }
+        
+        terms.push(fts_term(&term, phrase));
+    }
+    
+    terms.join(" ")
+}
+
 // List conversations - paginated
 async fn list_conversations(
     State(state): State<Arc<AppState>>,
@@ -508,4 +565,58 @@ async fn parse_claude(
     }
 
     Ok((conversation_count, message_count))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    
+    #[test]
+    fn test_sanitize_fts_query_quotes_special_terms() {
+        assert_eq!(sanitize_fts_query("rust async"), "rust async");
+        assert_eq!(sanitize_fts_query(r#"c++ "error:""#), r#""c++" "error:""#);
+        assert_eq!(sanitize_fts_query("title:x -y OR"), r#""title:x" "-y" "OR""#);
+        assert_eq!(sanitize_fts_query(r#"say "hi there"#), r#"say "hi there""#);
+        assert_eq!(sanitize_fts_query(r#"  ""  "#), "");
+    }
+    
+    #[tokio::test]
+    async fn test_search_with_fts_syntax_characters_finds_results() {
+        // One connection, so every query sees the same in-memory database
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        
+        sqlx::query(
+            "INSERT INTO conversations (id, provider_id, title, created_at, updated_at)
+             VALUES (1, 1, 'Compiler errors', '2024-05-01T10:00:00+00:00', '2024-05-01T10:00:00+00:00')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO messages (conversation_id, role, content, created_at, position)
+             VALUES (1, 'user', 'My c++ build fails with error: undefined reference', '2024-05-01T10:00:00+00:00', 0)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        
+        let state = Arc::new(AppState { pool });
+        let search_for = |q: &str| {
+            let query = SearchQuery { q: q.to_string(), limit: None, offset: None };
+            search(State(state.clone()), Query(query))
+        };
+        
+        let Json(results) = search_for(r#"c++ "error:""#).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].conversation_id, 1);
+        
+        let Json(results) = search_for(r#"NOT "* -:"#).await.unwrap();
+        assert!(results.is_empty());
+    }
+}
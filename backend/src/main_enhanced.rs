@@ -1,19 +1,23 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
     Router,
 };
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite, SqliteConnection};
 use std::sync::Arc;
 use tower::ServiceBuilder;
 use tower_http::cors::CorsLayer;
 
 mod cache;
+mod health;
 mod parsers;
 mod search_enhanced;
+mod search_limit;
+mod shutdown;
 mod streaming;
 
 use cache::{cache_maintenance_task, ConversationCache, SearchCache};
@@ -26,6 +30,7 @@ struct AppState {
     search_cache: Arc<SearchCache>,
     conv_cache: Arc<ConversationCache>,
     search_engine: Arc<EnhancedSearch>,
+    metrics: PrometheusHandle,
 }
 
 #[tokio::main]
@@ -40,6 +45,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Run migrations
     sqlx::migrate!("./migrations").run(&pool).await?;
 
+    // Record metrics for the /metrics endpoint
+    let metrics = PrometheusBuilder::new().install_recorder()?;
+
     // Initialize caches
     let search_cache = Arc::new(SearchCache::new());
     let conv_cache = Arc::new(ConversationCache::new());
@@ -48,19 +56,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Start cache maintenance task
     let cache_search = search_cache.clone();
     let cache_conv = conv_cache.clone();
-    tokio::spawn(async move {
+    let cache_task = tokio::spawn(async move {
         cache_maintenance_task(cache_search, cache_conv).await;
     });
 
     let state = AppState {
-        db: pool,
+        db: pool.clone(),
         search_cache,
         conv_cache,
         search_engine,
+        metrics,
     };
 
     let app = Router::new()
         .route("/health", get(health))
+        .route("/metrics", get(metrics_handler))
         .route("/api/search", get(search_handler))
         .route("/api/advanced-search", get(advanced_search_handler))
         .route("/api/conversations", get(list_conversations))
@@ -80,35 +90,48 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Server running on http://localhost:8000");
     axum::Server::bind(&"0.0.0.0:8000".parse()?)
         .serve(app.into_make_service())
+        .with_graceful_shutdown(shutdown::signal())
         .await?;
 
+    // In-flight requests have finished; stop maintenance and fold the WAL
+    // back into the database
+    println!("Shutting down");
+    cache_task.abort();
+    let _ = cache_task.await;
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&pool).await?;
+    pool.close().await;
+
     Ok(())
 }
 
-async fn health() -> &'static str {
-    "OK"
+async fn health(State(state): State<AppState>) -> impl IntoResponse {
+    health::check(&state.db).await
+}
+
+/// Prometheus text exposition of the cache and server metrics
+async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
 }
 
 #[derive(Deserialize)]
 struct SearchQuery {
     q: String,
-    #[serde(default = "default_limit")]
-    limit: i32,
-}
-
-fn default_limit() -> i32 {
-    50
+    limit: Option<i32>,
 }
 
 async fn search_handler(
     Query(params): Query<SearchQuery>,
     State(state): State<AppState>,
 ) -> Result<Json<SearchResponse>, StatusCode> {
+    let limit = search_limit::clamp(params.limit)?;
     let mut conn = state.db.acquire().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
     let results = state
         .search_engine
-        .incremental_search(&mut conn, &params.q, params.limit)
+        .incremental_search(&mut conn, &params.q, limit)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -200,11 +223,13 @@ async fn stats_handler(State(state): State<AppState>) -> Result<Json<StatsRespon
     
     let messages_by_role: std::collections::HashMap<String, i64> = role_rows.into_iter().collect();
     
-    // Activity by hour (simplified - would need timestamp parsing in real implementation)
-    let activity_by_hour = vec![0; 24]; // Placeholder
+    // Activity by hour
+    let activity_by_hour = activity_by_hour(&mut conn)
+        .await
+        .unwrap_or_else(|_| vec![0; 24]);
     
     // Growth by month
-    let growth_by_month = vec![]; // Placeholder
+    let growth_by_month = growth_by_month(&mut conn).await.unwrap_or_default();
     
     Ok(Json(StatsResponse {
         total_conversations,
@@ -217,6 +242,53 @@ async fn stats_handler(State(state): State<AppState>) -> Result<Json<StatsRespon
     }))
 }
 
+/// Message counts for each hour of the day (0-23)
+///
+/// Timestamps may be stored as RFC3339 text or as unix seconds.
+async fn activity_by_hour(conn: &mut SqliteConnection) -> sqlx::Result<Vec<i64>> {
+    let rows = sqlx::query_as::<_, (String, i64)>(
+        "SELECT strftime('%H', CASE WHEN typeof(timestamp) IN ('integer', 'real')
+                                    THEN datetime(timestamp, 'unixepoch')
+                                    ELSE timestamp END) AS hour,
+                COUNT(*) as count
+         FROM messages
+         WHERE hour IS NOT NULL
+         GROUP BY hour"
+    )
+    .fetch_all(conn)
+    .await?;
+    
+    let mut hours = vec![0; 24];
+    for (hour, count) in rows {
+        if let Some(slot) = hour.parse::<usize>().ok().and_then(|h| hours.get_mut(h)) {
+            *slot = count;
+        }
+    }
+    
+    Ok(hours)
+}
+
+/// New conversations per month, oldest first
+async fn growth_by_month(conn: &mut SqliteConnection) -> sqlx::Result<Vec<MonthGrowth>> {
+    let rows = sqlx::query_as::<_, (String, i64)>(
+        "SELECT strftime('%Y-%m', CASE WHEN typeof(created_at) IN ('integer', 'real')
+                                       THEN datetime(created_at, 'unixepoch')
+                                       ELSE created_at END) AS month,
+                COUNT(*) as count
+         FROM conversations
+         WHERE month IS NOT NULL
+         GROUP BY month
+         ORDER BY month"
+    )
+    .fetch_all(conn)
+    .await?;
+    
+    Ok(rows
+        .into_iter()
+        .map(|(month, count)| MonthGrowth { month, count })
+        .collect())
+}
+
 #[derive(Deserialize)]
 struct ExportQuery {
     format: Option<String>,
@@ -226,7 +298,7 @@ async fn export_conversation(
     Path(id): Path<i64>,
     Query(params): Query<ExportQuery>,
     State(state): State<AppState>,
-) -> Result<String, StatusCode> {
+) -> Result<Response, StatusCode> {
     let format = params.format.unwrap_or_else(|| "markdown".to_string());
     
     let mut conn = state.db.acquire().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -242,8 +314,30 @@ async fn export_conversation(
     
     match format.as_str() {
         "json" => {
-            // Export as JSON
-            Ok(serde_json::to_string_pretty(&messages).unwrap())
+            // Full conversation record with every message column
+            let export = conversation_json(&mut conn, id)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .ok_or(StatusCode::NOT_FOUND)?;
+            let body = serde_json::to_string_pretty(&export).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            Ok(([(header::CONTENT_TYPE, "application/json")], body).into_response())
+        }
+        "html" => {
+            // Standalone HTML document
+            let title: Option<String> = sqlx::query_scalar("SELECT title FROM conversations WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&mut conn)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .flatten();
+            
+            let html = render_html_export(title.as_deref().unwrap_or("Untitled Conversation"), &messages);
+            Ok(([(header::CONTENT_TYPE, "text/html; charset=utf-8")], html).into_response())
+        }
+        "openai-chat" => {
+            // Fine-tuning JSONL; conversations without a user/assistant exchange export no lines
+            let body = openai_chat_line(&messages).unwrap_or_default();
+            Ok(([(header::CONTENT_TYPE, "application/jsonl")], body).into_response())
         }
         "academic" => {
             // Academic format with citations
@@ -259,7 +353,7 @@ async fn export_conversation(
             output.push_str("## References\n\n[1] AI Conversation Archive, ");
             output.push_str(&format!("accessed {}\n", chrono::Local::now().format("%Y-%m-%d")));
             
-            Ok(output)
+            Ok(output.into_response())
         }
         "blog" => {
             // Blog post format
@@ -274,7 +368,7 @@ async fn export_conversation(
                 }
             }
             
-            Ok(output)
+            Ok(output.into_response())
         }
         _ => {
             // Default markdown format
@@ -290,11 +384,167 @@ async fn export_conversation(
                 ));
             }
             
-            Ok(output)
+            Ok(output.into_response())
         }
     }
 }
 
+/// Conversation styling shared with the server's conversation page
+const CONVERSATION_CSS: &str = include_str!("../../templates/conversation.css");
+
+/// Render a conversation as a self-contained HTML document
+fn render_html_export(title: &str, messages: &[(String, String, i64)]) -> String {
+    let title = html_escape::encode_text(title);
+    let mut body = String::new();
+    
+    for (role, content, timestamp) in messages {
+        let role = html_escape::encode_double_quoted_attribute(role);
+        let time = chrono::DateTime::from_timestamp(*timestamp, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        
+        body.push_str(&format!(
+            "<div class=\"message\">\n\
+             <div class=\"message-header\"><div class=\"message-role {role}\">{role}</div>\
+             <div class=\"message-model\">{time}</div></div>\n\
+             <div class=\"message-content\">{}</div>\n\
+             </div>\n",
+            render_message_html(content),
+        ));
+    }
+    
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"UTF-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n\
+         <title>{title}</title>\n<style>\n\
+         body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; \
+         background: #f5f5f5; color: #333; line-height: 1.6; margin: 0; }}\n\
+         .container {{ max-width: 960px; margin: 0 auto; padding: 20px; }}\n\
+         {CONVERSATION_CSS}</style>\n</head>\n<body>\n<div class=\"container\">\n\
+         <div class=\"conversation-header\"><h1 class=\"conversation-title\">{title}</h1>\
+         <div class=\"conversation-meta\"><span>{} messages</span></div></div>\n\
+         <div class=\"messages-container\">\n{body}</div>\n</div>\n</body>\n</html>\n",
+        messages.len(),
+    )
+}
+
+/// A conversation as `?format=json` exports it, with its messages in order
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ConversationExport {
+    id: i64,
+    provider: Option<String>,
+    title: Option<String>,
+    created_at: String,
+    updated_at: Option<String>,
+    #[sqlx(skip)]
+    messages: Vec<MessageExport>,
+}
+
+/// One message of a `ConversationExport`
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct MessageExport {
+    id: i64,
+    role: String,
+    content: String,
+    model: Option<String>,
+    timestamp: Option<i64>,
+    position: Option<i64>,
+    tokens: Option<i64>,
+    finish_reason: Option<String>,
+    #[serde(serialize_with = "json_text")]
+    attachments: Option<String>,
+}
+
+/// Serialize a column holding a JSON document as that document, or as a
+/// string if it doesn't parse
+fn json_text<S: serde::Serializer>(text: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    text.as_deref()
+        .map(|text| serde_json::from_str(text).unwrap_or_else(|_| serde_json::Value::String(text.to_string())))
+        .serialize(serializer)
+}
+
+/// A conversation with its provider name and complete messages in order
+async fn conversation_json(conn: &mut SqliteConnection, id: i64) -> Result<Option<ConversationExport>, sqlx::Error> {
+    let Some(mut export) = sqlx::query_as::<_, ConversationExport>(
+        "SELECT c.id, p.name AS provider, c.title, c.created_at, c.updated_at
+         FROM conversations c
+         LEFT JOIN providers p ON p.id = c.provider_id
+         WHERE c.id = ?"
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(None);
+    };
+    
+    export.messages = sqlx::query_as(
+        "SELECT id, role, content, model, timestamp, position, tokens, finish_reason, attachments
+         FROM messages
+         WHERE conversation_id = ?
+         ORDER BY position, id"
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+    
+    Ok(Some(export))
+}
+
+/// One OpenAI chat fine-tuning example: `{"messages":[{"role":..,"content":..}]}`
+///
+/// Only system/user/assistant turns are kept, and a conversation needs at
+/// least one user and one assistant message to be worth training on.
+fn openai_chat_line(messages: &[(String, String, i64)]) -> Option<String> {
+    let turns: Vec<_> = messages
+        .iter()
+        .filter(|(role, content, _)| {
+            matches!(role.as_str(), "system" | "user" | "assistant") && !content.trim().is_empty()
+        })
+        .map(|(role, content, _)| serde_json::json!({ "role": role, "content": content }))
+        .collect();
+    
+    let has_role = |role: &str| turns.iter().any(|t| t["role"] == role);
+    if !has_role("user") || !has_role("assistant") {
+        return None;
+    }
+    
+    Some(format!("{}\n", serde_json::json!({ "messages": turns })))
+}
+
+/// Escape message text and turn fenced code blocks into `<pre><code>`
+fn render_message_html(content: &str) -> String {
+    let mut html = String::new();
+    let mut in_code = false;
+    
+    for line in content.lines() {
+        if let Some(lang) = line.trim_start().strip_prefix("```") {
+            if in_code {
+                html.push_str("</code></pre>");
+            } else if lang.trim().is_empty() {
+                html.push_str("<pre><code>");
+            } else {
+                html.push_str(&format!(
+                    "<pre><code class=\"language-{}\">",
+                    html_escape::encode_double_quoted_attribute(lang.trim())
+                ));
+            }
+            in_code = !in_code;
+            continue;
+        }
+        
+        html.push_str(&html_escape::encode_text(line));
+        html.push('\n');
+    }
+    
+    // Close an unterminated fence
+    if in_code {
+        html.push_str("</code></pre>");
+    }
+    
+    html
+}
+
 async fn streaming_import_handler(
     State(state): State<AppState>,
     body: String,
@@ -346,4 +596,162 @@ async fn get_messages(Path(_id): Path<i64>, State(_state): State<AppState>) -> J
 
 async fn import_handler(State(_state): State<AppState>) -> Json<String> {
     Json("Import successful".to_string())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Connection;
+    
+    async fn stats_conn() -> SqliteConnection {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        
+        sqlx::query(
+            "CREATE TABLE conversations (id INTEGER PRIMARY KEY, created_at DATETIME NOT NULL);
+             CREATE TABLE messages (id INTEGER PRIMARY KEY, conversation_id INTEGER, timestamp DATETIME);"
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+        
+        conn
+    }
+    
+    #[tokio::test]
+    async fn test_activity_by_hour_buckets() {
+        let mut conn = stats_conn().await;
+        
+        for timestamp in [
+            "2024-01-05T09:15:00Z",
+            "2024-02-10T09:45:30+00:00",
+            "2024-03-01T23:59:59.123Z",
+            "2024-03-02T00:00:00Z",
+        ] {
+            sqlx::query("INSERT INTO messages (conversation_id, timestamp) VALUES (1, ?)")
+                .bind(timestamp)
+                .execute(&mut conn)
+                .await
+                .unwrap();
+        }
+        
+        // Unix seconds: 2023-11-14 22:13:20 UTC
+        sqlx::query("INSERT INTO messages (conversation_id, timestamp) VALUES (1, 1700000000)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        
+        let hours = activity_by_hour(&mut conn).await.unwrap();
+        
+        assert_eq!(hours.len(), 24);
+        assert_eq!(hours[0], 1);
+        assert_eq!(hours[9], 2);
+        assert_eq!(hours[22], 1);
+        assert_eq!(hours[23], 1);
+        assert_eq!(hours.iter().sum::<i64>(), 5);
+    }
+    
+    #[tokio::test]
+    async fn test_growth_by_month_is_chronological() {
+        let mut conn = stats_conn().await;
+        
+        for created_at in [
+            "2024-03-01T10:00:00Z",
+            "2023-12-31T23:00:00Z",
+            "2024-01-15T08:00:00Z",
+            "2024-03-20T12:00:00Z",
+        ] {
+            sqlx::query("INSERT INTO conversations (created_at) VALUES (?)")
+                .bind(created_at)
+                .execute(&mut conn)
+                .await
+                .unwrap();
+        }
+        
+        let growth = growth_by_month(&mut conn).await.unwrap();
+        let buckets: Vec<_> = growth.iter().map(|g| (g.month.as_str(), g.count)).collect();
+        
+        assert_eq!(buckets, vec![("2023-12", 1), ("2024-01", 1), ("2024-03", 2)]);
+    }
+    
+    #[test]
+    fn test_html_export_escapes_content() {
+        let messages = vec![
+            ("user".to_string(), "Is 1 < 2 && 3 > 2?".to_string(), 1700000000),
+            (
+                "assistant".to_string(),
+                "Yes:\n```rust\nfn main() { println!(\"<ok>\"); }\n```".to_string(),
+                1700000060,
+            ),
+        ];
+        
+        let html = render_html_export("Comparisons <basics>", &messages);
+        
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Comparisons &lt;basics&gt;</title>"));
+        assert!(html.contains("message-role user"));
+        assert!(html.contains("message-role assistant"));
+        assert!(html.contains("Is 1 &lt; 2 &amp;&amp; 3 &gt; 2?"));
+        assert!(html.contains("<pre><code class=\"language-rust\">fn main() { println!(\"&lt;ok&gt;\"); }"));
+        assert!(html.contains(".message-content pre"));
+        assert!(!html.contains("<ok>"));
+    }
+    
+    #[test]
+    fn test_openai_chat_line_roles() {
+        let messages = vec![
+            ("system".to_string(), "Be brief.".to_string(), 1700000000),
+            ("user".to_string(), "Hi".to_string(), 1700000001),
+            ("assistant".to_string(), "Hello".to_string(), 1700000002),
+        ];
+        
+        let line = openai_chat_line(&messages).unwrap();
+        assert_eq!(line.lines().count(), 1);
+        
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        let roles: Vec<_> = value["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, vec!["system", "user", "assistant"]);
+        
+        // No assistant reply, nothing to learn from
+        assert!(openai_chat_line(&messages[..2]).is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_json_export_includes_full_messages() {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        
+        sqlx::query(
+            r#"CREATE TABLE providers (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+             CREATE TABLE conversations (id INTEGER PRIMARY KEY, provider_id INTEGER, title TEXT,
+                                         created_at DATETIME NOT NULL, updated_at DATETIME);
+             CREATE TABLE messages (id INTEGER PRIMARY KEY, conversation_id INTEGER, role TEXT, content TEXT, model TEXT,
+                                    timestamp INTEGER, position INTEGER, tokens INTEGER, finish_reason TEXT, attachments TEXT);
+             INSERT INTO providers (id, name) VALUES (1, 'claude');
+             INSERT INTO conversations (id, provider_id, title, created_at) VALUES (7, 1, 'Receipts', '2024-05-01T10:00:00Z');
+             INSERT INTO messages (conversation_id, role, content, model, timestamp, position, tokens, finish_reason, attachments)
+             VALUES (7, 'user', 'Total these', NULL, 1714557600, 0, NULL, NULL, '[{"file_name": "receipt.png"}]'),
+                    (7, 'assistant', '$42', 'claude-3-haiku', 1714557605, 1, 12, 'end_turn', NULL);"#
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+        
+        let export = conversation_json(&mut conn, 7).await.unwrap().unwrap();
+        let export = serde_json::to_value(&export).unwrap();
+        
+        assert_eq!(export["provider"], "claude");
+        assert!(export.get("provider_id").is_none());
+        assert_eq!(export["title"], "Receipts");
+        assert_eq!(export["messages"].as_array().unwrap().len(), 2);
+        assert_eq!(export["messages"][0]["attachments"][0]["file_name"], "receipt.png");
+        assert_eq!(export["messages"][1]["model"], "claude-3-haiku");
+        assert_eq!(export["messages"][1]["tokens"], 12);
+        assert_eq!(export["messages"][1]["finish_reason"], "end_turn");
+        
+        assert!(conversation_json(&mut conn, 8).await.unwrap().is_none());
+    }
+}
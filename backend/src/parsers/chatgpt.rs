@@ -58,6 +58,7 @@ impl ChatProvider for ChatGPTProvider {
             }
         }
 
+        files.sort();
         Ok(files)
     }
 
@@ -67,8 +68,8 @@ impl ChatProvider for ChatGPTProvider {
         }
 
         // Quick check of file structure
-        match tokio::fs::read_to_string(file).await {
-            Ok(content) => match serde_json::from_str::<Value>(&content) {
+        match read_lossy(file).await {
+            Ok((content, _)) => match serde_json::from_str::<Value>(&content) {
                 Ok(Value::Array(arr)) => {
                     // Check first item for ChatGPT structure
                     arr.first()
@@ -96,7 +97,7 @@ impl ChatProvider for ChatGPTProvider {
         file: &Path,
         stats: &mut ImportStats,
     ) -> ParserResult<Vec<Conversation>> {
-        let content = tokio::fs::read_to_string(file).await?;
+        let content = read_export(file, stats).await?;
         let data: Value = serde_json::from_str(&content)?;
         
         let mut conversations = Vec::new();
@@ -108,6 +109,7 @@ impl ChatProvider for ChatGPTProvider {
                 for (idx, item) in arr.iter().enumerate() {
                     match self.extract_single_conversation(item, file, idx).await {
                         Ok(Some(conv)) => {
+                            warn_dangling_nodes(&conv, &file_str, stats);
                             stats.total_messages += conv.messages.len();
                             conversations.push(conv);
                         }
@@ -132,6 +134,7 @@ impl ChatProvider for ChatGPTProvider {
                 // Single conversation file
                 match self.extract_single_conversation(&data, file, 0).await {
                     Ok(Some(conv)) => {
+                        warn_dangling_nodes(&conv, &file_str, stats);
                         stats.total_messages += conv.messages.len();
                         conversations.push(conv);
                     }
@@ -228,8 +231,9 @@ impl ChatGPTProvider {
         let root_id = self.find_root_node(mapping)?;
         let mut messages = Vec::new();
         let mut visited = std::collections::HashSet::new();
+        let mut dangling = Vec::new();
         
-        self.traverse_message_tree(mapping, &root_id, &mut messages, &mut visited, create_time)?;
+        self.traverse_message_tree(mapping, &root_id, &mut messages, &mut visited, &mut dangling, create_time)?;
 
         if messages.is_empty() {
             return Ok(None);
@@ -245,6 +249,11 @@ impl ChatGPTProvider {
             None
         };
 
+        let mut metadata: HashMap<String, Value> = extract_metadata(data).into_iter().collect();
+        if !dangling.is_empty() {
+            metadata.insert("dangling_nodes".to_string(), serde_json::json!(dangling));
+        }
+
         // Calculate actual time range
         let start_time = messages.first().map(|m| m.timestamp).unwrap_or(create_time);
         let end_time = messages.last().map(|m| m.timestamp).unwrap_or(update_time);
@@ -260,7 +269,7 @@ impl ChatGPTProvider {
                 .map(|s| s.to_string()),
             start_time,
             end_time,
-            metadata: extract_metadata(data).into_iter().collect(),
+            metadata,
         };
 
         Ok(Some(conversation))
@@ -374,6 +383,7 @@ impl ChatGPTProvider {
         node_id: &str,
         messages: &mut Vec<Message>,
         visited: &mut std::collections::HashSet<String>,
+        dangling: &mut Vec<String>,
         default_timestamp: DateTime<Utc>,
     ) -> ParserResult<()> {
         if visited.contains(node_id) {
@@ -381,12 +391,13 @@ impl ChatGPTProvider {
         }
         visited.insert(node_id.to_string());
 
-        let node = mapping.get(node_id)
-            .and_then(|v| v.as_object())
-            .ok_or_else(|| ParserError::InvalidFormat {
-                provider: "ChatGPT".to_string(),
-                reason: format!("Node {} not found in mapping", node_id),
-            })?;
+        // Corrupted exports can reference nodes that were never written;
+        // skip the dangling branch so the rest of the tree still imports
+        let Some(node) = mapping.get(node_id).and_then(|v| v.as_object()) else {
+            warn!("Skipping dangling reference to missing node {}", node_id);
+            dangling.push(node_id.to_string());
+            return Ok(());
+        };
 
         // Extract message if present
         if let Some(msg_data) = node.get("message") {
@@ -399,7 +410,7 @@ impl ChatGPTProvider {
         if let Some(children) = node.get("children").and_then(|v| v.as_array()) {
             for child in children {
                 if let Some(child_id) = child.as_str() {
-                    self.traverse_message_tree(mapping, child_id, messages, visited, default_timestamp)?;
+                    self.traverse_message_tree(mapping, child_id, messages, visited, dangling, default_timestamp)?;
                 }
             }
         }
@@ -418,6 +429,14 @@ impl ChatGPTProvider {
                 reason: "Message must be an object".to_string(),
             })?;
 
+        // Background tasks leave nodes that only track `async_status`
+        if obj.contains_key("async_status")
+            || obj.get("metadata").is_some_and(|m| m.get("async_status").is_some())
+        {
+            debug!("Skipping status-only node");
+            return Ok(None);
+        }
+
         // Extract role
         let role_str = obj.get("role")
             .or_else(|| obj.get("author").and_then(|a| a.get("role")))
@@ -498,6 +517,8 @@ impl ChatGPTProvider {
                     Ok(String::new())
                 }
             }
+            "canvas" | "document" => Ok(titled_block("Canvas", content)),
+            "tether_quote" => Ok(titled_block("Quote", content)),
             "tether_browsing_display" => {
                 // Handle browsing results
                 let result = content.get("result")
@@ -514,6 +535,39 @@ impl ChatGPTProvider {
     }
 }
 
+/// Text of canvas documents and quoted tool output, headed by their title
+///
+/// Empty when there is no text, so the message is skipped like any other
+/// empty one.
+fn titled_block(label: &str, content: &serde_json::Map<String, Value>) -> String {
+    let text = extract_text_content(&Value::Object(content.clone()));
+    if text.trim().is_empty() {
+        return String::new();
+    }
+
+    let title = content.get("title")
+        .or_else(|| content.get("name"))
+        .and_then(|v| v.as_str())
+        .filter(|title| !title.trim().is_empty());
+    match title {
+        Some(title) => format!("[{}: {}]\n{}", label, title, text),
+        None => format!("[{}]\n{}", label, text),
+    }
+}
+
+/// Record a warning for conversations that were imported without some branches
+fn warn_dangling_nodes(conv: &Conversation, file: &str, stats: &mut ImportStats) {
+    if let Some(Value::Array(nodes)) = conv.metadata.get("dangling_nodes") {
+        stats.warnings.push(ImportWarning {
+            file: file.to_string(),
+            warning: format!("Skipped {} missing node(s) in \"{}\"", nodes.len(), conv.title),
+            context: Some(
+                nodes.iter().filter_map(|n| n.as_str()).collect::<Vec<_>>().join(", "),
+            ),
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -542,4 +596,161 @@ mod tests {
         
         assert!(provider.can_handle(&file).await);
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_dangling_child_reference_keeps_valid_messages() {
+        let provider = ChatGPTProvider::new();
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("conversations.json");
+        
+        let data = serde_json::json!([{
+            "title": "Partially corrupt",
+            "create_time": 1700000000.0,
+            "mapping": {
+                "root": { "parent": null, "children": ["q1"] },
+                "q1": {
+                    "parent": "root",
+                    "children": ["a1", "lost"],
+                    "message": {
+                        "author": { "role": "user" },
+                        "content": { "content_type": "text", "parts": ["What is Rust?"] },
+                        "create_time": 1700000001.0
+                    }
+                },
+                "a1": {
+                    "parent": "q1",
+                    "children": [],
+                    "message": {
+                        "author": { "role": "assistant" },
+                        "content": { "content_type": "text", "parts": ["A systems language."] },
+                        "create_time": 1700000002.0
+                    }
+                }
+            }
+        }]);
+        tokio::fs::write(&file, data.to_string()).await.unwrap();
+        
+        let mut stats = ImportStats::default();
+        let conversations = provider.extract_conversations(&file, &mut stats).await.unwrap();
+        
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0].messages.len(), 2);
+        assert!(stats.errors.is_empty());
+        assert_eq!(stats.warnings.len(), 1);
+        assert_eq!(stats.warnings[0].context.as_deref(), Some("lost"));
+    }
+    
+    #[tokio::test]
+    async fn test_canvas_content_is_captured_and_status_nodes_skipped() {
+        let provider = ChatGPTProvider::new();
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("conversations.json");
+        
+        let data = serde_json::json!([{
+            "title": "Canvas draft",
+            "create_time": 1700000000.0,
+            "mapping": {
+                "root": { "parent": null, "children": ["q1"] },
+                "q1": {
+                    "parent": "root",
+                    "children": ["status"],
+                    "message": {
+                        "author": { "role": "user" },
+                        "content": { "content_type": "text", "parts": ["Draft a cover letter"] },
+                        "create_time": 1700000001.0
+                    }
+                },
+                "status": {
+                    "parent": "q1",
+                    "children": ["doc"],
+                    "message": {
+                        "author": { "role": "assistant" },
+                        "content": { "content_type": "text", "parts": ["Generating your canvas"] },
+                        "async_status": 1,
+                        "create_time": 1700000002.0
+                    }
+                },
+                "doc": {
+                    "parent": "status",
+                    "children": ["quote"],
+                    "message": {
+                        "author": { "role": "assistant" },
+                        "content": {
+                            "content_type": "canvas",
+                            "title": "Cover letter",
+                            "content": "Dear hiring manager,"
+                        },
+                        "create_time": 1700000003.0
+                    }
+                },
+                "quote": {
+                    "parent": "doc",
+                    "children": [],
+                    "message": {
+                        "author": { "role": "assistant" },
+                        "content": {
+                            "content_type": "tether_quote",
+                            "title": "Style guide",
+                            "text": "Keep it to one page."
+                        },
+                        "create_time": 1700000004.0
+                    }
+                }
+            }
+        }]);
+        tokio::fs::write(&file, data.to_string()).await.unwrap();
+        
+        let mut stats = ImportStats::default();
+        let conversations = provider.extract_conversations(&file, &mut stats).await.unwrap();
+        
+        let contents: Vec<&str> = conversations[0].messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec![
+            "Draft a cover letter",
+            "[Canvas: Cover letter]\nDear hiring manager,",
+            "[Quote: Style guide]\nKeep it to one page.",
+        ]);
+    }
+    
+    #[tokio::test]
+    async fn test_malformed_utf8_is_replaced_with_a_warning() {
+        let provider = ChatGPTProvider::new();
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("conversations.json");
+        
+        let conversation = |title: &str, text: &str| serde_json::json!({
+            "title": title,
+            "create_time": 1700000000.0,
+            "mapping": {
+                "root": { "parent": null, "children": ["q1"] },
+                "q1": {
+                    "parent": "root",
+                    "children": [],
+                    "message": {
+                        "author": { "role": "user" },
+                        "content": { "content_type": "text", "parts": [text] },
+                        "create_time": 1700000001.0
+                    }
+                }
+            }
+        });
+        let data = serde_json::json!([
+            conversation("Truncated", "Costs 5 BROKEN"),
+            conversation("Intact", "Costs 5 euros"),
+        ]);
+        // A euro sign cut off after two of its three bytes
+        let text = data.to_string();
+        let (before, after) = text.split_once("BROKEN").unwrap();
+        tokio::fs::write(&file, [before.as_bytes(), &[0xE2, 0x82], after.as_bytes()].concat()).await.unwrap();
+        
+        assert!(provider.can_handle(&file).await);
+        
+        let mut stats = ImportStats::default();
+        let conversations = provider.extract_conversations(&file, &mut stats).await.unwrap();
+        
+        assert_eq!(conversations.len(), 2);
+        assert_eq!(conversations[0].messages[0].content, "Costs 5 \u{FFFD}");
+        assert_eq!(conversations[1].messages[0].content, "Costs 5 euros");
+        assert_eq!(stats.warnings.len(), 1);
+        assert_eq!(stats.warnings[0].file, file.to_string_lossy());
+    }
+}
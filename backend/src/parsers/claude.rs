@@ -1,7 +1,7 @@
 // parsers/claude.rs - Claude/Anthropic export parser
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -12,12 +12,27 @@ use super::{
     Message, MessageRole, ParserError, ParserResult,
 };
 
+/// Markers around `thinking` blocks kept in message content
+const THINKING_START: &str = "<thinking>";
+const THINKING_END: &str = "</thinking>";
+
 /// Claude provider implementation
-pub struct ClaudeProvider;
+pub struct ClaudeProvider {
+    /// Keep extended thinking blocks in message content
+    include_thinking: bool,
+}
 
 impl ClaudeProvider {
     pub fn new() -> Self {
-        Self
+        Self { include_thinking: false }
+    }
+
+    /// Keep `thinking` blocks, wrapped in `<thinking>` markers, in message
+    /// content instead of dropping them (`INCLUDE_THINKING` through
+    /// `ProviderRegistry::new`)
+    pub fn with_thinking(mut self, include_thinking: bool) -> Self {
+        self.include_thinking = include_thinking;
+        self
     }
 }
 
@@ -61,6 +76,7 @@ impl ChatProvider for ClaudeProvider {
             }
         }
 
+        files.sort();
         Ok(files)
     }
 
@@ -77,8 +93,8 @@ impl ChatProvider for ClaudeProvider {
         }
 
         // Check file structure
-        match tokio::fs::read_to_string(file).await {
-            Ok(content) => match serde_json::from_str::<Value>(&content) {
+        match read_lossy(file).await {
+            Ok((content, _)) => match serde_json::from_str::<Value>(&content) {
                 Ok(Value::Array(arr)) => {
                     // Check first item for Claude structure
                     arr.first()
@@ -109,7 +125,7 @@ impl ChatProvider for ClaudeProvider {
         file: &Path,
         stats: &mut ImportStats,
     ) -> ParserResult<Vec<Conversation>> {
-        let content = tokio::fs::read_to_string(file).await?;
+        let content = read_export(file, stats).await?;
         let data: Value = serde_json::from_str(&content)?;
         
         let mut conversations = Vec::new();
@@ -233,12 +249,12 @@ impl ClaudeProvider {
             };
 
             // Extract content
-            let content = if let Some(content_arr) = msg_obj.get("content").and_then(|v| v.as_array()) {
+            let (content, tool_calls) = if let Some(content_arr) = msg_obj.get("content").and_then(|v| v.as_array()) {
                 self.extract_text_from_content_array(content_arr)
             } else if let Some(text) = msg_obj.get("text").and_then(|v| v.as_str()) {
-                text.to_string()
+                (text.to_string(), Vec::new())
             } else {
-                String::new()
+                (String::new(), Vec::new())
             };
 
             // Extract timestamp
@@ -260,6 +276,10 @@ impl ClaudeProvider {
             if let Some(updated) = msg_obj.get("updated_at").and_then(|v| v.as_str()) {
                 metadata.insert("message_updated_at".to_string(), serde_json::json!(updated));
             }
+            let has_tool_calls = !tool_calls.is_empty();
+            if has_tool_calls {
+                metadata.insert("tool_calls".to_string(), Value::Array(tool_calls));
+            }
 
             // Handle attachments
             let mut message_media = Vec::new();
@@ -289,8 +309,8 @@ impl ClaudeProvider {
                 metadata.insert("attachments_references".to_string(), serde_json::json!(refs));
             }
 
-            // Only add message if it has content or introduced new media
-            if !content.trim().is_empty() || !message_media.is_empty() {
+            // Only add message if it has content, tool calls or introduced new media
+            if !content.trim().is_empty() || has_tool_calls || !message_media.is_empty() {
                 messages.push(Message {
                     role,
                     content: content.trim().to_string(),
@@ -367,36 +387,76 @@ impl ClaudeProvider {
     }
 
     fn parse_claude_timestamp(&self, timestamp_str: &str) -> Option<DateTime<Utc>> {
-        // Claude uses ISO 8601 format, often with 'Z' suffix
-        match parse_timestamp(&timestamp_str.replace("Z", "+00:00")) {
+        let timestamp_str = timestamp_str.trim();
+        
+        // RFC 3339 with any sub-second precision, e.g. 2024-01-15T10:30:00.123456Z
+        if let Ok(dt) = DateTime::parse_from_rfc3339(timestamp_str) {
+            return Some(dt.with_timezone(&Utc));
+        }
+        
+        // The same without an offset, which Claude means as UTC
+        for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+            if let Ok(dt) = NaiveDateTime::parse_from_str(timestamp_str, format) {
+                return Some(Utc.from_utc_datetime(&dt));
+            }
+        }
+        
+        match parse_timestamp(timestamp_str) {
             Ok(dt) => Some(dt),
             Err(_) => {
-                // Try parsing without subseconds
-                match DateTime::parse_from_str(timestamp_str, "%Y-%m-%d %H:%M:%S") {
-                    Ok(dt) => Some(dt.with_timezone(&Utc)),
-                    Err(_) => {
-                        warn!("Failed to parse Claude timestamp: {}", timestamp_str);
-                        None
-                    }
-                }
+                warn!("Failed to parse Claude timestamp: {}", timestamp_str);
+                None
             }
         }
     }
 
-    fn extract_text_from_content_array(&self, content_array: &[Value]) -> String {
+    /// Text and tool calls from a message's content blocks
+    ///
+    /// Text and `tool_result` blocks are joined into the text, `tool_use`
+    /// blocks become tool calls (`id`, `name`, `input`), and `thinking`
+    /// blocks are only kept when the provider includes thinking.
+    fn extract_text_from_content_array(&self, content_array: &[Value]) -> (String, Vec<Value>) {
         let mut text_parts = Vec::new();
+        let mut tool_calls = Vec::new();
         
         for block in content_array {
-            if let Some(obj) = block.as_object() {
-                if obj.get("type").and_then(|v| v.as_str()) == Some("text") {
+            let Some(obj) = block.as_object() else {
+                continue;
+            };
+            
+            match obj.get("type").and_then(|v| v.as_str()) {
+                Some("text") => {
                     if let Some(text) = obj.get("text").and_then(|v| v.as_str()) {
                         text_parts.push(text.to_string());
                     }
                 }
+                Some("thinking") if self.include_thinking => {
+                    if let Some(thinking) = obj.get("thinking").and_then(|v| v.as_str()) {
+                        text_parts.push(format!("{}\n{}\n{}", THINKING_START, thinking, THINKING_END));
+                    }
+                }
+                Some("tool_use") => {
+                    tool_calls.push(serde_json::json!({
+                        "id": obj.get("id"),
+                        "name": obj.get("name"),
+                        "input": obj.get("input"),
+                    }));
+                }
+                Some("tool_result") => {
+                    let result = match obj.get("content") {
+                        Some(Value::String(text)) => text.clone(),
+                        Some(Value::Array(blocks)) => self.extract_text_from_content_array(blocks).0,
+                        _ => String::new(),
+                    };
+                    if !result.trim().is_empty() {
+                        text_parts.push(result);
+                    }
+                }
+                _ => {}
             }
         }
         
-        text_parts.join("\n")
+        (text_parts.join("\n"), tool_calls)
     }
 
     fn process_attachment(
@@ -415,7 +475,10 @@ impl ClaudeProvider {
             .and_then(|v| v.as_str())
             .unwrap_or(&format!("msg{}_att{}", msg_idx, att_idx));
         
-        let logical_path = format!("claude_attachments/{}/{}/{}", conv_uuid, msg_uuid, file_name);
+        // Only the last component of the exported name is kept, so the
+        // logical path stays inside the data directory
+        let stored_name = Path::new(file_name).file_name()?.to_str()?;
+        let logical_path = format!("claude_attachments/{}/{}/{}", conv_uuid, msg_uuid, stored_name);
         
         // Check if we've already processed this file
         if let Some(existing) = all_media.get(&logical_path) {
@@ -496,4 +559,110 @@ mod tests {
         let ts = provider.parse_claude_timestamp("2024-01-15 10:30:00").unwrap();
         assert_eq!(ts.to_rfc3339(), "2024-01-15T10:30:00+00:00");
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_timestamp_parsing_keeps_subseconds() {
+        let provider = ClaudeProvider::new();
+        
+        let micros = provider.parse_claude_timestamp("2024-01-15T10:30:00.123456Z").unwrap();
+        assert_eq!(micros.timestamp_subsec_micros(), 123_456);
+        assert_eq!(micros.to_rfc3339(), "2024-01-15T10:30:00.123456+00:00");
+        
+        let millis = provider.parse_claude_timestamp("2024-01-15T10:30:00.123Z").unwrap();
+        assert_eq!(millis.timestamp_subsec_millis(), 123);
+        
+        // Offsets are applied rather than dropped
+        let offset = provider.parse_claude_timestamp("2024-01-15T12:30:00.5+02:00").unwrap();
+        assert_eq!(offset.to_rfc3339(), "2024-01-15T10:30:00.500+00:00");
+        
+        // Without an offset the time is taken as UTC
+        let naive = provider.parse_claude_timestamp("2024-01-15T10:30:00.654321").unwrap();
+        assert_eq!(naive.timestamp_subsec_micros(), 654_321);
+        let naive = provider.parse_claude_timestamp("2024-01-15 10:30:00.250").unwrap();
+        assert_eq!(naive.timestamp_subsec_millis(), 250);
+        
+        assert!(provider.parse_claude_timestamp("yesterday").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_subsecond_timestamps_keep_message_order() {
+        let provider = ClaudeProvider::new();
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("conversations.json");
+        
+        let data = serde_json::json!({
+            "uuid": "precise-uuid",
+            "name": "Precise",
+            "created_at": "2024-01-15T10:30:00.000000Z",
+            "chat_messages": [
+                { "sender": "human", "text": "first", "created_at": "2024-01-15T10:30:00.100000Z" },
+                { "sender": "assistant", "text": "second", "created_at": "2024-01-15T10:30:00.900000Z" },
+                { "sender": "human", "text": "third", "created_at": "2024-01-15T10:30:01.000001Z" }
+            ]
+        });
+        
+        let conv = provider
+            .extract_single_conversation(&data, &file, 0)
+            .await
+            .unwrap()
+            .unwrap();
+        let timestamps: Vec<_> = conv
+            .messages
+            .iter()
+            .map(|m| m.timestamp.to_rfc3339())
+            .collect();
+        assert_eq!(
+            timestamps,
+            vec![
+                "2024-01-15T10:30:00.100+00:00",
+                "2024-01-15T10:30:00.900+00:00",
+                "2024-01-15T10:30:01.000001+00:00",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_content_blocks_keep_text_and_capture_tool_use() {
+        let provider = ClaudeProvider::new();
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("conversations.json");
+        
+        let data = serde_json::json!({
+            "uuid": "tool-uuid",
+            "name": "Tool Chat",
+            "chat_messages": [{
+                "sender": "assistant",
+                "created_at": "2024-01-15T10:30:00Z",
+                "content": [
+                    {"type": "thinking", "thinking": "Need the weather tool"},
+                    {"type": "text", "text": "Let me check."},
+                    {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Oslo"}},
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": [{"type": "text", "text": "Sunny, 18C"}]},
+                    {"type": "text", "text": "It is sunny."}
+                ]
+            }]
+        });
+        
+        let conv = provider
+            .extract_single_conversation(&data, &file, 0)
+            .await
+            .unwrap()
+            .unwrap();
+        let message = &conv.messages[0];
+        assert_eq!(message.content, "Let me check.\nSunny, 18C\nIt is sunny.");
+        
+        let tool_calls = message.metadata["tool_calls"].as_array().unwrap();
+        assert_eq!(tool_calls.len(), 1);
+        assert_eq!(tool_calls[0]["name"], "get_weather");
+        assert_eq!(tool_calls[0]["input"]["city"], "Oslo");
+        
+        // Thinking is only kept when asked for
+        let provider = ClaudeProvider::new().with_thinking(true);
+        let conv = provider
+            .extract_single_conversation(&data, &file, 0)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(conv.messages[0].content.starts_with("<thinking>\nNeed the weather tool\n</thinking>\nLet me check."));
+    }
+}
@@ -5,8 +5,10 @@ use mime_guess::from_path;
 use regex::Regex;
 use serde_json::Value;
 use std::path::Path;
+use std::sync::OnceLock;
+use unicode_segmentation::UnicodeSegmentation;
 
-use super::{ParserError, ParserResult};
+use super::{ImportStats, ImportWarning, ParserError, ParserResult};
 
 /// Parse timestamp from various formats
 pub fn parse_timestamp(input: &str) -> ParserResult<DateTime<Utc>> {
@@ -138,19 +140,40 @@ pub fn find_media_references(text: &str) -> Vec<String> {
     refs
 }
 
+/// Default maximum title length, in grapheme clusters
+pub const DEFAULT_MAX_TITLE_LENGTH: usize = 200;
+
+/// Maximum title length, overridable with the `MAX_TITLE_LENGTH` env var
+pub fn max_title_length() -> usize {
+    static MAX: OnceLock<usize> = OnceLock::new();
+    *MAX.get_or_init(|| {
+        std::env::var("MAX_TITLE_LENGTH")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(DEFAULT_MAX_TITLE_LENGTH)
+    })
+}
+
 /// Sanitize and validate conversation title
 pub fn sanitize_title(title: &str, default: &str) -> String {
     let cleaned = title.trim();
     if cleaned.is_empty() {
         default.to_string()
     } else {
-        // Truncate very long titles
-        if cleaned.len() > 200 {
-            format!("{}...", &cleaned[..197])
-        } else {
-            cleaned.to_string()
-        }
+        truncate_title(cleaned, max_title_length())
+    }
+}
+
+/// Truncate a title to `max_len` grapheme clusters, ending with "..." when cut
+pub fn truncate_title(title: &str, max_len: usize) -> String {
+    let graphemes: Vec<&str> = title.graphemes(true).collect();
+    if graphemes.len() <= max_len {
+        return title.to_string();
     }
+    
+    let keep = max_len.saturating_sub(3);
+    format!("{}...", graphemes[..keep].concat().trim_end())
 }
 
 /// Generate a unique conversation ID from various inputs
@@ -166,21 +189,44 @@ pub fn generate_conversation_id(provider: &str, file_path: &Path, index: usize)
     format!("{}_{}_{:x}", provider, index, hasher.finish())
 }
 
-/// Check if a JSON file is valid
+/// Check if a JSON file is valid, ignoring malformed UTF-8 the way
+/// `read_export` does
 pub fn is_valid_json_file(path: &Path) -> bool {
     if !path.is_file() {
         return false;
     }
     
-    match std::fs::File::open(path) {
-        Ok(file) => {
-            let reader = std::io::BufReader::new(file);
-            serde_json::from_reader::<_, Value>(reader).is_ok()
-        }
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_str::<Value>(&String::from_utf8_lossy(&bytes)).is_ok(),
         Err(_) => false,
     }
 }
 
+/// Read a file, replacing malformed UTF-8 with U+FFFD
+///
+/// Also returns whether anything was replaced.
+pub async fn read_lossy(path: &Path) -> std::io::Result<(String, bool)> {
+    let bytes = tokio::fs::read(path).await?;
+    Ok(match String::from_utf8(bytes) {
+        Ok(text) => (text, false),
+        Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), true),
+    })
+}
+
+/// Read an export file for import, so a truncated multibyte sequence costs
+/// a warning rather than every conversation in the file
+pub async fn read_export(path: &Path, stats: &mut ImportStats) -> ParserResult<String> {
+    let (content, replaced) = read_lossy(path).await?;
+    if replaced {
+        stats.warnings.push(ImportWarning {
+            file: path.to_string_lossy().to_string(),
+            warning: "Replaced malformed UTF-8 with U+FFFD".to_string(),
+            context: None,
+        });
+    }
+    Ok(content)
+}
+
 /// Extract metadata from JSON object, preserving only safe types
 pub fn extract_metadata(obj: &serde_json::Map<String, Value>) -> serde_json::Map<String, Value> {
     let mut metadata = serde_json::Map::new();
@@ -272,4 +318,24 @@ mod tests {
         assert!(refs.contains(&"https://example.com/image.png".to_string()));
         assert!(refs.contains(&"doc.pdf".to_string()));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_truncate_multibyte_title() {
+        let title = "日本語のタイトル👨‍👩‍👧é".repeat(30);
+        let truncated = truncate_title(&title, DEFAULT_MAX_TITLE_LENGTH);
+        
+        assert!(truncated.ends_with("..."));
+        assert_eq!(truncated.graphemes(true).count(), DEFAULT_MAX_TITLE_LENGTH);
+        assert!(title.starts_with(truncated.trim_end_matches("...")));
+        
+        // Multi-codepoint family emoji is never split
+        let emoji = "👨‍👩‍👧".repeat(5);
+        assert_eq!(truncate_title(&emoji, 4), "👨‍👩‍👧...");
+    }
+    
+    #[test]
+    fn test_sanitize_title_short_and_empty() {
+        assert_eq!(sanitize_title("  Hello  ", "Untitled"), "Hello");
+        assert_eq!(sanitize_title("   ", "Untitled"), "Untitled");
+    }
+}
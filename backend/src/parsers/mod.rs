@@ -153,7 +153,8 @@ pub trait ChatProvider: Send + Sync {
     /// Provider name (e.g., "ChatGPT", "Claude")
     fn name(&self) -> &'static str;
     
-    /// Find all importable files in a directory
+    /// Find all importable files in a directory, sorted by path so imports
+    /// are reproducible across filesystems
     async fn find_files(&self, dir: &Path) -> ParserResult<Vec<std::path::PathBuf>>;
     
     /// Check if a specific file can be handled by this provider
@@ -167,18 +168,46 @@ pub trait ChatProvider: Send + Sync {
     ) -> ParserResult<Vec<Conversation>>;
 }
 
+/// Settings the providers in a registry are built with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProviderOptions {
+    /// Keep Claude `thinking` blocks in message content
+    pub include_thinking: bool,
+}
+
+impl ProviderOptions {
+    /// Options from the environment: `INCLUDE_THINKING=1` (or `true`/`yes`)
+    /// keeps thinking blocks
+    pub fn from_env() -> Self {
+        Self::parse(std::env::var("INCLUDE_THINKING").ok().as_deref())
+    }
+    
+    fn parse(include_thinking: Option<&str>) -> Self {
+        let include_thinking = include_thinking.is_some_and(|value| {
+            ["1", "true", "yes"].iter().any(|on| value.trim().eq_ignore_ascii_case(on))
+        });
+        Self { include_thinking }
+    }
+}
+
 /// Registry of available providers
 pub struct ProviderRegistry {
     providers: Vec<Box<dyn ChatProvider>>,
 }
 
 impl ProviderRegistry {
-    /// Create a new registry with all available providers
+    /// Create a new registry with all available providers, configured from
+    /// the environment
     pub fn new() -> Self {
+        Self::with_options(ProviderOptions::from_env())
+    }
+    
+    /// Create a new registry with all available providers
+    pub fn with_options(options: ProviderOptions) -> Self {
         Self {
             providers: vec![
                 Box::new(chatgpt::ChatGPTProvider::new()),
-                Box::new(claude::ClaudeProvider::new()),
+                Box::new(claude::ClaudeProvider::new().with_thinking(options.include_thinking)),
                 Box::new(gemini::GeminiProvider::new()),
                 Box::new(zed::ZedProvider::new()),
             ],
@@ -220,4 +249,14 @@ mod tests {
             Some(MessageRole::System)
         );
     }
+    
+    #[test]
+    fn test_provider_options_parse_include_thinking() {
+        assert!(!ProviderOptions::parse(None).include_thinking);
+        assert!(!ProviderOptions::parse(Some("0")).include_thinking);
+        assert!(!ProviderOptions::parse(Some("")).include_thinking);
+        for on in ["1", "true", "YES", " true "] {
+            assert!(ProviderOptions::parse(Some(on)).include_thinking, "{:?}", on);
+        }
+    }
 }
\ No newline at end of file
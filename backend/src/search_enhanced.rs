@@ -69,7 +69,7 @@ impl EnhancedSearch {
             FROM messages_fts
             JOIN conversations c ON c.id = messages_fts.conversation_id
             WHERE messages_fts MATCH ?
-            ORDER BY rank
+            ORDER BY rank, c.id DESC
             LIMIT ?
         "#;
 
@@ -146,19 +146,32 @@ impl EnhancedSearch {
         conn: &mut SqliteConnection,
         dsl_query: &SearchDSL,
     ) -> Result<Vec<SearchResult>, String> {
+        let fts_expression = dsl_query.to_fts_expression();
+
         let mut sql = String::from(
             "SELECT DISTINCT c.id, c.title, substr(m.content, 1, 150) as snippet, 1.0 as score
              FROM conversations c
-             JOIN messages m ON c.id = m.conversation_id
-             WHERE 1=1"
+             JOIN messages m ON c.id = m.conversation_id"
         );
+
+        if fts_expression.is_some() {
+            sql.push_str(" JOIN messages_fts ON messages_fts.rowid = m.id");
+        }
+
+        sql.push_str(" WHERE 1=1");
         
         let mut bindings = vec![];
 
         // Build dynamic query based on DSL
-        if let Some(text) = &dsl_query.text {
-            sql.push_str(" AND m.content LIKE ?");
-            bindings.push(format!("%{}%", text));
+        if let Some(expression) = fts_expression {
+            sql.push_str(" AND messages_fts MATCH ?");
+            bindings.push(expression);
+        } else {
+            // Negation-only queries have no FTS5 equivalent (NOT is binary)
+            for term in dsl_query.terms.iter().filter(|t| t.negated) {
+                sql.push_str(" AND m.content NOT LIKE ?");
+                bindings.push(format!("%{}%", term.value));
+            }
         }
 
         if let Some(provider) = &dsl_query.provider {
@@ -211,6 +224,7 @@ impl EnhancedSearch {
 #[derive(Default)]
 pub struct SearchDSL {
     pub text: Option<String>,
+    pub terms: Vec<TextTerm>,
     pub provider: Option<String>,
     pub role: Option<String>,
     pub after_timestamp: Option<i64>,
@@ -218,34 +232,108 @@ pub struct SearchDSL {
     pub limit: Option<i32>,
 }
 
+/// A free-text term of a DSL query: a bare word or a quoted phrase
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextTerm {
+    pub value: String,
+    pub phrase: bool,
+    pub negated: bool,
+    /// Joined to the previous term with OR instead of the implicit AND
+    pub or_previous: bool,
+}
+
+impl TextTerm {
+    /// Render as an FTS5 string
+    fn to_fts(&self) -> String {
+        fts_term(&self.value, self.phrase)
+    }
+}
+
+/// `value` as one FTS5 term, quoting anything that isn't a plain bareword
+///
+/// Phrases, operator keywords and words with characters other than letters,
+/// digits and `_` are quoted, so the term is never read as query syntax.
+pub fn fts_term(value: &str, phrase: bool) -> String {
+    let is_bareword = !phrase
+        && value.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !matches!(value, "AND" | "OR" | "NOT" | "NEAR");
+
+    if is_bareword {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    }
+}
+
 impl SearchDSL {
     /// Parse DSL from string like "provider:chatgpt role:user after:2024-01-01"
+    ///
+    /// Free text supports quoted phrases (`"machine learning"`), `OR` between
+    /// terms and a leading `-` for negation (`-spam`).
     pub fn parse(query: &str) -> Self {
         let mut dsl = Self::default();
-        let parts: Vec<&str> = query.split_whitespace().collect();
         let mut text_parts = vec![];
+        let mut pending_or = false;
 
-        for part in parts {
-            if let Some((key, value)) = part.split_once(':') {
-                match key {
-                    "provider" => dsl.provider = Some(value.to_string()),
-                    "role" => dsl.role = Some(value.to_string()),
-                    "after" => {
-                        // Parse date to timestamp
-                        if let Ok(ts) = parse_date(value) {
-                            dsl.after_timestamp = Some(ts);
+        for token in tokenize(query) {
+            if !token.quoted {
+                if let Some((key, value)) = token.raw.split_once(':') {
+                    let handled = match key {
+                        "provider" => {
+                            dsl.provider = Some(value.to_string());
+                            true
                         }
-                    }
-                    "before" => {
-                        if let Ok(ts) = parse_date(value) {
-                            dsl.before_timestamp = Some(ts);
+                        "role" => {
+                            dsl.role = Some(value.to_string());
+                            true
                         }
+                        "after" => {
+                            // Parse date to timestamp
+                            if let Ok(ts) = parse_date(value) {
+                                dsl.after_timestamp = Some(ts);
+                            }
+                            true
+                        }
+                        "before" => {
+                            if let Ok(ts) = parse_date(value) {
+                                dsl.before_timestamp = Some(ts);
+                            }
+                            true
+                        }
+                        _ => false,
+                    };
+                    if handled {
+                        continue;
+                    }
+                }
+
+                match token.raw.as_str() {
+                    "OR" => {
+                        pending_or = !dsl.terms.is_empty();
+                        text_parts.push(token.raw);
+                        continue;
                     }
-                    _ => text_parts.push(part),
+                    "AND" => {
+                        // Implicit in FTS5
+                        text_parts.push(token.raw);
+                        continue;
+                    }
+                    _ => {}
                 }
-            } else {
-                text_parts.push(part);
             }
+
+            if token.value.is_empty() {
+                continue;
+            }
+
+            dsl.terms.push(TextTerm {
+                value: token.value,
+                phrase: token.quoted,
+                negated: token.negated,
+                or_previous: pending_or,
+            });
+            pending_or = false;
+            text_parts.push(token.raw);
         }
 
         if !text_parts.is_empty() {
@@ -254,6 +342,127 @@ impl SearchDSL {
 
         dsl
     }
+
+    /// Compile the free-text terms into an FTS5 MATCH expression
+    ///
+    /// Returns `None` when there is nothing positive to match on, since FTS5
+    /// has no unary NOT.
+    pub fn to_fts_expression(&self) -> Option<String> {
+        let mut positive = String::new();
+        let mut has_or = false;
+
+        for term in self.terms.iter().filter(|t| !t.negated) {
+            if !positive.is_empty() {
+                if term.or_previous {
+                    positive.push_str(" OR ");
+                    has_or = true;
+                } else {
+                    positive.push(' ');
+                }
+            }
+            positive.push_str(&term.to_fts());
+        }
+
+        if positive.is_empty() {
+            return None;
+        }
+
+        let negated: Vec<String> = self.terms
+            .iter()
+            .filter(|t| t.negated)
+            .map(|t| t.to_fts())
+            .collect();
+
+        if negated.is_empty() {
+            return Some(positive);
+        }
+
+        // NOT binds tighter than OR, so group the positive side
+        let mut expression = if has_or {
+            format!("({})", positive)
+        } else {
+            positive
+        };
+        for term in negated {
+            expression.push_str(" NOT ");
+            expression.push_str(&term);
+        }
+
+        Some(expression)
+    }
+}
+
+/// Raw token produced by the DSL tokenizer
+struct Token {
+    raw: String,
+    value: String,
+    quoted: bool,
+    negated: bool,
+}
+
+/// Split a query on whitespace, keeping quoted phrases together
+fn tokenize(query: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut raw = String::new();
+        let mut negated = false;
+
+        if c == '-' {
+            chars.next();
+            raw.push('-');
+            match chars.peek() {
+                Some(next) if !next.is_whitespace() => negated = true,
+                _ => continue, // A lone "-" carries no meaning
+            }
+        }
+
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            raw.push('"');
+            let mut value = String::new();
+            for ch in chars.by_ref() {
+                if ch == '"' {
+                    break;
+                }
+                value.push(ch);
+            }
+            raw.push_str(&value);
+            raw.push('"');
+
+            tokens.push(Token {
+                raw,
+                value: value.split_whitespace().collect::<Vec<_>>().join(" "),
+                quoted: true,
+                negated,
+            });
+        } else {
+            let mut value = String::new();
+            while let Some(&ch) = chars.peek() {
+                if ch.is_whitespace() {
+                    break;
+                }
+                value.push(ch);
+                chars.next();
+            }
+            raw.push_str(&value);
+
+            tokens.push(Token {
+                raw,
+                value,
+                quoted: false,
+                negated,
+            });
+        }
+    }
+
+    tokens
 }
 
 fn parse_date(date_str: &str) -> Result<i64, String> {
@@ -290,4 +499,53 @@ mod tests {
         assert_eq!(dsl.role, Some("user".to_string()));
         assert!(dsl.after_timestamp.is_some());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_dsl_phrase_to_fts() {
+        let dsl = SearchDSL::parse("\"machine learning\" provider:claude");
+
+        assert_eq!(dsl.provider, Some("claude".to_string()));
+        assert_eq!(dsl.to_fts_expression(), Some("\"machine learning\"".to_string()));
+    }
+
+    #[test]
+    fn test_dsl_or_to_fts() {
+        let dsl = SearchDSL::parse("rust OR python");
+        assert_eq!(dsl.to_fts_expression(), Some("rust OR python".to_string()));
+
+        // Implicit AND is preserved as adjacency
+        let dsl = SearchDSL::parse("rust async");
+        assert_eq!(dsl.to_fts_expression(), Some("rust async".to_string()));
+    }
+
+    #[test]
+    fn test_dsl_negation_to_fts() {
+        let dsl = SearchDSL::parse("\"machine learning\" OR rust -spam role:user");
+
+        assert_eq!(dsl.role, Some("user".to_string()));
+        assert_eq!(
+            dsl.to_fts_expression(),
+            Some("(\"machine learning\" OR rust) NOT spam".to_string())
+        );
+
+        let dsl = SearchDSL::parse("rust -\"borrow checker\"");
+        assert_eq!(
+            dsl.to_fts_expression(),
+            Some("rust NOT \"borrow checker\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_dsl_negation_only_has_no_fts() {
+        let dsl = SearchDSL::parse("-spam");
+
+        assert_eq!(dsl.to_fts_expression(), None);
+        assert!(dsl.terms[0].negated);
+    }
+
+    #[test]
+    fn test_dsl_quotes_special_characters() {
+        let dsl = SearchDSL::parse("c++ OR \"say \"");
+        assert_eq!(dsl.to_fts_expression(), Some("\"c++\" OR \"say\"".to_string()));
+    }
+}
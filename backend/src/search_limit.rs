@@ -0,0 +1,20 @@
+use axum::http::StatusCode;
+
+/// Results returned when a search doesn't ask for a number; matches the
+/// main server's `search.default_limit`
+pub const DEFAULT_LIMIT: i32 = 20;
+
+/// Most results one search returns; matches the main server's `search.max_limit`
+pub const MAX_LIMIT: i32 = 100;
+
+/// The number of results to return for a requested limit
+///
+/// Anything over `MAX_LIMIT` is cut to it; zero and negative limits are a
+/// bad request.
+pub fn clamp(requested: Option<i32>) -> Result<i32, StatusCode> {
+    match requested {
+        None => Ok(DEFAULT_LIMIT),
+        Some(limit) if limit < 1 => Err(StatusCode::BAD_REQUEST),
+        Some(limit) => Ok(limit.min(MAX_LIMIT)),
+    }
+}
@@ -4,6 +4,7 @@ use futures::stream::{Stream, StreamExt};
 use serde_json::Value;
 use std::pin::Pin;
 use crate::parsers::{Message, Conversation};
+use crate::parsers::common::sanitize_title;
 
 /// Streaming JSON parser for massive files (1GB+)
 /// Processes one conversation at a time without loading entire file
@@ -148,7 +149,7 @@ fn parse_conversation(json: Value) -> Result<Conversation, String> {
     // Simple conversation parser
     Ok(Conversation {
         id: json["id"].as_str().unwrap_or("").to_string(),
-        title: json["title"].as_str().unwrap_or("Untitled").to_string(),
+        title: sanitize_title(json["title"].as_str().unwrap_or_default(), "Untitled"),
         messages: vec![], // Would parse messages here
         timestamp: json["create_time"].as_f64().unwrap_or(0.0) as i64,
         provider: "chatgpt".to_string(),
@@ -11,7 +11,11 @@ async fn test_health_endpoint() {
     let response = server.get("/health").await;
     
     assert_eq!(response.status_code(), StatusCode::OK);
-    assert_eq!(response.text(), "OK");
+    
+    let json: serde_json::Value = response.json();
+    assert_eq!(json["status"], "healthy");
+    assert_eq!(json["database"], true);
+    assert!(json["latency_ms"].is_number());
 }
 
 #[tokio::test]
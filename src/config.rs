@@ -1,6 +1,13 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use tracing::warn;
+
+use crate::export::Redactor;
+use crate::import::BranchMode;
+use crate::models::ModelGrouping;
+use crate::search::SnippetMarkers;
 
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +23,15 @@ pub struct Config {
     
     #[serde(default)]
     pub server: ServerConfig,
+    
+    #[serde(default)]
+    pub export: ExportConfig,
+    
+    /// Directory media files are kept in; logical attachment paths such as
+    /// `claude_attachments/<uuid>/...` resolve inside it. Defaults to a
+    /// `media` directory next to the database.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,22 +47,46 @@ pub struct DatabaseConfig {
     
     #[serde(default = "default_cache_size")]
     pub cache_size: i32,
+    
+    /// Seconds a writer waits for another import or the server to finish
+    #[serde(default = "default_lock_timeout_secs")]
+    pub lock_timeout_secs: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchConfig {
-    #[serde(default = "default_max_results")]
-    pub max_results: usize,
+    /// Results returned when a search doesn't ask for a number
+    #[serde(default = "default_limit")]
+    pub default_limit: usize,
+    
+    /// Most results one search returns; larger requests are cut to this
+    #[serde(default = "default_max_limit", alias = "max_results")]
+    pub max_limit: usize,
     
     #[serde(default = "default_snippet_length")]
     pub snippet_length: usize,
     
     #[serde(default = "default_true")]
     pub highlight_matches: bool,
+    
+    /// Strings wrapped around matched terms in snippets
+    #[serde(default)]
+    pub snippet_markers: SnippetMarkers,
+    
+    /// FTS5 tokenizer: `porter unicode61` (English stemming), `unicode61`, or
+    /// `trigram` (substring matching, also for CJK text). New databases are
+    /// built with it; existing ones keep theirs until `reindex` is run.
+    #[serde(default = "default_tokenizer")]
+    pub tokenizer: String,
+    
+    /// Answer blank queries with the most recent conversations instead of an error
+    #[serde(default = "default_true")]
+    pub browse_on_empty_query: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportConfig {
+    /// Conversations parsed before each batch is written, between 1 and 10000
     #[serde(default = "default_batch_size")]
     pub batch_size: usize,
     
@@ -55,6 +95,58 @@ pub struct ImportConfig {
     
     #[serde(default = "default_false")]
     pub skip_duplicates: bool,
+    
+    /// Give null-model assistant messages the model of the previous assistant message
+    #[serde(default = "default_false")]
+    pub carry_forward_model: bool,
+    
+    /// UTC offset (e.g. `+02:00`) for export timestamps that carry no zone
+    #[serde(default = "default_timezone")]
+    pub default_timezone: String,
+    
+    /// `active_only` or `all_branches` for regenerated/edited conversation trees
+    #[serde(default)]
+    pub branch_mode: BranchMode,
+    
+    /// Longest title derived from a conversation's first message
+    #[serde(default = "default_title_length")]
+    pub title_length: usize,
+    
+    /// Detect each conversation's language from its user messages
+    #[serde(default = "default_false")]
+    pub detect_language: bool,
+    
+    /// Model recorded for conversations whose export names none, by
+    /// provider; providers without an entry get no model
+    #[serde(default = "default_models")]
+    pub default_models: HashMap<String, String>,
+    
+    /// Never guess a model: conversations whose export names none are
+    /// stored without one, whatever `default_models` says
+    #[serde(default = "default_false")]
+    pub strict_models: bool,
+    
+    /// Longest message content stored and indexed; longer messages are cut
+    /// with a marker and kept whole in the conversation's `raw_json`. 0
+    /// means no limit
+    #[serde(default)]
+    pub max_message_chars: usize,
+    
+    /// Largest export fetched from a URL, in megabytes
+    #[serde(default = "default_max_download_mb")]
+    pub max_download_mb: u64,
+    
+    #[serde(default = "default_download_timeout_secs")]
+    pub download_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportConfig {
+    /// Regular expressions masked as `[REDACTED]` by `export --redact` and
+    /// `?redact=true`; defaults to emails, API keys, bearer tokens, long
+    /// base64 strings and phone numbers
+    #[serde(default = "default_redaction_patterns")]
+    pub redaction_patterns: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +159,40 @@ pub struct ServerConfig {
     
     #[serde(default = "default_static_dir")]
     pub static_dir: String,
+    
+    /// Deprecated name for the top-level `data_dir`, still honored when
+    /// `data_dir` isn't set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_dir: Option<String>,
+    
+    /// Directory `/api/import` may read exports from; relative paths in
+    /// requests resolve inside it. Unset refuses server paths.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub import_root: Option<String>,
+    
+    /// Hosts `/api/import` may download exports from over https; s3://
+    /// sources are checked as `<bucket>.s3.amazonaws.com`. Empty refuses
+    /// URLs.
+    #[serde(default)]
+    pub import_hosts: Vec<String>,
+    
+    /// Largest request body the import endpoints accept; other endpoints
+    /// keep axum's 2 MB default
+    #[serde(default = "default_max_import_bytes")]
+    pub max_import_bytes: usize,
+    
+    /// Requests allowed per client IP per minute; 0 disables the limit
+    #[serde(default = "default_requests_per_minute")]
+    pub requests_per_minute: u32,
+    
+    /// Seconds a request waits for another writer before answering 503;
+    /// imports started through the API wait `database.lock_timeout_secs`
+    #[serde(default = "default_write_wait_secs")]
+    pub write_wait_secs: u64,
+    
+    /// Model grouping in stats when a request doesn't pick one
+    #[serde(default)]
+    pub model_grouping: ModelGrouping,
 }
 
 impl Default for Config {
@@ -76,6 +202,8 @@ impl Default for Config {
             search: SearchConfig::default(),
             import: ImportConfig::default(),
             server: ServerConfig::default(),
+            export: ExportConfig::default(),
+            data_dir: None,
         }
     }
 }
@@ -87,6 +215,7 @@ impl Default for DatabaseConfig {
             wal_mode: true,
             mmap_size: default_mmap_size(),
             cache_size: default_cache_size(),
+            lock_timeout_secs: default_lock_timeout_secs(),
         }
     }
 }
@@ -94,9 +223,13 @@ impl Default for DatabaseConfig {
 impl Default for SearchConfig {
     fn default() -> Self {
         Self {
-            max_results: default_max_results(),
+            default_limit: default_limit(),
+            max_limit: default_max_limit(),
             snippet_length: default_snippet_length(),
             highlight_matches: true,
+            snippet_markers: SnippetMarkers::default(),
+            tokenizer: default_tokenizer(),
+            browse_on_empty_query: true,
         }
     }
 }
@@ -107,6 +240,24 @@ impl Default for ImportConfig {
             batch_size: default_batch_size(),
             python_bridge: true,
             skip_duplicates: false,
+            carry_forward_model: false,
+            default_timezone: default_timezone(),
+            branch_mode: BranchMode::default(),
+            title_length: default_title_length(),
+            detect_language: false,
+            default_models: default_models(),
+            strict_models: false,
+            max_message_chars: 0,
+            max_download_mb: default_max_download_mb(),
+            download_timeout_secs: default_download_timeout_secs(),
+        }
+    }
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            redaction_patterns: default_redaction_patterns(),
         }
     }
 }
@@ -117,10 +268,39 @@ impl Default for ServerConfig {
             port: default_port(),
             host: default_host(),
             static_dir: default_static_dir(),
+            media_dir: None,
+            import_root: None,
+            import_hosts: Vec::new(),
+            max_import_bytes: default_max_import_bytes(),
+            requests_per_minute: default_requests_per_minute(),
+            write_wait_secs: default_write_wait_secs(),
+            model_grouping: ModelGrouping::default(),
+        }
+    }
+}
+
+impl SearchConfig {
+    /// The number of results to return for a requested limit
+    ///
+    /// No limit means `default_limit`; anything over `max_limit` is cut to
+    /// it. Zero and negative limits are refused.
+    pub fn limit(&self, requested: Option<i64>) -> Result<usize> {
+        let max = self.max_limit.max(1);
+        match requested {
+            None => Ok(self.default_limit.clamp(1, max)),
+            Some(limit) if limit < 1 => bail!("Invalid limit {}; expected at least 1", limit),
+            Some(limit) => Ok(usize::try_from(limit).map_or(max, |limit| limit.min(max))),
         }
     }
 }
 
+impl ExportConfig {
+    /// Compile `redaction_patterns`, failing on the first invalid one
+    pub fn redactor(&self) -> Result<Redactor> {
+        Redactor::new(&self.redaction_patterns)
+    }
+}
+
 impl Config {
     /// Load configuration from file or use defaults
     pub fn load() -> Result<Self> {
@@ -138,6 +318,9 @@ impl Config {
             if path.exists() {
                 let content = std::fs::read_to_string(path)?;
                 let config: Config = toml::from_str(&content)?;
+                if config.server.media_dir.is_some() {
+                    warn!("server.media_dir in {:?} is deprecated; set data_dir instead", path);
+                }
                 return Ok(config);
             }
         }
@@ -146,6 +329,36 @@ impl Config {
         Ok(Config::default())
     }
     
+    /// Directory media files are read from and written to
+    pub fn data_dir(&self) -> PathBuf {
+        match self.data_dir.as_ref().or(self.server.media_dir.as_ref()) {
+            Some(dir) => PathBuf::from(shellexpand::tilde(dir).as_ref()),
+            // Kept apart from the database, its lock files and the config
+            None => Path::new(&self.database.path)
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .unwrap_or(Path::new("."))
+                .join("media"),
+        }
+    }
+    
+    /// Where a logical media path such as `claude_attachments/<uuid>/a.pdf`
+    /// lives on disk
+    ///
+    /// Only plain relative paths are accepted; URLs, absolute paths, and
+    /// `..` components that could reach outside `data_dir` are refused.
+    pub fn media_path(&self, logical: &str) -> Result<PathBuf> {
+        let relative = Path::new(logical);
+        let contained = relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if logical.is_empty() || logical.contains("://") || !contained {
+            bail!("Media path {:?} is outside the data directory", logical);
+        }
+        
+        Ok(self.data_dir().join(relative))
+    }
+    
     /// Save configuration to file
     pub fn save(&self, path: &Path) -> Result<()> {
         let toml = toml::to_string_pretty(self)?;
@@ -175,7 +388,15 @@ fn default_cache_size() -> i32 {
     -64000 // 64MB in pages
 }
 
-fn default_max_results() -> usize {
+fn default_lock_timeout_secs() -> u64 {
+    300
+}
+
+fn default_limit() -> usize {
+    20
+}
+
+fn default_max_limit() -> usize {
     100
 }
 
@@ -183,8 +404,36 @@ fn default_snippet_length() -> usize {
     200
 }
 
+fn default_tokenizer() -> String {
+    crate::db::schema::DEFAULT_FTS_TOKENIZER.to_string()
+}
+
 fn default_batch_size() -> usize {
-    1000
+    crate::import::parsers::DEFAULT_BATCH_SIZE
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn default_title_length() -> usize {
+    crate::import::parsers::DEFAULT_TITLE_LENGTH
+}
+
+fn default_models() -> HashMap<String, String> {
+    crate::import::parsers::default_models()
+}
+
+fn default_max_download_mb() -> u64 {
+    1024
+}
+
+fn default_download_timeout_secs() -> u64 {
+    300
+}
+
+fn default_redaction_patterns() -> Vec<String> {
+    crate::export::DEFAULT_REDACTION_PATTERNS.iter().map(|pattern| pattern.to_string()).collect()
 }
 
 fn default_port() -> u16 {
@@ -197,4 +446,59 @@ fn default_host() -> String {
 
 fn default_static_dir() -> String {
     "./static".to_string()
-}
\ No newline at end of file
+}
+
+fn default_max_import_bytes() -> usize {
+    256 * 1024 * 1024
+}
+
+fn default_requests_per_minute() -> u32 {
+    120
+}
+
+fn default_write_wait_secs() -> u64 {
+    5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_media_paths_resolve_under_data_dir() {
+        let config = Config {
+            data_dir: Some("/srv/archive".to_string()),
+            ..Config::default()
+        };
+        
+        assert_eq!(
+            config.media_path("claude_attachments/conv-1/msg-1/report.pdf").unwrap(),
+            Path::new("/srv/archive/claude_attachments/conv-1/msg-1/report.pdf")
+        );
+        
+        for escaping in ["../secret.txt", "claude_attachments/../../etc/passwd", "/etc/passwd", "https://example.com/a.png", ""] {
+            assert!(config.media_path(escaping).is_err(), "{} was accepted", escaping);
+        }
+    }
+    
+    #[test]
+    fn test_data_dir_defaults_to_media_next_to_database() {
+        let mut config = Config::default();
+        config.database.path = "/var/lib/llm-archive/archive.db".to_string();
+        assert_eq!(config.data_dir(), Path::new("/var/lib/llm-archive/media"));
+        assert!(config.media_path("archive.db").unwrap().starts_with("/var/lib/llm-archive/media"));
+        
+        config.database.path = "archive.db".to_string();
+        assert_eq!(config.data_dir(), Path::new("./media"));
+    }
+    
+    #[test]
+    fn test_server_media_dir_is_read_as_data_dir() {
+        let config: Config = toml::from_str("[server]\nmedia_dir = \"/srv/media\"\n").unwrap();
+        assert_eq!(config.data_dir(), Path::new("/srv/media"));
+        assert!(toml::to_string(&config).unwrap().contains("media_dir"));
+        
+        let config: Config = toml::from_str("data_dir = \"/srv/archive\"\n[server]\nmedia_dir = \"/srv/media\"\n").unwrap();
+        assert_eq!(config.data_dir(), Path::new("/srv/archive"));
+    }
+}
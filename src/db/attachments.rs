@@ -0,0 +1,280 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+/// A file attached to a message, normalized from the provider's JSON
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Attachment {
+    pub filename: Option<String>,
+    pub mime: Option<String>,
+    pub size: Option<i64>,
+    pub extracted_content: Option<String>,
+    pub path: Option<String>,
+}
+
+/// An attachment row as stored, with the ids needed to fetch it again
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct StoredAttachment {
+    pub id: i64,
+    pub message_id: i64,
+    pub filename: Option<String>,
+    pub mime: Option<String>,
+    pub size: Option<i64>,
+    pub extracted_content: Option<String>,
+    pub path: Option<String>,
+}
+
+/// Attachments described by a message's `attachments` JSON
+///
+/// Parsers store a list of objects whose keys vary by provider; entries
+/// without a filename, mime type, or path are not files and are skipped.
+pub fn from_json(value: &Value) -> Vec<Attachment> {
+    let Some(items) = value.as_array() else {
+        return Vec::new();
+    };
+    
+    items
+        .iter()
+        .filter_map(|item| {
+            let text = |keys: &[&str]| {
+                keys.iter()
+                    .find_map(|key| item.get(*key).and_then(|v| v.as_str()))
+                    .map(str::to_string)
+            };
+            
+            let filename = text(&["file_name", "filename", "name"]);
+            let path = text(&["path", "asset_pointer", "url", "uri"]);
+            let mime = text(&["mime_type", "mimeType", "mime", "file_type", "content_type"])
+                .and_then(|declared| normalize_mime(&declared))
+                .or_else(|| filename.as_deref().or(path.as_deref()).and_then(mime_from_name));
+            
+            if filename.is_none() && mime.is_none() && path.is_none() {
+                return None;
+            }
+            
+            Some(Attachment {
+                filename,
+                mime,
+                size: ["file_size", "size", "size_bytes", "sizeBytes"]
+                    .iter()
+                    .find_map(|key| item.get(*key).and_then(|v| v.as_i64())),
+                extracted_content: text(&["extracted_content"]),
+                path,
+            })
+        })
+        .collect()
+}
+
+/// Store the attachments of an inserted message
+pub async fn insert(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    message_id: i64,
+    attachments: &[Attachment],
+) -> Result<()> {
+    for attachment in attachments {
+        sqlx::query(
+            r#"
+            INSERT INTO message_attachments (message_id, filename, mime, size, extracted_content, path)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+        )
+        .bind(message_id)
+        .bind(&attachment.filename)
+        .bind(&attachment.mime)
+        .bind(attachment.size)
+        .bind(&attachment.extracted_content)
+        .bind(&attachment.path)
+        .execute(&mut **tx)
+        .await
+        .context("Failed to insert message attachment")?;
+    }
+    
+    Ok(())
+}
+
+/// Populate `message_attachments` from blobs imported before it existed
+///
+/// Returns the number of attachment rows created.
+pub async fn backfill(pool: &SqlitePool) -> Result<usize> {
+    let pending: Vec<(i64, String)> = sqlx::query_as(
+        r#"
+        SELECT m.id, m.attachments
+        FROM messages m
+        WHERE m.attachments IS NOT NULL
+          AND NOT EXISTS (SELECT 1 FROM message_attachments a WHERE a.message_id = m.id)
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to load attachment blobs")?;
+    
+    let mut tx = pool.begin().await?;
+    let mut migrated = 0;
+    
+    for (message_id, blob) in pending {
+        let Ok(value) = serde_json::from_str::<Value>(&blob) else { continue };
+        let attachments = from_json(&value);
+        insert(&mut tx, message_id, &attachments).await?;
+        migrated += attachments.len();
+    }
+    
+    tx.commit().await?;
+    Ok(migrated)
+}
+
+/// Attachments of every message in a conversation, in message order
+pub async fn for_conversation(pool: &SqlitePool, conversation_id: i64) -> Result<Vec<StoredAttachment>> {
+    sqlx::query_as(
+        r#"
+        SELECT a.id, a.message_id, a.filename, a.mime, a.size, a.extracted_content, a.path
+        FROM message_attachments a
+        JOIN messages m ON m.id = a.message_id
+        WHERE m.conversation_id = ?1
+        ORDER BY m.created_at, m.id, a.id
+        "#,
+    )
+    .bind(conversation_id)
+    .fetch_all(pool)
+    .await
+    .context("Failed to load conversation attachments")
+}
+
+/// A single attachment by id
+pub async fn get(pool: &SqlitePool, id: i64) -> Result<Option<StoredAttachment>> {
+    sqlx::query_as(
+        r#"
+        SELECT id, message_id, filename, mime, size, extracted_content, path
+        FROM message_attachments
+        WHERE id = ?1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .context("Failed to load attachment")
+}
+
+/// Accept `type/subtype` as is and map bare extensions like `pdf` to a mime type
+fn normalize_mime(declared: &str) -> Option<String> {
+    let declared = declared.trim().to_ascii_lowercase();
+    if declared.contains('/') {
+        return Some(declared);
+    }
+    mime_for_extension(&declared).map(str::to_string)
+}
+
+/// Guess a mime type from a file name or path
+fn mime_from_name(name: &str) -> Option<String> {
+    let (_, extension) = name.rsplit_once('.')?;
+    mime_for_extension(&extension.to_ascii_lowercase()).map(str::to_string)
+}
+
+fn mime_for_extension(extension: &str) -> Option<&'static str> {
+    Some(match extension {
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "zip" => "application/zip",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "txt" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::{conversation, message, test_pool};
+    use crate::import::process_conversation_batch;
+    use crate::search::conversations_with_attachment;
+    
+    #[test]
+    fn test_from_json_normalizes_provider_shapes() {
+        let claude = serde_json::json!([
+            { "file_name": "report.pdf", "file_type": "pdf", "file_size": 2048, "has_content": true }
+        ]);
+        assert_eq!(
+            from_json(&claude),
+            vec![Attachment {
+                filename: Some("report.pdf".to_string()),
+                mime: Some("application/pdf".to_string()),
+                size: Some(2048),
+                ..Attachment::default()
+            }]
+        );
+        
+        let chatgpt = serde_json::json!([
+            { "content_type": "image_asset_pointer", "asset_pointer": "file-service://file-abc", "size_bytes": 512 }
+        ]);
+        let parsed = from_json(&chatgpt);
+        assert_eq!(parsed[0].path.as_deref(), Some("file-service://file-abc"));
+        assert_eq!(parsed[0].mime, None);
+        
+        // Zed stores editor metadata, not files
+        assert!(from_json(&serde_json::json!({ "language": "rust" })).is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_attachments_queryable_by_mime() {
+        let (_dir, pool) = test_pool().await;
+        
+        let mut with_pdf = message("user", "see attached");
+        with_pdf.attachments = Some(serde_json::json!([
+            { "file_name": "invoice.pdf", "file_type": "application/pdf", "file_size": 1024 }
+        ]));
+        let mut with_image = message("user", "look at this");
+        with_image.attachments = Some(serde_json::json!([{ "file_name": "cat.png" }]));
+        
+        process_conversation_batch(
+            &pool,
+            vec![
+                (conversation("claude", "pdf", "Invoice"), vec![with_pdf]),
+                (conversation("claude", "img", "Cat"), vec![with_image]),
+                (conversation("claude", "plain", "Plain"), vec![message("user", "no files")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let titles = |conversations: Vec<crate::models::Conversation>| {
+            conversations.into_iter().filter_map(|c| c.title).collect::<Vec<_>>()
+        };
+        
+        assert_eq!(titles(conversations_with_attachment(&pool, Some("application/pdf"), 10).await.unwrap()), vec!["Invoice"]);
+        assert_eq!(titles(conversations_with_attachment(&pool, Some("image"), 10).await.unwrap()), vec!["Cat"]);
+        assert_eq!(conversations_with_attachment(&pool, None, 10).await.unwrap().len(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_backfill_migrates_existing_blobs() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(&pool, vec![(conversation("claude", "old", "Old"), vec![message("user", "hi")])])
+            .await
+            .unwrap();
+        
+        // A row written before message_attachments existed
+        sqlx::query("UPDATE messages SET attachments = ?1")
+            .bind(r#"[{"file_name": "notes.txt"}]"#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        
+        assert_eq!(backfill(&pool).await.unwrap(), 1);
+        assert_eq!(backfill(&pool).await.unwrap(), 0);
+        
+        let mime: String = sqlx::query_scalar("SELECT mime FROM message_attachments")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(mime, "text/plain");
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::{Context, Result};
+use futures::TryStreamExt;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use tracing::{debug, info};
+
+use super::terms;
+
+/// Duplicate conversations found, and removed unless it was a dry run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DedupeReport {
+    /// Conversations with at least one duplicate
+    pub groups: usize,
+    /// Duplicates removed, or that would be removed in a dry run
+    pub removed: usize,
+    /// Messages belonging to the removed duplicates
+    pub removed_messages: usize,
+    /// Ids of the removed duplicates
+    pub removed_ids: Vec<i64>,
+}
+
+/// Remove conversations whose messages exactly repeat an earlier conversation
+///
+/// Two conversations are duplicates when they have the same provider and
+/// the same messages, by role and content, in the same order. The earliest
+/// created copy is kept and the rest are deleted with their messages,
+/// attachments and tags; the delete triggers drop their FTS rows. External
+/// ids are ignored, since the duplicates this cleans up were imported
+/// before they were stable. Everything runs in one transaction, which a
+/// dry run rolls back.
+pub async fn dedupe(pool: &SqlitePool, dry_run: bool) -> Result<DedupeReport> {
+    let mut tx = pool.begin().await?;
+    
+    let mut fingerprints: HashMap<i64, Sha256> = HashMap::new();
+    {
+        let mut rows = sqlx::query_as::<_, (i64, String, String)>(
+            "SELECT conversation_id, role, content FROM messages ORDER BY conversation_id, created_at, id",
+        )
+        .fetch(&mut *tx);
+        
+        while let Some((conversation_id, role, content)) = rows
+            .try_next()
+            .await
+            .context("Failed to read messages for fingerprints")?
+        {
+            let mut message = Sha256::new();
+            message.update(role.as_bytes());
+            message.update([0u8]);
+            message.update(content.as_bytes());
+            
+            fingerprints
+                .entry(conversation_id)
+                .or_default()
+                .update(message.finalize());
+        }
+    }
+    
+    let fingerprints: HashMap<i64, Vec<u8>> = fingerprints
+        .into_iter()
+        .map(|(id, hasher)| (id, hasher.finalize().to_vec()))
+        .collect();
+    
+    let conversations: Vec<(i64, String)> =
+        sqlx::query_as("SELECT id, provider FROM conversations ORDER BY created_at, id")
+            .fetch_all(&mut *tx)
+            .await
+            .context("Failed to list conversations")?;
+    
+    let mut kept: HashMap<(String, &[u8]), i64> = HashMap::new();
+    let mut groups = HashSet::new();
+    let mut report = DedupeReport::default();
+    
+    for (id, provider) in conversations {
+        // Conversations without messages have nothing to compare
+        let Some(fingerprint) = fingerprints.get(&id) else {
+            continue;
+        };
+        
+        match kept.entry((provider, fingerprint.as_slice())) {
+            Entry::Occupied(original) => {
+                debug!("Conversation {} duplicates {}", id, original.get());
+                groups.insert(*original.get());
+                report.removed_ids.push(id);
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(id);
+            }
+        }
+    }
+    
+    report.groups = groups.len();
+    report.removed = report.removed_ids.len();
+    
+    let mut term_counts = HashMap::new();
+    for id in &report.removed_ids {
+        let contents: Vec<String> = sqlx::query_scalar("SELECT content FROM messages WHERE conversation_id = ?1")
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+        report.removed_messages += contents.len();
+        
+        if dry_run {
+            continue;
+        }
+        
+        for content in &contents {
+            terms::count(&mut term_counts, content, -1);
+        }
+        
+        sqlx::query("DELETE FROM messages WHERE conversation_id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete duplicate messages")?;
+        
+        sqlx::query("DELETE FROM conversations WHERE id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete duplicate conversation")?;
+    }
+    
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        terms::apply(&mut tx, &term_counts).await?;
+        tx.commit().await?;
+    }
+    
+    info!(
+        "{} {} duplicate conversations ({} messages) of {} originals",
+        if dry_run { "Found" } else { "Removed" },
+        report.removed,
+        report.removed_messages,
+        report.groups
+    );
+    
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::{conversation, message, test_pool};
+    use crate::import::process_conversation_batch;
+    
+    async fn count(pool: &SqlitePool, sql: &str) -> i64 {
+        sqlx::query_scalar(sql).fetch_one(pool).await.unwrap()
+    }
+    
+    #[tokio::test]
+    async fn test_dedupe_keeps_earliest_copy() {
+        let (_dir, pool) = test_pool().await;
+        let transcript = || vec![message("user", "sourdough hydration"), message("assistant", "about 75 percent")];
+        
+        process_conversation_batch(
+            &pool,
+            vec![
+                (conversation("claude", "old-id", "Bread"), transcript()),
+                (conversation("claude", "new-id", "Bread again"), transcript()),
+                // Same messages from another provider, and a different conversation
+                (conversation("chatgpt", "gpt-id", "Bread"), transcript()),
+                (conversation("claude", "other", "Pasta"), vec![message("user", "sourdough pasta")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let preview = dedupe(&pool, true).await.unwrap();
+        assert_eq!(preview.removed_ids, vec![2]);
+        assert_eq!(preview.removed_messages, 2);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM conversations").await, 4);
+        
+        let report = dedupe(&pool, false).await.unwrap();
+        assert_eq!(report, preview);
+        
+        let titles: Vec<String> = sqlx::query_scalar("SELECT title FROM conversations ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(titles, vec!["Bread", "Bread", "Pasta"]);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM messages").await, 5);
+        assert_eq!(
+            count(&pool, "SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'hydration'").await,
+            2
+        );
+        assert_eq!(
+            count(&pool, "SELECT count FROM term_frequencies WHERE term = 'sourdough'").await,
+            3
+        );
+        
+        assert_eq!(dedupe(&pool, false).await.unwrap().removed, 0);
+    }
+}
@@ -0,0 +1,458 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tracing::info;
+
+use super::schema;
+
+/// `index_settings` key holding the tokenizer the FTS index was built with
+const TOKENIZER_KEY: &str = "fts_tokenizer";
+
+/// Messages copied into the index per statement during a rebuild
+const REINDEX_CHUNK_SIZE: i64 = 10_000;
+
+/// Affected conversation ids listed in an index status report
+const INDEX_STATUS_SAMPLE_SIZE: i64 = 20;
+
+/// Outcome of a reindex run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReindexReport {
+    pub previous_tokenizer: Option<String>,
+    pub tokenizer: String,
+    pub rebuilt: bool,
+    pub messages: i64,
+}
+
+/// Rows missing from the FTS index, e.g. after an interrupted import
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IndexStatus {
+    pub messages: i64,
+    pub unindexed_messages: i64,
+    pub unindexed_titles: i64,
+    /// A sample of conversations with unindexed messages or titles
+    pub sample_conversation_ids: Vec<i64>,
+}
+
+/// Reject tokenizer specs that could not be safely placed in the FTS DDL
+pub fn validate_tokenizer(tokenizer: &str) -> Result<()> {
+    let valid = !tokenizer.trim().is_empty()
+        && tokenizer.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ' ');
+    
+    if !valid {
+        return Err(anyhow::anyhow!("Invalid FTS tokenizer: {:?}", tokenizer));
+    }
+    
+    Ok(())
+}
+
+/// A tokenizer spec in a canonical form, so equivalent specs compare equal
+///
+/// `porter` on its own wraps the default `unicode61`, which is how indexes
+/// built before `porter unicode61` became the default record it.
+fn normalize_tokenizer(tokenizer: &str) -> String {
+    let words: Vec<&str> = tokenizer.split_whitespace().collect();
+    match words.as_slice() {
+        ["porter"] => "porter unicode61".to_string(),
+        _ => words.join(" "),
+    }
+}
+
+/// Tokenizer the FTS index is currently built with
+pub async fn active_tokenizer(pool: &SqlitePool) -> Result<Option<String>> {
+    let stored: Option<(String,)> = sqlx::query_as("SELECT value FROM index_settings WHERE key = ?1")
+        .bind(TOKENIZER_KEY)
+        .fetch_optional(pool)
+        .await?;
+    
+    if let Some((tokenizer,)) = stored {
+        return Ok(Some(tokenizer));
+    }
+    
+    // Databases created before index_settings existed: read it from the DDL
+    let sql: Option<(String,)> = sqlx::query_as(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'messages_fts'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(sql.and_then(|(sql,)| tokenizer_from_sql(&sql)))
+}
+
+/// Create the FTS index if it does not exist yet
+pub async fn ensure_fts(pool: &SqlitePool, tokenizer: &str) -> Result<()> {
+    let exists: Option<(i64,)> = sqlx::query_as(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'messages_fts'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    
+    if exists.is_none() {
+        build_fts(pool, tokenizer).await?;
+        return Ok(());
+    }
+    
+    // Record the tokenizer of indexes built before it was tracked
+    let active = active_tokenizer(pool)
+        .await?
+        .unwrap_or_else(|| "unicode61".to_string());
+    record_tokenizer(pool, &active).await?;
+    
+    // Indexes built before titles were searchable
+    let titles_exist: Option<(i64,)> = sqlx::query_as(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'conversations_fts'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    
+    if titles_exist.is_none() {
+        validate_tokenizer(&active)?;
+        
+        let mut tx = pool.begin().await?;
+        sqlx::query(&schema::create_fts(&active))
+            .execute(&mut *tx)
+            .await
+            .context("Failed to create title FTS table")?;
+        index_titles(&mut tx).await?;
+        tx.commit().await?;
+    }
+    
+    Ok(())
+}
+
+/// Rebuild the FTS index if it was built with a different tokenizer
+pub async fn reindex(pool: &SqlitePool, tokenizer: &str, force: bool) -> Result<ReindexReport> {
+    validate_tokenizer(tokenizer)?;
+    
+    let previous_tokenizer = active_tokenizer(pool).await?;
+    let unchanged = previous_tokenizer
+        .as_deref()
+        .is_some_and(|previous| normalize_tokenizer(previous) == normalize_tokenizer(tokenizer));
+    
+    if unchanged && !force {
+        info!("Search index already uses tokenizer '{}', nothing to do", tokenizer);
+        return Ok(ReindexReport {
+            previous_tokenizer,
+            tokenizer: tokenizer.to_string(),
+            rebuilt: false,
+            messages: 0,
+        });
+    }
+    
+    info!(
+        "Rebuilding search index: tokenizer {:?} -> '{}'",
+        previous_tokenizer, tokenizer
+    );
+    let messages = build_fts(pool, tokenizer).await?;
+    
+    Ok(ReindexReport {
+        previous_tokenizer,
+        tokenizer: tokenizer.to_string(),
+        rebuilt: true,
+        messages,
+    })
+}
+
+/// Count messages and titles without FTS rows; `reindex --force` repairs them
+pub async fn index_status(pool: &SqlitePool) -> Result<IndexStatus> {
+    let (messages, unindexed_messages): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            COUNT(*),
+            COUNT(*) FILTER (WHERE NOT EXISTS (SELECT 1 FROM messages_fts f WHERE f.rowid = m.id))
+        FROM messages m
+        "#,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to count unindexed messages")?;
+    
+    let unindexed_titles: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM conversations c WHERE NOT EXISTS (SELECT 1 FROM conversations_fts f WHERE f.rowid = c.id)",
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to count unindexed titles")?;
+    
+    let sample_conversation_ids = sqlx::query_scalar(
+        r#"
+        SELECT m.conversation_id FROM messages m
+        WHERE NOT EXISTS (SELECT 1 FROM messages_fts f WHERE f.rowid = m.id)
+        UNION
+        SELECT c.id FROM conversations c
+        WHERE NOT EXISTS (SELECT 1 FROM conversations_fts f WHERE f.rowid = c.id)
+        ORDER BY 1
+        LIMIT ?1
+        "#,
+    )
+    .bind(INDEX_STATUS_SAMPLE_SIZE)
+    .fetch_all(pool)
+    .await
+    .context("Failed to sample unindexed conversations")?;
+    
+    Ok(IndexStatus {
+        messages,
+        unindexed_messages,
+        unindexed_titles,
+        sample_conversation_ids,
+    })
+}
+
+/// Drop and recreate the FTS tables, then index all messages in chunks
+async fn build_fts(pool: &SqlitePool, tokenizer: &str) -> Result<i64> {
+    validate_tokenizer(tokenizer)?;
+    
+    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+        .fetch_one(pool)
+        .await?;
+    
+    let mut tx = pool.begin().await?;
+    
+    sqlx::query("DROP TABLE IF EXISTS messages_fts; DROP TABLE IF EXISTS conversations_fts")
+        .execute(&mut *tx)
+        .await?;
+    
+    sqlx::query(&schema::create_fts(tokenizer))
+        .execute(&mut *tx)
+        .await
+        .context("Failed to create FTS table")?;
+    
+    let mut indexed = 0;
+    let mut last_id = 0;
+    
+    loop {
+        let chunk_last_id: Option<i64> = sqlx::query_scalar(
+            "SELECT MAX(id) FROM (SELECT id FROM messages WHERE id > ?1 ORDER BY id LIMIT ?2)",
+        )
+        .bind(last_id)
+        .bind(REINDEX_CHUNK_SIZE)
+        .fetch_one(&mut *tx)
+        .await?;
+        
+        let Some(chunk_last_id) = chunk_last_id else {
+            break;
+        };
+        
+        let result = sqlx::query(
+            r#"
+            INSERT INTO messages_fts (rowid, content, conversation_id, role)
+            SELECT id, content, conversation_id, role FROM messages
+            WHERE id > ?1 AND id <= ?2
+            "#,
+        )
+        .bind(last_id)
+        .bind(chunk_last_id)
+        .execute(&mut *tx)
+        .await?;
+        
+        indexed += result.rows_affected() as i64;
+        last_id = chunk_last_id;
+        info!("Indexed {}/{} messages", indexed, total);
+    }
+    
+    index_titles(&mut tx).await?;
+    
+    sqlx::query(
+        r#"
+        INSERT INTO index_settings (key, value) VALUES (?1, ?2)
+        ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP
+        "#,
+    )
+    .bind(TOKENIZER_KEY)
+    .bind(tokenizer)
+    .execute(&mut *tx)
+    .await?;
+    
+    tx.commit().await?;
+    
+    Ok(indexed)
+}
+
+/// Copy every conversation title into `conversations_fts`
+async fn index_titles(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO conversations_fts (rowid, title) SELECT id, COALESCE(title, '') FROM conversations",
+    )
+    .execute(&mut **tx)
+    .await
+    .context("Failed to index conversation titles")?;
+    
+    Ok(())
+}
+
+async fn record_tokenizer(pool: &SqlitePool, tokenizer: &str) -> Result<()> {
+    sqlx::query("INSERT OR IGNORE INTO index_settings (key, value) VALUES (?1, ?2)")
+        .bind(TOKENIZER_KEY)
+        .bind(tokenizer)
+        .execute(pool)
+        .await?;
+    
+    Ok(())
+}
+
+/// Extract the `tokenize = '...'` option from an FTS5 CREATE statement
+fn tokenizer_from_sql(sql: &str) -> Option<String> {
+    sql.match_indices("tokenize").find_map(|(start, key)| {
+        // Skip words like "tokenizer" in comments
+        let value = sql[start + key.len()..].trim_start().strip_prefix('=')?.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+        let value = &value[1..];
+        
+        Some(value[..value.find(quote)?].to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::{conversation, message, test_pool};
+    use crate::import::process_conversation_batch;
+    
+    async fn match_count(pool: &SqlitePool, query: &str) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH ?1")
+            .bind(query)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+    
+    #[tokio::test]
+    async fn test_reindex_rebuilds_when_tokenizer_changes() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![(
+                conversation("claude", "conv-1", "Runners"),
+                vec![message("user", "She was running quickly")],
+            )],
+        )
+        .await
+        .unwrap();
+        
+        // Porter stems "running" to "run"
+        assert_eq!(active_tokenizer(&pool).await.unwrap().as_deref(), Some("porter unicode61"));
+        assert_eq!(match_count(&pool, "run").await, 1);
+        
+        let unchanged = reindex(&pool, "porter unicode61", false).await.unwrap();
+        assert!(!unchanged.rebuilt);
+        
+        let report = reindex(&pool, "unicode61", false).await.unwrap();
+        assert!(report.rebuilt);
+        assert_eq!(report.previous_tokenizer.as_deref(), Some("porter unicode61"));
+        assert_eq!(report.messages, 1);
+        assert_eq!(active_tokenizer(&pool).await.unwrap().as_deref(), Some("unicode61"));
+        
+        // Without stemming only the exact word matches
+        assert_eq!(match_count(&pool, "run").await, 0);
+        assert_eq!(match_count(&pool, "running").await, 1);
+        
+        // Triggers keep indexing new messages after the rebuild
+        process_conversation_batch(
+            &pool,
+            vec![(
+                conversation("claude", "conv-2", "More"),
+                vec![message("user", "running again")],
+            )],
+        )
+        .await
+        .unwrap();
+        assert_eq!(match_count(&pool, "running").await, 2);
+    }
+    
+    #[tokio::test]
+    async fn test_reindex_treats_legacy_porter_as_porter_unicode61() {
+        let (_dir, pool) = test_pool().await;
+        sqlx::query("UPDATE index_settings SET value = 'porter' WHERE key = ?1")
+            .bind(TOKENIZER_KEY)
+            .execute(&pool)
+            .await
+            .unwrap();
+        
+        let report = reindex(&pool, "porter unicode61", false).await.unwrap();
+        assert!(!report.rebuilt);
+        assert!(!reindex(&pool, "porter", false).await.unwrap().rebuilt);
+        
+        assert_eq!(normalize_tokenizer("  porter   unicode61 "), "porter unicode61");
+        assert_eq!(normalize_tokenizer("porter ascii"), "porter ascii");
+        assert_eq!(normalize_tokenizer("trigram"), "trigram");
+    }
+    
+    #[tokio::test]
+    async fn test_index_status_counts_unindexed_messages() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![
+                (conversation("claude", "conv-1", "Indexed"), vec![message("user", "all good")]),
+                (
+                    conversation("claude", "conv-2", "Partial"),
+                    vec![message("user", "indexed"), message("assistant", "lost")],
+                ),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let clean = index_status(&pool).await.unwrap();
+        assert_eq!(clean.unindexed_messages, 0);
+        assert!(clean.sample_conversation_ids.is_empty());
+        
+        // Simulate a write that never reached the index
+        sqlx::query("DELETE FROM messages_fts WHERE rowid = (SELECT id FROM messages WHERE content = 'lost')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        
+        let status = index_status(&pool).await.unwrap();
+        assert_eq!(status.messages, 3);
+        assert_eq!(status.unindexed_messages, 1);
+        assert_eq!(status.unindexed_titles, 0);
+        assert_eq!(status.sample_conversation_ids, vec![2]);
+        
+        reindex(&pool, "porter unicode61", true).await.unwrap();
+        assert_eq!(index_status(&pool).await.unwrap().unindexed_messages, 0);
+    }
+    
+    #[tokio::test]
+    async fn test_reindex_rejects_unsafe_tokenizer() {
+        let (_dir, pool) = test_pool().await;
+        
+        assert!(reindex(&pool, "porter'); DROP TABLE messages; --", false).await.is_err());
+        assert_eq!(active_tokenizer(&pool).await.unwrap().as_deref(), Some("porter unicode61"));
+    }
+    
+    #[tokio::test]
+    async fn test_trigram_tokenizer_matches_substrings() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = crate::db::create_pool(&dir.path().join("trigram.db")).await.unwrap();
+        crate::db::run_migrations(&pool, "trigram").await.unwrap();
+        assert_eq!(active_tokenizer(&pool).await.unwrap().as_deref(), Some("trigram"));
+        
+        process_conversation_batch(
+            &pool,
+            vec![(
+                conversation("claude", "conv-1", "Study"),
+                vec![message("user", "I am learning Rust")],
+            )],
+        )
+        .await
+        .unwrap();
+        
+        assert_eq!(match_count(&pool, "learn").await, 1);
+        assert_eq!(match_count(&pool, "earni").await, 1);
+        
+        // Word tokenizers only match whole words or their stems
+        reindex(&pool, schema::DEFAULT_FTS_TOKENIZER, false).await.unwrap();
+        assert_eq!(match_count(&pool, "learn").await, 1);
+        assert_eq!(match_count(&pool, "earni").await, 0);
+    }
+    
+    #[test]
+    fn test_tokenizer_from_sql() {
+        let sql = "CREATE VIRTUAL TABLE messages_fts USING fts5(content, tokenize = 'porter unicode61')";
+        assert_eq!(tokenizer_from_sql(sql).as_deref(), Some("porter unicode61"));
+        
+        let commented = "CREATE VIRTUAL TABLE messages_fts USING fts5(\n    -- Use Porter tokenizer\n    tokenize = 'porter'\n)";
+        assert_eq!(tokenizer_from_sql(commented).as_deref(), Some("porter"));
+        assert_eq!(tokenizer_from_sql("CREATE VIRTUAL TABLE t USING fts5(content)"), None);
+    }
+}
@@ -0,0 +1,232 @@
+use anyhow::{bail, Context, Result};
+use fs2::FileExt;
+use sqlx::SqlitePool;
+use std::fs::{File, OpenOptions};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// Another writer held the lock for the whole wait
+#[derive(Debug)]
+pub struct LockTimeout {
+    pub path: PathBuf,
+    pub waited: Duration,
+}
+
+impl fmt::Display for LockTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Timed out after {:?} waiting for write lock {:?}", self.waited, self.path)
+    }
+}
+
+impl std::error::Error for LockTimeout {}
+
+/// Exclusive advisory lock serializing writers to a database file
+///
+/// CLI commands that write and every server write path take it, so
+/// concurrent imports queue up instead of racing for SQLite's write lock.
+/// Released on drop.
+#[derive(Debug)]
+pub struct WriteLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl WriteLock {
+    /// Wait up to `timeout` for the write lock on `database`
+    pub async fn acquire(database: &Path, timeout: Duration) -> Result<Self> {
+        let path = sidecar_path(database, "lock");
+        if let Some(parent) = database.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file {:?}", path))?;
+        
+        let start = Instant::now();
+        let mut waiting = false;
+        
+        loop {
+            match file.try_lock_exclusive() {
+                Ok(()) => {
+                    debug!("Acquired write lock {:?}", path);
+                    return Ok(Self { file, path });
+                }
+                Err(e) if e.raw_os_error() == fs2::lock_contended_error().raw_os_error() => {
+                    if start.elapsed() >= timeout {
+                        bail!(LockTimeout { path, waited: timeout });
+                    }
+                    if !waiting {
+                        info!("Another writer holds {:?}; waiting", path);
+                        waiting = true;
+                    }
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to lock {:?}", path));
+                }
+            }
+        }
+    }
+    
+    /// Wait up to `timeout` for the write lock on the database `pool` opens
+    pub async fn for_pool(pool: &SqlitePool, timeout: Duration) -> Result<Self> {
+        let database = pool.connect_options().as_ref().clone().get_filename();
+        Self::acquire(&database, timeout).await
+    }
+}
+
+impl Drop for WriteLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+        debug!("Released write lock {:?}", self.path);
+    }
+}
+
+/// Marker advertising that a server is running against a database
+///
+/// Holds the address the server is bound to so CLI commands can detect it;
+/// removed on drop.
+#[derive(Debug)]
+pub struct ServerMarker {
+    path: PathBuf,
+}
+
+impl ServerMarker {
+    pub fn create(database: &Path, addr: SocketAddr) -> Result<Self> {
+        let path = sidecar_path(database, "server");
+        std::fs::write(&path, addr.to_string())
+            .with_context(|| format!("Failed to write server marker {:?}", path))?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for ServerMarker {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Address of a live server using `database`, if any
+///
+/// A marker left behind by a crashed server is ignored when nothing is
+/// listening on its address. Markers holding only a port, from older
+/// servers, are probed on 127.0.0.1.
+pub fn running_server(database: &Path) -> Option<SocketAddr> {
+    let marker = std::fs::read_to_string(sidecar_path(database, "server")).ok()?;
+    let marker = marker.trim();
+    let addr = match marker.parse::<SocketAddr>() {
+        Ok(addr) => addr,
+        Err(_) => SocketAddr::from(([127, 0, 0, 1], marker.parse::<u16>().ok()?)),
+    };
+    
+    // A server on every interface answers on loopback
+    let probe = match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => SocketAddr::new(Ipv4Addr::LOCALHOST.into(), addr.port()),
+        IpAddr::V6(ip) if ip.is_unspecified() => SocketAddr::new(Ipv6Addr::LOCALHOST.into(), addr.port()),
+        _ => addr,
+    };
+    TcpStream::connect_timeout(&probe, Duration::from_millis(200)).ok()?;
+    
+    Some(addr)
+}
+
+/// `<database>.<suffix>` next to the database file
+fn sidecar_path(database: &Path, suffix: &str) -> PathBuf {
+    let mut name = database.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::{conversation, message};
+    use crate::db::{create_pool, run_migrations, schema};
+    use crate::import::process_conversation_batch;
+    
+    #[tokio::test]
+    async fn test_second_writer_waits_for_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("archive.db");
+        
+        let held = WriteLock::acquire(&database, Duration::from_secs(1)).await.unwrap();
+        assert!(WriteLock::acquire(&database, Duration::from_millis(200)).await.is_err());
+        
+        drop(held);
+        WriteLock::acquire(&database, Duration::from_millis(200)).await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_concurrent_import_and_server_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("archive.db");
+        
+        let pool = create_pool(&database).await.unwrap();
+        run_migrations(&pool, schema::DEFAULT_FTS_TOKENIZER).await.unwrap();
+        
+        // Two CLI imports against the same file
+        let writers = ["first", "second"].map(|writer| {
+            let pool = pool.clone();
+            let database = database.clone();
+            tokio::spawn(async move {
+                let _lock = WriteLock::acquire(&database, Duration::from_secs(10)).await?;
+                let batch = (0..20)
+                    .map(|i| {
+                        (
+                            conversation(writer, &format!("{}-{}", writer, i), "Concurrent"),
+                            vec![message("user", "hello"), message("assistant", "hi")],
+                        )
+                    })
+                    .collect();
+                process_conversation_batch(&pool, batch).await
+            })
+        });
+        
+        for writer in writers {
+            writer.await.unwrap().unwrap();
+        }
+        
+        let (conversations, messages): (i64, i64) = sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM conversations), (SELECT COUNT(*) FROM messages)",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(conversations, 40);
+        assert_eq!(messages, 80);
+    }
+    
+    #[tokio::test]
+    async fn test_running_server_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("archive.db");
+        assert_eq!(running_server(&database), None);
+        
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        
+        let marker = ServerMarker::create(&database, addr).unwrap();
+        assert_eq!(running_server(&database), Some(addr));
+        
+        // Bound to every interface, or written by a server that kept only the port
+        let everywhere = SocketAddr::from(([0, 0, 0, 0], addr.port()));
+        ServerMarker::create(&database, everywhere).unwrap();
+        assert_eq!(running_server(&database), Some(everywhere));
+        std::fs::write(sidecar_path(&database, "server"), addr.port().to_string()).unwrap();
+        assert_eq!(running_server(&database), Some(addr));
+        
+        // Stale marker from a server that is gone
+        drop(listener);
+        assert_eq!(running_server(&database), None);
+        
+        drop(marker);
+        assert!(!sidecar_path(&database, "server").exists());
+    }
+}
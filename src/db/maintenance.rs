@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Database size before and after a maintenance run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaintenanceReport {
+    /// Bytes on disk, counting the write-ahead log
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+impl MaintenanceReport {
+    /// Bytes returned to the filesystem
+    pub fn reclaimed(&self) -> u64 {
+        self.size_before.saturating_sub(self.size_after)
+    }
+}
+
+/// Compact the database and refresh query planner statistics
+///
+/// Checkpoints and truncates the WAL, recounts term frequencies, merges FTS
+/// index segments, rebuilds the file with `VACUUM`, and runs `ANALYZE`.
+/// Everything runs on one connection taken straight from the pool, so no
+/// transaction is open when `VACUUM` starts; callers must not hold a
+/// transaction on the pool.
+pub async fn maintain(pool: &SqlitePool) -> Result<MaintenanceReport> {
+    let mut conn = pool.acquire().await?;
+    
+    let file: String = sqlx::query_scalar("SELECT file FROM pragma_database_list WHERE name = 'main'")
+        .fetch_one(&mut *conn)
+        .await?;
+    let path = PathBuf::from(file);
+    let size_before = disk_size(&path);
+    
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(&mut *conn)
+        .await
+        .context("Failed to checkpoint the WAL")?;
+    
+    // Recount suggestion terms, which drift after merges
+    super::terms::rebuild(&mut conn).await?;
+    
+    for table in ["messages_fts", "conversations_fts"] {
+        sqlx::query(&format!("INSERT INTO {table}({table}) VALUES('optimize')"))
+            .execute(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to optimize {}", table))?;
+    }
+    
+    sqlx::query("VACUUM")
+        .execute(&mut *conn)
+        .await
+        .context("Failed to vacuum the database (is a transaction open?)")?;
+    
+    sqlx::query("ANALYZE")
+        .execute(&mut *conn)
+        .await
+        .context("Failed to analyze the database")?;
+    
+    // VACUUM writes through the WAL; fold it back into the main file
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(&mut *conn)
+        .await
+        .context("Failed to checkpoint the WAL")?;
+    
+    let report = MaintenanceReport {
+        size_before,
+        size_after: disk_size(&path),
+    };
+    info!(
+        "Maintenance complete: {} -> {} bytes",
+        report.size_before, report.size_after
+    );
+    
+    Ok(report)
+}
+
+/// Size of the database file plus its WAL, if any
+fn disk_size(path: &std::path::Path) -> u64 {
+    let len = |path: &std::path::Path| std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    
+    let mut wal = path.as_os_str().to_owned();
+    wal.push("-wal");
+    
+    len(path) + len(std::path::Path::new(&wal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::{conversation, message, test_pool};
+    use crate::import::process_conversation_batch;
+    
+    #[tokio::test]
+    async fn test_maintain_does_not_grow_the_database() {
+        let (_dir, pool) = test_pool().await;
+        
+        let filler = "lorem ipsum dolor sit amet ".repeat(200);
+        let batch = (0..500)
+            .map(|i| {
+                (
+                    conversation("claude", &format!("bulk-{}", i), "Bulk"),
+                    vec![message("user", &filler), message("assistant", &filler)],
+                )
+            })
+            .collect();
+        process_conversation_batch(&pool, batch).await.unwrap();
+        
+        for table in ["messages", "conversations"] {
+            sqlx::query(&format!("DELETE FROM {}", table)).execute(&pool).await.unwrap();
+        }
+        
+        let report = maintain(&pool).await.unwrap();
+        assert!(report.size_before > 0);
+        assert!(report.size_after <= report.size_before);
+        assert!(report.reclaimed() > 0);
+        
+        // The database is still usable afterwards
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages").fetch_one(&pool).await.unwrap();
+        assert_eq!(count, 0);
+    }
+}
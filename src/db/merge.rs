@@ -0,0 +1,508 @@
+use anyhow::{bail, Context, Result};
+use sqlx::{Connection, SqliteConnection, SqlitePool};
+use std::collections::HashSet;
+use std::path::Path;
+use tracing::{debug, info};
+
+/// What happened to each conversation in the source database
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Conversations the target did not have
+    pub added: usize,
+    /// Conversations the target had, replaced by a newer source copy
+    pub updated: usize,
+    /// Conversations the target already had at the same age or newer
+    pub skipped: usize,
+}
+
+/// Copy conversations from the archive at `source` into the pool's database
+///
+/// Conversations are matched on `(provider, external_id)`, or on provider,
+/// `created_at` and title when neither copy has an external id: new ones are
+/// added, ones whose source copy has a later `updated_at` replace the
+/// target's copy, and the rest are skipped. Messages, attachments, revisions
+/// and tags come along with fresh ids, and the FTS triggers index the merged
+/// rows.
+/// The whole merge is one transaction, so a failure leaves the target
+/// untouched. The source is attached read-only and never migrated; columns
+/// and tables an older archive lacks are copied as their defaults.
+pub async fn merge(pool: &SqlitePool, source: &Path) -> Result<MergeReport> {
+    // Checked first, since attaching would create an empty database
+    if !source.exists() {
+        bail!("Source database {:?} does not exist", source);
+    }
+    
+    let mut conn = pool.acquire().await?;
+    
+    // ATTACH is not allowed inside a transaction, so it brackets one
+    sqlx::query("ATTACH DATABASE ?1 AS source")
+        .bind(read_only_uri(source))
+        .execute(&mut *conn)
+        .await
+        .with_context(|| format!("Failed to attach {:?}", source))?;
+    
+    let merged = merge_attached(&mut conn).await;
+    
+    sqlx::query("DETACH DATABASE source")
+        .execute(&mut *conn)
+        .await
+        .context("Failed to detach the source database")?;
+    
+    let report = merged?;
+    
+    // Merged messages bypass the import path that keeps these counted, and
+    // archives from before message_attachments only carry the JSON blobs
+    if report.added + report.updated > 0 {
+        super::terms::rebuild(&mut conn).await?;
+        drop(conn);
+        super::attachments::backfill(pool).await?;
+    }
+    
+    info!(
+        "Merged {:?}: {} added, {} updated, {} skipped",
+        source, report.added, report.updated, report.skipped
+    );
+    
+    Ok(report)
+}
+
+/// `file:` URI opening `path` read-only, for `ATTACH`
+fn read_only_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('%', "%25").replace('?', "%3f").replace('#', "%23");
+    format!("file:{}?mode=ro", path)
+}
+
+/// Tables and conversation columns of the attached source, which may be
+/// an archive from before some of them were added
+struct SourceSchema {
+    tables: HashSet<String>,
+    conversation_columns: HashSet<String>,
+}
+
+impl SourceSchema {
+    async fn read(conn: &mut SqliteConnection) -> Result<Self> {
+        let tables: Vec<String> = sqlx::query_scalar("SELECT name FROM source.sqlite_master WHERE type = 'table'")
+            .fetch_all(&mut *conn)
+            .await?;
+        let conversation_columns: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM pragma_table_info('conversations', 'source')")
+                .fetch_all(&mut *conn)
+                .await?;
+        
+        Ok(Self {
+            tables: tables.into_iter().collect(),
+            conversation_columns: conversation_columns.into_iter().collect(),
+        })
+    }
+    
+    fn has_table(&self, table: &str) -> bool {
+        self.tables.contains(table)
+    }
+    
+    /// A source conversation column, or `default` under its name when the
+    /// source predates it
+    fn conversation_column(&self, column: &str, default: &str) -> String {
+        if self.conversation_columns.contains(column) {
+            column.to_string()
+        } else {
+            format!("{} AS {}", default, column)
+        }
+    }
+}
+
+/// Merge from the database attached as `source` inside one transaction
+async fn merge_attached(conn: &mut SqliteConnection) -> Result<MergeReport> {
+    let schema = SourceSchema::read(conn).await?;
+    if !schema.has_table("conversations") {
+        bail!("Source is not an archive database (no conversations table)");
+    }
+    
+    let mut report = MergeReport::default();
+    let mut tx = conn.begin().await?;
+    
+    // Each source conversation alongside its match in the target, if any,
+    // and whether the source copy is newer
+    let pairs: Vec<(i64, Option<i64>, bool)> = sqlx::query_as(
+        r#"
+        WITH matches AS (
+            SELECT s.id AS source_id, s.updated_at,
+                   (SELECT t.id FROM main.conversations t
+                    WHERE t.provider = s.provider
+                      AND (t.external_id = s.external_id
+                           OR (s.external_id IS NULL AND t.external_id IS NULL
+                               AND t.created_at = s.created_at AND t.title IS s.title))
+                    ORDER BY t.id
+                    LIMIT 1) AS target_id
+            FROM source.conversations s
+        )
+        SELECT m.source_id, m.target_id,
+               COALESCE(julianday(m.updated_at) > julianday(t.updated_at), 0)
+        FROM matches m
+        LEFT JOIN main.conversations t ON t.id = m.target_id
+        ORDER BY m.source_id
+        "#,
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .context("Failed to read source conversations")?;
+    
+    for (source_id, target_id, source_is_newer) in pairs {
+        let target_id = match target_id {
+            None => {
+                let id = insert_conversation(&mut tx, &schema, source_id).await?;
+                report.added += 1;
+                id
+            }
+            Some(target_id) if source_is_newer => {
+                replace_conversation(&mut tx, &schema, source_id, target_id).await?;
+                report.updated += 1;
+                target_id
+            }
+            Some(_) => {
+                report.skipped += 1;
+                continue;
+            }
+        };
+        
+        copy_messages(&mut tx, &schema, source_id, target_id).await?;
+        
+        if schema.has_table("conversation_tags") {
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO main.conversation_tags (conversation_id, tag, created_at)
+                SELECT ?1, tag, created_at FROM source.conversation_tags WHERE conversation_id = ?2
+                "#,
+            )
+            .bind(target_id)
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to copy tags")?;
+        }
+    }
+    
+    tx.commit().await?;
+    Ok(report)
+}
+
+/// Insert a copy of a source conversation and return its new id
+async fn insert_conversation(conn: &mut SqliteConnection, schema: &SourceSchema, source_id: i64) -> Result<i64> {
+    let sql = format!(
+        r#"
+        INSERT INTO main.conversations (
+            provider, external_id, title, model,
+            created_at, updated_at, raw_json,
+            system_prompt, temperature, max_tokens, user_id, is_archived, language, is_pinned
+        )
+        SELECT provider, external_id, title, model,
+               created_at, updated_at, raw_json,
+               system_prompt, temperature, max_tokens, user_id, {}, {}, {}
+        FROM source.conversations WHERE id = ?1
+        RETURNING id
+        "#,
+        schema.conversation_column("is_archived", "0"),
+        schema.conversation_column("language", "NULL"),
+        schema.conversation_column("is_pinned", "0"),
+    );
+    let id = sqlx::query_scalar(&sql)
+        .bind(source_id)
+        .fetch_one(&mut *conn)
+        .await
+        .context("Failed to insert conversation")?;
+    
+    Ok(id)
+}
+
+/// Overwrite a target conversation with its newer source copy and drop its
+/// messages; the delete trigger removes their FTS rows and attachments cascade
+async fn replace_conversation(
+    conn: &mut SqliteConnection,
+    schema: &SourceSchema,
+    source_id: i64,
+    target_id: i64,
+) -> Result<()> {
+    let sql = format!(
+        r#"
+        UPDATE main.conversations SET
+            title = s.title,
+            model = s.model,
+            updated_at = s.updated_at,
+            raw_json = s.raw_json,
+            system_prompt = s.system_prompt,
+            temperature = s.temperature,
+            max_tokens = s.max_tokens,
+            user_id = s.user_id,
+            language = COALESCE(s.language, main.conversations.language)
+        FROM (
+            SELECT title, model, updated_at, raw_json,
+                   system_prompt, temperature, max_tokens, user_id, {}
+            FROM source.conversations WHERE id = ?1
+        ) AS s
+        WHERE main.conversations.id = ?2
+        "#,
+        schema.conversation_column("language", "NULL"),
+    );
+    sqlx::query(&sql)
+        .bind(source_id)
+        .bind(target_id)
+        .execute(&mut *conn)
+        .await
+        .context("Failed to update conversation")?;
+    
+    sqlx::query("DELETE FROM main.messages WHERE conversation_id = ?1")
+        .bind(target_id)
+        .execute(&mut *conn)
+        .await
+        .context("Failed to clear previous messages")?;
+    
+    Ok(())
+}
+
+/// Copy a source conversation's messages, attachments and revisions under
+/// `target_id`, in their original order
+async fn copy_messages(
+    conn: &mut SqliteConnection,
+    schema: &SourceSchema,
+    source_id: i64,
+    target_id: i64,
+) -> Result<()> {
+    let message_ids: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM source.messages WHERE conversation_id = ?1 ORDER BY id",
+    )
+    .bind(source_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    
+    for source_message in &message_ids {
+        let message_id: i64 = sqlx::query_scalar(
+            r#"
+            INSERT INTO main.messages (
+                conversation_id, role, content, model,
+                created_at, tokens, finish_reason,
+                tool_calls, attachments
+            )
+            SELECT ?1, role, content, model,
+                   created_at, tokens, finish_reason,
+                   tool_calls, attachments
+            FROM source.messages WHERE id = ?2
+            RETURNING id
+            "#,
+        )
+        .bind(target_id)
+        .bind(source_message)
+        .fetch_one(&mut *conn)
+        .await
+        .context("Failed to insert message")?;
+        
+        // Without the table the attachments blob is backfilled afterwards
+        if schema.has_table("message_attachments") {
+            sqlx::query(
+                r#"
+                INSERT INTO main.message_attachments (message_id, filename, mime, size, extracted_content, path)
+                SELECT ?1, filename, mime, size, extracted_content, path
+                FROM source.message_attachments WHERE message_id = ?2
+                "#,
+            )
+            .bind(message_id)
+            .bind(source_message)
+            .execute(&mut *conn)
+            .await
+            .context("Failed to copy attachments")?;
+        }
+        
+        if schema.has_table("message_revisions") {
+            sqlx::query(
+                r#"
+                INSERT INTO main.message_revisions (message_id, revision, content, created_at)
+                SELECT ?1, revision, content, created_at
+                FROM source.message_revisions WHERE message_id = ?2
+                "#,
+            )
+            .bind(message_id)
+            .bind(source_message)
+            .execute(&mut *conn)
+            .await
+            .context("Failed to copy revisions")?;
+        }
+    }
+    
+    debug!("Copied {} messages into conversation {}", message_ids.len(), target_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::{conversation, message, test_pool};
+    use crate::import::process_conversation_batch;
+    
+    #[tokio::test]
+    async fn test_merge_adds_new_and_skips_overlapping_conversations() {
+        let (_target_dir, target) = test_pool().await;
+        let (source_dir, source) = test_pool().await;
+        
+        process_conversation_batch(
+            &target,
+            vec![
+                (conversation("claude", "shared", "Shared"), vec![message("user", "hello there")]),
+                (conversation("claude", "target-only", "Target"), vec![message("user", "kept")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let mut shared = conversation("claude", "shared", "Shared");
+        shared.updated_at = chrono::DateTime::UNIX_EPOCH;
+        process_conversation_batch(
+            &source,
+            vec![
+                (shared, vec![message("user", "hello there")]),
+                (
+                    conversation("chatgpt", "source-only", "Source"),
+                    vec![message("user", "merged question"), message("assistant", "merged answer")],
+                ),
+            ],
+        )
+        .await
+        .unwrap();
+        source.close().await;
+        
+        let report = merge(&target, &source_dir.path().join("test.db")).await.unwrap();
+        assert_eq!(report, MergeReport { added: 1, updated: 0, skipped: 1 });
+        
+        let conversations: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM conversations")
+            .fetch_one(&target)
+            .await
+            .unwrap();
+        assert_eq!(conversations, 3);
+        
+        let messages: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+            .fetch_one(&target)
+            .await
+            .unwrap();
+        assert_eq!(messages, 4);
+        
+        // Merged messages are searchable and point at their new conversation
+        let title: String = sqlx::query_scalar(
+            r#"
+            SELECT c.title FROM messages_fts
+            JOIN messages m ON m.id = messages_fts.rowid
+            JOIN conversations c ON c.id = m.conversation_id
+            WHERE messages_fts MATCH 'merged' LIMIT 1
+            "#,
+        )
+        .fetch_one(&target)
+        .await
+        .unwrap();
+        assert_eq!(title, "Source");
+    }
+    
+    #[tokio::test]
+    async fn test_merging_twice_matches_conversations_without_external_ids() {
+        let (_target_dir, target) = test_pool().await;
+        let (source_dir, source) = test_pool().await;
+        let source_path = source_dir.path().join("test.db");
+        
+        let mut untracked = conversation("markdown", "unused", "Pasted notes");
+        untracked.external_id = None;
+        process_conversation_batch(
+            &source,
+            vec![
+                (untracked, vec![message("user", "notes without an id")]),
+                (conversation("claude", "tracked", "Tracked"), vec![message("user", "has an id")]),
+            ],
+        )
+        .await
+        .unwrap();
+        source.close().await;
+        
+        let report = merge(&target, &source_path).await.unwrap();
+        assert_eq!(report, MergeReport { added: 2, updated: 0, skipped: 0 });
+        
+        // Nothing changed in the source, so nothing is added twice
+        let report = merge(&target, &source_path).await.unwrap();
+        assert_eq!(report, MergeReport { added: 0, updated: 0, skipped: 2 });
+        
+        // A newer copy of the id-less conversation replaces the merged one
+        let source = crate::db::create_pool(&source_path).await.unwrap();
+        sqlx::query("UPDATE conversations SET updated_at = datetime(updated_at, '+1 hour') WHERE external_id IS NULL")
+            .execute(&source)
+            .await
+            .unwrap();
+        source.close().await;
+        
+        let report = merge(&target, &source_path).await.unwrap();
+        assert_eq!(report, MergeReport { added: 0, updated: 1, skipped: 1 });
+        
+        let counts: (i64, i64) = sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM conversations), (SELECT COUNT(*) FROM messages)",
+        )
+        .fetch_one(&target)
+        .await
+        .unwrap();
+        assert_eq!(counts, (2, 2));
+    }
+    
+    #[tokio::test]
+    async fn test_merge_reads_an_unmigrated_archive_without_changing_it() {
+        let (_target_dir, target) = test_pool().await;
+        let source_dir = tempfile::tempdir().unwrap();
+        let source_path = source_dir.path().join("old.db");
+        
+        // The first release's schema: no archive or pin flags, no tags,
+        // attachments only as a JSON blob
+        let source = crate::db::create_pool(&source_path).await.unwrap();
+        sqlx::query(
+            r#"
+            CREATE TABLE conversations (
+                id INTEGER PRIMARY KEY, provider TEXT NOT NULL, external_id TEXT, title TEXT, model TEXT,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                raw_json TEXT, system_prompt TEXT, temperature REAL, max_tokens INTEGER, user_id TEXT,
+                UNIQUE(provider, external_id)
+            );
+            CREATE TABLE messages (
+                id INTEGER PRIMARY KEY, conversation_id INTEGER NOT NULL, role TEXT NOT NULL,
+                content TEXT NOT NULL, model TEXT, created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                tokens INTEGER, finish_reason TEXT, tool_calls TEXT, attachments TEXT
+            );
+            INSERT INTO conversations (provider, external_id, title) VALUES ('claude', 'old', 'Old archive');
+            INSERT INTO messages (conversation_id, role, content, attachments)
+            VALUES (1, 'user', 'legacy question', '[{"file_name": "notes.txt"}]');
+            "#,
+        )
+        .execute(&source)
+        .await
+        .unwrap();
+        source.close().await;
+        
+        let report = merge(&target, &source_path).await.unwrap();
+        assert_eq!(report, MergeReport { added: 1, updated: 0, skipped: 0 });
+        
+        let merged: (i64, bool, bool, i64) = sqlx::query_as(
+            r#"
+            SELECT (SELECT COUNT(*) FROM messages), c.is_archived, c.is_pinned,
+                   (SELECT COUNT(*) FROM message_attachments WHERE filename = 'notes.txt')
+            FROM conversations c WHERE c.external_id = 'old'
+            "#,
+        )
+        .fetch_one(&target)
+        .await
+        .unwrap();
+        assert_eq!(merged, (1, false, false, 1));
+        
+        // The source was only read
+        let source = crate::db::create_pool(&source_path).await.unwrap();
+        let tables: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+                .fetch_all(&source)
+                .await
+                .unwrap();
+        assert_eq!(tables, vec!["conversations", "messages"]);
+        let columns: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pragma_table_info('conversations')")
+            .fetch_one(&source)
+            .await
+            .unwrap();
+        assert_eq!(columns, 12);
+    }
+}
@@ -3,7 +3,20 @@ use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
 use std::path::Path;
 use tracing::info;
 
+pub mod attachments;
+pub mod dedupe;
+pub mod fts;
+pub mod lock;
+pub mod maintenance;
+pub mod merge;
+pub mod revisions;
 pub mod schema;
+pub mod tags;
+pub mod terms;
+
+pub use dedupe::{dedupe, DedupeReport};
+pub use maintenance::{maintain, MaintenanceReport};
+pub use merge::{merge, MergeReport};
 
 /// Create a connection pool with optimized settings
 pub async fn create_pool(path: &Path) -> Result<SqlitePool> {
@@ -41,7 +54,10 @@ pub async fn create_pool(path: &Path) -> Result<SqlitePool> {
 }
 
 /// Run database migrations
-pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
+///
+/// A new search index is built with `fts_tokenizer`; an existing one keeps
+/// the tokenizer it was built with until `reindex` is run.
+pub async fn run_migrations(pool: &SqlitePool, fts_tokenizer: &str) -> Result<()> {
     info!("Running database migrations");
     
     // Create tables with proper indexes from day 1
@@ -49,16 +65,106 @@ pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
         .execute(pool)
         .await?;
     
-    // Create FTS5 table for search
-    sqlx::query(schema::CREATE_FTS)
-        .execute(pool)
-        .await?;
+    // Columns added after the first release
+    add_column_if_missing(pool, "conversations", "is_archived", "BOOLEAN NOT NULL DEFAULT 0").await?;
+    add_column_if_missing(pool, "conversations", "language", "TEXT").await?;
+    add_column_if_missing(pool, "conversations", "is_pinned", "BOOLEAN NOT NULL DEFAULT 0").await?;
+    
+    // Create FTS5 table for search, keeping whatever tokenizer it was built with
+    fts::ensure_fts(pool, fts_tokenizer).await?;
     
     // Create essential indexes
     sqlx::query(schema::CREATE_INDEXES)
         .execute(pool)
         .await?;
     
+    // Normalize attachment blobs stored before message_attachments existed
+    let migrated = attachments::backfill(pool).await?;
+    if migrated > 0 {
+        info!("Migrated {} message attachments", migrated);
+    }
+    
+    terms::backfill(pool).await?;
+    
     info!("Database migrations completed");
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Add a column to a table created before the column existed
+async fn add_column_if_missing(pool: &SqlitePool, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists: bool = sqlx::query_scalar("SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2")
+        .bind(table)
+        .bind(column)
+        .fetch_one(pool)
+        .await?;
+    
+    if !exists {
+        info!("Adding column {}.{}", table, column);
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(pool)
+            .await?;
+    }
+    
+    Ok(())
+}
+
+/// Helpers for tests that need a migrated database
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+    use chrono::Utc;
+
+    use crate::models::{Conversation, Message};
+
+    /// Create a migrated database in a temporary directory
+    ///
+    /// The directory guard must be kept alive for as long as the pool is used.
+    pub async fn test_pool() -> (tempfile::TempDir, SqlitePool) {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let pool = create_pool(&dir.path().join("test.db"))
+            .await
+            .expect("Failed to create pool");
+        run_migrations(&pool, schema::DEFAULT_FTS_TOKENIZER)
+            .await
+            .expect("Failed to run migrations");
+        (dir, pool)
+    }
+
+    /// Build a conversation ready for `process_conversation_batch`
+    pub fn conversation(provider: &str, external_id: &str, title: &str) -> Conversation {
+        let now = Utc::now();
+        Conversation {
+            id: 0,
+            provider: provider.to_string(),
+            external_id: Some(external_id.to_string()),
+            title: Some(title.to_string()),
+            model: None,
+            created_at: now,
+            updated_at: now,
+            raw_json: None,
+            system_prompt: None,
+            temperature: None,
+            max_tokens: None,
+            user_id: None,
+            is_pinned: false,
+            is_archived: false,
+        }
+    }
+
+    /// Build a message ready for `process_conversation_batch`
+    pub fn message(role: &str, content: &str) -> Message {
+        Message {
+            id: 0,
+            conversation_id: 0,
+            role: role.to_string(),
+            content: content.to_string(),
+            model: None,
+            created_at: Utc::now(),
+            tokens: None,
+            finish_reason: None,
+            tool_calls: None,
+            attachments: None,
+            revisions: Vec::new(),
+        }
+    }
+}
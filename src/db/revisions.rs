@@ -0,0 +1,55 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::models::MessageRevision;
+
+/// A revision row as stored, numbered from 1 for the oldest
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct StoredRevision {
+    pub revision: i64,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Store the earlier versions of an inserted message, oldest first
+pub async fn insert(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    message_id: i64,
+    revisions: &[MessageRevision],
+) -> Result<()> {
+    for (index, revision) in revisions.iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO message_revisions (message_id, revision, content, created_at)
+            VALUES (?1, ?2, ?3, ?4)
+            "#,
+        )
+        .bind(message_id)
+        .bind(index as i64 + 1)
+        .bind(&revision.content)
+        .bind(revision.created_at.to_rfc3339())
+        .execute(&mut **tx)
+        .await
+        .context("Failed to insert message revision")?;
+    }
+    
+    Ok(())
+}
+
+/// Earlier versions of a message, oldest first
+pub async fn for_message(pool: &SqlitePool, message_id: i64) -> Result<Vec<StoredRevision>> {
+    sqlx::query_as(
+        r#"
+        SELECT revision, content, created_at
+        FROM message_revisions
+        WHERE message_id = ?1
+        ORDER BY revision
+        "#,
+    )
+    .bind(message_id)
+    .fetch_all(pool)
+    .await
+    .context("Failed to load message revisions")
+}
@@ -25,6 +25,15 @@ CREATE TABLE IF NOT EXISTS conversations (
     max_tokens INTEGER,
     user_id TEXT,
     
+    -- Hidden from browsing, search, and stats unless asked for
+    is_archived BOOLEAN NOT NULL DEFAULT 0,
+    
+    -- ISO 639-1 code detected from the user's messages, if any
+    language TEXT,
+    
+    -- Listed ahead of everything else when browsing
+    is_pinned BOOLEAN NOT NULL DEFAULT 0,
+    
     -- Unique constraint to prevent duplicate imports
     UNIQUE(provider, external_id)
 );
@@ -47,6 +56,40 @@ CREATE TABLE IF NOT EXISTS messages (
     FOREIGN KEY(conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
 );
 
+-- Files attached to messages, normalized from messages.attachments
+CREATE TABLE IF NOT EXISTS message_attachments (
+    id INTEGER PRIMARY KEY,
+    message_id INTEGER NOT NULL,
+    filename TEXT,
+    mime TEXT,
+    size INTEGER,
+    extracted_content TEXT,
+    path TEXT,
+    
+    FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
+
+-- Earlier versions of edited messages; revision 1 is the oldest
+CREATE TABLE IF NOT EXISTS message_revisions (
+    message_id INTEGER NOT NULL,
+    revision INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    
+    PRIMARY KEY(message_id, revision),
+    FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
+
+-- User-assigned labels on conversations
+CREATE TABLE IF NOT EXISTS conversation_tags (
+    conversation_id INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    
+    PRIMARY KEY(conversation_id, tag),
+    FOREIGN KEY(conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+);
+
 -- Import event log for audit trail (as suggested in review)
 CREATE TABLE IF NOT EXISTS import_events (
     id INTEGER PRIMARY KEY,
@@ -59,32 +102,57 @@ CREATE TABLE IF NOT EXISTS import_events (
     created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
 );
 
+-- Files already imported, keyed by path and content hash so unchanged
+-- files can be skipped on re-import
+CREATE TABLE IF NOT EXISTS import_manifest (
+    file_path TEXT NOT NULL,
+    file_sha256 TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    status TEXT NOT NULL,
+    imported_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (file_path, file_sha256)
+);
+
+-- Settings the search index was built with, e.g. the FTS tokenizer
+CREATE TABLE IF NOT EXISTS index_settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Occurrences of each word in message content, for search suggestions;
+-- kept up to date by imports and rebuilt by maintenance
+CREATE TABLE IF NOT EXISTS term_frequencies (
+    term TEXT PRIMARY KEY,
+    count INTEGER NOT NULL
+);
+
 -- Insert default providers
 INSERT OR IGNORE INTO providers (name) VALUES 
     ('chatgpt'), ('claude'), ('gemini'), ('xai'), ('zed');
 "#;
 
-/// FTS5 virtual table for blazing fast search
-pub const CREATE_FTS: &str = r#"
--- Drop if exists to allow schema updates
-DROP TABLE IF EXISTS messages_fts;
+/// Tokenizer used for new databases: Porter stemming over unicode61 for English
+pub const DEFAULT_FTS_TOKENIZER: &str = "porter unicode61";
 
+/// FTS5 virtual table for blazing fast search
+///
+/// `tokenizer` is an FTS5 tokenizer spec such as `porter unicode61`, `unicode61`
+/// or `trigram`;
+/// it must be validated before being interpolated here.
+pub fn create_fts(tokenizer: &str) -> String {
+    format!(r#"
 -- Create FTS5 table for full-text search
-CREATE VIRTUAL TABLE messages_fts USING fts5(
+CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
     content,
     conversation_id UNINDEXED,
     
     -- Store additional searchable fields
     role UNINDEXED,
     
-    -- Use Porter tokenizer for better stemming
-    tokenize = 'porter'
+    tokenize = '{tokenizer}'
 );
 
--- Populate FTS from existing messages
-INSERT OR IGNORE INTO messages_fts (rowid, content, conversation_id, role)
-SELECT id, content, conversation_id, role FROM messages;
-
 -- Create triggers to keep FTS in sync
 CREATE TRIGGER IF NOT EXISTS messages_ai AFTER INSERT ON messages
 BEGIN
@@ -103,7 +171,32 @@ BEGIN
     SET content = new.content, role = new.role
     WHERE rowid = new.id;
 END;
-"#;
+
+-- Conversation titles, searched alongside message content
+CREATE VIRTUAL TABLE IF NOT EXISTS conversations_fts USING fts5(
+    title,
+    tokenize = '{tokenizer}'
+);
+
+CREATE TRIGGER IF NOT EXISTS conversations_ai AFTER INSERT ON conversations
+BEGIN
+    INSERT INTO conversations_fts (rowid, title)
+    VALUES (new.id, COALESCE(new.title, ''));
+END;
+
+CREATE TRIGGER IF NOT EXISTS conversations_ad AFTER DELETE ON conversations
+BEGIN
+    DELETE FROM conversations_fts WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS conversations_au AFTER UPDATE OF title ON conversations
+BEGIN
+    UPDATE conversations_fts
+    SET title = COALESCE(new.title, '')
+    WHERE rowid = new.id;
+END;
+"#)
+}
 
 /// Essential indexes for performance
 pub const CREATE_INDEXES: &str = r#"
@@ -120,6 +213,15 @@ ON conversations(model);
 CREATE INDEX IF NOT EXISTS idx_conversations_user_id 
 ON conversations(user_id);
 
+CREATE INDEX IF NOT EXISTS idx_conversations_is_archived 
+ON conversations(is_archived);
+
+CREATE INDEX IF NOT EXISTS idx_conversations_language 
+ON conversations(language);
+
+CREATE INDEX IF NOT EXISTS idx_conversations_pinned 
+ON conversations(is_pinned DESC, updated_at DESC);
+
 -- Message indexes
 CREATE INDEX IF NOT EXISTS idx_messages_conversation_id 
 ON messages(conversation_id);
@@ -130,6 +232,17 @@ ON messages(created_at);
 CREATE INDEX IF NOT EXISTS idx_messages_role 
 ON messages(role);
 
+-- Attachment indexes
+CREATE INDEX IF NOT EXISTS idx_message_attachments_message_id 
+ON message_attachments(message_id);
+
+CREATE INDEX IF NOT EXISTS idx_message_attachments_mime 
+ON message_attachments(mime);
+
+-- Tag indexes
+CREATE INDEX IF NOT EXISTS idx_conversation_tags_tag 
+ON conversation_tags(tag);
+
 -- Import event indexes
 CREATE INDEX IF NOT EXISTS idx_import_events_created_at 
 ON import_events(created_at DESC);
@@ -0,0 +1,30 @@
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+
+/// Tag conversations in one transaction, returning how many were newly tagged
+pub async fn add(pool: &SqlitePool, conversation_ids: &[i64], tag: &str) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let mut tagged = 0;
+    
+    for conversation_id in conversation_ids {
+        tagged += sqlx::query("INSERT OR IGNORE INTO conversation_tags (conversation_id, tag) VALUES (?1, ?2)")
+            .bind(conversation_id)
+            .bind(tag)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to tag conversation")?
+            .rows_affected();
+    }
+    
+    tx.commit().await?;
+    Ok(tagged)
+}
+
+/// Tags on a conversation, alphabetically
+pub async fn for_conversation(pool: &SqlitePool, conversation_id: i64) -> Result<Vec<String>> {
+    sqlx::query_scalar("SELECT tag FROM conversation_tags WHERE conversation_id = ?1 ORDER BY tag")
+        .bind(conversation_id)
+        .fetch_all(pool)
+        .await
+        .context("Failed to load conversation tags")
+}
@@ -0,0 +1,153 @@
+use anyhow::{Context, Result};
+use sqlx::{Connection, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use tracing::info;
+
+/// Messages read per query while rebuilding
+const REBUILD_CHUNK_SIZE: i64 = 10_000;
+
+/// Longest word counted; longer ones are mostly hashes, keys and base64
+const MAX_TERM_CHARS: usize = 32;
+
+/// Words too common to say anything about what a conversation is about
+const STOPWORDS: &[&str] = &[
+    "about", "after", "again", "also", "and", "any", "are", "because", "been", "before",
+    "being", "but", "can", "could", "did", "does", "doing", "for", "from", "get", "had",
+    "has", "have", "having", "her", "here", "him", "his", "how", "into", "its", "just",
+    "like", "make", "more", "most", "not", "now", "only", "other", "our", "out", "over",
+    "please", "same", "she", "should", "some", "such", "than", "that", "the", "their",
+    "them", "then", "there", "these", "they", "this", "those", "through", "too", "under",
+    "use", "using", "very", "want", "was", "way", "were", "what", "when", "where", "which",
+    "while", "who", "why", "will", "with", "would", "you", "your",
+];
+
+/// Lowercased words of at least three characters, skipping stopwords
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(|w| w.to_lowercase())
+        .filter(|w| w.chars().count() >= 3 && !STOPWORDS.contains(&w.as_str()))
+}
+
+/// Add the words of `text` to `counts`, or take them away with a negative `sign`
+pub fn count(counts: &mut HashMap<String, i64>, text: &str, sign: i64) {
+    let terms = words(text)
+        .filter(|w| w.chars().count() <= MAX_TERM_CHARS && !w.chars().all(|c| c.is_numeric()));
+    
+    for term in terms {
+        *counts.entry(term).or_default() += sign;
+    }
+}
+
+/// Apply counts from `count` to the table, dropping the terms it brings to
+/// zero
+pub async fn apply(conn: &mut SqliteConnection, counts: &HashMap<String, i64>) -> Result<()> {
+    for (term, delta) in counts.iter().filter(|(_, delta)| **delta != 0) {
+        sqlx::query(
+            r#"
+            INSERT INTO term_frequencies (term, count) VALUES (?1, ?2)
+            ON CONFLICT(term) DO UPDATE SET count = count + excluded.count
+            "#,
+        )
+        .bind(term)
+        .bind(delta)
+        .execute(&mut *conn)
+        .await
+        .context("Failed to update term frequencies")?;
+        
+        // Only terms this batch lowered can have reached zero
+        if *delta < 0 {
+            sqlx::query("DELETE FROM term_frequencies WHERE term = ?1 AND count <= 0")
+                .bind(term)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+    
+    Ok(())
+}
+
+/// Recount every message from scratch and return the number of terms
+///
+/// Needed after changes that bypass the import path, such as a merge.
+pub async fn rebuild(conn: &mut SqliteConnection) -> Result<usize> {
+    let mut tx = conn.begin().await?;
+    let mut counts = HashMap::new();
+    let mut last_id = 0i64;
+    
+    loop {
+        let chunk: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT id, content FROM messages WHERE id > ?1 ORDER BY id LIMIT ?2",
+        )
+        .bind(last_id)
+        .bind(REBUILD_CHUNK_SIZE)
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to read messages for term frequencies")?;
+        
+        let Some((chunk_last_id, _)) = chunk.last() else {
+            break;
+        };
+        last_id = *chunk_last_id;
+        
+        for (_, content) in &chunk {
+            count(&mut counts, content, 1);
+        }
+    }
+    
+    sqlx::query("DELETE FROM term_frequencies")
+        .execute(&mut *tx)
+        .await?;
+    apply(&mut tx, &counts).await?;
+    
+    tx.commit().await?;
+    info!("Counted {} distinct terms", counts.len());
+    Ok(counts.len())
+}
+
+/// Count terms for archives created before term frequencies were tracked
+pub async fn backfill(pool: &SqlitePool) -> Result<usize> {
+    let missing: bool = sqlx::query_scalar(
+        "SELECT NOT EXISTS (SELECT 1 FROM term_frequencies) AND EXISTS (SELECT 1 FROM messages)",
+    )
+    .fetch_one(pool)
+    .await?;
+    
+    if !missing {
+        return Ok(0);
+    }
+    
+    let mut conn = pool.acquire().await?;
+    rebuild(&mut conn).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::test_pool;
+    
+    #[tokio::test]
+    async fn test_apply_drops_only_the_terms_it_lowers_to_zero() {
+        let (_dir, pool) = test_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        
+        // A row no batch touches stays as it is
+        sqlx::query("INSERT INTO term_frequencies (term, count) VALUES ('untouched', 0)")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        
+        let mut counts = HashMap::new();
+        count(&mut counts, "gradient descent gradient", 1);
+        apply(&mut conn, &counts).await.unwrap();
+        
+        let mut counts = HashMap::new();
+        count(&mut counts, "gradient descent", -1);
+        apply(&mut conn, &counts).await.unwrap();
+        
+        let rows: Vec<(String, i64)> = sqlx::query_as("SELECT term, count FROM term_frequencies ORDER BY term")
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![("gradient".to_string(), 1), ("untouched".to_string(), 0)]);
+    }
+}
@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -18,6 +18,12 @@ pub enum AppError {
     /// Bad request errors
     BadRequest(String),
     
+    /// Request bodies over a configured size limit
+    PayloadTooLarge(String),
+    
+    /// Another writer holds the database; the client should retry
+    Busy(String),
+    
     /// Internal server errors
     Internal(anyhow::Error),
     
@@ -34,6 +40,8 @@ impl fmt::Display for AppError {
             AppError::Database(e) => write!(f, "Database error: {}", e),
             AppError::NotFound(msg) => write!(f, "Not found: {}", msg),
             AppError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
+            AppError::PayloadTooLarge(msg) => write!(f, "Payload too large: {}", msg),
+            AppError::Busy(msg) => write!(f, "Busy: {}", msg),
             AppError::Internal(e) => write!(f, "Internal error: {}", e),
             AppError::ImportError(msg) => write!(f, "Import error: {}", msg),
             AppError::SearchError(msg) => write!(f, "Search error: {}", msg),
@@ -55,30 +63,40 @@ impl From<anyhow::Error> for AppError {
     }
 }
 
+/// Seconds a `Busy` response asks the client to wait before retrying
+const BUSY_RETRY_AFTER_SECS: u64 = 5;
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let busy = matches!(self, AppError::Busy(_));
         let (status, error_message) = match self {
             AppError::Database(e) => {
                 tracing::error!("Database error: {:?}", e);
-                (StatusCode::INTERNAL_SERVER_ERROR, "Database error occurred")
+                (StatusCode::INTERNAL_SERVER_ERROR, "Database error occurred".to_string())
             }
             AppError::NotFound(msg) => {
-                (StatusCode::NOT_FOUND, msg.as_str())
+                (StatusCode::NOT_FOUND, msg)
             }
             AppError::BadRequest(msg) => {
-                (StatusCode::BAD_REQUEST, msg.as_str())
+                (StatusCode::BAD_REQUEST, msg)
+            }
+            AppError::PayloadTooLarge(msg) => {
+                (StatusCode::PAYLOAD_TOO_LARGE, msg)
+            }
+            AppError::Busy(msg) => {
+                (StatusCode::SERVICE_UNAVAILABLE, msg)
             }
             AppError::Internal(e) => {
                 tracing::error!("Internal error: {:?}", e);
-                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
             }
             AppError::ImportError(msg) => {
                 tracing::error!("Import error: {}", msg);
-                (StatusCode::INTERNAL_SERVER_ERROR, msg.as_str())
+                (StatusCode::INTERNAL_SERVER_ERROR, msg)
             }
             AppError::SearchError(msg) => {
                 tracing::error!("Search error: {}", msg);
-                (StatusCode::INTERNAL_SERVER_ERROR, msg.as_str())
+                (StatusCode::INTERNAL_SERVER_ERROR, msg)
             }
         };
 
@@ -86,7 +104,11 @@ impl IntoResponse for AppError {
             "error": error_message,
         }));
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if busy {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(BUSY_RETRY_AFTER_SECS));
+        }
+        response
     }
 }
 
@@ -0,0 +1,989 @@
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde_json::{json, Value};
+use sqlx::{FromRow, SqlitePool};
+use std::io::Write;
+use tracing::debug;
+
+use crate::models::{Conversation, Message, MessageRevision};
+use crate::search::{get_conversation_messages, MessagePage};
+
+/// Supported export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// OpenAI chat fine-tuning JSONL, one conversation per line
+    OpenAiChat,
+    /// Archive JSONL: one conversation per line, or one message per line when flattened
+    Jsonl,
+    /// Pretty-printed JSON: one conversation, or an array of them
+    Json,
+    /// Markdown transcript
+    Markdown,
+    /// Standalone HTML transcript
+    Html,
+    /// One row of metadata and message counts per conversation
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::OpenAiChat | Self::Jsonl => "application/jsonl",
+            Self::Json => "application/json",
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Html => "text/html; charset=utf-8",
+            Self::Csv => "text/csv; charset=utf-8",
+        }
+    }
+    
+    /// File name extension for downloads
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::OpenAiChat | Self::Jsonl => "jsonl",
+            Self::Json => "json",
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Csv => "csv",
+        }
+    }
+    
+    /// Whether the format renders human-readable transcripts
+    fn is_transcript(self) -> bool {
+        matches!(self, Self::Markdown | Self::Html)
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "openai-chat" => Ok(Self::OpenAiChat),
+            "jsonl" => Ok(Self::Jsonl),
+            "json" => Ok(Self::Json),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            "csv" => Ok(Self::Csv),
+            other => bail!("Unknown export format '{}' (expected: openai-chat, jsonl, json, markdown, html, csv)", other),
+        }
+    }
+}
+
+/// Built-in transcript layouts for markdown and html exports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TranscriptTemplate {
+    /// A heading per message with role and timestamp
+    #[default]
+    Default,
+    /// Chat bubbles: "You" and "Assistant", no timestamps
+    Chat,
+    /// Question and answer pairs separated by rules
+    Qa,
+    /// Interviewer and respondent with times of day
+    Interview,
+}
+
+impl std::str::FromStr for TranscriptTemplate {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "default" => Ok(Self::Default),
+            "chat" => Ok(Self::Chat),
+            "qa" => Ok(Self::Qa),
+            "interview" => Ok(Self::Interview),
+            other => bail!("Unknown template '{}' (expected: default, chat, qa, interview)", other),
+        }
+    }
+}
+
+impl TranscriptTemplate {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Chat => "chat",
+            Self::Qa => "qa",
+            Self::Interview => "interview",
+        }
+    }
+    
+    /// Label shown for a message role
+    fn label(self, role: &str) -> String {
+        let label = match (self, role) {
+            (Self::Default, _) => return role.to_uppercase(),
+            (Self::Chat, "user") => "You",
+            (Self::Chat, "assistant") => "Assistant",
+            (Self::Qa, "user") => "Q",
+            (Self::Qa, "assistant") => "A",
+            (Self::Interview, "user") => "Interviewer",
+            (Self::Interview, "assistant") => "Respondent",
+            (_, "system") => "System",
+            (_, other) => other,
+        };
+        label.to_string()
+    }
+    
+    /// Timestamp format, or `None` to leave timestamps out
+    fn timestamp_format(self) -> Option<&'static str> {
+        match self {
+            Self::Default => Some("%Y-%m-%d %H:%M:%S"),
+            Self::Interview => Some("%H:%M"),
+            Self::Chat | Self::Qa => None,
+        }
+    }
+    
+    /// Markdown placed between consecutive messages
+    fn separator(self) -> &'static str {
+        match self {
+            Self::Qa => "\n\n---\n\n",
+            _ => "\n\n",
+        }
+    }
+}
+
+/// Patterns `export.redaction_patterns` defaults to: bearer tokens, API keys
+/// like `sk-...`, email addresses, long base64 strings, and phone numbers
+pub const DEFAULT_REDACTION_PATTERNS: &[&str] = &[
+    r"(?i)\bbearer\s+[a-z0-9._~+/-]+=*",
+    r"\b(?:sk|pk|rk)-[A-Za-z0-9_-]{16,}",
+    r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+    r"\b[A-Za-z0-9+/_-]{40,}={0,2}",
+    r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{3}\)|\b\d{3})[\s.-]\d{3}[\s.-]\d{4}\b",
+    r"\+\d{1,3}(?:[\s.-]\d{2,5}){2,4}\b",
+];
+
+/// Text that replaces every redacted match
+const REDACTED: &str = "[REDACTED]";
+
+/// Masks secrets and personal details in exported text
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    /// Compile the patterns, applied in order
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern).with_context(|| format!("Invalid redaction pattern '{}'", pattern)))
+            .collect::<Result<_>>()?;
+        Ok(Self { patterns })
+    }
+    
+    /// `text` with every match replaced by `[REDACTED]`
+    pub fn redact(&self, text: &str) -> String {
+        self.patterns.iter().fold(text.to_string(), |text, pattern| {
+            pattern.replace_all(&text, REDACTED).into_owned()
+        })
+    }
+    
+    /// Redact every string inside a JSON value
+    fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => *text = self.redact(text),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_value(item)),
+            Value::Object(fields) => fields.values_mut().for_each(|field| self.redact_value(field)),
+            _ => {}
+        }
+    }
+    
+    /// Copies of a conversation and its messages with all their text redacted
+    ///
+    /// Covers titles, prompts, message content, and JSON like the raw export,
+    /// so every format built from the copies is clean.
+    pub fn conversation(&self, conversation: &Conversation, messages: &[Message]) -> (Conversation, Vec<Message>) {
+        let redact_text = |text: &Option<String>| text.as_deref().map(|text| self.redact(text));
+        let redact_json = |value: &Option<Value>| {
+            value.clone().map(|mut value| {
+                self.redact_value(&mut value);
+                value
+            })
+        };
+        
+        let conversation = Conversation {
+            title: redact_text(&conversation.title),
+            system_prompt: redact_text(&conversation.system_prompt),
+            raw_json: redact_json(&conversation.raw_json),
+            ..conversation.clone()
+        };
+        let messages = messages
+            .iter()
+            .map(|message| Message {
+                content: self.redact(&message.content),
+                tool_calls: redact_json(&message.tool_calls),
+                attachments: redact_json(&message.attachments),
+                revisions: message
+                    .revisions
+                    .iter()
+                    .map(|revision| MessageRevision { content: self.redact(&revision.content), ..revision.clone() })
+                    .collect(),
+                ..message.clone()
+            })
+            .collect();
+        
+        (conversation, messages)
+    }
+}
+
+/// Options for an export run
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Emit one record per message (for RAG ingestion) instead of per conversation
+    pub flatten: bool,
+    /// Transcript layout for markdown and html
+    pub template: TranscriptTemplate,
+    /// Combine markdown transcripts into one document with a table of contents
+    pub combine: bool,
+    /// Mask secrets in everything exported
+    pub redactor: Option<Redactor>,
+}
+
+impl ExportOptions {
+    pub fn new(format: ExportFormat, flatten: bool) -> Result<Self> {
+        if flatten && format != ExportFormat::Jsonl {
+            bail!("flatten is only supported for the jsonl format");
+        }
+        Ok(Self { format, flatten, template: TranscriptTemplate::default(), combine: false, redactor: None })
+    }
+    
+    /// Set `combine`, which only the markdown format supports
+    pub fn with_combine(self, combine: bool) -> Result<Self> {
+        if combine && self.format != ExportFormat::Markdown {
+            bail!("combine is only supported for the markdown format");
+        }
+        Ok(Self { combine, ..self })
+    }
+    
+    /// Download file name for `conversation` in this export's format, built
+    /// from the redacted title when secrets are masked
+    pub fn file_name(&self, conversation: &Conversation) -> String {
+        match &self.redactor {
+            Some(redactor) => {
+                let title = conversation.title.as_deref().map(|title| redactor.redact(title));
+                file_name(&Conversation { title, ..conversation.clone() }, self.format)
+            }
+            None => file_name(conversation, self.format),
+        }
+    }
+}
+
+/// Export every conversation in the archive, returning the number of records written
+///
+/// Markdown transcripts are written one after another, or as one document
+/// with a table of contents when combined; html exports are a single
+/// document with a section per conversation, and json exports one array.
+pub async fn export_all<W: Write>(pool: &SqlitePool, options: ExportOptions, out: &mut W) -> Result<usize> {
+    if options.format == ExportFormat::Csv {
+        let mut rows = csv_rows(pool).await?;
+        if let Some(redactor) = &options.redactor {
+            for row in &mut rows {
+                row.title = row.title.as_deref().map(|title| redactor.redact(title));
+            }
+        }
+        write_csv(&rows, out)?;
+        out.flush()?;
+        return Ok(rows.len());
+    }
+    
+    let rows = sqlx::query(
+        r#"
+        SELECT id, provider, external_id, title, model, created_at, updated_at,
+               raw_json, system_prompt, temperature, max_tokens, user_id, is_pinned, is_archived
+        FROM conversations
+        ORDER BY id
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to load conversations for export")?;
+    
+    let mut written = 0;
+    let mut sections = Vec::new();
+    let mut chapters = Vec::new();
+    let mut documents = Vec::new();
+    
+    for row in &rows {
+        let mut conversation = Conversation::from_row(row)?;
+        let mut messages = get_conversation_messages(pool, conversation.id, &MessagePage::default()).await?;
+        if let Some(redactor) = &options.redactor {
+            (conversation, messages) = redactor.conversation(&conversation, &messages);
+        }
+        
+        match options.format {
+            ExportFormat::Markdown if options.combine => {
+                chapters.push((conversation, messages));
+                written += 1;
+            }
+            ExportFormat::Markdown => {
+                out.write_all(markdown_transcript(&conversation, &messages, options.template).as_bytes())?;
+                out.write_all(b"\n")?;
+                written += 1;
+            }
+            ExportFormat::Html => {
+                sections.push(html_section(&conversation, &messages, options.template));
+                written += 1;
+            }
+            ExportFormat::Json => {
+                documents.extend(conversation_records(&conversation, &messages, &options));
+                written += 1;
+            }
+            ExportFormat::OpenAiChat | ExportFormat::Jsonl | ExportFormat::Csv => {
+                let records = conversation_records(&conversation, &messages, &options);
+                if records.is_empty() {
+                    debug!("Skipping conversation {} with nothing to export", conversation.id);
+                    continue;
+                }
+                written += write_jsonl(&records, out)?;
+            }
+        }
+    }
+    
+    if options.format == ExportFormat::Html {
+        out.write_all(html_document("Conversation Export", &sections).as_bytes())?;
+    }
+    if options.format == ExportFormat::Json {
+        serde_json::to_writer_pretty(&mut *out, &documents)?;
+        out.write_all(b"\n")?;
+    }
+    if options.combine {
+        let chapters: Vec<_> = chapters.iter().map(|(c, m)| (c, m.as_slice())).collect();
+        out.write_all(markdown_book(&chapters, options.template).as_bytes())?;
+    }
+    
+    out.flush()?;
+    Ok(written)
+}
+
+/// A single conversation rendered in the requested format
+pub fn render_conversation(conversation: &Conversation, messages: &[Message], options: &ExportOptions) -> Result<Vec<u8>> {
+    let redacted;
+    let (conversation, messages) = match &options.redactor {
+        Some(redactor) => {
+            redacted = redactor.conversation(conversation, messages);
+            (&redacted.0, redacted.1.as_slice())
+        }
+        None => (conversation, messages),
+    };
+    
+    if options.format == ExportFormat::Csv {
+        let mut body = Vec::new();
+        write_csv(&[CsvRow::from_messages(conversation, messages)], &mut body)?;
+        return Ok(body);
+    }
+    
+    if options.format == ExportFormat::Json {
+        let document = json!({ "conversation": conversation, "messages": messages });
+        return Ok(serde_json::to_vec_pretty(&document)?);
+    }
+    
+    if !options.format.is_transcript() {
+        let mut body = Vec::new();
+        write_jsonl(&conversation_records(conversation, messages, options), &mut body)?;
+        return Ok(body);
+    }
+    
+    let text = match options.format {
+        ExportFormat::Html => html_document(
+            conversation.title.as_deref().unwrap_or("Untitled Conversation"),
+            &[html_section(conversation, messages, options.template)],
+        ),
+        _ if options.combine => markdown_book(&[(conversation, messages)], options.template),
+        _ => markdown_transcript(conversation, messages, options.template),
+    };
+    Ok(text.into_bytes())
+}
+
+/// Download file name for a conversation: its title reduced to ASCII
+/// letters, digits, `-` and `_`, plus the format's extension
+///
+/// Untitled conversations, or titles with nothing left after sanitizing,
+/// fall back to `conversation-<id>`.
+pub fn file_name(conversation: &Conversation, format: ExportFormat) -> String {
+    const MAX_STEM_CHARS: usize = 80;
+    
+    let title = conversation.title.as_deref().unwrap_or_default();
+    let mut stem = String::new();
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            stem.push(c);
+        } else if !stem.is_empty() && !stem.ends_with('-') {
+            stem.push('-');
+        }
+        if stem.len() >= MAX_STEM_CHARS {
+            break;
+        }
+    }
+    let stem = stem.trim_end_matches('-');
+    
+    if stem.is_empty() {
+        format!("conversation-{}.{}", conversation.id, format.extension())
+    } else {
+        format!("{}.{}", stem, format.extension())
+    }
+}
+
+/// Markdown transcript of a conversation in the given layout
+pub fn markdown_transcript(conversation: &Conversation, messages: &[Message], template: TranscriptTemplate) -> String {
+    let title = conversation.title.as_deref().unwrap_or("Untitled Conversation");
+    let mut out = format!("# {}\n\n", title);
+    
+    let turns: Vec<String> = messages
+        .iter()
+        .map(|message| {
+            let label = template.label(&message.role);
+            let timestamp = template
+                .timestamp_format()
+                .map(|format| message.created_at.format(format).to_string());
+            
+            match (template, timestamp) {
+                (TranscriptTemplate::Default, Some(timestamp)) => {
+                    format!("## {} ({})\n\n{}", label, timestamp, message.content)
+                }
+                (_, Some(timestamp)) => format!("**{}** ({}): {}", label, timestamp, message.content),
+                (_, None) => format!("**{}:** {}", label, message.content),
+            }
+        })
+        .collect();
+    
+    out.push_str(&turns.join(template.separator()));
+    out.push('\n');
+    out
+}
+
+/// Markdown transcripts combined into one document
+///
+/// A table of contents links to an anchor placed before each transcript,
+/// so the document reads like a book with a chapter per conversation.
+pub fn markdown_book(chapters: &[(&Conversation, &[Message])], template: TranscriptTemplate) -> String {
+    let mut out = String::from("# Conversation Export\n\n## Contents\n\n");
+    
+    for (i, (conversation, _)) in chapters.iter().enumerate() {
+        let title = conversation.title.as_deref().unwrap_or("Untitled Conversation");
+        out.push_str(&format!(
+            "{}. [{}](#{})\n",
+            i + 1,
+            title.replace('[', "\\[").replace(']', "\\]"),
+            chapter_anchor(conversation)
+        ));
+    }
+    
+    for (conversation, messages) in chapters {
+        out.push_str(&format!("\n---\n\n<a id=\"{}\"></a>\n\n", chapter_anchor(conversation)));
+        out.push_str(&markdown_transcript(conversation, messages, template));
+    }
+    
+    out
+}
+
+/// Anchor id of a conversation's chapter in a combined markdown export
+fn chapter_anchor(conversation: &Conversation) -> String {
+    format!("conversation-{}", conversation.id)
+}
+
+/// One conversation as an html `<section>` in the given layout
+pub fn html_section(conversation: &Conversation, messages: &[Message], template: TranscriptTemplate) -> String {
+    let title = conversation.title.as_deref().unwrap_or("Untitled Conversation");
+    let mut out = format!(
+        "<section class=\"transcript transcript-{}\">\n<h1>{}</h1>\n",
+        template.name(),
+        escape_html(title)
+    );
+    
+    let separator = if template == TranscriptTemplate::Qa { "<hr>\n" } else { "" };
+    
+    for (i, message) in messages.iter().enumerate() {
+        if i > 0 {
+            out.push_str(separator);
+        }
+        
+        let timestamp = template
+            .timestamp_format()
+            .map(|format| format!(" <time>{}</time>", message.created_at.format(format)))
+            .unwrap_or_default();
+        
+        out.push_str(&format!(
+            "<div class=\"message {}\"><div class=\"message-role\">{}{}</div><div class=\"message-content\">{}</div></div>\n",
+            escape_html(&message.role),
+            escape_html(&template.label(&message.role)),
+            timestamp,
+            escape_html(&message.content).replace('\n', "<br>\n"),
+        ));
+    }
+    
+    out.push_str("</section>\n");
+    out
+}
+
+/// Wrap transcript sections in a standalone html document
+fn html_document(title: &str, sections: &[String]) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        TRANSCRIPT_CSS,
+        sections.concat()
+    )
+}
+
+const TRANSCRIPT_CSS: &str = "\
+body { font-family: sans-serif; max-width: 48rem; margin: 2rem auto; line-height: 1.5; }
+.message { margin: 1rem 0; }
+.message-role { font-weight: bold; }
+.transcript-chat .message { padding: 0.5rem 1rem; border-radius: 1rem; max-width: 80%; }
+.transcript-chat .message.user { background: #dbeafe; margin-left: auto; }
+.transcript-chat .message.assistant { background: #f3f4f6; }
+";
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Columns of the csv export, in order
+pub const CSV_COLUMNS: [&str; 10] = [
+    "id",
+    "provider",
+    "model",
+    "title",
+    "created_at",
+    "updated_at",
+    "message_count",
+    "user_message_count",
+    "assistant_message_count",
+    "total_chars",
+];
+
+/// A conversation's csv export row
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct CsvRow {
+    pub id: i64,
+    pub provider: String,
+    pub model: Option<String>,
+    pub title: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub message_count: i64,
+    pub user_message_count: i64,
+    pub assistant_message_count: i64,
+    pub total_chars: i64,
+}
+
+impl CsvRow {
+    /// Row for a conversation whose messages are already loaded
+    fn from_messages(conversation: &Conversation, messages: &[Message]) -> Self {
+        let count_role = |role: &str| messages.iter().filter(|m| m.role == role).count() as i64;
+        
+        Self {
+            id: conversation.id,
+            provider: conversation.provider.clone(),
+            model: conversation.model.clone(),
+            title: conversation.title.clone(),
+            created_at: conversation.created_at.to_rfc3339(),
+            updated_at: conversation.updated_at.to_rfc3339(),
+            message_count: messages.len() as i64,
+            user_message_count: count_role("user"),
+            assistant_message_count: count_role("assistant"),
+            total_chars: messages.iter().map(|m| m.content.chars().count() as i64).sum(),
+        }
+    }
+    
+    fn fields(&self) -> [String; 10] {
+        [
+            self.id.to_string(),
+            self.provider.clone(),
+            self.model.clone().unwrap_or_default(),
+            self.title.clone().unwrap_or_default(),
+            self.created_at.clone(),
+            self.updated_at.clone(),
+            self.message_count.to_string(),
+            self.user_message_count.to_string(),
+            self.assistant_message_count.to_string(),
+            self.total_chars.to_string(),
+        ]
+    }
+}
+
+/// Write one csv row per conversation, returning the number of rows
+///
+/// Counts come from a single grouped query rather than a query per conversation.
+pub async fn export_csv<W: Write>(pool: &SqlitePool, out: &mut W) -> Result<usize> {
+    let rows = csv_rows(pool).await?;
+    write_csv(&rows, out)?;
+    out.flush()?;
+    Ok(rows.len())
+}
+
+/// Every conversation's csv row, by id
+async fn csv_rows(pool: &SqlitePool) -> Result<Vec<CsvRow>> {
+    sqlx::query_as(
+        r#"
+        SELECT
+            c.id,
+            c.provider,
+            c.model,
+            c.title,
+            c.created_at,
+            c.updated_at,
+            COUNT(m.id) AS message_count,
+            COALESCE(SUM(m.role = 'user'), 0) AS user_message_count,
+            COALESCE(SUM(m.role = 'assistant'), 0) AS assistant_message_count,
+            COALESCE(SUM(LENGTH(m.content)), 0) AS total_chars
+        FROM conversations c
+        LEFT JOIN messages m ON m.conversation_id = c.id
+        GROUP BY c.id
+        ORDER BY c.id
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to load conversations for csv export")
+}
+
+/// Write the header and rows as RFC 4180 csv
+fn write_csv<W: Write>(rows: &[CsvRow], out: &mut W) -> Result<()> {
+    // CRLF line endings as spreadsheets expect
+    write!(out, "{}\r\n", CSV_COLUMNS.join(","))?;
+    for row in rows {
+        let fields: Vec<String> = row.fields().iter().map(|field| csv_field(field)).collect();
+        write!(out, "{}\r\n", fields.join(","))?;
+    }
+    Ok(())
+}
+
+/// Quote a field if it contains a delimiter, quote, or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// JSON records for a single conversation in the requested format
+///
+/// Transcript formats have no records; render them with `render_conversation`.
+pub fn conversation_records(
+    conversation: &Conversation,
+    messages: &[Message],
+    options: &ExportOptions,
+) -> Vec<Value> {
+    match options.format {
+        ExportFormat::OpenAiChat => openai_chat_record(conversation, messages).into_iter().collect(),
+        ExportFormat::Jsonl if options.flatten => flat_message_records(conversation, messages),
+        ExportFormat::Jsonl | ExportFormat::Json => {
+            vec![json!({ "conversation": conversation, "messages": messages })]
+        }
+        ExportFormat::Markdown | ExportFormat::Html | ExportFormat::Csv => Vec::new(),
+    }
+}
+
+/// Write records one per line, returning how many were written
+pub fn write_jsonl<W: Write>(records: &[Value], out: &mut W) -> Result<usize> {
+    for record in records {
+        serde_json::to_writer(&mut *out, record)?;
+        out.write_all(b"\n")?;
+    }
+    Ok(records.len())
+}
+
+/// One self-contained chunk per message, carrying its conversation's context
+pub fn flat_message_records(conversation: &Conversation, messages: &[Message]) -> Vec<Value> {
+    messages
+        .iter()
+        .enumerate()
+        .map(|(position, message)| {
+            json!({
+                "conversation_id": conversation.id,
+                "position": position,
+                "role": message.role,
+                "content": message.content,
+                "title": conversation.title,
+                "provider": conversation.provider,
+                "model": message.model.as_ref().or(conversation.model.as_ref()),
+                "timestamp": message.created_at.to_rfc3339(),
+            })
+        })
+        .collect()
+}
+
+/// Build a `{"messages": [...]}` fine-tuning example
+///
+/// The conversation's system prompt leads when present. Tool messages and
+/// empty turns are dropped; conversations without both a user turn and an
+/// assistant reply yield `None`.
+pub fn openai_chat_record(conversation: &Conversation, messages: &[Message]) -> Option<Value> {
+    let mut out = Vec::new();
+    
+    if let Some(prompt) = conversation.system_prompt.as_deref().filter(|p| !p.trim().is_empty()) {
+        out.push(json!({ "role": "system", "content": prompt }));
+    }
+    
+    for message in messages {
+        let Some(role) = openai_role(&message.role) else { continue };
+        if message.content.trim().is_empty() {
+            continue;
+        }
+        // The system prompt is often also stored as the first message
+        if role == "system" && out.iter().any(|m| m["content"] == message.content.as_str()) {
+            continue;
+        }
+        out.push(json!({ "role": role, "content": message.content }));
+    }
+    
+    let has_role = |role: &str| out.iter().any(|m| m["role"] == role);
+    if !has_role("user") || !has_role("assistant") {
+        return None;
+    }
+    
+    Some(json!({ "messages": out }))
+}
+
+/// Map an archived role onto OpenAI's chat roles
+fn openai_role(role: &str) -> Option<&'static str> {
+    match role {
+        "system" | "developer" => Some("system"),
+        "user" | "human" => Some("user"),
+        "assistant" | "model" => Some("assistant"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::{conversation, message, test_pool};
+    use crate::import::process_conversation_batch;
+    
+    #[tokio::test]
+    async fn test_openai_chat_export_role_sequence() {
+        let (_dir, pool) = test_pool().await;
+        
+        let mut with_prompt = conversation("claude", "finetune", "Fine-tune me");
+        with_prompt.system_prompt = Some("You are terse.".to_string());
+        
+        let batch = vec![
+            (
+                with_prompt,
+                vec![
+                    message("user", "What is 2 + 2?"),
+                    message("assistant", "4"),
+                    message("tool", "{\"result\": 4}"),
+                    message("user", "And 3 + 3?"),
+                    message("assistant", "6"),
+                ],
+            ),
+            (
+                conversation("chatgpt", "unanswered", "No reply"),
+                vec![message("user", "Hello?")],
+            ),
+        ];
+        process_conversation_batch(&pool, batch).await.unwrap();
+        
+        let mut out = Vec::new();
+        let options = ExportOptions::new(ExportFormat::OpenAiChat, false).unwrap();
+        let written = export_all(&pool, options, &mut out).await.unwrap();
+        assert_eq!(written, 1);
+        
+        let lines: Vec<Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        
+        let roles: Vec<&str> = lines[0]["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, vec!["system", "user", "assistant", "user", "assistant"]);
+        assert_eq!(lines[0]["messages"][0]["content"], "You are terse.");
+    }
+    
+    #[test]
+    fn test_export_format_parse() {
+        assert_eq!("openai-chat".parse::<ExportFormat>().unwrap(), ExportFormat::OpenAiChat);
+        assert_eq!("markdown".parse::<ExportFormat>().unwrap(), ExportFormat::Markdown);
+        assert_eq!("json".parse::<ExportFormat>().unwrap(), ExportFormat::Json);
+        assert!("docx".parse::<ExportFormat>().is_err());
+        assert_eq!("qa".parse::<TranscriptTemplate>().unwrap(), TranscriptTemplate::Qa);
+        assert!("sonnet".parse::<TranscriptTemplate>().is_err());
+        assert!(ExportOptions::new(ExportFormat::OpenAiChat, true).is_err());
+    }
+    
+    #[test]
+    fn test_file_name_sanitizes_title() {
+        let mut conv = conversation("claude", "file", "Plans: \"Q3\" / budget?");
+        conv.id = 7;
+        assert_eq!(file_name(&conv, ExportFormat::Markdown), "Plans-Q3-budget.md");
+        
+        conv.title = Some("../../etc/passwd".to_string());
+        assert_eq!(file_name(&conv, ExportFormat::Json), "etc-passwd.json");
+        
+        conv.title = Some("日本語".to_string());
+        assert_eq!(file_name(&conv, ExportFormat::Html), "conversation-7.html");
+        
+        conv.title = None;
+        assert_eq!(file_name(&conv, ExportFormat::Jsonl), "conversation-7.jsonl");
+    }
+    
+    #[test]
+    fn test_templates_render_distinct_transcripts() {
+        let conv = conversation("claude", "tpl", "Tea <brewing>");
+        let mut question = message("user", "How long should green tea steep?");
+        question.created_at = "2024-05-01T09:30:00Z".parse().unwrap();
+        let mut answer = message("assistant", "About 2-3 minutes at 80°C.");
+        answer.created_at = "2024-05-01T09:31:00Z".parse().unwrap();
+        let messages = vec![question, answer];
+        
+        let default = markdown_transcript(&conv, &messages, TranscriptTemplate::Default);
+        assert_eq!(
+            default,
+            "# Tea <brewing>\n\n\
+             ## USER (2024-05-01 09:30:00)\n\nHow long should green tea steep?\n\n\
+             ## ASSISTANT (2024-05-01 09:31:00)\n\nAbout 2-3 minutes at 80°C.\n"
+        );
+        
+        let qa = markdown_transcript(&conv, &messages, TranscriptTemplate::Qa);
+        assert_eq!(
+            qa,
+            "# Tea <brewing>\n\n\
+             **Q:** How long should green tea steep?\n\n---\n\n\
+             **A:** About 2-3 minutes at 80°C.\n"
+        );
+        
+        let interview = markdown_transcript(&conv, &messages, TranscriptTemplate::Interview);
+        assert!(interview.contains("**Interviewer** (09:30): How long"));
+        
+        let chat = String::from_utf8(render_conversation(
+            &conv,
+            &messages,
+            &ExportOptions { template: TranscriptTemplate::Chat, ..ExportOptions::new(ExportFormat::Html, false).unwrap() },
+        ).unwrap()).unwrap();
+        assert!(chat.starts_with("<!DOCTYPE html>"));
+        assert!(chat.contains("<title>Tea &lt;brewing&gt;</title>"));
+        assert!(chat.contains("<section class=\"transcript transcript-chat\">"));
+        assert!(chat.contains("<div class=\"message-role\">You</div>"));
+        assert!(!chat.contains("<time>"));
+        assert!(chat.trim_end().ends_with("</html>"));
+    }
+    
+    #[test]
+    fn test_redaction_masks_secrets_in_every_format() {
+        let config = crate::config::Config::default();
+        let redactor = config.export.redactor().unwrap();
+        
+        assert_eq!(
+            redactor.redact("Mail jane.doe@example.com the key sk-proj-4fJ9xQ2mLp7RtY8wZk3N"),
+            "Mail [REDACTED] the key [REDACTED]"
+        );
+        let ordinary = "Release 2.4.1 ships on 2024-05-01 at 10:30; see section 3 of the guide.";
+        assert_eq!(redactor.redact(ordinary), ordinary);
+        
+        let mut conv = conversation("chatgpt", "secrets", "Keys for ops@example.com");
+        conv.raw_json = Some(json!({ "note": "token sk-live-abcdefghijklmnopqrstu" }));
+        let messages = vec![
+            message("user", "My key is sk-abcdefghijklmnopqrstuvwx, email me at me@example.org"),
+            message("assistant", ordinary),
+        ];
+        
+        for format in [
+            ExportFormat::OpenAiChat,
+            ExportFormat::Jsonl,
+            ExportFormat::Json,
+            ExportFormat::Markdown,
+            ExportFormat::Html,
+            ExportFormat::Csv,
+        ] {
+            let options = ExportOptions { redactor: Some(redactor.clone()), ..ExportOptions::new(format, false).unwrap() };
+            let body = String::from_utf8(render_conversation(&conv, &messages, &options).unwrap()).unwrap();
+            let name = options.file_name(&conv);
+            
+            for secret in ["example.com", "example.org", "sk-abcdefghij", "sk-live"] {
+                assert!(!body.contains(secret), "{:?} leaked {}", format, secret);
+                assert!(!name.contains(secret.split('.').next().unwrap()), "{:?} file name {} leaked {}", format, name, secret);
+            }
+            assert!(body.contains("[REDACTED]"), "{:?}", format);
+            assert_eq!(name, format!("Keys-for-REDACTED.{}", format.extension()));
+            // Csv rows carry counts rather than message text
+            if format != ExportFormat::Csv {
+                assert!(body.contains("Release 2.4.1 ships on 2024-05-01"), "{:?}", format);
+            }
+        }
+    }
+    
+    /// Split RFC 4180 csv into records of fields
+    fn parse_csv(text: &str) -> Vec<Vec<String>> {
+        let mut records = Vec::new();
+        let mut record = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = text.chars().peekable();
+        
+        while let Some(c) = chars.next() {
+            match (c, quoted) {
+                ('"', true) if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                ('"', _) => quoted = !quoted,
+                (',', false) => record.push(std::mem::take(&mut field)),
+                ('\r', false) => {}
+                ('\n', false) => {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+                (c, _) => field.push(c),
+            }
+        }
+        records
+    }
+    
+    #[tokio::test]
+    async fn test_csv_export_counts_messages_per_conversation() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![
+                (
+                    conversation("claude", "budget", "Budget, \"Q3\"\nreview"),
+                    vec![
+                        message("user", "Totals?"),
+                        message("assistant", "About €40k."),
+                        message("user", "Thanks"),
+                        message("system", "noted"),
+                    ],
+                ),
+                (conversation("chatgpt", "empty", "Empty"), vec![]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let mut out = Vec::new();
+        let options = ExportOptions::new(ExportFormat::Csv, false).unwrap();
+        assert_eq!(export_all(&pool, options, &mut out).await.unwrap(), 2);
+        
+        let records = parse_csv(&String::from_utf8(out).unwrap());
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0], CSV_COLUMNS);
+        
+        let column = |record: &[String], name: &str| {
+            record[CSV_COLUMNS.iter().position(|c| *c == name).unwrap()].clone()
+        };
+        let budget = &records[1];
+        assert_eq!(column(budget, "title"), "Budget, \"Q3\"\nreview");
+        assert_eq!(column(budget, "provider"), "claude");
+        assert_eq!(column(budget, "message_count"), "4");
+        assert_eq!(column(budget, "user_message_count"), "2");
+        assert_eq!(column(budget, "assistant_message_count"), "1");
+        assert_eq!(column(budget, "total_chars"), (7 + 11 + 6 + 5).to_string());
+        
+        assert_eq!(column(&records[2], "message_count"), "0");
+        assert_eq!(column(&records[2], "total_chars"), "0");
+    }
+}
@@ -0,0 +1,161 @@
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use tracing::debug;
+
+use super::parsers::chatgpt::{self, Branch};
+use super::{replace_messages, truncate_messages, ImportOptions};
+use crate::db::terms;
+use crate::models::{Conversation, Message};
+
+/// Branches of a stored conversation and the one its messages follow
+#[derive(Debug, Serialize)]
+pub struct BranchList {
+    pub branch_count: usize,
+    /// Branch chosen through `select`, otherwise the one the export had open
+    pub selected_branch: Option<usize>,
+    pub branches: Vec<Branch>,
+}
+
+/// Branches of a conversation, or `None` if it does not exist
+///
+/// Only ChatGPT exports keep their message tree; other conversations are a
+/// single branch and list none.
+pub async fn list(pool: &SqlitePool, conversation_id: i64) -> Result<Option<BranchList>> {
+    let Some((provider, raw_json)) = stored(pool, conversation_id).await? else {
+        return Ok(None);
+    };
+    
+    let branches = match (provider.as_str(), &raw_json) {
+        ("chatgpt", Some(raw_json)) => chatgpt::branches(raw_json)?,
+        _ => Vec::new(),
+    };
+    
+    let selected_branch = raw_json.as_ref()
+        .and_then(|raw_json| raw_json.get("selected_branch"))
+        .and_then(Value::as_u64)
+        .map(|index| index as usize)
+        .or_else(|| branches.iter().position(|branch| branch.active));
+    
+    Ok(Some(BranchList {
+        branch_count: branches.len().max(1),
+        selected_branch,
+        branches,
+    }))
+}
+
+/// Switch a conversation to branch `index`, replacing its messages and their
+/// search index entries with those along that branch
+///
+/// Returns how many messages the branch has. Messages over
+/// `import.max_message_chars` are cut as an import would cut them. The
+/// choice is kept in `raw_json` and carried over when the conversation is
+/// imported again.
+pub async fn select(
+    pool: &SqlitePool,
+    conversation_id: i64,
+    index: usize,
+    options: &ImportOptions,
+) -> Result<usize> {
+    let Some((provider, Some(mut raw_json))) = stored(pool, conversation_id).await? else {
+        bail!("Conversation {} has no stored message tree", conversation_id);
+    };
+    if provider != "chatgpt" {
+        bail!("Conversation {} has no branches", conversation_id);
+    }
+    let Some(mut messages) = chatgpt::branch_messages(&raw_json, index, options)? else {
+        bail!("Conversation {} has no branch {}", conversation_id, index);
+    };
+    
+    // Full texts are kept by position, so the old branch's no longer apply
+    if let Some(object) = raw_json.as_object_mut() {
+        object.remove("truncated_messages");
+    }
+    if let Some(max_chars) = options.max_message_chars {
+        let originals = truncate_messages(&mut messages, max_chars);
+        if !originals.is_empty() {
+            raw_json["truncated_messages"] = Value::Object(originals);
+        }
+    }
+    raw_json["selected_branch"] = Value::from(index);
+    
+    let mut tx = pool.begin().await?;
+    let mut term_counts = HashMap::new();
+    
+    let count = replace_messages(&mut tx, conversation_id, messages, &mut term_counts).await?;
+    terms::apply(&mut tx, &term_counts).await?;
+    
+    sqlx::query("UPDATE conversations SET raw_json = ?2 WHERE id = ?1")
+        .bind(conversation_id)
+        .bind(raw_json.to_string())
+        .execute(&mut *tx)
+        .await
+        .context("Failed to record the selected branch")?;
+    
+    tx.commit().await?;
+    
+    Ok(count)
+}
+
+/// Keep branches chosen through `select` when conversations are imported
+/// again
+///
+/// A re-imported ChatGPT conversation with a selected branch gets that
+/// branch's messages instead of the ones the export had open. A selection
+/// the new export no longer has a branch for is dropped.
+pub(crate) async fn keep_selected(
+    pool: &SqlitePool,
+    conversations: &mut [(Conversation, Vec<Message>)],
+    options: &ImportOptions,
+) -> Result<()> {
+    for (conv, messages) in conversations.iter_mut() {
+        let (Some(external_id), Some(raw_json)) = (&conv.external_id, conv.raw_json.as_mut()) else {
+            continue;
+        };
+        if conv.provider != "chatgpt" {
+            continue;
+        }
+        
+        let selected: Option<i64> = sqlx::query_scalar(
+            "SELECT json_extract(raw_json, '$.selected_branch') FROM conversations WHERE provider = ?1 AND external_id = ?2",
+        )
+        .bind(&conv.provider)
+        .bind(external_id)
+        .fetch_optional(pool)
+        .await?
+        .flatten();
+        let Some(index) = selected else {
+            continue;
+        };
+        
+        match chatgpt::branch_messages(raw_json, index as usize, options)? {
+            Some(branch) => {
+                *messages = branch;
+                raw_json["selected_branch"] = Value::from(index);
+            }
+            None => debug!("Dropping selected branch {} of {:?}; the export no longer has it", index, external_id),
+        }
+    }
+    
+    Ok(())
+}
+
+/// Provider and parsed `raw_json` of a conversation
+async fn stored(pool: &SqlitePool, conversation_id: i64) -> Result<Option<(String, Option<Value>)>> {
+    let row: Option<(String, Option<String>)> =
+        sqlx::query_as("SELECT provider, raw_json FROM conversations WHERE id = ?1")
+            .bind(conversation_id)
+            .fetch_optional(pool)
+            .await?;
+    
+    row.map(|(provider, raw_json)| {
+        let raw_json = raw_json
+            .map(|text| serde_json::from_str(&text))
+            .transpose()
+            .context("Stored raw_json is not valid JSON")?;
+        Ok((provider, raw_json))
+    })
+    .transpose()
+}
@@ -0,0 +1,102 @@
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+
+use crate::models::{Conversation, Message};
+
+/// Fewest letters of user text a language is guessed from; shorter
+/// conversations are left without a language
+const MIN_LETTERS: usize = 40;
+
+/// ISO 639-1 codes for the ISO 639-3 codes `whatlang` detects
+const ISO_639_1: &[(&str, &str)] = &[
+    ("afr", "af"), ("aka", "ak"), ("amh", "am"), ("ara", "ar"), ("aze", "az"),
+    ("bel", "be"), ("ben", "bn"), ("bul", "bg"), ("cat", "ca"), ("ces", "cs"),
+    ("cmn", "zh"), ("dan", "da"), ("deu", "de"), ("ell", "el"), ("eng", "en"),
+    ("epo", "eo"), ("est", "et"), ("fin", "fi"), ("fra", "fr"), ("guj", "gu"),
+    ("heb", "he"), ("hin", "hi"), ("hrv", "hr"), ("hun", "hu"), ("hye", "hy"),
+    ("ind", "id"), ("ita", "it"), ("jav", "jv"), ("jpn", "ja"), ("kan", "kn"),
+    ("kat", "ka"), ("khm", "km"), ("kor", "ko"), ("lat", "la"), ("lav", "lv"),
+    ("lit", "lt"), ("mal", "ml"), ("mar", "mr"), ("mkd", "mk"), ("mya", "my"),
+    ("nep", "ne"), ("nld", "nl"), ("nob", "nb"), ("ori", "or"), ("pan", "pa"),
+    ("pes", "fa"), ("pol", "pl"), ("por", "pt"), ("ron", "ro"), ("rus", "ru"),
+    ("sin", "si"), ("slk", "sk"), ("slv", "sl"), ("sna", "sn"), ("spa", "es"),
+    ("srp", "sr"), ("swe", "sv"), ("tam", "ta"), ("tel", "te"), ("tgl", "tl"),
+    ("tha", "th"), ("tuk", "tk"), ("tur", "tr"), ("ukr", "uk"), ("urd", "ur"),
+    ("uzb", "uz"), ("vie", "vi"), ("yid", "yi"), ("zul", "zu"),
+];
+
+/// Language of a conversation, from its user messages
+///
+/// Returns an ISO 639-1 code such as `en`, or `None` when there is too
+/// little text or the guess is unreliable.
+pub fn detect(messages: &[Message]) -> Option<String> {
+    let text = messages
+        .iter()
+        .filter(|m| m.role == "user")
+        .map(|m| m.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    
+    if text.chars().filter(|c| c.is_alphabetic()).count() < MIN_LETTERS {
+        return None;
+    }
+    
+    let info = whatlang::detect(&text).filter(|info| info.is_reliable())?;
+    let code = info.lang().code();
+    let code = ISO_639_1
+        .iter()
+        .find(|(iso_639_3, _)| *iso_639_3 == code)
+        .map_or(code, |(_, iso_639_1)| *iso_639_1);
+    
+    Some(code.to_string())
+}
+
+/// Detect the language of each conversation in a batch, keyed by
+/// `(provider, external_id)` so it can be stored once the batch is in
+///
+/// Conversations without an external id can't be found again and are skipped.
+pub fn detect_batch(conversations: &[(Conversation, Vec<Message>)]) -> Vec<(String, String, Option<String>)> {
+    conversations
+        .iter()
+        .filter_map(|(conv, messages)| {
+            let external_id = conv.external_id.clone()?;
+            Some((conv.provider.clone(), external_id, detect(messages)))
+        })
+        .collect()
+}
+
+/// Store languages from `detect_batch`; undetected ones are stored as NULL
+pub async fn store(pool: &SqlitePool, languages: &[(String, String, Option<String>)]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    
+    for (provider, external_id, language) in languages {
+        sqlx::query("UPDATE conversations SET language = ?1 WHERE provider = ?2 AND external_id = ?3")
+            .bind(language)
+            .bind(provider)
+            .bind(external_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to store conversation language")?;
+    }
+    
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::message;
+    
+    #[test]
+    fn test_detect_needs_enough_user_text() {
+        let english = message("user", "Could you explain how the tides work and why there are two of them every day?");
+        assert_eq!(detect(&[english.clone()]).as_deref(), Some("en"));
+        
+        assert_eq!(detect(&[message("user", "Hi there")]), None);
+        
+        // Assistant replies don't count towards the language
+        let reply = message("assistant", &english.content);
+        assert_eq!(detect(&[message("user", "Hi there"), reply]), None);
+    }
+}
@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+/// Hash a file with SHA-256, reading it in chunks so large exports
+/// are never fully buffered
+pub async fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open {:?} for hashing", path))?;
+    
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Check whether this exact file content was already imported successfully
+/// from the source `key` names
+pub async fn is_imported(pool: &SqlitePool, key: &str, sha256: &str) -> Result<bool> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        r#"
+        SELECT 1 FROM import_manifest
+        WHERE file_path = ?1 AND file_sha256 = ?2 AND status = 'completed'
+        "#,
+    )
+    .bind(key)
+    .bind(sha256)
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(row.is_some())
+}
+
+/// Record the outcome of importing a file from the source `key` names
+pub async fn record(
+    pool: &SqlitePool,
+    key: &str,
+    sha256: &str,
+    provider: &str,
+    status: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO import_manifest (file_path, file_sha256, provider, status)
+        VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT(file_path, file_sha256) DO UPDATE SET
+            provider = excluded.provider,
+            status = excluded.status,
+            imported_at = CURRENT_TIMESTAMP
+        "#,
+    )
+    .bind(key)
+    .bind(sha256)
+    .bind(provider)
+    .bind(status)
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+/// Canonical path used as a local file's manifest key, so `./a.json` and
+/// `a.json` match
+pub fn path_key(path: &Path) -> String {
+    std::fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
@@ -1,83 +1,581 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
-use std::path::Path;
-use tracing::{error, info, warn};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tracing::{debug, error, info, warn};
 
+pub mod branches;
+pub mod language;
+pub mod manifest;
 pub mod python_bridge;
 pub mod parsers;
+pub mod remote;
+pub mod tokens;
 
-use crate::models::{Conversation, ImportStats, Message, ProviderType};
+use crate::config::ImportConfig;
+use crate::db::terms;
+use crate::models::{Conversation, ImportEvent, ImportStats, Message, ProviderType};
+
+/// Options controlling a single import run
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Use Python parsers via the PyO3 bridge
+    pub use_python_bridge: bool,
+    /// Re-import files even if the manifest says they are unchanged
+    pub force: bool,
+    /// Carry the last-seen assistant model forward to null-model assistant messages
+    pub carry_forward_model: bool,
+    /// Offset applied to timestamps without zone info; `None` means UTC
+    pub default_timezone: Option<FixedOffset>,
+    /// What to do with regenerated/edited branches in tree-shaped exports
+    pub branch_mode: BranchMode,
+    /// Skip conversations last updated before this instant
+    pub since: Option<DateTime<Utc>>,
+    /// Count tokens for messages whose export has no count
+    pub count_tokens: bool,
+    /// Detect each conversation's language from its user messages
+    pub detect_language: bool,
+    /// Longest title derived from a first message; `None` means the default
+    pub title_length: Option<usize>,
+    /// Conversations stored per transaction; `None` means the default
+    pub batch_size: Option<usize>,
+    /// Fallback model per provider; `None` means the built-in defaults
+    pub default_models: Option<HashMap<String, String>>,
+    /// Store no model rather than a fallback when the export names none
+    pub strict_models: bool,
+    /// Replace missing and placeholder titles with one made from the first user message
+    pub generate_titles: bool,
+    /// Cut message content longer than this many characters
+    pub max_message_chars: Option<usize>,
+    /// Notified with the running totals after each file and each stored batch
+    pub progress: Option<ImportProgress>,
+    /// Size and time limits for `http(s)://` and `s3://` sources
+    pub download_limits: remote::DownloadLimits,
+}
+
+impl ImportOptions {
+    /// Build options from the `[import]` config section
+    pub fn from_config(config: &ImportConfig) -> Result<Self> {
+        let default_timezone = parsers::parse_utc_offset(&config.default_timezone)
+            .ok_or_else(|| anyhow::anyhow!(
+                "Invalid import.default_timezone {:?}; expected UTC or an offset like +02:00",
+                config.default_timezone
+            ))?;
+        
+        if !(1..=parsers::MAX_BATCH_SIZE).contains(&config.batch_size) {
+            anyhow::bail!(
+                "Invalid import.batch_size {}; expected a value between 1 and {}",
+                config.batch_size,
+                parsers::MAX_BATCH_SIZE
+            );
+        }
+        
+        Ok(Self {
+            carry_forward_model: config.carry_forward_model,
+            default_timezone: Some(default_timezone),
+            branch_mode: config.branch_mode,
+            title_length: Some(config.title_length),
+            batch_size: Some(config.batch_size),
+            default_models: Some(config.default_models.clone()),
+            strict_models: config.strict_models,
+            max_message_chars: (config.max_message_chars > 0).then_some(config.max_message_chars),
+            detect_language: config.detect_language,
+            download_limits: remote::DownloadLimits {
+                max_bytes: config.max_download_mb * 1024 * 1024,
+                timeout: std::time::Duration::from_secs(config.download_timeout_secs),
+                allowed_hosts: None,
+            },
+            ..Self::default()
+        })
+    }
+    
+    /// Offset to interpret naive timestamps in
+    pub fn timezone(&self) -> FixedOffset {
+        self.default_timezone
+            .unwrap_or_else(|| FixedOffset::east_opt(0).expect("zero offset is valid"))
+    }
+    
+    /// Character budget for titles derived from message text
+    pub fn title_length(&self) -> usize {
+        self.title_length.unwrap_or(parsers::DEFAULT_TITLE_LENGTH)
+    }
+    
+    /// Conversations to collect before writing them in one transaction
+    pub fn batch_size(&self) -> usize {
+        self.batch_size.unwrap_or(parsers::DEFAULT_BATCH_SIZE).max(1)
+    }
+    
+    /// Model to record for a `provider` conversation whose export names none
+    pub fn default_model(&self, provider: ProviderType) -> Option<String> {
+        if self.strict_models {
+            return None;
+        }
+        
+        match &self.default_models {
+            Some(models) => models.get(provider.as_str()).cloned(),
+            None => parsers::default_models().remove(provider.as_str()),
+        }
+    }
+}
+
+/// Callback receiving running import totals
+#[derive(Clone)]
+pub struct ImportProgress(Arc<dyn Fn(&ImportStats) + Send + Sync>);
+
+impl ImportProgress {
+    pub fn new(callback: impl Fn(&ImportStats) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+    
+    pub fn report(&self, stats: &ImportStats) {
+        (self.0)(stats)
+    }
+}
+
+/// Pass the running totals to the progress callback, if there is one
+fn report_progress(options: &ImportOptions, stats: &ImportStats) {
+    if let Some(progress) = &options.progress {
+        progress.report(stats);
+    }
+}
+
+impl std::fmt::Debug for ImportProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ImportProgress")
+    }
+}
+
+/// How branching conversation trees (regenerations, edits) are imported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BranchMode {
+    /// Import only the branch the user last viewed
+    #[default]
+    ActiveOnly,
+    /// Import the active branch and keep the alternates in conversation metadata
+    AllBranches,
+}
 
 /// Import conversations from export files
+///
+/// Files whose path and content hash were already imported successfully
+/// are skipped unless `force` is set. `path` may also be an `http(s)://`
+/// or `s3://` URL, which is downloaded first.
+///
+/// With provider `auto`, each file in a directory is detected on its own and
+/// files no provider recognizes are skipped and listed in
+/// `ImportStats::unrecognized_files`; a single file that can't be detected,
+/// or an unknown provider name, is an error.
 pub async fn import_conversations(
     pool: &SqlitePool,
     provider: &str,
     path: &Path,
-    use_python_bridge: bool,
-) -> Result<usize> {
-    let provider_type = ProviderType::from_str(provider);
+    options: &ImportOptions,
+) -> Result<ImportStats> {
+    // Downloads land in a new temporary directory each time, so the
+    // manifest knows them by URL instead
+    let remote_key = path
+        .to_str()
+        .filter(|source| remote::is_remote(source))
+        .map(|source| remote::manifest_source(source).to_string());
     
-    if provider_type == ProviderType::Unknown {
-        return Err(anyhow::anyhow!("Unknown provider: {}", provider));
-    }
+    // Held until the import finishes so the download is cleaned up after
+    let (_download_dir, path) = fetch_source(path, options).await?;
+    let path = path.as_path();
     
+    let provider_type = if !provider.eq_ignore_ascii_case("auto") {
+        match ProviderType::from_str(provider) {
+            ProviderType::Unknown => return Err(anyhow::anyhow!("Unknown provider: {}", provider)),
+            known => Some(known),
+        }
+    } else if path.is_dir() {
+        // Detected per file in `import_files`
+        None
+    } else {
+        let detected = detect_provider_for_file(path).await?;
+        info!("Detected provider: {}", detected.as_str());
+        Some(detected)
+    };
+    
+    let provider = provider_type.map_or("auto", |provider_type| provider_type.as_str());
     info!("Starting import for provider: {}", provider);
     
     // Log import event
     let event_id = log_import_start(pool, provider, path).await?;
     
-    let mut stats = ImportStats::default();
+    let mut stats = ImportStats {
+        parser_version: parser_version(options),
+        ..ImportStats::default()
+    };
     let start = std::time::Instant::now();
     
-    let result = if use_python_bridge {
-        // Use Python parsers via PyO3 bridge (temporary)
-        python_bridge::import_with_python(pool, provider_type, path, &mut stats).await
-    } else {
-        // Use native Rust parsers
-        match provider_type {
-            ProviderType::ChatGPT => parsers::chatgpt::import(pool, path, &mut stats).await,
-            ProviderType::Claude => parsers::claude::import(pool, path, &mut stats).await,
-            ProviderType::Gemini => parsers::gemini::import(pool, path, &mut stats).await,
-            ProviderType::XAI => parsers::xai::import(pool, path, &mut stats).await,
-            ProviderType::Zed => parsers::zed::import(pool, path, &mut stats).await,
-            _ => Err(anyhow::anyhow!("Native parser not implemented for {}", provider)),
-        }
-    };
+    let result = import_files(pool, provider_type, path, remote_key.as_deref(), options, &mut stats).await;
     
     stats.duration_ms = start.elapsed().as_millis() as u64;
     
     // Log import completion
     log_import_complete(pool, event_id, &stats, result.as_ref().err()).await?;
     
+    info!(
+        "Processed {} files, skipped {} unchanged files",
+        stats.files_processed, stats.files_skipped
+    );
+    if !stats.unrecognized_files.is_empty() {
+        warn!("Skipped {} files no provider recognized", stats.unrecognized_files.len());
+    }
+    if let Some(since) = options.since {
+        info!("Skipped {} conversations last updated before {}", stats.filtered, since);
+    }
+    
     match result {
         Ok(_) => {
             info!(
                 "Import completed: {} conversations, {} messages in {}ms",
                 stats.conversations, stats.messages, stats.duration_ms
             );
-            crate::metrics::track_import(
-                provider,
-                stats.conversations,
-                stats.messages,
-                std::time::Duration::from_millis(stats.duration_ms),
-                true,
-            );
-            Ok(stats.conversations)
+            crate::metrics::track_import(provider, std::time::Duration::from_millis(stats.duration_ms), true);
+            Ok(stats)
         }
         Err(e) => {
             error!("Import failed: {}", e);
-            crate::metrics::track_import(
-                provider,
-                stats.conversations,
-                stats.messages,
-                std::time::Duration::from_millis(stats.duration_ms),
-                false,
-            );
+            crate::metrics::track_import(provider, std::time::Duration::from_millis(stats.duration_ms), false);
             Err(e)
         }
     }
 }
 
+/// Download remote sources into a temporary directory; local paths pass through
+async fn fetch_source(path: &Path, options: &ImportOptions) -> Result<(Option<tempfile::TempDir>, PathBuf)> {
+    let Some(source) = path.to_str().filter(|source| remote::is_remote(source)) else {
+        return Ok((None, path.to_path_buf()));
+    };
+    
+    let dir = tempfile::tempdir().context("Failed to create download directory")?;
+    let local = remote::download(source, dir.path(), &options.download_limits).await?;
+    Ok((Some(dir), local))
+}
+
+/// Import every export file under `path`, consulting the import manifest
+///
+/// Without a `provider`, each file's provider is detected from its content.
+/// Files are keyed in the manifest by `remote_key` when `path` was
+/// downloaded, and by their canonical path otherwise.
+async fn import_files(
+    pool: &SqlitePool,
+    provider: Option<ProviderType>,
+    path: &Path,
+    remote_key: Option<&str>,
+    options: &ImportOptions,
+    stats: &mut ImportStats,
+) -> Result<()> {
+    let files = collect_export_files(path, provider).await?;
+    stats.files_total = files.len();
+    // Transcript ids are relative to this, so a moved tree keeps them
+    let root = if path.is_dir() { path } else { path.parent().unwrap_or(Path::new("")) };
+    report_progress(options, stats);
+    
+    for file in files {
+        let sha256 = manifest::file_sha256(&file).await?;
+        let key = remote_key.map_or_else(|| manifest::path_key(&file), str::to_string);
+        
+        if !options.force && manifest::is_imported(pool, &key, &sha256).await? {
+            debug!("Skipping unchanged file {:?}", file);
+            stats.files_skipped += 1;
+            report_progress(options, stats);
+            continue;
+        }
+        
+        let provider_type = match provider {
+            Some(provider_type) => provider_type,
+            None => match detect_provider_for_file(&file).await {
+                Ok(detected) => detected,
+                Err(e) => {
+                    warn!("Skipping {:?}: {}", file, e);
+                    stats.unrecognized_files.push(file);
+                    report_progress(options, stats);
+                    continue;
+                }
+            },
+        };
+        
+        let result = import_file(pool, provider_type, root, &file, options, stats).await;
+        let status = if result.is_ok() { "completed" } else { "failed" };
+        manifest::record(pool, &key, &sha256, provider_type.as_str(), status).await?;
+        
+        result.with_context(|| format!("Failed to import {:?}", file))?;
+        stats.files_processed += 1;
+        report_progress(options, stats);
+    }
+    
+    Ok(())
+}
+
+/// Import a single export file with the parser for `provider_type`,
+/// recording what it produced in the per-provider import metrics
+///
+/// A file that fails outright counts as one parse error on top of any the
+/// parser recorded before failing.
+async fn import_file(
+    pool: &SqlitePool,
+    provider_type: ProviderType,
+    root: &Path,
+    path: &Path,
+    options: &ImportOptions,
+    stats: &mut ImportStats,
+) -> Result<()> {
+    let before = (stats.conversations, stats.messages, stats.errors, stats.warnings);
+    let result = parse_file(pool, provider_type, root, path, options, stats).await;
+    
+    crate::metrics::track_import_file(
+        provider_type.as_str(),
+        stats.conversations - before.0,
+        stats.messages - before.1,
+        stats.errors - before.2 + usize::from(result.is_err()),
+        stats.warnings - before.3,
+    );
+    
+    result
+}
+
+/// Run the parser for `provider_type` over one export file found under `root`
+async fn parse_file(
+    pool: &SqlitePool,
+    provider_type: ProviderType,
+    root: &Path,
+    path: &Path,
+    options: &ImportOptions,
+    stats: &mut ImportStats,
+) -> Result<()> {
+    // There is no Python parser for transcripts
+    if options.use_python_bridge && provider_type != ProviderType::Markdown {
+        // Use Python parsers via PyO3 bridge (temporary)
+        python_bridge::import_with_python(pool, provider_type, path, options, stats).await
+    } else {
+        // Use native Rust parsers
+        match provider_type {
+            ProviderType::ChatGPT => parsers::chatgpt::import(pool, path, stats, options).await,
+            ProviderType::Claude => parsers::claude::import(pool, path, stats, options).await,
+            ProviderType::Gemini => parsers::gemini::import(pool, path, stats, options).await,
+            ProviderType::XAI => parsers::xai::import(pool, path, stats, options).await,
+            ProviderType::Zed => parsers::zed::import(pool, path, stats, options).await,
+            ProviderType::Markdown => parsers::markdown::import(pool, root, path, stats, options).await,
+            _ => Err(anyhow::anyhow!("Native parser not implemented for {}", provider_type.as_str())),
+        }
+    }
+}
+
+/// Expand `path` into the list of export files to import
+///
+/// Directory entries are sorted by path so conversations get the same ids
+/// in the same order on every machine, whatever order the filesystem lists
+/// them in.
+///
+/// Transcripts (`.md`, `.markdown`, `.txt`) are only collected for the
+/// markdown provider, which also walks subdirectories, and for
+/// auto-detection; other providers take JSON files only, so a README next
+/// to an export doesn't fail the import.
+async fn collect_export_files(path: &Path, provider: Option<ProviderType>) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    
+    if !path.is_dir() {
+        return Err(anyhow::anyhow!("Path is neither file nor directory: {:?}", path));
+    }
+    
+    let transcripts = matches!(provider, None | Some(ProviderType::Markdown));
+    let recursive = provider == Some(ProviderType::Markdown);
+    
+    let mut files = Vec::new();
+    let mut dirs = vec![path.to_path_buf()];
+    
+    while let Some(dir) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_path = entry.path();
+            if file_path.is_dir() {
+                if recursive {
+                    dirs.push(file_path);
+                }
+                continue;
+            }
+            
+            let is_export = match file_path.extension().and_then(|s| s.to_str()) {
+                Some("json" | "jsonl") => true,
+                Some("md" | "markdown" | "txt") => transcripts,
+                _ => false,
+            };
+            if file_path.is_file() && is_export {
+                files.push(file_path);
+            }
+        }
+    }
+    
+    files.sort();
+    Ok(files)
+}
+
+/// Store a parsed batch, applying import-wide filters, and add its counts to `stats`
+///
+/// Native parsers hand their batches here so filters such as `since`,
+/// token counting, and language detection behave the same for every
+/// export format.
+pub async fn import_batch(
+    pool: &SqlitePool,
+    mut conversations: Vec<(Conversation, Vec<Message>)>,
+    options: &ImportOptions,
+    stats: &mut ImportStats,
+) -> Result<ImportStats> {
+    if let Some(since) = options.since {
+        let before = conversations.len();
+        conversations.retain(|(conv, _)| conv.updated_at >= since);
+        stats.filtered += before - conversations.len();
+    }
+    
+    branches::keep_selected(pool, &mut conversations, options).await?;
+    
+    if options.generate_titles {
+        for (conv, messages) in conversations.iter_mut() {
+            if !parsers::is_placeholder_title(conv.title.as_deref()) {
+                continue;
+            }
+            let title = parsers::synthesize_title(messages);
+            if !title.is_empty() {
+                conv.title = Some(title);
+            }
+        }
+    }
+    
+    if let Some(max_chars) = options.max_message_chars {
+        for (conv, messages) in conversations.iter_mut() {
+            stats.warnings += truncate_long_messages(conv, messages, max_chars);
+        }
+    }
+    
+    if options.count_tokens {
+        for (_, messages) in conversations.iter_mut() {
+            tokens::fill_missing(messages);
+        }
+    }
+    
+    let languages = options.detect_language.then(|| language::detect_batch(&conversations));
+    
+    let batch_stats = insert_batch_with_retry(pool, conversations).await?;
+    if let Some(languages) = languages {
+        language::store(pool, &languages).await?;
+    }
+    stats.conversations += batch_stats.conversations;
+    stats.messages += batch_stats.messages;
+    stats.new += batch_stats.new;
+    stats.updated += batch_stats.updated;
+    stats.skipped_duplicates += batch_stats.skipped_duplicates;
+    report_progress(options, stats);
+    
+    Ok(batch_stats)
+}
+
+/// Cut messages longer than `max_chars`, ending them with a marker saying
+/// how much was dropped
+///
+/// The full text goes into the conversation's `raw_json` under
+/// `truncated_messages`, keyed by message position, so only the stored and
+/// indexed content is shortened. Returns how many messages were cut.
+fn truncate_long_messages(conv: &mut Conversation, messages: &mut [Message], max_chars: usize) -> usize {
+    let originals = truncate_messages(messages, max_chars);
+    let truncated = originals.len();
+    if truncated == 0 {
+        return 0;
+    }
+    debug!("Truncated {} long messages in {:?}", truncated, conv.external_id);
+    
+    let raw_json = conv.raw_json.get_or_insert_with(|| serde_json::json!({}));
+    if !raw_json.is_object() {
+        *raw_json = serde_json::json!({ "export": raw_json.take() });
+    }
+    raw_json["truncated_messages"] = serde_json::Value::Object(originals);
+    
+    truncated
+}
+
+/// Cut messages longer than `max_chars`, returning their full text keyed
+/// by message position
+pub(crate) fn truncate_messages(messages: &mut [Message], max_chars: usize) -> serde_json::Map<String, serde_json::Value> {
+    let mut originals = serde_json::Map::new();
+    
+    for (position, message) in messages.iter_mut().enumerate() {
+        let Some((cut, _)) = message.content.char_indices().nth(max_chars) else {
+            continue;
+        };
+        let dropped = message.content[cut..].chars().count();
+        let original = std::mem::take(&mut message.content);
+        message.content = format!("{}…[truncated {} chars]", &original[..cut], dropped);
+        originals.insert(position.to_string(), serde_json::Value::String(original));
+    }
+    
+    originals
+}
+
+/// Attempts `insert_batch_with_retry` makes before giving up
+const INSERT_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry, doubled for each one after
+const INSERT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Store a batch, retrying with exponential backoff while SQLite reports
+/// the database busy or locked
+///
+/// Another writer outlasting the busy timeout is usually done a moment
+/// later; any other error is returned straight away.
+pub async fn insert_batch_with_retry(
+    pool: &SqlitePool,
+    conversations: Vec<(Conversation, Vec<Message>)>,
+) -> Result<ImportStats> {
+    retry_transient(|| process_conversation_batch(pool, conversations.clone())).await
+}
+
+/// Run `operation` up to `INSERT_ATTEMPTS` times while it fails with a lock error
+async fn retry_transient<T, F, Fut>(mut operation: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut backoff = INSERT_BACKOFF;
+    let mut attempt = 1;
+    
+    loop {
+        match operation().await {
+            Err(e) if attempt < INSERT_ATTEMPTS && is_locked(&e) => {
+                warn!(
+                    "Database locked (attempt {} of {}), retrying in {:?}: {:#}",
+                    attempt, INSERT_ATTEMPTS, backoff, e
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether an error comes from SQLite reporting the database busy or locked
+fn is_locked(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| match cause.downcast_ref::<sqlx::Error>() {
+        Some(sqlx::Error::Database(e)) => {
+            // SQLITE_BUSY and SQLITE_LOCKED, including their extended codes
+            let primary = e.code().and_then(|code| code.parse::<i32>().ok()).map(|code| code & 0xff);
+            matches!(primary, Some(5 | 6)) || e.message().contains("database is locked")
+        }
+        _ => false,
+    })
+}
+
 /// Process a single conversation batch
 pub async fn process_conversation_batch(
     pool: &SqlitePool,
@@ -87,8 +585,32 @@ pub async fn process_conversation_batch(
     
     // Start transaction for atomic import
     let mut tx = pool.begin().await?;
+    let mut term_counts = HashMap::new();
+    
+    // Exports can repeat a conversation; as the upsert would, the last copy wins
+    let mut last_copy = HashMap::new();
+    for (index, (conv, _)) in conversations.iter().enumerate() {
+        if let Some(external_id) = &conv.external_id {
+            last_copy.insert((conv.provider.clone(), external_id.clone()), index);
+        }
+    }
     
-    for (conv, messages) in conversations {
+    for (index, (conv, messages)) in conversations.into_iter().enumerate() {
+        let repeated = conv.external_id.as_ref().is_some_and(|external_id| {
+            last_copy.get(&(conv.provider.clone(), external_id.clone())) != Some(&index)
+        });
+        if repeated {
+            stats.skipped_duplicates += 1;
+            continue;
+        }
+        
+        let existing: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM conversations WHERE provider = ?1 AND external_id = ?2")
+                .bind(&conv.provider)
+                .bind(&conv.external_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        
         // Insert conversation
         let conv_id = sqlx::query!(
             r#"
@@ -125,41 +647,91 @@ pub async fn process_conversation_batch(
         .context("Failed to insert conversation")?;
         
         stats.conversations += 1;
-        
-        // Insert messages in batches
-        for message in messages {
-            sqlx::query!(
-                r#"
-                INSERT INTO messages (
-                    conversation_id, role, content, model,
-                    created_at, tokens, finish_reason, 
-                    tool_calls, attachments
-                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-                "#,
-                conv_id.id,
-                message.role,
-                message.content,
-                message.model,
-                message.created_at,
-                message.tokens,
-                message.finish_reason,
-                message.tool_calls.as_ref().map(|v| v.to_string()),
-                message.attachments.as_ref().map(|v| v.to_string()),
-            )
-            .execute(&mut *tx)
-            .await
-            .context("Failed to insert message")?;
-            
-            stats.messages += 1;
+        if existing.is_some() {
+            stats.updated += 1;
+        } else {
+            stats.new += 1;
         }
+        
+        // A re-imported conversation replaces its messages rather than
+        // appending to them
+        stats.messages += replace_messages(&mut tx, conv_id.id, messages, &mut term_counts).await?;
     }
     
+    terms::apply(&mut tx, &term_counts).await?;
+    
     // Commit transaction
     tx.commit().await?;
     
     Ok(stats)
 }
 
+/// Replace a conversation's messages, returning how many were inserted
+///
+/// The delete trigger drops the old messages' FTS rows and attachments
+/// cascade; their words are uncounted in `term_counts`, which the caller
+/// applies.
+pub(crate) async fn replace_messages(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    conversation_id: i64,
+    messages: Vec<Message>,
+    term_counts: &mut HashMap<String, i64>,
+) -> Result<usize> {
+    let mut inserted_count = 0;
+    
+    let previous: Vec<String> = sqlx::query_scalar("SELECT content FROM messages WHERE conversation_id = ?1")
+        .bind(conversation_id)
+        .fetch_all(&mut **tx)
+        .await?;
+    for content in &previous {
+        terms::count(term_counts, content, -1);
+    }
+    
+    sqlx::query("DELETE FROM messages WHERE conversation_id = ?1")
+        .bind(conversation_id)
+        .execute(&mut **tx)
+        .await
+        .context("Failed to clear previous messages")?;
+    
+    // Insert messages in batches
+    for message in messages {
+        terms::count(term_counts, &message.content, 1);
+        
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO messages (
+                conversation_id, role, content, model,
+                created_at, tokens, finish_reason, 
+                tool_calls, attachments
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            conversation_id,
+            message.role,
+            message.content,
+            message.model,
+            message.created_at,
+            message.tokens,
+            message.finish_reason,
+            message.tool_calls.as_ref().map(|v| v.to_string()),
+            message.attachments.as_ref().map(|v| v.to_string()),
+        )
+        .execute(&mut **tx)
+        .await
+        .context("Failed to insert message")?;
+        
+        if let Some(attachments) = &message.attachments {
+            let records = crate::db::attachments::from_json(attachments);
+            crate::db::attachments::insert(tx, inserted.last_insert_rowid(), &records).await?;
+        }
+        
+        crate::db::revisions::insert(tx, inserted.last_insert_rowid(), &message.revisions).await?;
+        
+        inserted_count += 1;
+    }
+    
+    Ok(inserted_count)
+}
+
 /// Log import start event
 async fn log_import_start(pool: &SqlitePool, provider: &str, path: &Path) -> Result<i64> {
     let result = sqlx::query!(
@@ -189,7 +761,16 @@ async fn log_import_complete(
         "conversations": stats.conversations,
         "messages": stats.messages,
         "errors": stats.errors,
+        "warnings": stats.warnings,
         "duration_ms": stats.duration_ms,
+        "files_processed": stats.files_processed,
+        "files_skipped": stats.files_skipped,
+        "filtered": stats.filtered,
+        "failed_conversations": stats.failed_conversations,
+        "new": stats.new,
+        "updated": stats.updated,
+        "skipped_duplicates": stats.skipped_duplicates,
+        "parser_version": stats.parser_version,
     });
     
     sqlx::query!(
@@ -209,20 +790,782 @@ async fn log_import_complete(
     Ok(())
 }
 
+/// Most recent import runs, newest first, with their stats parsed
+pub async fn history(pool: &SqlitePool, limit: usize) -> Result<Vec<ImportEvent>> {
+    let rows: Vec<(i64, String, Option<String>, String, Option<String>, Option<String>, String)> = sqlx::query_as(
+        r#"
+        SELECT id, provider, file_path, status, stats, error, created_at
+        FROM import_events
+        ORDER BY created_at DESC, id DESC
+        LIMIT ?1
+        "#,
+    )
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await
+    .context("Failed to load import history")?;
+    
+    rows.into_iter()
+        .map(|(id, provider, file_path, status, stats, error, created_at)| {
+            let stats = stats
+                .map(|stats| serde_json::from_str(&stats))
+                .transpose()
+                .context("Stored import stats are not valid JSON")?;
+            Ok(ImportEvent { id, provider, file_path, status, stats, error, created_at })
+        })
+        .collect()
+}
+
+/// Parsers an import runs with, recorded in its stats
+fn parser_version(options: &ImportOptions) -> String {
+    let parsers = if options.use_python_bridge { "python" } else { "native" };
+    format!("{}-{}", parsers, env!("CARGO_PKG_VERSION"))
+}
+
 /// Detect provider from file content
+///
+/// Returns `None` when no heuristic matches or more than one does.
 pub fn detect_provider(content: &str) -> Option<ProviderType> {
-    // Quick heuristics to detect format
-    if content.contains("\"conversation_id\"") && content.contains("\"message\"") {
-        return Some(ProviderType::ChatGPT);
+    match detect_provider_candidates(content).as_slice() {
+        [provider] => Some(*provider),
+        _ => None,
+    }
+}
+
+/// All providers whose heuristics match the file content
+pub fn detect_provider_candidates(content: &str) -> Vec<ProviderType> {
+    let has = |key: &str| content.contains(key);
+    let mut candidates = Vec::new();
+    
+    // Web export mapping, or Assistants API thread messages
+    if (has("\"conversation_id\"") && has("\"message\""))
+        || (has("\"mapping\"") && has("\"message\""))
+        || has("\"thread.message\"")
+    {
+        candidates.push(ProviderType::ChatGPT);
+    }
+    
+    // Web export conversations, console (Workbench) prompts, or API logs
+    if (has("\"uuid\"") && has("\"chat_messages\""))
+        || (has("\"messages\"") && (has("\"max_tokens\"") || has("\"stop_reason\"")))
+    {
+        candidates.push(ProviderType::Claude);
+    }
+    
+    // Takeout exports, or AI Studio prompts with their `history`
+    if (has("\"conversations\"") && has("\"gemini\""))
+        || ((has("\"turns\"") || has("\"history\"")) && has("\"parts\""))
+    {
+        candidates.push(ProviderType::Gemini);
+    }
+    
+    // Older exports, or agent threads with segmented messages
+    if has("\"zed_version\"") || (has("\"thread\"") && has("\"segments\"")) {
+        candidates.push(ProviderType::Zed);
+    }
+    
+    // Assistants threads also carry `thread_id`, so only count it without thread messages
+    if (has("\"thread_id\"") && !has("\"thread.message\"")) || has("\"exchanges\"") {
+        candidates.push(ProviderType::XAI);
+    }
+    
+    if parsers::markdown::looks_like_transcript(content) {
+        candidates.push(ProviderType::Markdown);
+    }
+    
+    candidates
+}
+
+/// Detect the provider of an export file from its first bytes
+async fn detect_provider_for_file(sample_path: &Path) -> Result<ProviderType> {
+    const SAMPLE_BYTES: u64 = 64 * 1024;
+    
+    let mut sample = Vec::new();
+    tokio::fs::File::open(&sample_path)
+        .await
+        .with_context(|| format!("Failed to open {:?}", sample_path))?
+        .take(SAMPLE_BYTES)
+        .read_to_end(&mut sample)
+        .await?;
+    
+    let candidates = detect_provider_candidates(&String::from_utf8_lossy(&sample));
+    
+    match candidates.as_slice() {
+        [provider] => Ok(*provider),
+        [] => Err(anyhow::anyhow!(
+            "Could not detect provider for {:?}; pass it explicitly",
+            sample_path
+        )),
+        _ => Err(anyhow::anyhow!(
+            "Ambiguous provider for {:?} (matches {}); pass it explicitly",
+            sample_path,
+            candidates.iter().map(|p| p.as_str()).collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::test_pool;
+    
+    fn claude_export(uuid: &str) -> String {
+        serde_json::json!({
+            "uuid": uuid,
+            "name": format!("Conversation {}", uuid),
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+            "chat_messages": [
+                {
+                    "uuid": format!("{}-m1", uuid),
+                    "text": "hello there",
+                    "sender": "human",
+                    "created_at": "2024-01-01T00:00:00Z"
+                }
+            ]
+        })
+        .to_string()
+    }
+    
+    fn chatgpt_conversation(id: &str) -> String {
+        serde_json::json!({
+            "id": id,
+            "title": format!("Conversation {}", id),
+            "create_time": 1704067200.0,
+            "mapping": {
+                "m1": {
+                    "id": "m1",
+                    "message": {
+                        "id": "m1",
+                        "author": { "role": "user" },
+                        "content": { "content_type": "text", "parts": ["hello there"] }
+                    },
+                    "parent": null,
+                    "children": []
+                }
+            },
+            "current_node": "m1"
+        })
+        .to_string()
+    }
+    
+    #[test]
+    fn test_detect_provider_samples() {
+        let samples = [
+            (include_str!("../../test-data/chatgpt-sample.json"), ProviderType::ChatGPT),
+            (include_str!("../../test-data/chatgpt-assistants-thread.json"), ProviderType::ChatGPT),
+            (include_str!("../../test-data/claude-sample.json"), ProviderType::Claude),
+            (include_str!("../../test-data/claude-workbench-sample.json"), ProviderType::Claude),
+            (include_str!("../../test-data/gemini-sample.json"), ProviderType::Gemini),
+            (include_str!("../../test-data/zed-sample.json"), ProviderType::Zed),
+            (include_str!("../../test-data/xai-sample.json"), ProviderType::XAI),
+        ];
+        
+        for (content, expected) in samples {
+            assert_eq!(detect_provider(content), Some(expected));
+        }
+    }
+    
+    #[test]
+    fn test_detect_provider_ambiguous() {
+        let content = r#"{"uuid": "1", "chat_messages": [], "zed_version": "0.100.0"}"#;
+        
+        assert_eq!(detect_provider(content), None);
+        assert_eq!(
+            detect_provider_candidates(content),
+            vec![ProviderType::Claude, ProviderType::Zed]
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_auto_import_detects_provider() {
+        let (_dir, pool) = test_pool().await;
+        let export_dir = tempfile::tempdir().unwrap();
+        std::fs::write(export_dir.path().join("conv.json"), claude_export("uuid-auto")).unwrap();
+        
+        let stats = import_conversations(&pool, "auto", export_dir.path(), &ImportOptions::default())
+            .await
+            .unwrap();
+        
+        assert_eq!(stats.conversations, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_auto_import_skips_unrecognized_files() {
+        let (_dir, pool) = test_pool().await;
+        let export_dir = tempfile::tempdir().unwrap();
+        std::fs::write(export_dir.path().join("a-notes.json"), r#"{"shopping": ["eggs", "milk"]}"#).unwrap();
+        std::fs::write(export_dir.path().join("b-conv.json"), claude_export("uuid-b")).unwrap();
+        std::fs::write(export_dir.path().join("c-conv.json"), claude_export("uuid-c")).unwrap();
+        
+        let stats = import_conversations(&pool, "auto", export_dir.path(), &ImportOptions::default())
+            .await
+            .unwrap();
+        
+        assert_eq!(stats.conversations, 2);
+        assert_eq!(stats.files_processed, 2);
+        assert_eq!(stats.unrecognized_files, vec![export_dir.path().join("a-notes.json")]);
+        
+        // Without a directory to fall back on, an undetectable file is an error
+        let notes = export_dir.path().join("a-notes.json");
+        let single = import_conversations(&pool, "auto", &notes, &ImportOptions::default()).await;
+        assert!(single.unwrap_err().to_string().contains("Could not detect provider"));
+        
+        let unknown = import_conversations(&pool, "myspace", export_dir.path(), &ImportOptions::default()).await;
+        assert!(unknown.unwrap_err().to_string().contains("Unknown provider: myspace"));
+    }
+    
+    #[test]
+    fn test_parse_errors_are_counted_per_provider() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+        
+        let export_dir = tempfile::tempdir().unwrap();
+        let export = export_dir.path().join("conversations.jsonl");
+        // The cut-off last line is a conversation the parser can't read
+        let lines = [claude_export("uuid-a"), claude_export("uuid-b"), r#"{"uuid": "uuid-c", "chat_messages": ["#.to_string()];
+        std::fs::write(&export, lines.join("\n")).unwrap();
+        
+        // Recorded on this thread only, so other tests' imports don't count
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let stats = metrics::with_local_recorder(&recorder, || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    let (_dir, pool) = test_pool().await;
+                    import_conversations(&pool, "claude", &export, &ImportOptions::default()).await.unwrap()
+                })
+        });
+        assert_eq!(stats.conversations, 2);
+        assert_eq!(stats.errors, 1);
+        
+        let counters: HashMap<String, DebugValue> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .filter(|(key, ..)| key.key().labels().any(|label| label.key() == "provider" && label.value() == "claude"))
+            .map(|(key, _, _, value)| (key.key().name().to_string(), value))
+            .collect();
+        assert_eq!(counters["llm_archive_import_parse_errors_total"], DebugValue::Counter(1));
+        assert_eq!(counters["llm_archive_import_parse_warnings_total"], DebugValue::Counter(0));
+        assert_eq!(counters["llm_archive_import_conversations_total"], DebugValue::Counter(2));
+    }
+    
+    #[tokio::test]
+    async fn test_strict_models_stores_no_guessed_model() {
+        let (_dir, pool) = test_pool().await;
+        let export_dir = tempfile::tempdir().unwrap();
+        let export = export_dir.path().join("prompt.json");
+        let prompt = serde_json::json!({
+            "id": "prompt-1",
+            "history": [
+                { "role": "user", "parts": [{ "text": "Which model are you?" }] },
+                { "role": "model", "parts": [{ "text": "The export doesn't say." }] }
+            ]
+        });
+        std::fs::write(&export, prompt.to_string()).unwrap();
+        
+        let strict = ImportOptions::from_config(&ImportConfig { strict_models: true, ..ImportConfig::default() }).unwrap();
+        import_conversations(&pool, "gemini", &export, &strict).await.unwrap();
+        
+        let models: Vec<Option<String>> = sqlx::query_scalar("SELECT model FROM conversations")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(models, vec![None]);
+        
+        // Otherwise the configured fallback is used, and providers without one get none
+        let lenient = ImportOptions::from_config(&ImportConfig::default()).unwrap();
+        assert_eq!(lenient.default_model(ProviderType::Gemini).as_deref(), Some("gemini-pro"));
+        assert_eq!(lenient.default_model(ProviderType::Claude), None);
+    }
+    
+    #[tokio::test]
+    async fn test_reimport_skips_unchanged_files() {
+        let (_dir, pool) = test_pool().await;
+        let export_dir = tempfile::tempdir().unwrap();
+        
+        for i in 0..3 {
+            std::fs::write(
+                export_dir.path().join(format!("conv-{}.json", i)),
+                claude_export(&format!("uuid-{}", i)),
+            )
+            .unwrap();
+        }
+        
+        let first = import_conversations(&pool, "claude", export_dir.path(), &ImportOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(first.files_processed, 3);
+        assert_eq!(first.files_skipped, 0);
+        
+        let second = import_conversations(&pool, "claude", export_dir.path(), &ImportOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(second.files_processed, 0);
+        assert_eq!(second.files_skipped, 3);
+        assert_eq!(second.conversations, 0);
+        
+        let force = ImportOptions { force: true, ..ImportOptions::default() };
+        let forced = import_conversations(&pool, "claude", export_dir.path(), &force)
+            .await
+            .unwrap();
+        assert_eq!(forced.files_processed, 3);
+        assert_eq!(forced.files_skipped, 0);
+    }
+    
+    #[tokio::test]
+    async fn test_transcripts_are_only_collected_for_markdown_and_auto() {
+        let export_dir = tempfile::tempdir().unwrap();
+        std::fs::write(export_dir.path().join("conv.json"), claude_export("uuid-conv")).unwrap();
+        std::fs::write(export_dir.path().join("README.md"), "# My exports\n\nDownloaded in May.\n").unwrap();
+        std::fs::write(export_dir.path().join("notes.txt"), "remember the milk").unwrap();
+        std::fs::create_dir(export_dir.path().join("2024")).unwrap();
+        std::fs::write(export_dir.path().join("2024/notes.md"), "**User:** hi\n\n**Assistant:** hello\n").unwrap();
+        
+        let (_dir, pool) = test_pool().await;
+        let stats = import_conversations(&pool, "claude", export_dir.path(), &ImportOptions::default())
+            .await
+            .unwrap();
+        assert_eq!((stats.files_total, stats.conversations), (1, 1));
+        
+        let names = |files: Vec<PathBuf>| -> Vec<String> {
+            files
+                .iter()
+                .map(|file| file.strip_prefix(export_dir.path()).unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+        let auto = collect_export_files(export_dir.path(), None).await.unwrap();
+        assert_eq!(names(auto), vec!["README.md", "conv.json", "notes.txt"]);
+        let markdown = collect_export_files(export_dir.path(), Some(ProviderType::Markdown)).await.unwrap();
+        assert_eq!(names(markdown), vec!["2024/notes.md", "README.md", "conv.json", "notes.txt"]);
+    }
+    
+    #[tokio::test]
+    async fn test_directory_import_order_is_deterministic() {
+        let export_dir = tempfile::tempdir().unwrap();
+        for name in ["delta", "alpha", "charlie", "bravo"] {
+            std::fs::write(
+                export_dir.path().join(format!("{}.json", name)),
+                claude_export(&format!("uuid-{}", name)),
+            )
+            .unwrap();
+        }
+        
+        let mut runs = Vec::new();
+        for _ in 0..2 {
+            let (_dir, pool) = test_pool().await;
+            import_conversations(&pool, "claude", export_dir.path(), &ImportOptions::default())
+                .await
+                .unwrap();
+            let rows: Vec<(i64, String)> = sqlx::query_as("SELECT id, external_id FROM conversations ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+            runs.push(rows);
+        }
+        
+        assert_eq!(runs[0], runs[1]);
+        let external_ids: Vec<&str> = runs[0].iter().map(|(_, id)| id.as_str()).collect();
+        assert_eq!(external_ids, vec!["uuid-alpha", "uuid-bravo", "uuid-charlie", "uuid-delta"]);
+    }
+    
+    #[tokio::test]
+    async fn test_progress_is_reported_per_file_and_batch() {
+        let (_dir, pool) = test_pool().await;
+        let export_dir = tempfile::tempdir().unwrap();
+        for name in ["alpha", "bravo", "charlie"] {
+            std::fs::write(
+                export_dir.path().join(format!("{}.json", name)),
+                claude_export(&format!("uuid-{}", name)),
+            )
+            .unwrap();
+        }
+        
+        let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = reports.clone();
+        let options = ImportOptions {
+            progress: Some(ImportProgress::new(move |stats| {
+                seen.lock().unwrap().push((stats.files_total, stats.files_processed, stats.conversations));
+            })),
+            ..ImportOptions::default()
+        };
+        
+        import_conversations(&pool, "claude", export_dir.path(), &options)
+            .await
+            .unwrap();
+        
+        // Once files are listed, then after each file's one batch and the file itself
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1 + 3 * 2);
+        assert_eq!(reports[0], (3, 0, 0));
+        assert_eq!(reports[1], (3, 0, 1));
+        assert_eq!(reports[2], (3, 1, 1));
+        assert_eq!(reports.last(), Some(&(3, 3, 3)));
     }
     
-    if content.contains("\"uuid\"") && content.contains("\"chat_messages\"") {
-        return Some(ProviderType::Claude);
+    #[tokio::test]
+    async fn test_chatgpt_directory_of_conversation_files() {
+        let (_dir, pool) = test_pool().await;
+        let export_dir = tempfile::tempdir().unwrap();
+        for id in ["conv-a", "conv-b", "conv-c"] {
+            std::fs::write(export_dir.path().join(format!("{}.json", id)), chatgpt_conversation(id)).unwrap();
+        }
+        
+        let mut stats = ImportStats::default();
+        parsers::chatgpt::import(&pool, export_dir.path(), &mut stats, &ImportOptions::default())
+            .await
+            .unwrap();
+        
+        assert_eq!(stats.conversations, 3);
+        assert_eq!(stats.errors, 0);
+        
+        let external_ids: Vec<String> = sqlx::query_scalar("SELECT external_id FROM conversations ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(external_ids, vec!["conv-a", "conv-b", "conv-c"]);
     }
     
-    if content.contains("\"conversations\"") && content.contains("\"gemini\"") {
-        return Some(ProviderType::Gemini);
+    #[tokio::test]
+    async fn test_batch_size_does_not_change_what_is_imported() {
+        let export_dir = tempfile::tempdir().unwrap();
+        let conversations: Vec<serde_json::Value> = (0..7)
+            .map(|i| serde_json::from_str(&chatgpt_conversation(&format!("conv-{}", i))).unwrap())
+            .collect();
+        std::fs::write(
+            export_dir.path().join("conversations.json"),
+            serde_json::to_string(&conversations).unwrap(),
+        )
+        .unwrap();
+    
+        let mut counts = Vec::new();
+        for batch_size in [1, 1000] {
+            let (_dir, pool) = test_pool().await;
+            let options = ImportOptions {
+                batch_size: Some(batch_size),
+                ..ImportOptions::default()
+            };
+            let stats = import_conversations(&pool, "chatgpt", export_dir.path(), &options)
+                .await
+                .unwrap();
+            let stored: (i64, i64) = sqlx::query_as(
+                "SELECT (SELECT COUNT(*) FROM conversations), (SELECT COUNT(*) FROM messages)"
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            counts.push((stats.conversations, stats.messages, stats.errors, stored));
+        }
+    
+        assert_eq!(counts[0], counts[1]);
+        assert_eq!(counts[0].3, (7, 7));
+    }
+    
+    #[test]
+    fn test_batch_size_must_be_within_bounds() {
+        for batch_size in [1, 100, 10_000] {
+            let config = ImportConfig { batch_size, ..ImportConfig::default() };
+            assert_eq!(ImportOptions::from_config(&config).unwrap().batch_size(), batch_size);
+        }
+        for batch_size in [0, 10_001] {
+            let config = ImportConfig { batch_size, ..ImportConfig::default() };
+            let error = ImportOptions::from_config(&config).unwrap_err();
+            assert!(error.to_string().contains("import.batch_size"));
+        }
     }
     
-    None
-}
\ No newline at end of file
+    #[tokio::test]
+    async fn test_insert_retries_while_database_is_locked() {
+        use crate::db::testing::{conversation, message};
+        use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+        use std::sync::atomic::{AtomicU32, Ordering};
+        
+        let (dir, pool) = test_pool().await;
+        
+        // A second pool that reports a held lock at once instead of waiting
+        let impatient = SqlitePoolOptions::new()
+            .connect_with(
+                SqliteConnectOptions::new()
+                    .filename(dir.path().join("test.db"))
+                    .busy_timeout(Duration::ZERO),
+            )
+            .await
+            .unwrap();
+        
+        let mut writer = pool.acquire().await.unwrap();
+        sqlx::query("BEGIN IMMEDIATE").execute(&mut *writer).await.unwrap();
+        let writer = tokio::sync::Mutex::new(writer);
+        
+        let batch = vec![(conversation("claude", "locked", "Locked"), vec![message("user", "hello")])];
+        let attempts = AtomicU32::new(0);
+        
+        let stats = retry_transient(|| async {
+            // The other writer finishes between the first and second attempt
+            if attempts.fetch_add(1, Ordering::SeqCst) == 1 {
+                sqlx::query("COMMIT").execute(&mut **writer.lock().await).await?;
+            }
+            process_conversation_batch(&impatient, batch.clone()).await
+        })
+        .await
+        .unwrap();
+        
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(stats.conversations, 1);
+        
+        // Errors other than lock contention are not retried
+        attempts.store(0, Ordering::SeqCst);
+        let failed: Result<()> = retry_transient(|| async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(anyhow::anyhow!("malformed export"))
+        })
+        .await;
+        assert!(failed.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_count_tokens_fills_missing_counts() {
+        use crate::db::testing::{conversation, message};
+        
+        let (_dir, pool) = test_pool().await;
+        let short = "Why do tides happen?";
+        let long = "Tides rise and fall twice a day as the moon pulls on the oceans. ".repeat(20);
+        let mut counted = message("assistant", "Counted by the provider");
+        counted.tokens = Some(99);
+        
+        let batch = vec![(
+            conversation("claude", "tokens", "Tokens"),
+            vec![message("user", short), message("assistant", &long), counted],
+        )];
+        let options = ImportOptions { count_tokens: true, ..ImportOptions::default() };
+        import_batch(&pool, batch, &options, &mut ImportStats::default()).await.unwrap();
+        
+        let tokens: Vec<Option<i32>> = sqlx::query_scalar("SELECT tokens FROM messages ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        let [Some(short_tokens), Some(long_tokens), Some(counted_tokens)] = tokens[..] else {
+            panic!("tokens not populated: {:?}", tokens);
+        };
+        
+        // Roughly two to eight characters per token, whichever tokenizer is built in
+        for (text, tokens) in [(short, short_tokens), (long.as_str(), long_tokens)] {
+            let chars_per_token = text.len() as f64 / tokens as f64;
+            assert!((2.0..=8.0).contains(&chars_per_token), "{} chars per token", chars_per_token);
+        }
+        assert!(long_tokens > short_tokens * 20);
+        assert_eq!(counted_tokens, 99);
+    }
+    
+    #[tokio::test]
+    async fn test_long_messages_are_truncated_and_kept_in_raw_json() {
+        use crate::db::testing::{conversation, message};
+    
+        let (_dir, pool) = test_pool().await;
+        let pasted = format!("Here is my log: {}", "tick ".repeat(10) + "zebra");
+        let batch = vec![(
+            conversation("claude", "pasted", "Pasted"),
+            vec![message("user", "short question"), message("user", &pasted)],
+        )];
+        let options = ImportOptions { max_message_chars: Some(20), ..ImportOptions::default() };
+        import_batch(&pool, batch, &options, &mut ImportStats::default()).await.unwrap();
+    
+        let contents: Vec<String> = sqlx::query_scalar("SELECT content FROM messages ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(contents[0], "short question");
+        assert_eq!(contents[1], format!("Here is my log: tick…[truncated {} chars]", pasted.len() - 20));
+    
+        let raw_json: String = sqlx::query_scalar("SELECT raw_json FROM conversations")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let raw_json: serde_json::Value = serde_json::from_str(&raw_json).unwrap();
+        assert_eq!(raw_json["truncated_messages"], serde_json::json!({ "1": pasted }));
+    
+        // Only the stored text is searchable
+        for (term, expected) in [("tick", 1), ("zebra", 0)] {
+            let matches: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH ?1")
+                .bind(term)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            assert_eq!(matches, expected, "{}", term);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_selected_branches_are_truncated_too() {
+        let (dir, pool) = test_pool().await;
+        let regenerated = format!("regenerated punchline {}", "zebra ".repeat(10));
+        let node = |id: &str, parent: Option<&str>, children: &[&str], role: &str, text: &str| {
+            serde_json::json!({
+                "id": id,
+                "parent": parent,
+                "children": children,
+                "message": {
+                    "id": id,
+                    "author": { "role": role },
+                    "content": { "content_type": "text", "parts": [text] },
+                },
+            })
+        };
+        let export = serde_json::json!([{
+            "id": "conv-branches",
+            "title": "Branches",
+            "current_node": "a1",
+            "mapping": {
+                "root": { "id": "root", "parent": null, "children": ["u1"] },
+                "u1": node("u1", Some("root"), &["a1", "a2"], "user", "tell me a joke"),
+                "a1": node("a1", Some("u1"), &[], "assistant", "first answer"),
+                "a2": node("a2", Some("u1"), &[], "assistant", &regenerated),
+            },
+        }]);
+        let path = dir.path().join("conversations.json");
+        std::fs::write(&path, export.to_string()).unwrap();
+        
+        let options = ImportOptions { max_message_chars: Some(20), ..ImportOptions::default() };
+        parsers::chatgpt::import(&pool, &path, &mut ImportStats::default(), &options).await.unwrap();
+        branches::select(&pool, 1, 1, &options).await.unwrap();
+        
+        let contents: Vec<String> = sqlx::query_scalar("SELECT content FROM messages ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            contents,
+            vec![
+                "tell me a joke".to_string(),
+                format!("regenerated punchlin…[truncated {} chars]", regenerated.len() - 20),
+            ]
+        );
+        
+        let raw_json: String = sqlx::query_scalar("SELECT raw_json FROM conversations")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let raw_json: serde_json::Value = serde_json::from_str(&raw_json).unwrap();
+        assert_eq!(raw_json["truncated_messages"], serde_json::json!({ "1": regenerated }));
+        assert_eq!(raw_json["selected_branch"], 1);
+        
+        let matches: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'zebra'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(matches, 0);
+    }
+    
+    #[tokio::test]
+    async fn test_generate_titles_only_replaces_placeholders() {
+        use crate::db::testing::{conversation, message};
+        
+        let (_dir, pool) = test_pool().await;
+        let question = "how do I **pin** a tokio runtime to one thread without spawning more?";
+        let batch = vec![
+            (conversation("claude", "placeholder", "Claude Conversation"), vec![message("user", question)]),
+            (conversation("claude", "named", "Runtime pinning"), vec![message("user", question)]),
+        ];
+        let options = ImportOptions { generate_titles: true, ..ImportOptions::default() };
+        import_batch(&pool, batch, &options, &mut ImportStats::default()).await.unwrap();
+        
+        let titles: Vec<(String, String)> = sqlx::query_as("SELECT external_id, title FROM conversations ORDER BY external_id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(titles, vec![
+            ("named".to_string(), "Runtime pinning".to_string()),
+            ("placeholder".to_string(), "How Do I Pin A Tokio Runtime To…".to_string()),
+        ]);
+    }
+    
+    #[tokio::test]
+    async fn test_since_skips_older_conversations() {
+        let (_dir, pool) = test_pool().await;
+        let options = ImportOptions {
+            since: Some("2024-01-01T00:00:00Z".parse().unwrap()),
+            ..ImportOptions::default()
+        };
+        
+        let stats = import_conversations(
+            &pool,
+            "claude",
+            Path::new("test-data/claude-mixed-dates.json"),
+            &options,
+        )
+        .await
+        .unwrap();
+        
+        assert_eq!(stats.conversations, 2);
+        assert_eq!(stats.filtered, 2);
+        
+        let ids: Vec<String> = sqlx::query_scalar("SELECT external_id FROM conversations ORDER BY external_id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(ids, vec!["mixed-3", "mixed-4"]);
+    }
+    
+    #[tokio::test]
+    async fn test_reimport_replaces_messages() {
+        let (_dir, pool) = test_pool().await;
+        let export_dir = tempfile::tempdir().unwrap();
+        std::fs::write(export_dir.path().join("conv.json"), claude_export("uuid-dup")).unwrap();
+        
+        let force = ImportOptions { force: true, ..ImportOptions::default() };
+        for _ in 0..2 {
+            import_conversations(&pool, "claude", export_dir.path(), &force)
+                .await
+                .unwrap();
+        }
+        
+        let count = |sql: &'static str| {
+            let pool = pool.clone();
+            async move { sqlx::query_scalar::<_, i64>(sql).fetch_one(&pool).await.unwrap() }
+        };
+        assert_eq!(count("SELECT COUNT(*) FROM conversations").await, 1);
+        assert_eq!(count("SELECT COUNT(*) FROM messages").await, 1);
+        assert_eq!(count("SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'hello'").await, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_import_from_url() {
+        let (_dir, pool) = test_pool().await;
+        
+        let app = axum::Router::new().route(
+            "/exports/claude.json",
+            axum::routing::get(|| async { include_str!("../../test-data/claude-mixed-dates.json") }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        
+        let url = format!("http://{}/exports/claude.json?X-Amz-Signature=abc", addr);
+        let stats = import_conversations(&pool, "auto", Path::new(&url), &ImportOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(stats.conversations, 4);
+        
+        // The same export under a fresh signature is recognized as unchanged
+        let resigned = format!("http://{}/exports/claude.json?X-Amz-Signature=def", addr);
+        let stats = import_conversations(&pool, "auto", Path::new(&resigned), &ImportOptions::default())
+            .await
+            .unwrap();
+        assert_eq!((stats.files_skipped, stats.conversations), (1, 0));
+        
+        let limited = ImportOptions {
+            download_limits: remote::DownloadLimits { max_bytes: 16, ..Default::default() },
+            ..ImportOptions::default()
+        };
+        let error = import_conversations(&pool, "auto", Path::new(&url), &limited).await.unwrap_err();
+        assert!(error.to_string().contains("download limit"));
+        
+        let missing = format!("http://{}/missing.json", addr);
+        assert!(import_conversations(&pool, "auto", Path::new(&missing), &ImportOptions::default())
+            .await
+            .is_err());
+    }
+}
@@ -3,13 +3,13 @@ use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sqlx::SqlitePool;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use tracing::{debug, info, warn};
 
-use crate::models::{Conversation, ImportStats, Message};
-use crate::import::process_conversation_batch;
-use super::{get_f32, get_i32, get_string, parse_timestamp};
+use crate::models::{normalize_model_name, Conversation, ImportStats, Message, ProviderType};
+use crate::import::{import_batch, BranchMode, ImportOptions};
+use super::{carry_forward_models, get_f32, get_i32, get_string, parse_timestamp, title_from_text};
 
 /// ChatGPT export format structures
 #[derive(Debug, Deserialize)]
@@ -17,13 +17,15 @@ struct ChatGPTExport {
     conversations: Vec<ChatGPTConversation>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct ChatGPTConversation {
     id: String,
     title: String,
     create_time: Option<f64>,
     update_time: Option<f64>,
     mapping: HashMap<String, ChatGPTNode>,
+    #[serde(default)]
+    current_node: Option<String>,
     conversation_id: Option<String>,
     #[serde(default)]
     conversation_template_id: Option<String>,
@@ -33,7 +35,7 @@ struct ChatGPTConversation {
     is_archived: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct ChatGPTNode {
     id: String,
     message: Option<ChatGPTMessage>,
@@ -41,7 +43,7 @@ struct ChatGPTNode {
     children: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct ChatGPTMessage {
     id: String,
     author: ChatGPTAuthor,
@@ -53,21 +55,31 @@ struct ChatGPTMessage {
     recipient: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct ChatGPTAuthor {
     role: String,
     name: Option<String>,
     metadata: Option<Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct ChatGPTContent {
     content_type: String,
     parts: Option<Vec<Value>>,
     text: Option<String>,
+    // tether_quote
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    // tether_browsing_display
+    #[serde(default)]
+    result: Option<String>,
+    #[serde(default)]
+    summary: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct ChatGPTMetadata {
     model_slug: Option<String>,
     finish_details: Option<ChatGPTFinishDetails>,
@@ -83,53 +95,102 @@ struct ChatGPTMetadata {
     content_references: Option<Vec<Value>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct ChatGPTFinishDetails {
     #[serde(rename = "type")]
     finish_type: Option<String>,
     stop_tokens: Option<Vec<i32>>,
 }
 
-/// Import ChatGPT conversations from export file
-pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) -> Result<()> {
+/// Assistants API thread export structures
+#[derive(Debug, Deserialize)]
+struct AssistantsThread {
+    thread_id: String,
+    created_at: Option<i64>,
+    #[serde(default)]
+    metadata: Option<Value>,
+    #[serde(default)]
+    assistant: Option<AssistantsAssistant>,
+    messages: Vec<AssistantsMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssistantsAssistant {
+    id: Option<String>,
+    model: Option<String>,
+    instructions: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssistantsMessage {
+    id: String,
+    created_at: Option<i64>,
+    role: String,
+    content: Vec<AssistantsContent>,
+    #[serde(default)]
+    assistant_id: Option<String>,
+    #[serde(default)]
+    run_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssistantsContent {
+    #[serde(rename = "type")]
+    content_type: String,
+    text: Option<AssistantsText>,
+    image_file: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssistantsText {
+    value: String,
+}
+
+/// Conversation id paired with its parse result
+type ParsedConversation = (String, Result<(Conversation, Vec<Message>)>);
+
+/// Import ChatGPT conversations from an export file or a directory of them
+pub async fn import(
+    pool: &SqlitePool,
+    path: &Path,
+    stats: &mut ImportStats,
+    options: &ImportOptions,
+) -> Result<()> {
     info!("Starting native ChatGPT import from {:?}", path);
     
-    // Read and parse JSON file
-    let content = tokio::fs::read_to_string(path)
-        .await
-        .context("Failed to read ChatGPT export file")?;
-    
-    let export: ChatGPTExport = serde_json::from_str(&content)
-        .context("Failed to parse ChatGPT export JSON")?;
-    
-    info!("Found {} conversations to import", export.conversations.len());
+    // The bulk `conversations.json`, or a directory of per-conversation files
+    let parsed = if path.is_file() {
+        import_single_file(path, stats, options).await?
+    } else if path.is_dir() {
+        import_directory(path, stats, options).await?
+    } else {
+        return Err(anyhow::anyhow!("Path is neither file nor directory"));
+    };
     
     // Process conversations in batches
     let mut batch = Vec::new();
     
-    for conv in export.conversations {
-        match parse_conversation(&conv) {
+    for (id, result) in parsed {
+        match result {
             Ok((conversation, messages)) => {
                 if messages.is_empty() {
-                    debug!("Skipping conversation {} with no messages", conv.id);
+                    debug!("Skipping conversation {} with no messages", id);
                     continue;
                 }
                 
                 batch.push((conversation, messages));
                 
-                // Process batch when it reaches 100 conversations
-                if batch.len() >= 100 {
+                // Process batch when it reaches the configured size
+                if batch.len() >= options.batch_size() {
                     let batch_to_process = std::mem::take(&mut batch);
-                    let batch_stats = process_conversation_batch(pool, batch_to_process).await?;
-                    stats.conversations += batch_stats.conversations;
-                    stats.messages += batch_stats.messages;
+                    let batch_stats = import_batch(pool, batch_to_process, options, stats).await?;
                     
                     debug!("Processed batch: {} conversations, {} messages", 
                            batch_stats.conversations, batch_stats.messages);
                 }
             }
             Err(e) => {
-                warn!("Failed to parse conversation {}: {}", conv.id, e);
+                warn!("Failed to parse conversation {}: {}", id, e);
                 stats.errors += 1;
             }
         }
@@ -137,16 +198,224 @@ pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) ->
     
     // Process remaining conversations
     if !batch.is_empty() {
-        let batch_stats = process_conversation_batch(pool, batch).await?;
-        stats.conversations += batch_stats.conversations;
-        stats.messages += batch_stats.messages;
+        import_batch(pool, batch, options, stats).await?;
     }
     
     Ok(())
 }
 
+/// Parse a single ChatGPT export file
+async fn import_single_file(
+    path: &Path,
+    stats: &mut ImportStats,
+    options: &ImportOptions,
+) -> Result<Vec<ParsedConversation>> {
+    let content = super::read_export(path, stats)
+        .await
+        .context("Failed to read ChatGPT export file")?;
+    
+    let value: Value = serde_json::from_str(&content)
+        .context("Failed to parse ChatGPT export JSON")?;
+    
+    // Assistants API thread exports have a different shape from the web export
+    if is_assistants_export(&value) {
+        let threads: Vec<AssistantsThread> = match value {
+            Value::Array(_) => serde_json::from_value(value),
+            _ => serde_json::from_value(value).map(|thread| vec![thread]),
+        }
+        .context("Failed to parse Assistants thread export")?;
+        
+        info!("Found {} Assistants threads to import", threads.len());
+        
+        return Ok(threads.iter()
+            .map(|thread| (thread.thread_id.clone(), parse_thread(thread, options)))
+            .collect());
+    }
+    
+    let conversations = web_conversations(value)?;
+    
+    info!("Found {} conversations to import", conversations.len());
+    
+    Ok(conversations.iter()
+        .map(|conv| (conv.id.clone(), parse_conversation(conv, options)))
+        .collect())
+}
+
+/// Web export conversations: wrapped in `{ "conversations": [...] }`, a
+/// bare array, or a single conversation per file
+fn web_conversations(value: Value) -> Result<Vec<ChatGPTConversation>> {
+    match value {
+        Value::Array(_) => serde_json::from_value(value),
+        Value::Object(ref fields) if fields.contains_key("conversations") => {
+            serde_json::from_value::<ChatGPTExport>(value).map(|export| export.conversations)
+        }
+        _ => serde_json::from_value(value).map(|conv| vec![conv]),
+    }
+    .context("Failed to parse ChatGPT export JSON")
+}
+
+/// Parse all ChatGPT export files in a directory, in file name order
+async fn import_directory(
+    dir: &Path,
+    stats: &mut ImportStats,
+    options: &ImportOptions,
+) -> Result<Vec<ParsedConversation>> {
+    let mut paths = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        
+        if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    
+    let mut parsed = Vec::new();
+    for path in paths {
+        match import_single_file(&path, stats, options).await {
+            Ok(conversations) => parsed.extend(conversations),
+            Err(e) => warn!("Failed to import {:?}: {}", path, e),
+        }
+    }
+    
+    Ok(parsed)
+}
+
+/// Check whether a parsed export is one or more Assistants API threads
+fn is_assistants_export(value: &Value) -> bool {
+    let thread = match value {
+        Value::Array(items) => items.first(),
+        other => Some(other),
+    };
+    
+    thread
+        .map(|t| t.get("thread_id").is_some() && t.get("messages").is_some())
+        .unwrap_or(false)
+}
+
+/// Parse an Assistants API thread into our domain model
+fn parse_thread(
+    thread: &AssistantsThread,
+    options: &ImportOptions,
+) -> Result<(Conversation, Vec<Message>)> {
+    let assistant_model = thread.assistant.as_ref()
+        .and_then(|a| a.model.clone())
+        .map(|slug| normalize_model_name(ProviderType::ChatGPT, &slug));
+    
+    // The API lists messages newest first
+    let mut thread_messages: Vec<_> = thread.messages.iter().collect();
+    thread_messages.sort_by_key(|m| m.created_at.unwrap_or(0));
+    
+    let mut messages: Vec<Message> = thread_messages.into_iter()
+        .filter_map(|m| parse_thread_message(m, assistant_model.as_deref()))
+        .collect();
+    
+    if options.carry_forward_model {
+        carry_forward_models(&mut messages);
+    }
+    
+    let created_at = thread.created_at
+        .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+        .or_else(|| messages.first().map(|m| m.created_at))
+        .unwrap_or_else(Utc::now);
+    
+    let updated_at = messages.last()
+        .map(|m| m.created_at)
+        .unwrap_or(created_at);
+    
+    let title = thread.metadata.as_ref()
+        .and_then(|m| get_string(m, "title"))
+        .or_else(|| {
+            messages.iter()
+                .find(|m| m.role == "user")
+                .and_then(|m| title_from_text(&m.content, options.title_length()))
+        });
+    
+    // Keep the assistant and run ids alongside the thread id
+    let run_ids: Vec<_> = thread.messages.iter().filter_map(|m| m.run_id.clone()).collect();
+    let assistant_id = thread.assistant.as_ref()
+        .and_then(|a| a.id.clone())
+        .or_else(|| thread.messages.iter().find_map(|m| m.assistant_id.clone()));
+    
+    let conversation = Conversation {
+        id: 0,
+        provider: "chatgpt".to_string(),
+        external_id: Some(thread.thread_id.clone()),
+        title,
+        model: assistant_model.or_else(|| messages.iter().find_map(|m| m.model.clone())),
+        created_at,
+        updated_at,
+        raw_json: Some(serde_json::json!({
+            "source": "assistants",
+            "thread_id": thread.thread_id,
+            "assistant_id": assistant_id,
+            "run_ids": run_ids,
+            "metadata": thread.metadata,
+        })),
+        system_prompt: thread.assistant.as_ref().and_then(|a| a.instructions.clone()),
+        temperature: None,
+        max_tokens: None,
+        user_id: None,
+        is_pinned: false,
+        is_archived: false,
+    };
+    
+    Ok((conversation, messages))
+}
+
+/// Parse an Assistants API thread message
+fn parse_thread_message(msg: &AssistantsMessage, assistant_model: Option<&str>) -> Option<Message> {
+    let role = match msg.role.as_str() {
+        "user" => "user",
+        "assistant" => "assistant",
+        _ => return None,
+    };
+    
+    let content = msg.content.iter()
+        .filter(|c| c.content_type == "text")
+        .filter_map(|c| c.text.as_ref().map(|t| t.value.as_str()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    
+    let images: Vec<_> = msg.content.iter()
+        .filter_map(|c| c.image_file.clone())
+        .collect();
+    
+    if content.is_empty() && images.is_empty() {
+        debug!("Skipping empty thread message {}", msg.id);
+        return None;
+    }
+    
+    let model = if role == "assistant" {
+        assistant_model.map(|m| m.to_string())
+    } else {
+        None
+    };
+    
+    Some(Message {
+        id: 0,
+        conversation_id: 0,
+        role: role.to_string(),
+        content,
+        model,
+        created_at: msg.created_at
+            .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+            .unwrap_or_else(Utc::now),
+        tokens: None,
+        finish_reason: None,
+        tool_calls: None,
+        attachments: (!images.is_empty()).then(|| Value::Array(images)),
+        revisions: Vec::new(),
+    })
+}
+
 /// Parse a ChatGPT conversation into our domain model
-fn parse_conversation(conv: &ChatGPTConversation) -> Result<(Conversation, Vec<Message>)> {
+fn parse_conversation(
+    conv: &ChatGPTConversation,
+    options: &ImportOptions,
+) -> Result<(Conversation, Vec<Message>)> {
     let created_at = conv.create_time
         .and_then(|ts| chrono::DateTime::from_timestamp(ts as i64, 0))
         .unwrap_or_else(Utc::now);
@@ -155,13 +424,25 @@ fn parse_conversation(conv: &ChatGPTConversation) -> Result<(Conversation, Vec<M
         .and_then(|ts| chrono::DateTime::from_timestamp(ts as i64, 0))
         .unwrap_or(created_at);
     
-    // Extract messages from the mapping
-    let messages = extract_messages(&conv.mapping)?;
+    // Extract messages along the active branch
+    let path = active_path(conv);
+    let messages = path_messages(conv, &path, options);
     
     // Determine model from messages
     let model = messages.iter()
         .find_map(|m| m.model.clone())
-        .or_else(|| Some("gpt-3.5-turbo".to_string())); // Default model
+        .or_else(|| options.default_model(ProviderType::ChatGPT));
+    
+    let mut raw_json = serde_json::to_value(conv)?;
+    if let Some(obj) = raw_json.as_object_mut() {
+        obj.insert("branch_count".to_string(), leaf_paths(conv).len().max(1).into());
+    }
+    if options.branch_mode == BranchMode::AllBranches {
+        let branches = alternate_branches(conv, &path, options);
+        if let (Some(obj), false) = (raw_json.as_object_mut(), branches.is_empty()) {
+            obj.insert("branches".to_string(), Value::Array(branches));
+        }
+    }
     
     // Create conversation
     let conversation = Conversation {
@@ -172,36 +453,197 @@ fn parse_conversation(conv: &ChatGPTConversation) -> Result<(Conversation, Vec<M
         model,
         created_at,
         updated_at,
-        raw_json: Some(serde_json::to_value(conv)?),
+        raw_json: Some(raw_json),
         system_prompt: None, // ChatGPT doesn't expose this in exports
         temperature: None,
         max_tokens: None,
         user_id: None,
+        is_pinned: false,
+        is_archived: false,
     };
     
     Ok((conversation, messages))
 }
 
-/// Extract messages from ChatGPT's node mapping
-fn extract_messages(mapping: &HashMap<String, ChatGPTNode>) -> Result<Vec<Message>> {
-    let mut messages = Vec::new();
-    let mut processed = std::collections::HashSet::new();
+/// Node ids on the active branch, root first
+///
+/// Walks up from `current_node`; exports without it fall back to following
+/// the most recent child (the last regeneration) down from the root.
+fn active_path(conv: &ChatGPTConversation) -> Vec<String> {
+    let mut visited = HashSet::new();
+    let mut path = Vec::new();
+    
+    if let Some(current) = conv.current_node.as_ref().filter(|id| conv.mapping.contains_key(*id)) {
+        let mut node_id = Some(current.clone());
+        while let Some(id) = node_id {
+            if !visited.insert(id.clone()) {
+                break;
+            }
+            node_id = conv.mapping.get(&id).and_then(|node| node.parent.clone());
+            path.push(id);
+        }
+        path.reverse();
+        return path;
+    }
     
-    // Find root node(s)
-    let roots: Vec<_> = mapping.iter()
+    let mut node_id = conv.mapping.iter()
         .filter(|(_, node)| node.parent.is_none())
         .map(|(id, _)| id.clone())
+        .min();
+    while let Some(id) = node_id {
+        if !visited.insert(id.clone()) {
+            break;
+        }
+        node_id = conv.mapping.get(&id).and_then(|node| node.children.last().cloned());
+        path.push(id);
+    }
+    path
+}
+
+/// Branches that fork off the active path, each flattened depth-first
+fn alternate_branches(
+    conv: &ChatGPTConversation,
+    path: &[String],
+    options: &ImportOptions,
+) -> Vec<Value> {
+    let on_path: HashSet<&String> = path.iter().collect();
+    let mut branches = Vec::new();
+    
+    for fork_id in path {
+        let Some(fork) = conv.mapping.get(fork_id) else { continue };
+        
+        for child_id in fork.children.iter().filter(|id| !on_path.contains(id)) {
+            let mut messages = Vec::new();
+            let mut processed = HashSet::new();
+            traverse_messages(child_id, &conv.mapping, &mut messages, &mut processed);
+            
+            if options.carry_forward_model {
+                carry_forward_models(&mut messages);
+            }
+            
+            branches.push(serde_json::json!({
+                "parent_node": fork_id,
+                "node": child_id,
+                "messages": messages,
+            }));
+        }
+    }
+    
+    branches
+}
+
+/// One way through a conversation's message tree, from the root to a leaf
+#[derive(Debug, Clone, Serialize)]
+pub struct Branch {
+    pub index: usize,
+    /// Leaf node the branch ends at
+    pub node: String,
+    /// Deepest node on the branch with several children, where it last
+    /// diverged from another branch
+    pub fork_node: Option<String>,
+    /// Content of the first message after `fork_node`
+    pub first_message: Option<String>,
+    pub message_count: usize,
+    /// Whether the export had this branch open
+    pub active: bool,
+}
+
+/// Every branch of a conversation imported by this parser, read back from
+/// its stored `raw_json`
+pub fn branches(raw_json: &Value) -> Result<Vec<Branch>> {
+    let conv = ChatGPTConversation::deserialize(raw_json)
+        .context("Stored conversation has no ChatGPT message tree")?;
+    let active = active_path(&conv);
+    let paths = leaf_paths(&conv);
+    let active_index = paths.iter().position(|path| path.starts_with(&active));
+    
+    Ok(paths.iter()
+        .enumerate()
+        .map(|(index, path)| {
+            let fork = path.iter()
+                .rposition(|id| conv.mapping.get(id).is_some_and(|node| node.children.len() > 1));
+            let first_message = path[fork.map_or(0, |i| i + 1)..].iter()
+                .filter_map(|id| conv.mapping.get(id)?.message.as_ref())
+                .find_map(parse_message)
+                .map(|message| message.content);
+            
+            Branch {
+                index,
+                node: path.last().cloned().unwrap_or_default(),
+                fork_node: fork.map(|i| path[i].clone()),
+                first_message,
+                message_count: path_messages(&conv, path, &ImportOptions::default()).len(),
+                active: Some(index) == active_index,
+            }
+        })
+        .collect())
+}
+
+/// Messages along branch `index` of a stored conversation, as an import
+/// would have produced them had the export left that branch open
+pub fn branch_messages(raw_json: &Value, index: usize, options: &ImportOptions) -> Result<Option<Vec<Message>>> {
+    let conv = ChatGPTConversation::deserialize(raw_json)
+        .context("Stored conversation has no ChatGPT message tree")?;
+    
+    Ok(leaf_paths(&conv).get(index).map(|path| path_messages(&conv, path, options)))
+}
+
+/// Parsed messages of the nodes on `path`
+fn path_messages(conv: &ChatGPTConversation, path: &[String], options: &ImportOptions) -> Vec<Message> {
+    let mut messages: Vec<Message> = path.iter()
+        .filter_map(|id| conv.mapping.get(id)?.message.as_ref())
+        .filter_map(parse_message)
+        .collect();
+    
+    if options.carry_forward_model {
+        carry_forward_models(&mut messages);
+    }
+    
+    messages
+}
+
+/// Node ids of every root-to-leaf path, children visited in export order
+fn leaf_paths(conv: &ChatGPTConversation) -> Vec<Vec<String>> {
+    let mut roots: Vec<&String> = conv.mapping.iter()
+        .filter(|(_, node)| node.parent.is_none())
+        .map(|(id, _)| id)
         .collect();
+    roots.sort();
     
-    // Traverse from each root
-    for root_id in roots {
-        traverse_messages(&root_id, mapping, &mut messages, &mut processed);
+    let mut paths = Vec::new();
+    let mut visited = HashSet::new();
+    for root in roots {
+        collect_leaf_paths(root, &conv.mapping, &mut Vec::new(), &mut visited, &mut paths);
+    }
+    
+    paths
+}
+
+fn collect_leaf_paths(
+    node_id: &str,
+    mapping: &HashMap<String, ChatGPTNode>,
+    path: &mut Vec<String>,
+    visited: &mut HashSet<String>,
+    paths: &mut Vec<Vec<String>>,
+) {
+    let Some(node) = mapping.get(node_id) else { return };
+    if !visited.insert(node_id.to_string()) {
+        return;
     }
     
-    // Sort messages by their order in the conversation
-    // Since we traverse in order, they should already be sorted
+    path.push(node_id.to_string());
     
-    Ok(messages)
+    let children: Vec<&String> = node.children.iter()
+        .filter(|id| mapping.contains_key(*id))
+        .collect();
+    if children.is_empty() {
+        paths.push(path.clone());
+    }
+    for child_id in children {
+        collect_leaf_paths(child_id, mapping, path, visited, paths);
+    }
+    
+    path.pop();
 }
 
 /// Recursively traverse the message tree
@@ -209,7 +651,7 @@ fn traverse_messages(
     node_id: &str,
     mapping: &HashMap<String, ChatGPTNode>,
     messages: &mut Vec<Message>,
-    processed: &mut std::collections::HashSet<String>,
+    processed: &mut HashSet<String>,
 ) {
     if processed.contains(node_id) {
         return;
@@ -244,29 +686,26 @@ fn parse_message(msg: &ChatGPTMessage) -> Option<Message> {
     
     // Extract content based on content type
     let content = match msg.content.content_type.as_str() {
-        "text" => {
-            // Try text field first, then parts
-            msg.content.text.clone().or_else(|| {
-                msg.content.parts.as_ref().and_then(|parts| {
-                    parts.iter()
-                        .filter_map(|p| p.as_str().map(|s| s.to_string()))
-                        .collect::<Vec<_>>()
-                        .join("\n")
-                        .into()
-                })
-            })?
+        // Try text field first, then parts
+        "text" | "code" | "execution_output" => {
+            msg.content.text.clone().or_else(|| text_parts(&msg.content))?
+        }
+        "multimodal_text" => text_parts(&msg.content).unwrap_or_default(),
+        "tether_quote" => {
+            let source = match (&msg.content.title, &msg.content.url) {
+                (Some(title), Some(url)) => format!("[{}]({})\n\n", title, url),
+                (None, Some(url)) => format!("{}\n\n", url),
+                _ => String::new(),
+            };
+            format!("{}{}", source, msg.content.text.as_deref().unwrap_or_default())
         }
-        "code" => {
-            // Handle code content
-            msg.content.text.clone().or_else(|| {
-                msg.content.parts.as_ref().and_then(|parts| {
-                    parts.iter()
-                        .filter_map(|p| p.as_str().map(|s| s.to_string()))
-                        .collect::<Vec<_>>()
-                        .join("\n")
-                        .into()
-                })
-            })?
+        "tether_browsing_display" => {
+            [&msg.content.summary, &msg.content.result]
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("\n\n")
         }
         _ => return None, // Skip other content types for now
     };
@@ -274,7 +713,7 @@ fn parse_message(msg: &ChatGPTMessage) -> Option<Message> {
     // Extract model from metadata
     let model = msg.metadata.as_ref()
         .and_then(|m| m.model_slug.clone())
-        .map(|slug| normalize_model_name(&slug));
+        .map(|slug| normalize_model_name(ProviderType::ChatGPT, &slug));
     
     // Extract finish reason
     let finish_reason = msg.metadata.as_ref()
@@ -284,6 +723,13 @@ fn parse_message(msg: &ChatGPTMessage) -> Option<Message> {
     // Create timestamp (ChatGPT doesn't provide per-message timestamps)
     let created_at = Utc::now();
     
+    // Sources cited by browsing/research answers ride along with any tool payload
+    let mut tool_calls = tool_payload(msg, role);
+    let citations = citation_links(msg.metadata.as_ref());
+    if !citations.is_empty() {
+        tool_calls.get_or_insert_with(|| serde_json::json!({}))["citations"] = Value::Array(citations);
+    }
+    
     Some(Message {
         id: 0,
         conversation_id: 0,
@@ -293,23 +739,434 @@ fn parse_message(msg: &ChatGPTMessage) -> Option<Message> {
         created_at,
         tokens: None, // ChatGPT doesn't export token counts
         finish_reason,
-        tool_calls: None, // TODO: Extract from content if needed
-        attachments: None, // TODO: Extract if present
+        tool_calls,
+        attachments: multimodal_attachments(&msg.content),
+        revisions: Vec::new(),
     })
 }
 
-/// Normalize ChatGPT model names to standard format
-fn normalize_model_name(slug: &str) -> String {
-    match slug {
-        "gpt-4" => "gpt-4".to_string(),
-        "gpt-4-gizmo" => "gpt-4".to_string(),
-        "gpt-4-browsing" => "gpt-4-browsing".to_string(),
-        "gpt-4-plugins" => "gpt-4-plugins".to_string(),
-        "gpt-4-mobile" => "gpt-4".to_string(),
-        "gpt-4o" => "gpt-4o".to_string(),
-        "gpt-4o-mini" => "gpt-4o-mini".to_string(),
-        "text-davinci-002-render-sha" => "gpt-3.5-turbo".to_string(),
-        "text-davinci-002-render-paid" => "gpt-3.5-turbo".to_string(),
-        _ => slug.to_string(),
-    }
-}
\ No newline at end of file
+/// `{title, url}` of every source in a message's `citations` and
+/// `content_references`, first occurrence of each url only
+///
+/// Citations keep the source in `metadata`; content references are either a
+/// single source or group several under `items` or `sources`.
+fn citation_links(metadata: Option<&ChatGPTMetadata>) -> Vec<Value> {
+    let Some(metadata) = metadata else { return Vec::new() };
+    
+    let sources = metadata.citations.iter().flatten()
+        .map(|citation| citation.get("metadata").unwrap_or(citation))
+        .chain(metadata.content_references.iter().flatten().flat_map(|reference| {
+            let grouped = ["items", "sources"]
+                .into_iter()
+                .filter_map(|key| reference.get(key).and_then(Value::as_array))
+                .flatten();
+            std::iter::once(reference).chain(grouped)
+        }));
+    
+    let mut seen = HashSet::new();
+    sources
+        .filter_map(|source| {
+            let url = get_string(source, "url").filter(|url| !url.is_empty())?;
+            seen.insert(url.clone()).then(|| {
+                serde_json::json!({ "title": get_string(source, "title"), "url": url })
+            })
+        })
+        .collect()
+}
+
+/// Join the string parts of a message, taking `text` from object parts
+fn text_parts(content: &ChatGPTContent) -> Option<String> {
+    let parts = content.parts.as_ref()?;
+    
+    Some(
+        parts.iter()
+            .filter_map(|p| match p {
+                Value::String(s) => Some(s.clone()),
+                Value::Object(_) => get_string(p, "text"),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+/// Non-text parts of a multimodal message, such as image asset pointers
+fn multimodal_attachments(content: &ChatGPTContent) -> Option<Value> {
+    if content.content_type != "multimodal_text" {
+        return None;
+    }
+    
+    let assets: Vec<Value> = content.parts.as_ref()?
+        .iter()
+        .filter(|p| p.is_object() && p.get("text").is_none())
+        .cloned()
+        .collect();
+    
+    (!assets.is_empty()).then(|| Value::Array(assets))
+}
+
+/// Structured record of a tool invocation (assistant addressing a tool)
+/// or a tool result, with any JSON payload parsed out of the text
+fn tool_payload(msg: &ChatGPTMessage, role: &str) -> Option<Value> {
+    let recipient = msg.recipient.as_deref().filter(|r| *r != "all");
+    if role != "tool" && recipient.is_none() {
+        return None;
+    }
+    
+    let mut payload = serde_json::json!({ "content_type": msg.content.content_type });
+    
+    if let Some(recipient) = recipient {
+        payload["recipient"] = Value::String(recipient.to_string());
+    }
+    
+    if role == "tool" {
+        if let Some(name) = &msg.author.name {
+            payload["name"] = Value::String(name.clone());
+        }
+    }
+    
+    let json = msg.content.text.clone()
+        .or_else(|| text_parts(&msg.content))
+        .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+        .filter(|v| v.is_object() || v.is_array());
+    
+    if let Some(json) = json {
+        payload["payload"] = json;
+    }
+    
+    Some(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn node(id: &str, parent: Option<&str>, child: Option<&str>, message: Value) -> (String, Value) {
+        (
+            id.to_string(),
+            serde_json::json!({
+                "id": id,
+                "message": message,
+                "parent": parent,
+                "children": child.map(|c| vec![c]).unwrap_or_default(),
+            }),
+        )
+    }
+    
+    fn message(id: &str, role: &str, text: &str, model: Option<&str>) -> Value {
+        serde_json::json!({
+            "id": id,
+            "author": { "role": role },
+            "content": { "content_type": "text", "parts": [text] },
+            "metadata": { "model_slug": model },
+        })
+    }
+    
+    fn conversation_with_partial_models() -> ChatGPTConversation {
+        let mapping: serde_json::Map<String, Value> = [
+            node("root", None, Some("u1"), Value::Null),
+            node("u1", Some("root"), Some("a1"), message("u1", "user", "hi", None)),
+            node("a1", Some("u1"), Some("u2"), message("a1", "assistant", "hello", Some("gpt-4o"))),
+            node("u2", Some("a1"), Some("a2"), message("u2", "user", "more", None)),
+            node("a2", Some("u2"), Some("a3"), message("a2", "assistant", "sure", None)),
+            node("a3", Some("a2"), None, message("a3", "assistant", "also", None)),
+        ]
+        .into_iter()
+        .collect();
+        
+        serde_json::from_value(serde_json::json!({
+            "id": "conv-1",
+            "title": "Models",
+            "create_time": 1700000000.0,
+            "update_time": 1700000100.0,
+            "mapping": mapping,
+        }))
+        .unwrap()
+    }
+    
+    #[test]
+    fn test_assistants_thread_parses_with_roles() {
+        let value: Value = serde_json::from_str(
+            include_str!("../../../test-data/chatgpt-assistants-thread.json")
+        ).unwrap();
+        assert!(is_assistants_export(&value));
+        
+        let thread: AssistantsThread = serde_json::from_value(value).unwrap();
+        let (conversation, messages) = parse_thread(&thread, &ImportOptions::default()).unwrap();
+        
+        assert_eq!(conversation.external_id.as_deref(), Some("thread_abc123"));
+        assert_eq!(conversation.title.as_deref(), Some("Weekend trip planning"));
+        assert_eq!(conversation.model.as_deref(), Some("gpt-4-turbo"));
+        assert_eq!(conversation.system_prompt.as_deref(), Some("You help users plan short trips."));
+        assert_eq!(conversation.raw_json.as_ref().unwrap()["run_ids"], serde_json::json!(["run_002"]));
+        
+        let roles: Vec<_> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["user", "user", "assistant"]);
+        assert_eq!(messages[0].content, "Can you plan a weekend in Lisbon?");
+        assert!(messages[1].attachments.is_some());
+        assert_eq!(messages[2].model.as_deref(), Some("gpt-4-turbo"));
+        assert!(messages[0].model.is_none());
+        
+        // Untitled threads are named from the first user message
+        let mut untitled: Value = serde_json::from_str(
+            include_str!("../../../test-data/chatgpt-assistants-thread.json")
+        ).unwrap();
+        untitled["metadata"] = serde_json::json!({});
+        let thread: AssistantsThread = serde_json::from_value(untitled).unwrap();
+        let options = ImportOptions { title_length: Some(20), ..ImportOptions::default() };
+        let (conversation, _) = parse_thread(&thread, &options).unwrap();
+        assert_eq!(conversation.title.as_deref(), Some("Can you plan a…"));
+    }
+    
+    #[test]
+    fn test_web_export_is_not_assistants_export() {
+        let value = serde_json::json!({ "conversations": [] });
+        assert!(!is_assistants_export(&value));
+    }
+    
+    #[test]
+    fn test_web_export_accepts_wrapped_bare_and_single_conversations() {
+        let conversation = serde_json::json!({
+            "id": "conv-1",
+            "title": "Single",
+            "mapping": {},
+        });
+        
+        let wrapped = web_conversations(serde_json::json!({ "conversations": [conversation.clone()] })).unwrap();
+        let bare = web_conversations(serde_json::json!([conversation.clone(), conversation.clone()])).unwrap();
+        let single = web_conversations(conversation).unwrap();
+        
+        assert_eq!(wrapped.len(), 1);
+        assert_eq!(bare.len(), 2);
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].id, "conv-1");
+        
+        assert!(web_conversations(serde_json::json!({ "user": "someone" })).is_err());
+    }
+    
+    #[test]
+    fn test_tool_messages_are_retained() {
+        let mapping: serde_json::Map<String, Value> = [
+            node("root", None, Some("u1"), Value::Null),
+            node("u1", Some("root"), Some("a1"), message("u1", "user", "plot sin(x)", None)),
+            node("a1", Some("u1"), Some("t1"), serde_json::json!({
+                "id": "a1",
+                "author": { "role": "assistant" },
+                "content": { "content_type": "code", "language": "python", "text": "import math" },
+                "recipient": "python",
+            })),
+            node("t1", Some("a1"), Some("t2"), serde_json::json!({
+                "id": "t1",
+                "author": { "role": "tool", "name": "python" },
+                "content": { "content_type": "execution_output", "text": "0.8414709848078965" },
+                "recipient": "all",
+            })),
+            node("t2", Some("t1"), None, serde_json::json!({
+                "id": "t2",
+                "author": { "role": "tool", "name": "browser" },
+                "content": { "content_type": "text", "parts": ["{\"results\": [\"sine wave\"]}"] },
+                "recipient": "all",
+            })),
+        ]
+        .into_iter()
+        .collect();
+        
+        let conv: ChatGPTConversation = serde_json::from_value(serde_json::json!({
+            "id": "conv-tools",
+            "title": "Tools",
+            "mapping": mapping,
+        }))
+        .unwrap();
+        
+        let (_, messages) = parse_conversation(&conv, &ImportOptions::default()).unwrap();
+        assert_eq!(messages.len(), 4);
+        
+        let call = &messages[1];
+        assert_eq!(call.content, "import math");
+        assert_eq!(call.tool_calls.as_ref().unwrap()["recipient"], "python");
+        
+        let output = &messages[2];
+        assert_eq!(output.role, "tool");
+        assert_eq!(output.content, "0.8414709848078965");
+        assert_eq!(output.tool_calls.as_ref().unwrap()["content_type"], "execution_output");
+        assert_eq!(output.tool_calls.as_ref().unwrap()["name"], "python");
+        
+        let browser = &messages[3];
+        assert_eq!(browser.role, "tool");
+        assert_eq!(browser.tool_calls.as_ref().unwrap()["payload"]["results"][0], "sine wave");
+        
+        assert!(messages[0].tool_calls.is_none());
+    }
+    
+    #[test]
+    fn test_citations_are_normalized_into_tool_calls() {
+        let msg: ChatGPTMessage = serde_json::from_value(serde_json::json!({
+            "id": "m1",
+            "author": { "role": "assistant" },
+            "content": { "content_type": "text", "parts": ["Rust 1.0 shipped in 2015."] },
+            "recipient": "all",
+            "metadata": {
+                "citations": [
+                    {
+                        "start_ix": 0,
+                        "end_ix": 10,
+                        "metadata": { "type": "webpage", "title": "Rust 1.0", "url": "https://blog.rust-lang.org/2015/05/15/Rust-1.0.html" }
+                    }
+                ],
+                "content_references": [
+                    {
+                        "type": "grouped_webpages",
+                        "items": [
+                            { "title": "Rust 1.0", "url": "https://blog.rust-lang.org/2015/05/15/Rust-1.0.html" },
+                            { "title": "Rust (programming language)", "url": "https://en.wikipedia.org/wiki/Rust_(programming_language)" }
+                        ]
+                    },
+                    { "type": "sources_footnote", "sources": [] }
+                ]
+            }
+        }))
+        .unwrap();
+        
+        let parsed = parse_message(&msg).unwrap();
+        assert_eq!(
+            parsed.tool_calls.unwrap()["citations"],
+            serde_json::json!([
+                { "title": "Rust 1.0", "url": "https://blog.rust-lang.org/2015/05/15/Rust-1.0.html" },
+                { "title": "Rust (programming language)", "url": "https://en.wikipedia.org/wiki/Rust_(programming_language)" }
+            ])
+        );
+    }
+    
+    #[test]
+    fn test_multimodal_text_keeps_text_and_assets() {
+        let msg: ChatGPTMessage = serde_json::from_value(serde_json::json!({
+            "id": "m1",
+            "author": { "role": "user" },
+            "content": {
+                "content_type": "multimodal_text",
+                "parts": [
+                    { "content_type": "image_asset_pointer", "asset_pointer": "file-service://file-abc" },
+                    "What is in this picture?"
+                ]
+            }
+        }))
+        .unwrap();
+        
+        let parsed = parse_message(&msg).unwrap();
+        assert_eq!(parsed.content, "What is in this picture?");
+        assert_eq!(parsed.attachments.unwrap()[0]["asset_pointer"], "file-service://file-abc");
+    }
+    
+    fn regenerated_conversation() -> ChatGPTConversation {
+        let mapping: serde_json::Map<String, Value> = [
+            node("root", None, Some("u1"), Value::Null),
+            (
+                "u1".to_string(),
+                serde_json::json!({
+                    "id": "u1",
+                    "message": message("u1", "user", "tell me a joke", None),
+                    "parent": "root",
+                    "children": ["a1", "a2"],
+                }),
+            ),
+            node("a1", Some("u1"), None, message("a1", "assistant", "first answer", Some("gpt-4o"))),
+            node("a2", Some("u1"), None, message("a2", "assistant", "regenerated answer", Some("gpt-4o"))),
+        ]
+        .into_iter()
+        .collect();
+        
+        serde_json::from_value(serde_json::json!({
+            "id": "conv-branches",
+            "title": "Branches",
+            "current_node": "a1",
+            "mapping": mapping,
+        }))
+        .unwrap()
+    }
+    
+    #[test]
+    fn test_active_only_keeps_current_branch() {
+        let (conversation, messages) =
+            parse_conversation(&regenerated_conversation(), &ImportOptions::default()).unwrap();
+        
+        let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["tell me a joke", "first answer"]);
+        assert!(conversation.raw_json.unwrap().get("branches").is_none());
+    }
+    
+    #[test]
+    fn test_all_branches_keeps_alternates() {
+        let options = ImportOptions { branch_mode: BranchMode::AllBranches, ..ImportOptions::default() };
+        let (conversation, messages) =
+            parse_conversation(&regenerated_conversation(), &options).unwrap();
+        
+        let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["tell me a joke", "first answer"]);
+        
+        let raw_json = conversation.raw_json.unwrap();
+        let branches = raw_json["branches"].as_array().unwrap();
+        assert_eq!(branches.len(), 1);
+        assert_eq!(branches[0]["parent_node"], "u1");
+        assert_eq!(branches[0]["node"], "a2");
+        assert_eq!(branches[0]["messages"][0]["content"], "regenerated answer");
+    }
+    
+    #[test]
+    fn test_branches_summarize_each_divergence() {
+        let (conversation, _) =
+            parse_conversation(&regenerated_conversation(), &ImportOptions::default()).unwrap();
+        let raw_json = conversation.raw_json.unwrap();
+        assert_eq!(raw_json["branch_count"], 2);
+        
+        let branches = branches(&raw_json).unwrap();
+        let summaries: Vec<_> = branches.iter()
+            .map(|b| (b.node.as_str(), b.fork_node.as_deref(), b.first_message.as_deref(), b.active))
+            .collect();
+        assert_eq!(summaries, vec![
+            ("a1", Some("u1"), Some("first answer"), true),
+            ("a2", Some("u1"), Some("regenerated answer"), false),
+        ]);
+        
+        let messages = branch_messages(&raw_json, 1, &ImportOptions::default()).unwrap().unwrap();
+        let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["tell me a joke", "regenerated answer"]);
+        assert!(branch_messages(&raw_json, 2, &ImportOptions::default()).unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_missing_current_node_follows_latest_child() {
+        let mut conv = regenerated_conversation();
+        conv.current_node = None;
+        
+        let (_, messages) = parse_conversation(&conv, &ImportOptions::default()).unwrap();
+        assert_eq!(messages.last().unwrap().content, "regenerated answer");
+    }
+    
+    #[test]
+    fn test_carry_forward_model_fills_null_assistant_models() {
+        let conv = conversation_with_partial_models();
+        let options = ImportOptions { carry_forward_model: true, ..ImportOptions::default() };
+        
+        let (_, messages) = parse_conversation(&conv, &options).unwrap();
+        let assistant_models: Vec<_> = messages.iter()
+            .filter(|m| m.role == "assistant")
+            .map(|m| m.model.as_deref())
+            .collect();
+        
+        assert_eq!(assistant_models, vec![Some("gpt-4o"); 3]);
+        assert!(messages.iter().filter(|m| m.role == "user").all(|m| m.model.is_none()));
+    }
+    
+    #[test]
+    fn test_carry_forward_model_disabled_by_default() {
+        let conv = conversation_with_partial_models();
+        
+        let (_, messages) = parse_conversation(&conv, &ImportOptions::default()).unwrap();
+        let assistant_models: Vec<_> = messages.iter()
+            .filter(|m| m.role == "assistant")
+            .map(|m| m.model.as_deref())
+            .collect();
+        
+        assert_eq!(assistant_models, vec![Some("gpt-4o"), None, None]);
+    }
+}
@@ -6,9 +6,9 @@ use sqlx::SqlitePool;
 use std::path::Path;
 use tracing::{debug, info, warn};
 
-use crate::models::{Conversation, ImportStats, Message};
-use crate::import::process_conversation_batch;
-use super::{get_f32, get_i32, get_string, parse_timestamp};
+use crate::models::{normalize_model_name, Conversation, ImportStats, Message, ProviderType};
+use crate::import::{import_batch, ImportOptions};
+use super::{get_f32, get_i32, get_string, parse_timestamp_in};
 
 /// Gemini export format structures
 #[derive(Debug, Deserialize)]
@@ -32,7 +32,11 @@ struct GeminiConversation {
     model: Option<String>,
     messages: Option<Vec<GeminiMessage>>,
     turns: Option<Vec<GeminiMessage>>, // Alternative field name
+    history: Option<Vec<GeminiMessage>>, // AI Studio
     settings: Option<GeminiSettings>,
+    /// AI Studio keeps the system prompt as content parts
+    #[serde(rename = "systemInstruction")]
+    system_instruction: Option<GeminiContent>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -57,6 +61,12 @@ enum GeminiPart {
     },
 }
 
+#[derive(Debug, Deserialize)]
+struct GeminiContent {
+    #[serde(default)]
+    parts: Vec<GeminiPart>,
+}
+
 #[derive(Debug, Deserialize)]
 struct InlineData {
     mime_type: String,
@@ -71,10 +81,15 @@ struct GeminiSettings {
 }
 
 /// Import Gemini conversations from export file
-pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) -> Result<()> {
+pub async fn import(
+    pool: &SqlitePool,
+    path: &Path,
+    stats: &mut ImportStats,
+    options: &ImportOptions,
+) -> Result<()> {
     info!("Starting native Gemini import from {:?}", path);
     
-    let content = tokio::fs::read_to_string(path)
+    let content = super::read_export(path, stats)
         .await
         .context("Failed to read Gemini export file")?;
     
@@ -92,7 +107,7 @@ pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) ->
     let mut batch = Vec::new();
     
     for conv in conversations {
-        match parse_conversation(&conv) {
+        match parse_conversation(&conv, options) {
             Ok((conversation, messages)) => {
                 if messages.is_empty() {
                     debug!("Skipping conversation with no messages");
@@ -101,11 +116,9 @@ pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) ->
                 
                 batch.push((conversation, messages));
                 
-                if batch.len() >= 100 {
+                if batch.len() >= options.batch_size() {
                     let batch_to_process = std::mem::take(&mut batch);
-                    let batch_stats = process_conversation_batch(pool, batch_to_process).await?;
-                    stats.conversations += batch_stats.conversations;
-                    stats.messages += batch_stats.messages;
+                    import_batch(pool, batch_to_process, options, stats).await?;
                 }
             }
             Err(e) => {
@@ -117,28 +130,30 @@ pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) ->
     
     // Process remaining conversations
     if !batch.is_empty() {
-        let batch_stats = process_conversation_batch(pool, batch).await?;
-        stats.conversations += batch_stats.conversations;
-        stats.messages += batch_stats.messages;
+        import_batch(pool, batch, options, stats).await?;
     }
     
     Ok(())
 }
 
 /// Parse a Gemini conversation into our domain model
-fn parse_conversation(conv: &GeminiConversation) -> Result<(Conversation, Vec<Message>)> {
+fn parse_conversation(
+    conv: &GeminiConversation,
+    options: &ImportOptions,
+) -> Result<(Conversation, Vec<Message>)> {
     let created_at = conv.created_at
         .as_ref()
-        .and_then(parse_timestamp)
+        .and_then(|ts| parse_timestamp_in(ts, options.timezone()))
         .unwrap_or_else(Utc::now);
     
     let updated_at = conv.updated_at
         .as_ref()
-        .and_then(parse_timestamp)
+        .and_then(|ts| parse_timestamp_in(ts, options.timezone()))
         .unwrap_or(created_at);
     
-    let model = conv.model.clone()
-        .unwrap_or_else(|| "gemini-pro".to_string());
+    let model = conv.model.as_deref()
+        .map(|m| normalize_model_name(ProviderType::Gemini, m))
+        .or_else(|| options.default_model(ProviderType::Gemini));
     
     // Extract settings
     let (system_prompt, temperature, max_tokens) = if let Some(settings) = &conv.settings {
@@ -151,12 +166,17 @@ fn parse_conversation(conv: &GeminiConversation) -> Result<(Conversation, Vec<Me
         (None, None, None)
     };
     
+    let studio_instruction = conv.system_instruction.as_ref()
+        .map(|instruction| parts_text(&instruction.parts))
+        .filter(|text| !text.trim().is_empty());
+    let system_prompt = system_prompt.or_else(|| studio_instruction.clone());
+    
     let conversation = Conversation {
         id: 0,
         provider: "gemini".to_string(),
         external_id: conv.id.clone(),
         title: conv.title.clone(),
-        model: Some(model),
+        model,
         created_at,
         updated_at,
         raw_json: Some(serde_json::to_value(conv)?),
@@ -164,24 +184,50 @@ fn parse_conversation(conv: &GeminiConversation) -> Result<(Conversation, Vec<Me
         temperature,
         max_tokens,
         user_id: None,
+        is_pinned: false,
+        is_archived: false,
     };
     
     // Parse messages
     let messages_data = conv.messages.as_ref()
         .or(conv.turns.as_ref())
+        .or(conv.history.as_ref())
         .map(|v| v.as_slice())
         .unwrap_or(&[]);
     
-    let messages = messages_data
+    let mut messages: Vec<Message> = messages_data
         .iter()
-        .filter_map(|msg| parse_message(msg, created_at))
+        .filter_map(|msg| parse_message(msg, created_at, options))
         .collect();
     
+    // AI Studio shows the system instruction as the opening turn
+    if let Some(instruction) = studio_instruction {
+        if !messages.is_empty() && messages.first().map(|m| m.role.as_str()) != Some("system") {
+            messages.insert(0, Message {
+                id: 0,
+                conversation_id: 0,
+                role: "system".to_string(),
+                content: instruction,
+                model: None,
+                created_at,
+                tokens: None,
+                finish_reason: None,
+                tool_calls: None,
+                attachments: None,
+                revisions: Vec::new(),
+            });
+        }
+    }
+    
     Ok((conversation, messages))
 }
 
 /// Parse a Gemini message
-fn parse_message(msg: &GeminiMessage, default_time: DateTime<Utc>) -> Option<Message> {
+fn parse_message(
+    msg: &GeminiMessage,
+    default_time: DateTime<Utc>,
+    options: &ImportOptions,
+) -> Option<Message> {
     let role = msg.role.as_ref()?.to_lowercase();
     let role = match role.as_str() {
         "user" | "human" => "user",
@@ -192,29 +238,14 @@ fn parse_message(msg: &GeminiMessage, default_time: DateTime<Utc>) -> Option<Mes
     
     // Extract content from parts or direct content
     let content = if let Some(parts) = &msg.parts {
-        let text_parts: Vec<String> = parts.iter()
-            .filter_map(|part| match part {
-                GeminiPart::Text(s) => Some(s.clone()),
-                GeminiPart::Object { text, inline_data } => {
-                    if let Some(t) = text {
-                        Some(t.clone())
-                    } else if let Some(data) = inline_data {
-                        Some(format!("[Attached: {}]", data.mime_type))
-                    } else {
-                        None
-                    }
-                }
-            })
-            .collect();
-        
-        text_parts.join("\n")
+        parts_text(parts)
     } else {
         msg.content.clone()?
     };
     
     let created_at = msg.created_at
         .as_ref()
-        .and_then(parse_timestamp)
+        .and_then(|ts| parse_timestamp_in(ts, options.timezone()))
         .unwrap_or(default_time);
     
     Some(Message {
@@ -228,5 +259,56 @@ fn parse_message(msg: &GeminiMessage, default_time: DateTime<Utc>) -> Option<Mes
         finish_reason: None,
         tool_calls: None,
         attachments: None,
+        revisions: Vec::new(),
     })
-}
\ No newline at end of file
+}
+
+/// Text of content parts, one per line, with inline data as placeholders
+fn parts_text(parts: &[GeminiPart]) -> String {
+    let text_parts: Vec<String> = parts.iter()
+        .filter_map(|part| match part {
+            GeminiPart::Text(s) => Some(s.clone()),
+            GeminiPart::Object { text, inline_data } => {
+                if let Some(t) = text {
+                    Some(t.clone())
+                } else if let Some(data) = inline_data {
+                    Some(format!("[Attached: {}]", data.mime_type))
+                } else {
+                    None
+                }
+            }
+        })
+        .collect();
+    
+    text_parts.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_ai_studio_system_instruction_and_history() {
+        let conv: GeminiConversation = serde_json::from_value(serde_json::json!({
+            "history": [
+                { "role": "user", "parts": [{ "text": "What is machine learning?" }] },
+                { "role": "model", "parts": [{ "text": "Machine learning is a subset of AI..." }] }
+            ],
+            "systemInstruction": {
+                "parts": [{ "text": "You are a helpful AI assistant." }, { "text": "Answer briefly." }]
+            }
+        }))
+        .unwrap();
+        
+        let (conversation, messages) = parse_conversation(&conv, &ImportOptions::default()).unwrap();
+        assert_eq!(
+            conversation.system_prompt.as_deref(),
+            Some("You are a helpful AI assistant.\nAnswer briefly.")
+        );
+        
+        let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["system", "user", "assistant"]);
+        assert_eq!(messages[0].content, "You are a helpful AI assistant.\nAnswer briefly.");
+        assert_eq!(messages[1].content, "What is machine learning?");
+    }
+}
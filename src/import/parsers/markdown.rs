@@ -0,0 +1,322 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use std::path::Path;
+use tracing::{debug, info};
+
+use crate::models::{Conversation, ImportStats, Message};
+use crate::import::{import_batch, ImportOptions};
+use super::parse_timestamp_str;
+
+/// Speaker labels recognized in role headers, matched case-insensitively
+///
+/// Covers the labels every export template writes plus common hand-written ones.
+const ROLE_LABELS: &[(&str, &str)] = &[
+    ("user", "user"),
+    ("you", "user"),
+    ("human", "user"),
+    ("me", "user"),
+    ("q", "user"),
+    ("interviewer", "user"),
+    ("assistant", "assistant"),
+    ("ai", "assistant"),
+    ("a", "assistant"),
+    ("respondent", "assistant"),
+    ("claude", "assistant"),
+    ("chatgpt", "assistant"),
+    ("gemini", "assistant"),
+    ("grok", "assistant"),
+    ("system", "system"),
+    ("tool", "tool"),
+];
+
+/// A line that starts a new message
+#[derive(Debug)]
+struct Header<'a> {
+    role: &'static str,
+    timestamp: Option<&'a str>,
+    /// Message text on the header line itself, as in `**User:** hello`
+    text: &'a str,
+}
+
+/// Import a markdown or plain text transcript found under `root` as one
+/// conversation
+///
+/// The conversation's external id is the file's path relative to `root`,
+/// or an `id:` field in the frontmatter, so re-importing an edited file
+/// replaces it.
+pub async fn import(
+    pool: &SqlitePool,
+    root: &Path,
+    path: &Path,
+    stats: &mut ImportStats,
+    options: &ImportOptions,
+) -> Result<()> {
+    info!("Starting native markdown import from {:?}", path);
+    
+    let content = super::read_export(path, stats)
+        .await
+        .context("Failed to read markdown transcript")?;
+    
+    let modified = tokio::fs::metadata(path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .map(DateTime::<Utc>::from);
+    let name = path.strip_prefix(root).unwrap_or(path);
+    
+    let (conversation, messages) = parse_transcript(&content, name, modified, options);
+    if messages.is_empty() {
+        debug!("Skipping {:?}: no role headers found", path);
+        return Ok(());
+    }
+    
+    import_batch(pool, vec![(conversation, messages)], options, stats).await?;
+    Ok(())
+}
+
+/// Whether text reads like a transcript rather than a JSON export
+pub fn looks_like_transcript(content: &str) -> bool {
+    !content.trim_start().starts_with(['{', '['])
+        && content.lines().any(|line| parse_header(line).is_some())
+}
+
+/// Split a transcript into a conversation and its messages
+///
+/// Text before the first role header is not part of any message; a `# `
+/// heading there is the title, falling back to the file name. Without a
+/// `created:` field or any timestamps, the conversation dates from
+/// `modified`, the file's modification time.
+fn parse_transcript(
+    content: &str,
+    name: &Path,
+    modified: Option<DateTime<Utc>>,
+    options: &ImportOptions,
+) -> (Conversation, Vec<Message>) {
+    let (frontmatter, body) = split_frontmatter(content);
+    let field = |key: &str| {
+        frontmatter
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let parse_time = |text: &str| parse_timestamp_str(text.trim(), options.timezone());
+    
+    let mut heading = None;
+    let mut turns: Vec<(&'static str, Option<DateTime<Utc>>, Vec<&str>)> = Vec::new();
+    
+    for line in body.lines() {
+        if let Some(header) = parse_header(line) {
+            let text = if header.text.is_empty() { Vec::new() } else { vec![header.text] };
+            turns.push((header.role, header.timestamp.and_then(parse_time), text));
+        } else if let Some((_, _, lines)) = turns.last_mut() {
+            lines.push(line);
+        } else if heading.is_none() {
+            heading = line.strip_prefix("# ").map(str::trim).filter(|title| !title.is_empty());
+        }
+    }
+    
+    let created_at = field("created")
+        .and_then(|created| parse_time(&created))
+        .or_else(|| turns.iter().find_map(|(_, timestamp, _)| *timestamp))
+        .or(modified)
+        .unwrap_or_else(Utc::now);
+    
+    let mut last_time = created_at;
+    let messages: Vec<Message> = turns
+        .into_iter()
+        .filter_map(|(role, timestamp, lines)| {
+            last_time = timestamp.unwrap_or(last_time);
+            let content = message_text(&lines.join("\n"));
+            (!content.is_empty()).then(|| Message {
+                id: 0,
+                conversation_id: 0,
+                role: role.to_string(),
+                content,
+                model: None,
+                created_at: last_time,
+                tokens: None,
+                finish_reason: None,
+                tool_calls: None,
+                attachments: None,
+                revisions: Vec::new(),
+            })
+        })
+        .collect();
+    
+    // `/`-separated whatever the platform, so ids match across machines
+    let relative_path = name
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let title = field("title")
+        .or_else(|| heading.map(str::to_string))
+        .or_else(|| name.file_stem().map(|stem| stem.to_string_lossy().into_owned()));
+    
+    let conversation = Conversation {
+        id: 0,
+        provider: "markdown".to_string(),
+        external_id: field("id").or(Some(relative_path)).filter(|id| !id.is_empty()),
+        title,
+        model: None,
+        created_at,
+        updated_at: messages.last().map_or(created_at, |m| m.created_at),
+        raw_json: None,
+        system_prompt: None,
+        temperature: None,
+        max_tokens: None,
+        user_id: None,
+        is_pinned: false,
+        is_archived: false,
+    };
+    
+    (conversation, messages)
+}
+
+/// YAML-style frontmatter between `---` lines, and the text after it
+fn split_frontmatter(content: &str) -> (&str, &str) {
+    let Some(rest) = content.strip_prefix("---\n").or_else(|| content.strip_prefix("---\r\n")) else {
+        return ("", content);
+    };
+    
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return (&rest[..offset], &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    
+    ("", content)
+}
+
+/// A role header in any of the layouts the markdown export writes
+///
+/// `## USER (2024-01-15 10:30:00)` and `## User` headings start a message on
+/// the following lines; `**User:** text`, `**User**: text` and
+/// `**User** (10:30): text` start one on the same line.
+fn parse_header(line: &str) -> Option<Header<'_>> {
+    let line = line.trim_end();
+    
+    if let Some(heading) = line.strip_prefix("## ").or_else(|| line.strip_prefix("### ")) {
+        let heading = heading.trim();
+        let (label, timestamp) = match heading.strip_suffix(')').and_then(|h| h.rsplit_once(" (")) {
+            Some((label, timestamp)) => (label, Some(timestamp)),
+            None => (heading, None),
+        };
+        return Some(Header { role: role_for_label(label)?, timestamp, text: "" });
+    }
+    
+    let (label, rest) = line.strip_prefix("**")?.split_once("**")?;
+    let (label, timestamp, text) = match label.strip_suffix(':') {
+        Some(label) => (label, None, rest),
+        None => {
+            let rest = rest.trim_start();
+            let (timestamp, rest) = match rest.strip_prefix('(') {
+                Some(rest) => {
+                    let (timestamp, rest) = rest.split_once(')')?;
+                    (Some(timestamp), rest)
+                }
+                None => (None, rest),
+            };
+            (label, timestamp, rest.strip_prefix(':')?)
+        }
+    };
+    
+    Some(Header { role: role_for_label(label)?, timestamp, text: text.trim_start() })
+}
+
+fn role_for_label(label: &str) -> Option<&'static str> {
+    let label = label.trim();
+    ROLE_LABELS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(label))
+        .map(|(_, role)| *role)
+}
+
+/// Message text without surrounding blank lines or a trailing `---` separator
+fn message_text(text: &str) -> String {
+    let text = text.trim_end();
+    let text = text.strip_suffix("\n---").map_or(text, str::trim_end);
+    text.trim_start_matches(['\n', '\r']).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::{conversation, message};
+    use crate::export::{markdown_transcript, TranscriptTemplate};
+    use chrono::TimeZone;
+    
+    #[test]
+    fn test_exported_transcripts_round_trip() {
+        let original = conversation("claude", "conv-1", "Sourdough starter");
+        let mut messages = vec![
+            message("user", "How do I feed a starter?"),
+            message("assistant", "Equal parts flour and water.\n\n## Ratios\n\n- 1:1:1 by weight"),
+            message("user", "**Thanks!** That helps."),
+        ];
+        for (i, message) in messages.iter_mut().enumerate() {
+            message.created_at = Utc.with_ymd_and_hms(2024, 3, 1, 9, i as u32, 0).unwrap();
+        }
+        
+        let options = ImportOptions::default();
+        let path = Path::new("exports/Sourdough-starter.md");
+        
+        for template in ["default", "chat", "qa", "interview"] {
+            let template: TranscriptTemplate = template.parse().unwrap();
+            let exported = markdown_transcript(&original, &messages, template);
+            let (parsed, parsed_messages) = parse_transcript(&exported, path, None, &options);
+            
+            assert_eq!(parsed.title.as_deref(), Some("Sourdough starter"), "{:?}", template);
+            assert_eq!(
+                parsed_messages.iter().map(|m| (m.role.as_str(), m.content.as_str())).collect::<Vec<_>>(),
+                messages.iter().map(|m| (m.role.as_str(), m.content.as_str())).collect::<Vec<_>>(),
+                "{:?}",
+                template
+            );
+        }
+        
+        // The default layout carries full timestamps
+        let exported = markdown_transcript(&original, &messages, TranscriptTemplate::Default);
+        let (parsed, parsed_messages) = parse_transcript(&exported, path, None, &options);
+        assert_eq!(parsed.created_at, messages[0].created_at);
+        assert_eq!(parsed.updated_at, messages[2].created_at);
+        assert_eq!(parsed_messages[1].created_at, messages[1].created_at);
+    }
+    
+    #[test]
+    fn test_hand_written_transcript_with_frontmatter() {
+        let transcript = "---\ncreated: 2024-05-01T10:30:00Z\n---\n\nSaved from the app.\n\n**User:** What's a monad?\n\n**Assistant:** A monoid in the category of endofunctors.\n\nKidding.\n";
+        let (conversation, messages) = parse_transcript(transcript, Path::new("notes/monads.txt"), None, &ImportOptions::default());
+        
+        assert_eq!(conversation.title.as_deref(), Some("monads"));
+        assert_eq!(conversation.external_id.as_deref(), Some("notes/monads.txt"));
+        assert_eq!(conversation.created_at.to_rfc3339(), "2024-05-01T10:30:00+00:00");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content, "What's a monad?");
+        assert_eq!(messages[1].content, "A monoid in the category of endofunctors.\n\nKidding.");
+        assert!(messages.iter().all(|m| m.created_at == conversation.created_at));
+        
+        assert!(looks_like_transcript(transcript));
+        assert!(!looks_like_transcript("{\"text\": \"**User:** hi\"}"));
+    }
+    
+    #[test]
+    fn test_undated_transcript_dates_from_modification_time() {
+        let modified = Utc.with_ymd_and_hms(2024, 2, 3, 4, 5, 6).unwrap();
+        let transcript = "**User:** hi\n\n**Assistant:** hello\n";
+        
+        // Same file name in two directories, imported twice
+        let parse = |name: &str| parse_transcript(transcript, Path::new(name), Some(modified), &ImportOptions::default()).0;
+        let (first, again, other) = (parse("work/notes.md"), parse("work/notes.md"), parse("home/notes.md"));
+        
+        assert_eq!(first.created_at, modified);
+        assert_eq!(again.created_at, first.created_at);
+        assert_eq!(first.external_id, again.external_id);
+        assert_ne!(first.external_id, other.external_id);
+    }
+}
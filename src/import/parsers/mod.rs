@@ -4,34 +4,69 @@
 pub mod chatgpt;
 pub mod claude;
 pub mod gemini;
+pub mod markdown;
 pub mod xai;
 pub mod zed;
 
 // Common parsing utilities
 use serde_json::Value;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::warn;
 
-/// Parse a timestamp from various formats
+use crate::models::{ImportStats, Message, ProviderType};
+
+/// Title length used when the config doesn't set one
+pub const DEFAULT_TITLE_LENGTH: usize = 80;
+
+/// Conversations stored per transaction when the config doesn't set a size
+pub const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// Largest accepted `import.batch_size`
+pub const MAX_BATCH_SIZE: usize = 10_000;
+
+/// Models assumed for exports that name none, by provider
+pub fn default_models() -> HashMap<String, String> {
+    [
+        (ProviderType::ChatGPT, "gpt-3.5-turbo"),
+        (ProviderType::Gemini, "gemini-pro"),
+        (ProviderType::XAI, "grok-1"),
+        (ProviderType::Zed, "zed-ai"),
+    ]
+    .into_iter()
+    .map(|(provider, model)| (provider.as_str().to_string(), model.to_string()))
+    .collect()
+}
+
+/// Read an export file, replacing malformed UTF-8 with U+FFFD
+///
+/// A truncated multibyte sequence then costs a warning naming the file
+/// rather than every conversation in it.
+pub async fn read_export(path: &Path, stats: &mut ImportStats) -> std::io::Result<String> {
+    let bytes = tokio::fs::read(path).await?;
+    
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok(text),
+        Err(e) => {
+            warn!("Replaced malformed UTF-8 in {:?} with U+FFFD", path);
+            stats.warnings += 1;
+            Ok(String::from_utf8_lossy(e.as_bytes()).into_owned())
+        }
+    }
+}
+
+/// Parse a timestamp from various formats, treating naive times as UTC
 pub fn parse_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    parse_timestamp_in(value, utc_offset())
+}
+
+/// Parse a timestamp from various formats
+///
+/// Timestamps without an offset are interpreted in `default_offset`.
+pub fn parse_timestamp_in(value: &Value, default_offset: FixedOffset) -> Option<DateTime<Utc>> {
     match value {
-        Value::String(s) => {
-            // Try RFC3339 first
-            if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
-                return Some(dt.with_timezone(&Utc));
-            }
-            
-            // Try Unix timestamp
-            if let Ok(ts) = s.parse::<i64>() {
-                return DateTime::from_timestamp(ts, 0);
-            }
-            
-            // Try float Unix timestamp
-            if let Ok(ts) = s.parse::<f64>() {
-                return DateTime::from_timestamp(ts as i64, ((ts.fract() * 1_000_000_000.0) as u32));
-            }
-            
-            None
-        }
+        Value::String(s) => parse_timestamp_str(s, default_offset),
         Value::Number(n) => {
             if let Some(ts) = n.as_i64() {
                 DateTime::from_timestamp(ts, 0)
@@ -45,6 +80,71 @@ pub fn parse_timestamp(value: &Value) -> Option<DateTime<Utc>> {
     }
 }
 
+/// Parse a timestamp string, interpreting naive times in `default_offset`
+pub fn parse_timestamp_str(s: &str, default_offset: FixedOffset) -> Option<DateTime<Utc>> {
+    // Try RFC3339 first
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    
+    // Try Unix timestamp
+    if let Ok(ts) = s.parse::<i64>() {
+        return DateTime::from_timestamp(ts, 0);
+    }
+    
+    // Try float Unix timestamp
+    if let Ok(ts) = s.parse::<f64>() {
+        return DateTime::from_timestamp(ts as i64, ((ts.fract() * 1_000_000_000.0) as u32));
+    }
+    
+    // Try naive date-times without an offset
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, format) {
+            return default_offset
+                .from_local_datetime(&naive)
+                .single()
+                .map(|dt| dt.with_timezone(&Utc));
+        }
+    }
+    
+    None
+}
+
+/// Parse a UTC offset such as `+02:00`, `-0530`, `+05` or `UTC`
+pub fn parse_utc_offset(s: &str) -> Option<FixedOffset> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("utc") || s.eq_ignore_ascii_case("z") {
+        return Some(utc_offset());
+    }
+    
+    let (sign, rest) = match s.as_bytes().first()? {
+        b'+' => (1, &s[1..]),
+        b'-' => (-1, &s[1..]),
+        _ => return None,
+    };
+    
+    let digits: String = rest.chars().filter(|c| *c != ':').collect();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (digits[..2].parse::<i32>().ok()?, digits[2..].parse::<i32>().ok()?),
+        _ => return None,
+    };
+    
+    if minutes >= 60 {
+        return None;
+    }
+    
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+fn utc_offset() -> FixedOffset {
+    FixedOffset::east_opt(0).expect("zero offset is valid")
+}
+
 /// Extract string value from JSON
 pub fn get_string(value: &Value, key: &str) -> Option<String> {
     value.get(key)?.as_str().map(|s| s.to_string())
@@ -64,4 +164,193 @@ pub fn get_i32(value: &Value, key: &str) -> Option<i32> {
         Value::Number(n) => n.as_i64().map(|i| i as i32),
         _ => None,
     }
-}
\ No newline at end of file
+}
+
+/// Fill in null models on assistant messages from the last assistant
+/// message that had one
+pub fn carry_forward_models(messages: &mut [Message]) {
+    let mut last_model: Option<String> = None;
+    
+    for message in messages.iter_mut().filter(|m| m.role == "assistant") {
+        match &message.model {
+            Some(model) => last_model = Some(model.clone()),
+            None => message.model = last_model.clone(),
+        }
+    }
+}
+
+/// Title taken from the first line of a message
+///
+/// Code fences are skipped, leading headings, quotes, list markers and
+/// emphasis are stripped, and lines longer than `max_len` characters are
+/// cut at a word boundary and end in an ellipsis.
+pub fn title_from_text(text: &str, max_len: usize) -> Option<String> {
+    let line = text.lines()
+        .filter(|line| !line.trim_start().starts_with("```"))
+        .map(|line| strip_leading_markdown(line).replace("**", "").replace("__", "").replace('`', ""))
+        .find(|line| !line.trim().is_empty())?;
+    let line = line.trim();
+    
+    if line.chars().count() <= max_len {
+        return Some(line.to_string());
+    }
+    
+    // Leave room for the ellipsis
+    let budget = max_len.saturating_sub(1);
+    let end = line.char_indices().nth(budget).map_or(line.len(), |(i, _)| i);
+    let cut = &line[..end];
+    
+    // Keep the cut if it already ends on a word, otherwise back up to the
+    // last space; a single overlong word is cut where it is
+    let at_boundary = line[end..].starts_with(char::is_whitespace);
+    let kept = match cut.rfind(char::is_whitespace) {
+        Some(space) if !at_boundary => &cut[..space],
+        _ => cut,
+    };
+    let kept = kept.trim_end_matches(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | ':' | '.' | '-'));
+    
+    Some(format!("{}…", kept))
+}
+
+/// Titles exporters give conversations nobody named
+const PLACEHOLDER_TITLES: &[&str] = &[
+    "untitled",
+    "untitled conversation",
+    "new chat",
+    "new conversation",
+    "claude conversation",
+    "zed ai session",
+];
+
+/// Whether a title is missing, blank, or an exporter's placeholder
+pub fn is_placeholder_title(title: Option<&str>) -> bool {
+    title.map(str::trim).map_or(true, |title| {
+        title.is_empty() || PLACEHOLDER_TITLES.iter().any(|placeholder| title.eq_ignore_ascii_case(placeholder))
+    })
+}
+
+/// Words `synthesize_title` keeps from the first user message
+const SYNTHESIZED_TITLE_WORDS: usize = 8;
+
+/// Title made from the first words of the first user message
+///
+/// The message's first line is cleaned up as in `title_from_text`, each
+/// kept word is capitalized, and an ellipsis marks dropped words. Empty
+/// when no user message has any text.
+pub fn synthesize_title(messages: &[Message]) -> String {
+    let Some(line) = messages.iter()
+        .filter(|message| message.role == "user")
+        .find_map(|message| title_from_text(&message.content, usize::MAX))
+    else {
+        return String::new();
+    };
+    
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let title = words.iter()
+        .take(SYNTHESIZED_TITLE_WORDS)
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| first.to_uppercase().chain(chars).collect())
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    
+    if words.len() > SYNTHESIZED_TITLE_WORDS {
+        let kept = title.trim_end_matches(|c: char| matches!(c, ',' | ';' | ':' | '.' | '-'));
+        format!("{}…", kept)
+    } else {
+        title
+    }
+}
+
+/// Strip markdown block syntax from the start of a line
+fn strip_leading_markdown(line: &str) -> &str {
+    let mut line = line.trim();
+    
+    loop {
+        let stripped = line.trim_start_matches(|c| c == '#' || c == '>').trim_start();
+        
+        // Bullets need a following space so "-5 degrees" keeps its sign
+        let stripped = ["- ", "* ", "+ "].iter()
+            .find_map(|bullet| stripped.strip_prefix(bullet))
+            .unwrap_or(stripped);
+        
+        // Numbered list items: "1. " or "1) "
+        let digits = stripped.len() - stripped.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let stripped = if digits > 0 {
+            [". ", ") "].iter()
+                .find_map(|marker| stripped[digits..].strip_prefix(marker))
+                .unwrap_or(stripped)
+        } else {
+            stripped
+        };
+        
+        let stripped = stripped.trim_start();
+        if stripped.len() == line.len() {
+            return line;
+        }
+        line = stripped;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_naive_timestamp_uses_default_offset() {
+        let offset = parse_utc_offset("+02:00").unwrap();
+        let parsed = parse_timestamp_in(&Value::String("2024-05-01T10:30:00".to_string()), offset).unwrap();
+        
+        assert_eq!(parsed.to_rfc3339(), "2024-05-01T08:30:00+00:00");
+    }
+    
+    #[test]
+    fn test_explicit_offset_ignores_default() {
+        let offset = parse_utc_offset("+02:00").unwrap();
+        let parsed = parse_timestamp_in(&Value::String("2024-05-01T10:30:00Z".to_string()), offset).unwrap();
+        
+        assert_eq!(parsed.to_rfc3339(), "2024-05-01T10:30:00+00:00");
+    }
+    
+    #[test]
+    fn test_parse_utc_offset_forms() {
+        assert_eq!(parse_utc_offset("UTC"), FixedOffset::east_opt(0));
+        assert_eq!(parse_utc_offset("-0530"), FixedOffset::west_opt(5 * 3600 + 30 * 60));
+        assert_eq!(parse_utc_offset("+05"), FixedOffset::east_opt(5 * 3600));
+        assert_eq!(parse_utc_offset("Europe/Berlin"), None);
+        assert_eq!(parse_utc_offset("+02:75"), None);
+    }
+    
+    #[test]
+    fn test_title_from_long_first_message_is_word_bounded() {
+        let text = "\n## > **Question:** how should I structure a Rust workspace with several binaries sharing one library?\n\nMore detail here.";
+        let title = title_from_text(text, 40).unwrap();
+        
+        assert_eq!(title, "Question: how should I structure a Rust…");
+        assert!(title.chars().count() <= 40);
+        
+        assert_eq!(title_from_text("```rust\nfn main() {}\n```", 40).as_deref(), Some("fn main() {}"));
+        assert_eq!(title_from_text("1. Compare -5 and 5", 40).as_deref(), Some("Compare -5 and 5"));
+        assert_eq!(title_from_text("Supercalifragilistic", 10).as_deref(), Some("Supercali…"));
+        assert_eq!(title_from_text("  \n> \n", 40), None);
+    }
+    
+    #[tokio::test]
+    async fn test_read_export_replaces_malformed_utf8_with_a_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let clean = dir.path().join("clean.json");
+        let truncated = dir.path().join("truncated.json");
+        std::fs::write(&clean, "{\"title\": \"caf\u{e9}\"}").unwrap();
+        std::fs::write(&truncated, b"{\"title\": \"caf\xc3\"}").unwrap();
+        
+        let mut stats = ImportStats::default();
+        assert_eq!(read_export(&clean, &mut stats).await.unwrap(), "{\"title\": \"caf\u{e9}\"}");
+        assert_eq!(stats.warnings, 0);
+        
+        let text = read_export(&truncated, &mut stats).await.unwrap();
+        assert_eq!(text, "{\"title\": \"caf\u{fffd}\"}");
+        assert_eq!(serde_json::from_str::<Value>(&text).unwrap()["title"], "caf\u{fffd}");
+        assert_eq!(stats.warnings, 1);
+    }
+}
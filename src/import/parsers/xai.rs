@@ -6,9 +6,9 @@ use sqlx::SqlitePool;
 use std::path::Path;
 use tracing::{debug, info, warn};
 
-use crate::models::{Conversation, ImportStats, Message};
-use crate::import::process_conversation_batch;
-use super::{get_f32, get_i32, get_string, parse_timestamp};
+use crate::models::{normalize_model_name, Conversation, ImportStats, Message, ProviderType};
+use crate::import::{import_batch, ImportOptions};
+use super::{get_f32, get_i32, get_string, parse_timestamp_in};
 
 /// XAI/Grok export format structures
 #[derive(Debug, Deserialize)]
@@ -70,10 +70,15 @@ struct XAIMessage {
 }
 
 /// Import XAI/Grok conversations from export file
-pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) -> Result<()> {
+pub async fn import(
+    pool: &SqlitePool,
+    path: &Path,
+    stats: &mut ImportStats,
+    options: &ImportOptions,
+) -> Result<()> {
     info!("Starting native XAI/Grok import from {:?}", path);
     
-    let content = tokio::fs::read_to_string(path)
+    let content = super::read_export(path, stats)
         .await
         .context("Failed to read XAI export file")?;
     
@@ -96,7 +101,7 @@ pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) ->
     let mut batch = Vec::new();
     
     for conv in conversations {
-        match parse_conversation(&conv) {
+        match parse_conversation(&conv, options) {
             Ok((conversation, messages)) => {
                 if messages.is_empty() {
                     debug!("Skipping conversation with no messages");
@@ -105,11 +110,9 @@ pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) ->
                 
                 batch.push((conversation, messages));
                 
-                if batch.len() >= 100 {
+                if batch.len() >= options.batch_size() {
                     let batch_to_process = std::mem::take(&mut batch);
-                    let batch_stats = process_conversation_batch(pool, batch_to_process).await?;
-                    stats.conversations += batch_stats.conversations;
-                    stats.messages += batch_stats.messages;
+                    import_batch(pool, batch_to_process, options, stats).await?;
                 }
             }
             Err(e) => {
@@ -121,28 +124,30 @@ pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) ->
     
     // Process remaining conversations
     if !batch.is_empty() {
-        let batch_stats = process_conversation_batch(pool, batch).await?;
-        stats.conversations += batch_stats.conversations;
-        stats.messages += batch_stats.messages;
+        import_batch(pool, batch, options, stats).await?;
     }
     
     Ok(())
 }
 
 /// Parse an XAI conversation into our domain model
-fn parse_conversation(conv: &XAIConversation) -> Result<(Conversation, Vec<Message>)> {
+fn parse_conversation(
+    conv: &XAIConversation,
+    options: &ImportOptions,
+) -> Result<(Conversation, Vec<Message>)> {
     let created_at = conv.created_at
         .as_ref()
-        .and_then(parse_timestamp)
+        .and_then(|ts| parse_timestamp_in(ts, options.timezone()))
         .unwrap_or_else(Utc::now);
     
     let updated_at = conv.updated_at
         .as_ref()
-        .and_then(parse_timestamp)
+        .and_then(|ts| parse_timestamp_in(ts, options.timezone()))
         .unwrap_or(created_at);
     
-    let model = conv.model.clone()
-        .unwrap_or_else(|| "grok-1".to_string());
+    let model = conv.model.as_deref()
+        .map(|m| normalize_model_name(ProviderType::XAI, m))
+        .or_else(|| options.default_model(ProviderType::XAI));
     
     // Extract user ID
     let user_id = conv.user.as_ref().and_then(|u| match u {
@@ -156,7 +161,7 @@ fn parse_conversation(conv: &XAIConversation) -> Result<(Conversation, Vec<Messa
         provider: "xai".to_string(),
         external_id: conv.id.clone(),
         title: conv.title.clone(),
-        model: Some(model),
+        model,
         created_at,
         updated_at,
         raw_json: Some(serde_json::to_value(conv)?),
@@ -164,19 +169,25 @@ fn parse_conversation(conv: &XAIConversation) -> Result<(Conversation, Vec<Messa
         temperature: None,
         max_tokens: None,
         user_id,
+        is_pinned: false,
+        is_archived: false,
     };
     
     // Parse messages
     let messages = conv.messages
         .as_ref()
-        .map(|msgs| msgs.iter().filter_map(|msg| parse_message(msg, created_at)).collect())
+        .map(|msgs| msgs.iter().filter_map(|msg| parse_message(msg, created_at, options)).collect())
         .unwrap_or_default();
     
     Ok((conversation, messages))
 }
 
 /// Parse an XAI message
-fn parse_message(msg: &XAIMessage, default_time: DateTime<Utc>) -> Option<Message> {
+fn parse_message(
+    msg: &XAIMessage,
+    default_time: DateTime<Utc>,
+    options: &ImportOptions,
+) -> Option<Message> {
     let role = msg.role.as_ref()?.to_lowercase();
     let role = match role.as_str() {
         "user" | "human" | "question" => "user",
@@ -189,10 +200,12 @@ fn parse_message(msg: &XAIMessage, default_time: DateTime<Utc>) -> Option<Messag
     
     let created_at = msg.created_at
         .as_ref()
-        .and_then(parse_timestamp)
+        .and_then(|ts| parse_timestamp_in(ts, options.timezone()))
         .unwrap_or(default_time);
     
-    let model = msg.model.clone().or_else(|| msg.engine.clone());
+    let model = msg.model.as_deref()
+        .or(msg.engine.as_deref())
+        .map(|m| normalize_model_name(ProviderType::XAI, m));
     
     let tokens = msg.token_count.or(msg.tokens);
     
@@ -212,5 +225,35 @@ fn parse_message(msg: &XAIMessage, default_time: DateTime<Utc>) -> Option<Messag
         finish_reason: None,
         tool_calls: None,
         attachments,
+        revisions: Vec::new(),
     })
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ImportConfig;
+    
+    #[test]
+    fn test_naive_timestamps_use_configured_timezone() {
+        let config = ImportConfig {
+            default_timezone: "+02:00".to_string(),
+            ..ImportConfig::default()
+        };
+        let options = ImportOptions::from_config(&config).unwrap();
+        
+        let conv: XAIConversation = serde_json::from_value(serde_json::json!({
+            "id": "grok-1",
+            "title": "Local time",
+            "created_at": "2024-05-01 10:30:00",
+            "messages": [
+                { "role": "user", "content": "hi", "timestamp": "2024-05-01T10:31:00" }
+            ]
+        }))
+        .unwrap();
+        
+        let (conversation, messages) = parse_conversation(&conv, &options).unwrap();
+        
+        assert_eq!(conversation.created_at.to_rfc3339(), "2024-05-01T08:30:00+00:00");
+        assert_eq!(messages[0].created_at.to_rfc3339(), "2024-05-01T08:31:00+00:00");
+    }
+}
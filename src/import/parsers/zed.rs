@@ -6,9 +6,9 @@ use sqlx::SqlitePool;
 use std::path::Path;
 use tracing::{debug, info, warn};
 
-use crate::models::{Conversation, ImportStats, Message};
-use crate::import::process_conversation_batch;
-use super::{get_f32, get_i32, get_string, parse_timestamp};
+use crate::models::{normalize_model_name, Conversation, ImportStats, Message, ProviderType};
+use crate::import::{import_batch, ImportOptions};
+use super::{get_f32, get_i32, get_string, parse_timestamp_in};
 
 /// Zed AI export format structures
 #[derive(Debug, Deserialize)]
@@ -22,10 +22,11 @@ enum ZedExport {
     },
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct ZedConversation {
     #[serde(alias = "session_id")]
     id: Option<String>,
+    #[serde(alias = "summary")]
     title: Option<String>,
     file_path: Option<String>,
     #[serde(alias = "started_at")]
@@ -37,15 +38,28 @@ struct ZedConversation {
     language: Option<String>,
     #[serde(alias = "interactions")]
     messages: Option<Vec<ZedMessage>>,
+    /// Newer agent threads keep their messages here instead
+    thread: Option<ZedThread>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Agent thread as saved by newer Zed versions
+#[derive(Debug, Serialize, Deserialize)]
+struct ZedThread {
+    summary: Option<String>,
+    updated_at: Option<Value>,
+    messages: Vec<ZedMessage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 struct ZedMessage {
-    id: Option<String>,
+    /// A string in older exports, a number in agent threads
+    id: Option<Value>,
     #[serde(alias = "type")]
     role: Option<String>,
     #[serde(alias = "text")]
     content: Option<String>,
+    /// Text and tool calls of an agent-thread message, in order
+    segments: Option<Vec<ZedSegment>>,
     code: Option<String>,
     language: Option<String>,
     context: Option<ZedContext>,
@@ -55,23 +69,47 @@ struct ZedMessage {
     suggestions: Option<Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ZedSegment {
+    Text {
+        text: String,
+    },
+    #[serde(alias = "tool_use")]
+    ToolCall {
+        id: Option<String>,
+        name: String,
+        #[serde(alias = "arguments")]
+        input: Option<Value>,
+    },
+    /// Thinking and other segments aren't imported
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 struct ZedContext {
+    #[serde(alias = "path")]
     file: Option<String>,
     selection: Option<ZedSelection>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct ZedSelection {
     start: i32,
     end: i32,
 }
 
 /// Import Zed conversations from export file
-pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) -> Result<()> {
+pub async fn import(
+    pool: &SqlitePool,
+    path: &Path,
+    stats: &mut ImportStats,
+    options: &ImportOptions,
+) -> Result<()> {
     info!("Starting native Zed import from {:?}", path);
     
-    let content = tokio::fs::read_to_string(path)
+    let content = super::read_export(path, stats)
         .await
         .context("Failed to read Zed export file")?;
     
@@ -89,7 +127,7 @@ pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) ->
     let mut batch = Vec::new();
     
     for conv in conversations {
-        match parse_conversation(&conv) {
+        match parse_conversation(&conv, options) {
             Ok((conversation, messages)) => {
                 if messages.is_empty() {
                     debug!("Skipping conversation with no messages");
@@ -98,11 +136,9 @@ pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) ->
                 
                 batch.push((conversation, messages));
                 
-                if batch.len() >= 100 {
+                if batch.len() >= options.batch_size() {
                     let batch_to_process = std::mem::take(&mut batch);
-                    let batch_stats = process_conversation_batch(pool, batch_to_process).await?;
-                    stats.conversations += batch_stats.conversations;
-                    stats.messages += batch_stats.messages;
+                    import_batch(pool, batch_to_process, options, stats).await?;
                 }
             }
             Err(e) => {
@@ -114,33 +150,37 @@ pub async fn import(pool: &SqlitePool, path: &Path, stats: &mut ImportStats) ->
     
     // Process remaining conversations
     if !batch.is_empty() {
-        let batch_stats = process_conversation_batch(pool, batch).await?;
-        stats.conversations += batch_stats.conversations;
-        stats.messages += batch_stats.messages;
+        import_batch(pool, batch, options, stats).await?;
     }
     
     Ok(())
 }
 
 /// Parse a Zed conversation into our domain model
-fn parse_conversation(conv: &ZedConversation) -> Result<(Conversation, Vec<Message>)> {
+fn parse_conversation(
+    conv: &ZedConversation,
+    options: &ImportOptions,
+) -> Result<(Conversation, Vec<Message>)> {
     let created_at = conv.created_at
         .as_ref()
-        .and_then(parse_timestamp)
+        .and_then(|ts| parse_timestamp_in(ts, options.timezone()))
         .unwrap_or_else(Utc::now);
     
     let updated_at = conv.updated_at
         .as_ref()
-        .and_then(parse_timestamp)
+        .or_else(|| conv.thread.as_ref()?.updated_at.as_ref())
+        .and_then(|ts| parse_timestamp_in(ts, options.timezone()))
         .unwrap_or(created_at);
     
     // Generate title from file path if not provided
     let title = conv.title.clone()
+        .or_else(|| conv.thread.as_ref()?.summary.clone())
         .or_else(|| conv.file_path.clone())
         .unwrap_or_else(|| "Zed AI Session".to_string());
     
-    let model = conv.model.clone()
-        .unwrap_or_else(|| "zed-ai".to_string());
+    let model = conv.model.as_deref()
+        .map(|m| normalize_model_name(ProviderType::Zed, m))
+        .or_else(|| options.default_model(ProviderType::Zed));
     
     // Store workspace info in raw_json along with other metadata
     let mut raw_json = serde_json::to_value(conv)?;
@@ -158,7 +198,7 @@ fn parse_conversation(conv: &ZedConversation) -> Result<(Conversation, Vec<Messa
         provider: "zed".to_string(),
         external_id: conv.id.clone(),
         title: Some(title),
-        model: Some(model),
+        model,
         created_at,
         updated_at,
         raw_json: Some(raw_json),
@@ -166,19 +206,26 @@ fn parse_conversation(conv: &ZedConversation) -> Result<(Conversation, Vec<Messa
         temperature: None,
         max_tokens: None,
         user_id: None,
+        is_pinned: false,
+        is_archived: false,
     };
     
     // Parse messages
     let messages = conv.messages
         .as_ref()
-        .map(|msgs| msgs.iter().filter_map(|msg| parse_message(msg, created_at)).collect())
+        .or_else(|| conv.thread.as_ref().map(|thread| &thread.messages))
+        .map(|msgs| msgs.iter().filter_map(|msg| parse_message(msg, created_at, options)).collect())
         .unwrap_or_default();
     
     Ok((conversation, messages))
 }
 
 /// Parse a Zed message
-fn parse_message(msg: &ZedMessage, default_time: DateTime<Utc>) -> Option<Message> {
+fn parse_message(
+    msg: &ZedMessage,
+    default_time: DateTime<Utc>,
+    options: &ImportOptions,
+) -> Option<Message> {
     let role = msg.role.as_ref()?.to_lowercase();
     let role = match role.as_str() {
         "user" | "human" | "developer" => "user",
@@ -187,7 +234,9 @@ fn parse_message(msg: &ZedMessage, default_time: DateTime<Utc>) -> Option<Messag
         _ => return None,
     };
     
-    let mut content = msg.content.clone().unwrap_or_default();
+    let mut content = msg.content.clone()
+        .or_else(|| msg.segments.as_deref().map(segment_text))
+        .unwrap_or_default();
     
     // Add code block if present
     if let Some(code) = &msg.code {
@@ -211,7 +260,7 @@ fn parse_message(msg: &ZedMessage, default_time: DateTime<Utc>) -> Option<Messag
     
     let created_at = msg.created_at
         .as_ref()
-        .and_then(parse_timestamp)
+        .and_then(|ts| parse_timestamp_in(ts, options.timezone()))
         .unwrap_or(default_time);
     
     // Store additional metadata in attachments
@@ -225,6 +274,9 @@ fn parse_message(msg: &ZedMessage, default_time: DateTime<Utc>) -> Option<Messag
     if let Some(sugg) = &msg.suggestions {
         metadata.insert("suggestions".to_string(), sugg.clone());
     }
+    if let Some(ctx) = &msg.context {
+        metadata.insert("context".to_string(), serde_json::to_value(ctx).ok()?);
+    }
     
     let attachments = if metadata.is_empty() {
         None
@@ -241,7 +293,91 @@ fn parse_message(msg: &ZedMessage, default_time: DateTime<Utc>) -> Option<Messag
         created_at,
         tokens: None,
         finish_reason: None,
-        tool_calls: None,
+        tool_calls: msg.segments.as_deref().and_then(segment_tool_calls),
         attachments,
+        revisions: Vec::new(),
     })
-}
\ No newline at end of file
+}
+
+/// Text segments of an agent-thread message, one paragraph each
+fn segment_text(segments: &[ZedSegment]) -> String {
+    segments.iter()
+        .filter_map(|segment| match segment {
+            ZedSegment::Text { text } if !text.trim().is_empty() => Some(text.trim()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// `[{id, name, input}]` of the tool-call segments, if there are any
+fn segment_tool_calls(segments: &[ZedSegment]) -> Option<Value> {
+    let calls: Vec<Value> = segments.iter()
+        .filter_map(|segment| match segment {
+            ZedSegment::ToolCall { id, name, input } => Some(serde_json::json!({
+                "id": id,
+                "name": name,
+                "input": input,
+            })),
+            _ => None,
+        })
+        .collect();
+    
+    (!calls.is_empty()).then(|| Value::Array(calls))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_segmented_thread_keeps_text_and_tool_calls() {
+        let export = serde_json::json!({
+            "id": "thread-1",
+            "thread": {
+                "summary": "Rename the config loader",
+                "updated_at": "2025-06-01T09:30:00Z",
+                "messages": [
+                    {
+                        "id": 0,
+                        "role": "user",
+                        "segments": [{ "type": "text", "text": "Rename load_config to read_config" }],
+                        "context": { "path": "src/config.rs", "selection": { "start": 10, "end": 24 } },
+                    },
+                    {
+                        "id": 1,
+                        "role": "assistant",
+                        "segments": [
+                            { "type": "thinking", "text": "Find the callers first" },
+                            { "type": "text", "text": "I'll look for callers." },
+                            { "type": "tool_call", "id": "call-1", "name": "grep", "input": { "regex": "load_config" } },
+                            { "type": "text", "text": "Renamed it in two places." },
+                        ],
+                    },
+                ],
+            },
+        });
+        assert_eq!(crate::import::detect_provider(&export.to_string()), Some(ProviderType::Zed));
+        
+        let conv: ZedConversation = serde_json::from_value(export).unwrap();
+        let (conversation, messages) = parse_conversation(&conv, &ImportOptions::default()).unwrap();
+        
+        assert_eq!(conversation.title.as_deref(), Some("Rename the config loader"));
+        assert_eq!(conversation.updated_at.to_rfc3339(), "2025-06-01T09:30:00+00:00");
+        assert_eq!(messages.len(), 2);
+        
+        let question = &messages[0];
+        assert_eq!(question.content, "[File: src/config.rs] [Selection: lines 10-24]\nRename load_config to read_config");
+        assert_eq!(question.attachments.as_ref().unwrap()["context"]["file"], "src/config.rs");
+        assert_eq!(question.attachments.as_ref().unwrap()["context"]["selection"]["end"], 24);
+        assert!(question.tool_calls.is_none());
+        
+        let answer = &messages[1];
+        assert_eq!(answer.role, "assistant");
+        assert_eq!(answer.content, "I'll look for callers.\n\nRenamed it in two places.");
+        assert_eq!(
+            answer.tool_calls,
+            Some(serde_json::json!([{ "id": "call-1", "name": "grep", "input": { "regex": "load_config" } }]))
+        );
+    }
+}
@@ -7,13 +7,14 @@ use std::path::Path;
 use tracing::{debug, info, warn};
 
 use crate::models::{Conversation, ImportStats, Message, ProviderType};
-use super::process_conversation_batch;
+use super::{import_batch, ImportOptions};
 
 /// Import conversations using Python parsers via PyO3
 pub async fn import_with_python(
     pool: &SqlitePool,
     provider_type: ProviderType,
     path: &Path,
+    options: &ImportOptions,
     stats: &mut ImportStats,
 ) -> Result<()> {
     info!("Using Python bridge for {} import", provider_type.as_str());
@@ -53,23 +54,12 @@ pub async fn import_with_python(
                 Ok((conv, messages)) => {
                     batch.push((conv, messages));
                     
-                    // Process in batches of 100
-                    if batch.len() >= 100 {
+                    // Process in batches of the configured size
+                    if batch.len() >= options.batch_size() {
                         let batch_to_process = std::mem::take(&mut batch);
                         py.allow_threads(|| {
                             let rt = tokio::runtime::Handle::current();
-                            rt.block_on(async {
-                                match process_conversation_batch(pool, batch_to_process).await {
-                                    Ok(batch_stats) => {
-                                        stats.conversations += batch_stats.conversations;
-                                        stats.messages += batch_stats.messages;
-                                    }
-                                    Err(e) => {
-                                        warn!("Failed to process batch: {}", e);
-                                        stats.errors += 1;
-                                    }
-                                }
-                            });
+                            rt.block_on(store_batch(pool, batch_to_process, options, stats));
                         });
                     }
                 }
@@ -84,18 +74,7 @@ pub async fn import_with_python(
         if !batch.is_empty() {
             py.allow_threads(|| {
                 let rt = tokio::runtime::Handle::current();
-                rt.block_on(async {
-                    match process_conversation_batch(pool, batch).await {
-                        Ok(batch_stats) => {
-                            stats.conversations += batch_stats.conversations;
-                            stats.messages += batch_stats.messages;
-                        }
-                        Err(e) => {
-                            warn!("Failed to process final batch: {}", e);
-                            stats.errors += 1;
-                        }
-                    }
-                });
+                rt.block_on(store_batch(pool, batch, options, stats));
             });
         }
         
@@ -105,6 +84,25 @@ pub async fn import_with_python(
     Ok(())
 }
 
+/// Store a parsed batch like the native parsers do, recording its
+/// conversations as failed if it can't be stored even after retries so the
+/// rest of the import goes on
+async fn store_batch(
+    pool: &SqlitePool,
+    batch: Vec<(Conversation, Vec<Message>)>,
+    options: &ImportOptions,
+    stats: &mut ImportStats,
+) {
+    let count = batch.len();
+    let ids: Vec<String> = batch.iter().filter_map(|(conv, _)| conv.external_id.clone()).collect();
+    
+    if let Err(e) = import_batch(pool, batch, options, stats).await {
+        warn!("Failed to store batch of {} conversations: {:#}", count, e);
+        stats.errors += count;
+        stats.failed_conversations.extend(ids);
+    }
+}
+
 /// Parse a Python conversation object into Rust structs
 fn parse_conversation(
     py: Python,
@@ -174,6 +172,8 @@ fn parse_conversation(
         temperature,
         max_tokens,
         user_id,
+        is_pinned: false,
+        is_archived: false,
     };
     
     // Parse messages
@@ -249,6 +249,7 @@ fn parse_message(py: Python, msg_py: &PyAny) -> Result<Message> {
         finish_reason,
         tool_calls,
         attachments,
+        revisions: Vec::new(),
     })
 }
 
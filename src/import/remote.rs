@@ -0,0 +1,215 @@
+use anyhow::{bail, Context, Result};
+use futures::StreamExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+/// Most redirects followed for one download
+const MAX_REDIRECTS: usize = 10;
+
+/// Guards applied while downloading a remote export
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadLimits {
+    pub max_bytes: u64,
+    pub timeout: Duration,
+    /// Hosts every redirect must stay on, checked like the first URL by
+    /// `check_allowed`; `None` follows redirects anywhere
+    pub allowed_hosts: Option<Vec<String>>,
+}
+
+impl Default for DownloadLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 1024 * 1024 * 1024,
+            timeout: Duration::from_secs(300),
+            allowed_hosts: None,
+        }
+    }
+}
+
+/// Whether an import source names a remote object rather than a local path
+pub fn is_remote(source: &str) -> bool {
+    ["http://", "https://", "s3://"].iter().any(|scheme| source.starts_with(scheme))
+}
+
+/// The part of a source that names it across downloads, used as its import
+/// manifest key; presigned query strings change on every request
+pub fn manifest_source(source: &str) -> &str {
+    source.split(['?', '#']).next().unwrap_or(source)
+}
+
+/// Refuse a source unless it is fetched over https from one of `hosts`
+///
+/// `s3://` sources are checked as the https endpoint they resolve to.
+pub fn check_allowed(source: &str, hosts: &[String]) -> Result<()> {
+    let url = resolve_url(source)?;
+    let url = reqwest::Url::parse(&url).with_context(|| format!("Invalid URL {:?}", source))?;
+    if url.scheme() != "https" {
+        bail!("Only https:// and s3:// sources can be imported, not {:?}", source);
+    }
+    
+    let host = url.host_str().unwrap_or_default();
+    if !hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host)) {
+        bail!("Downloads from {:?} are not allowed", host);
+    }
+    
+    Ok(())
+}
+
+/// HTTP(S) URL to fetch a source from
+///
+/// `s3://bucket/key` maps to the bucket's virtual-hosted endpoint, which
+/// serves public objects; private objects need a presigned https URL.
+fn resolve_url(source: &str) -> Result<String> {
+    let Some(location) = source.strip_prefix("s3://") else {
+        return Ok(source.to_string());
+    };
+    
+    match location.split_once('/') {
+        Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => {
+            Ok(format!("https://{}.s3.amazonaws.com/{}", bucket, key))
+        }
+        _ => bail!("Invalid S3 source {:?}; expected s3://bucket/key", source),
+    }
+}
+
+/// Stream a remote export into `dir`, returning the downloaded file
+pub async fn download(source: &str, dir: &Path, limits: &DownloadLimits) -> Result<PathBuf> {
+    let url = resolve_url(source)?;
+    
+    let allowed_hosts = limits.allowed_hosts.clone();
+    let redirects = reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() > MAX_REDIRECTS {
+            return attempt.error(format!("more than {} redirects", MAX_REDIRECTS));
+        }
+        match &allowed_hosts {
+            Some(hosts) => match check_allowed(attempt.url().as_str(), hosts) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e),
+            },
+            None => attempt.follow(),
+        }
+    });
+    
+    let client = reqwest::Client::builder()
+        .timeout(limits.timeout)
+        .redirect(redirects)
+        .build()
+        .context("Failed to create HTTP client")?;
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("Failed to fetch {}", url))?;
+    
+    if let Some(length) = response.content_length() {
+        if length > limits.max_bytes {
+            bail!("{} is {} bytes, over the {} byte download limit", url, length, limits.max_bytes);
+        }
+    }
+    
+    // Keep the remote file name so the extension survives; query strings
+    // on presigned URLs are not part of it
+    let name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .and_then(|segment| Path::new(segment).file_name())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("export.json"));
+    let path = dir.join(name);
+    
+    let mut file = tokio::fs::File::create(&path)
+        .await
+        .with_context(|| format!("Failed to create {:?}", path))?;
+    let mut stream = response.bytes_stream();
+    let mut received: u64 = 0;
+    
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.with_context(|| format!("Download of {} interrupted", url))?;
+        received += chunk.len() as u64;
+        if received > limits.max_bytes {
+            bail!("{} exceeded the {} byte download limit", url, limits.max_bytes);
+        }
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    
+    info!("Downloaded {} bytes from {}", received, url);
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_resolve_url() {
+        assert_eq!(resolve_url("https://example.com/a.json").unwrap(), "https://example.com/a.json");
+        assert_eq!(
+            resolve_url("s3://exports/2024/claude.json").unwrap(),
+            "https://exports.s3.amazonaws.com/2024/claude.json"
+        );
+        assert!(resolve_url("s3://exports").is_err());
+        assert!(!is_remote("./exports/claude.json"));
+    }
+    
+    #[test]
+    fn test_check_allowed() {
+        let hosts = vec!["exports.example.com".to_string(), "backups.s3.amazonaws.com".to_string()];
+        
+        assert!(check_allowed("https://exports.example.com/a.json", &hosts).is_ok());
+        assert!(check_allowed("https://EXPORTS.example.com/a.json?sig=1", &hosts).is_ok());
+        assert!(check_allowed("s3://backups/2024/claude.json", &hosts).is_ok());
+        
+        assert!(check_allowed("http://exports.example.com/a.json", &hosts).is_err());
+        assert!(check_allowed("https://169.254.169.254/latest/meta-data", &hosts).is_err());
+        assert!(check_allowed("https://exports.example.com.evil.test/a.json", &hosts).is_err());
+        assert!(check_allowed("s3://other/claude.json", &hosts).is_err());
+        assert!(check_allowed("https://exports.example.com/a.json", &[]).is_err());
+    }
+    
+    #[test]
+    fn test_manifest_source_drops_query_strings() {
+        assert_eq!(
+            manifest_source("https://example.com/a.json?X-Amz-Signature=abc#top"),
+            "https://example.com/a.json"
+        );
+        assert_eq!(manifest_source("s3://exports/claude.json"), "s3://exports/claude.json");
+    }
+    
+    #[tokio::test]
+    async fn test_redirects_are_checked_against_allowed_hosts() {
+        let app = axum::Router::new()
+            .route("/moved.json", axum::routing::get(|| async { axum::response::Redirect::temporary("/export.json") }))
+            .route("/export.json", axum::routing::get(|| async { "{}" }))
+            .route(
+                "/metadata",
+                axum::routing::get(|| async {
+                    axum::response::Redirect::temporary("https://169.254.169.254/latest/meta-data")
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let dir = tempfile::tempdir().unwrap();
+        
+        // The CLI follows redirects anywhere
+        let path = download(&format!("http://{}/moved.json", addr), dir.path(), &DownloadLimits::default())
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "{}");
+        
+        // API imports stop at the first hop leaving the allowed hosts
+        let limits = DownloadLimits {
+            allowed_hosts: Some(vec!["127.0.0.1".to_string()]),
+            ..Default::default()
+        };
+        let error = download(&format!("http://{}/metadata", addr), dir.path(), &limits)
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("\"169.254.169.254\" are not allowed"), "{:#}", error);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::models::Message;
+
+/// Token count of `text`
+///
+/// With the `tiktoken` feature this is the exact `cl100k_base` count;
+/// without it, an estimate of four tokens for every three
+/// whitespace-separated words.
+pub fn count_tokens(text: &str) -> i32 {
+    let count = count(text);
+    i32::try_from(count).unwrap_or(i32::MAX)
+}
+
+#[cfg(feature = "tiktoken")]
+fn count(text: &str) -> usize {
+    use once_cell::sync::Lazy;
+    use tiktoken_rs::CoreBPE;
+    
+    static BPE: Lazy<CoreBPE> = Lazy::new(|| tiktoken_rs::cl100k_base().expect("cl100k_base is bundled"));
+    
+    BPE.encode_ordinary(text).len()
+}
+
+#[cfg(not(feature = "tiktoken"))]
+fn count(text: &str) -> usize {
+    let words = text.split_whitespace().count();
+    (words * 4).div_ceil(3)
+}
+
+/// Fill in `tokens` for messages whose export didn't include a count
+pub fn fill_missing(messages: &mut [Message]) {
+    for message in messages.iter_mut().filter(|m| m.tokens.is_none()) {
+        message.tokens = Some(count_tokens(&message.content));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_count_grows_with_content() {
+        let short = count_tokens("The quick brown fox jumps over the lazy dog.");
+        let long = count_tokens(&"The quick brown fox jumps over the lazy dog. ".repeat(10));
+        
+        assert!(short > 0);
+        assert!(long >= short * 8 && long <= short * 12, "{} vs {}", short, long);
+        assert_eq!(count_tokens(""), 0);
+    }
+}
@@ -1,12 +1,17 @@
 use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
 use clap::{Parser, Subcommand};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
 use std::path::PathBuf;
-use tracing::{info, Level};
+use tracing::{info, Level, Subscriber};
+use tracing_subscriber::fmt::MakeWriter;
 use tracing_subscriber::FmtSubscriber;
 
 mod config;
 mod db;
 mod errors;
+mod export;
 mod import;
 mod metrics;
 mod models;
@@ -19,10 +24,43 @@ use crate::config::Config;
 #[command(name = "llm-archive")]
 #[command(about = "Fast, focused tool for searching LLM conversation archives", long_about = None)]
 struct Cli {
+    /// Only log warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    
+    /// Log debug detail; repeat (-vv) for trace
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+    
+    /// Log line format; `json` writes one object per line for log pipelines
+    #[arg(long, global = true, value_enum, env = "LOG_FORMAT", default_value_t = LogFormat::Pretty)]
+    log_format: LogFormat,
+    
     #[command(subcommand)]
     command: Commands,
 }
 
+impl Cli {
+    /// Log level selected by `--quiet`/`--verbose`, INFO by default
+    fn log_level(&self) -> Level {
+        match (self.quiet, self.verbose) {
+            (true, _) => Level::WARN,
+            (false, 0) => Level::INFO,
+            (false, 1) => Level::DEBUG,
+            (false, _) => Level::TRACE,
+        }
+    }
+}
+
+/// How log events are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum LogFormat {
+    /// Compact lines for reading in a terminal
+    Pretty,
+    /// JSON objects with `timestamp`, `level`, and `fields`
+    Json,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Start the web server
@@ -30,16 +68,21 @@ enum Commands {
         #[arg(short, long, default_value = "8080")]
         port: u16,
         
+        /// IP address to listen on, e.g. 0.0.0.0 for other devices on the
+        /// LAN; defaults to `server.host`
+        #[arg(long)]
+        host: Option<String>,
+        
         #[arg(short, long, default_value = "./llm_archive.db")]
         database: PathBuf,
     },
     
     /// Import conversations from various formats
     Import {
-        /// Provider type (chatgpt, claude, gemini, xai)
+        /// Provider type (chatgpt, claude, gemini, xai, zed, markdown), or `auto` to detect it
         provider: String,
         
-        /// Path to export file(s)
+        /// Path to export file(s), or an http(s):// or s3:// URL
         path: PathBuf,
         
         #[arg(short, long, default_value = "./llm_archive.db")]
@@ -48,6 +91,26 @@ enum Commands {
         /// Use Python bridge for parsing (temporary)
         #[arg(long)]
         python_bridge: bool,
+        
+        /// Re-import files even if they are unchanged since the last import
+        #[arg(long)]
+        force: bool,
+        
+        /// Skip conversations last updated before this date (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_since)]
+        since: Option<DateTime<Utc>>,
+        
+        /// Count tokens for messages the export has no count for
+        #[arg(long)]
+        count_tokens: bool,
+        
+        /// Detect each conversation's language (also `import.detect_language`)
+        #[arg(long)]
+        detect_language: bool,
+        
+        /// Title untitled conversations after their first user message
+        #[arg(long)]
+        generate_titles: bool,
     },
     
     /// Search conversations
@@ -58,8 +121,75 @@ enum Commands {
         #[arg(short, long, default_value = "./llm_archive.db")]
         database: PathBuf,
         
-        #[arg(short, long, default_value = "10")]
-        limit: usize,
+        /// Results to show; defaults to `search.default_limit`, capped at `search.max_limit`
+        #[arg(short, long, allow_negative_numbers = true)]
+        limit: Option<i64>,
+    },
+    
+    /// Export conversations (openai-chat, jsonl, json, markdown, html, csv)
+    Export {
+        /// Output format
+        #[arg(short, long, default_value = "openai-chat")]
+        format: String,
+        
+        /// With jsonl, write one line per message instead of per conversation
+        #[arg(long)]
+        flatten: bool,
+        
+        /// Transcript layout for markdown and html (default, chat, qa, interview)
+        #[arg(long, default_value = "default")]
+        template: String,
+        
+        /// With markdown, write one document with a table of contents
+        #[arg(long)]
+        combine: bool,
+        
+        /// Mask emails, API keys and other `export.redaction_patterns` matches
+        #[arg(long)]
+        redact: bool,
+        
+        /// Output file; defaults to stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        
+        #[arg(short, long, default_value = "./llm_archive.db")]
+        database: PathBuf,
+    },
+    
+    /// Rebuild the search index if the configured tokenizer changed
+    Reindex {
+        #[arg(short, long, default_value = "./llm_archive.db")]
+        database: PathBuf,
+        
+        /// Rebuild even if the tokenizer is unchanged
+        #[arg(long)]
+        force: bool,
+    },
+    
+    /// Compact the database file and refresh the search index and statistics
+    Maintain {
+        #[arg(short, long, default_value = "./llm_archive.db")]
+        database: PathBuf,
+    },
+    
+    /// Remove conversations whose messages exactly repeat an earlier one
+    Dedupe {
+        #[arg(short, long, default_value = "./llm_archive.db")]
+        database: PathBuf,
+        
+        /// Report duplicates without deleting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    
+    /// Copy conversations from another archive database into this one
+    Merge {
+        /// Archive to copy conversations from; it is opened read-only
+        source: PathBuf,
+        
+        /// Archive to merge into
+        #[arg(long, default_value = "./llm_archive.db")]
+        into: PathBuf,
     },
     
     /// Initialize database
@@ -69,23 +199,27 @@ enum Commands {
     },
 }
 
+/// Parse a `--since` date as midnight UTC
+fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc())
+        .map_err(|e| format!("expected YYYY-MM-DD: {}", e))
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
-        .with_target(false)
-        .compact()
-        .build();
-    
-    tracing::subscriber::set_global_default(subscriber)?;
-    
     let cli = Cli::parse();
     
+    // Initialize tracing
+    tracing::subscriber::set_global_default(subscriber(cli.log_level(), cli.log_format, std::io::stdout))?;
+    
     match cli.command {
-        Commands::Serve { port, database } => {
+        Commands::Serve { port, host, database } => {
             info!("Starting LLM Archive server on port {}", port);
-            let config = Config::load()?;
+            let mut config = Config::load()?;
+            if let Some(host) = host {
+                config.server.host = host;
+            }
             server::run(port, database, config).await?;
         }
         
@@ -94,28 +228,63 @@ async fn main() -> Result<()> {
             path,
             database,
             python_bridge,
+            force,
+            since,
+            count_tokens,
+            detect_language,
+            generate_titles,
         } => {
             info!("Importing {} conversations from {:?}", provider, path);
+            let config = Config::load()?;
+            
+            if let Some(addr) = db::lock::running_server(&database) {
+                info!("Server at {} is using {:?}; serializing writes with it", addr, database);
+            }
+            let _lock = db::lock::WriteLock::acquire(
+                &database,
+                std::time::Duration::from_secs(config.database.lock_timeout_secs),
+            ).await?;
+            
             let pool = db::create_pool(&database).await?;
+            let progress_bar = import_progress_bar();
+            let options = import::ImportOptions {
+                use_python_bridge: python_bridge,
+                force,
+                since,
+                count_tokens,
+                detect_language: detect_language || config.import.detect_language,
+                generate_titles,
+                progress: progress_bar.as_ref().map(|(_, progress)| progress.clone()),
+                ..import::ImportOptions::from_config(&config.import)?
+            };
             
             let start = std::time::Instant::now();
-            let count = import::import_conversations(
+            let result = import::import_conversations(
                 &pool,
                 &provider,
                 &path,
-                python_bridge,
-            ).await?;
+                &options,
+            ).await;
+            if let Some((bar, _)) = progress_bar {
+                bar.finish_and_clear();
+            }
+            let stats = result?;
             
             let elapsed = start.elapsed();
             info!(
                 "Imported {} conversations in {:.2}s ({:.0} msgs/sec)",
-                count,
+                stats.conversations,
                 elapsed.as_secs_f64(),
-                count as f64 / elapsed.as_secs_f64()
+                stats.messages as f64 / elapsed.as_secs_f64()
             );
+            if since.is_some() {
+                info!("Skipped {} conversations older than --since", stats.filtered);
+            }
         }
         
         Commands::Search { query, database, limit } => {
+            let config = Config::load()?;
+            let limit = config.search.limit(limit)?;
             let pool = db::create_pool(&database).await?;
             let results = search::search_conversations(&pool, &query, limit).await?;
             
@@ -131,13 +300,258 @@ async fn main() -> Result<()> {
             }
         }
         
+        Commands::Export { format, flatten, template, combine, redact, output, database } => {
+            let config = Config::load()?;
+            let options = export::ExportOptions {
+                template: template.parse()?,
+                redactor: if redact { Some(config.export.redactor()?) } else { None },
+                ..export::ExportOptions::new(format.parse()?, flatten)?
+            }
+            .with_combine(combine)?;
+            let pool = db::create_pool(&database).await?;
+            
+            let written = match &output {
+                Some(path) => {
+                    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+                    export::export_all(&pool, options, &mut file).await?
+                }
+                None => export::export_all(&pool, options, &mut std::io::stdout().lock()).await?,
+            };
+            
+            if let Some(path) = output {
+                info!("Exported {} records to {:?}", written, path);
+            }
+        }
+        
+        Commands::Reindex { database, force } => {
+            let config = Config::load()?;
+            let _lock = db::lock::WriteLock::acquire(
+                &database,
+                std::time::Duration::from_secs(config.database.lock_timeout_secs),
+            ).await?;
+            
+            let pool = db::create_pool(&database).await?;
+            db::run_migrations(&pool, &config.search.tokenizer).await?;
+            
+            let report = db::fts::reindex(&pool, &config.search.tokenizer, force).await?;
+            if report.rebuilt {
+                info!(
+                    "Reindexed {} messages with tokenizer '{}' (was {:?})",
+                    report.messages, report.tokenizer, report.previous_tokenizer
+                );
+            } else {
+                info!("Search index is up to date (tokenizer '{}')", report.tokenizer);
+            }
+        }
+        
+        Commands::Maintain { database } => {
+            let config = Config::load()?;
+            let _lock = db::lock::WriteLock::acquire(
+                &database,
+                std::time::Duration::from_secs(config.database.lock_timeout_secs),
+            ).await?;
+            
+            let pool = db::create_pool(&database).await?;
+            db::run_migrations(&pool, &config.search.tokenizer).await?;
+            
+            let report = db::maintain(&pool).await?;
+            info!(
+                "Database size: {:.1} MB -> {:.1} MB ({:.1} MB reclaimed)",
+                report.size_before as f64 / 1_048_576.0,
+                report.size_after as f64 / 1_048_576.0,
+                report.reclaimed() as f64 / 1_048_576.0
+            );
+        }
+        
+        Commands::Dedupe { database, dry_run } => {
+            let config = Config::load()?;
+            let _lock = db::lock::WriteLock::acquire(
+                &database,
+                std::time::Duration::from_secs(config.database.lock_timeout_secs),
+            ).await?;
+            
+            let pool = db::create_pool(&database).await?;
+            db::run_migrations(&pool, &config.search.tokenizer).await?;
+            
+            let report = db::dedupe(&pool, dry_run).await?;
+            if dry_run {
+                info!(
+                    "Would remove {} duplicate conversations ({} messages); run without --dry-run to delete them",
+                    report.removed, report.removed_messages
+                );
+            } else {
+                info!(
+                    "Removed {} duplicate conversations ({} messages)",
+                    report.removed, report.removed_messages
+                );
+            }
+        }
+        
+        Commands::Merge { source, into } => {
+            let config = Config::load()?;
+            let _lock = db::lock::WriteLock::acquire(
+                &into,
+                std::time::Duration::from_secs(config.database.lock_timeout_secs),
+            ).await?;
+            
+            let pool = db::create_pool(&into).await?;
+            db::run_migrations(&pool, &config.search.tokenizer).await?;
+            
+            let report = db::merge(&pool, &source).await?;
+            info!(
+                "Merged {:?} into {:?}: {} conversations added, {} updated, {} skipped",
+                source, into, report.added, report.updated, report.skipped
+            );
+        }
+        
         Commands::Init { database } => {
-            info!("Initializing database at {:?}", database);
+            let config = Config::load()?;
+            info!(
+                "Initializing database at {:?} (search tokenizer '{}')",
+                database, config.search.tokenizer
+            );
             let pool = db::create_pool(&database).await?;
-            db::run_migrations(&pool).await?;
+            db::run_migrations(&pool, &config.search.tokenizer).await?;
             info!("Database initialized successfully");
         }
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Subscriber logging events at `level` and above to `writer` in `format`
+fn subscriber<W>(level: Level, format: LogFormat, writer: W) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let builder = FmtSubscriber::builder()
+        .with_max_level(level)
+        .with_writer(writer);
+    
+    match format {
+        LogFormat::Pretty => Box::new(builder.with_target(false).compact().finish()),
+        LogFormat::Json => Box::new(builder.json().finish()),
+    }
+}
+
+/// Progress bar for `import` with the callback that drives it, or `None`
+/// when stderr is not a terminal so piped and logged runs stay plain
+fn import_progress_bar() -> Option<(ProgressBar, import::ImportProgress)> {
+    if !std::io::stderr().is_terminal() {
+        return None;
+    }
+    
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::with_template("{spinner} [{elapsed}] [{bar:30}] {pos}/{len} files, {msg}")
+            .expect("progress template is valid")
+            .progress_chars("=> "),
+    );
+    
+    let callback_bar = bar.clone();
+    let progress = import::ImportProgress::new(move |stats| {
+        let files_done = stats.files_processed + stats.files_skipped + stats.unrecognized_files.len();
+        callback_bar.set_length(stats.files_total as u64);
+        callback_bar.set_position(files_done as u64);
+        callback_bar.set_message(format!("{} conversations, {} messages", stats.conversations, stats.messages));
+    });
+    
+    Some((bar, progress))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn level(args: &[&str]) -> Level {
+        Cli::try_parse_from(args).unwrap().log_level()
+    }
+    
+    #[test]
+    fn test_verbosity_flags_set_log_level() {
+        assert_eq!(level(&["llm-archive", "init"]), Level::INFO);
+        assert_eq!(level(&["llm-archive", "-q", "init"]), Level::WARN);
+        assert_eq!(level(&["llm-archive", "-v", "init"]), Level::DEBUG);
+        assert_eq!(level(&["llm-archive", "-vv", "init"]), Level::TRACE);
+        
+        // Global flags also work after the subcommand
+        assert_eq!(level(&["llm-archive", "init", "--verbose"]), Level::DEBUG);
+        
+        assert!(Cli::try_parse_from(["llm-archive", "-q", "-v", "init"]).is_err());
+    }
+    
+    /// Log output collected in memory
+    #[derive(Clone, Default)]
+    struct Captured(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+    
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    
+    impl Captured {
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+    }
+    
+    #[test]
+    fn test_log_format_flag_selects_json_or_pretty() {
+        let format = |args: &[&str]| Cli::try_parse_from(args).unwrap().log_format;
+        assert_eq!(format(&["llm-archive", "init"]), LogFormat::Pretty);
+        assert_eq!(format(&["llm-archive", "--log-format", "json", "init"]), LogFormat::Json);
+        assert_eq!(format(&["llm-archive", "init", "--log-format", "pretty"]), LogFormat::Pretty);
+        assert!(Cli::try_parse_from(["llm-archive", "--log-format", "xml", "init"]).is_err());
+        
+        let json = Captured::default();
+        let writer = json.clone();
+        tracing::subscriber::with_default(subscriber(Level::INFO, LogFormat::Json, move || writer.clone()), || {
+            info!(files = 3, "Import finished");
+            tracing::debug!("filtered out");
+        });
+        
+        let lines = json.lines();
+        assert_eq!(lines.len(), 1);
+        let event: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(event["level"], "INFO");
+        assert!(event["timestamp"].is_string());
+        assert_eq!(event["fields"]["message"], "Import finished");
+        assert_eq!(event["fields"]["files"], 3);
+        
+        let pretty = Captured::default();
+        let writer = pretty.clone();
+        tracing::subscriber::with_default(subscriber(Level::INFO, LogFormat::Pretty, move || writer.clone()), || {
+            info!("Import finished");
+        });
+        
+        let lines = pretty.lines();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("Import finished"));
+        assert!(serde_json::from_str::<serde_json::Value>(&lines[0]).is_err());
+    }
+    
+    #[test]
+    fn test_debug_events_only_enabled_when_verbose() {
+        for (args, debug_enabled) in [(&["llm-archive", "init"][..], false), (&["llm-archive", "-v", "init"][..], true)] {
+            let subscriber = FmtSubscriber::builder()
+                .with_max_level(level(args))
+                .with_writer(std::io::sink)
+                .finish();
+            
+            tracing::subscriber::with_default(subscriber, || {
+                assert_eq!(tracing::enabled!(Level::DEBUG), debug_enabled);
+                assert!(tracing::enabled!(Level::WARN));
+            });
+        }
+    }
+}
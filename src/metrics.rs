@@ -41,6 +41,16 @@ pub fn init_metrics() -> anyhow::Result<()> {
         "Total number of messages imported"
     );
     
+    describe_counter!(
+        "llm_archive_import_parse_errors_total",
+        "Conversations or records an import could not parse"
+    );
+    
+    describe_counter!(
+        "llm_archive_import_parse_warnings_total",
+        "Problems an import worked around, such as truncated messages"
+    );
+    
     describe_gauge!(
         "llm_archive_database_size_bytes",
         "Size of the database file in bytes"
@@ -105,31 +115,46 @@ pub fn track_search(provider: Option<&str>, result_count: usize, duration: Durat
 }
 
 /// Track import operation
-pub fn track_import(provider: &str, conversations: usize, messages: usize, duration: Duration, success: bool) {
+///
+/// Conversation, message, error and warning counts are tracked per file
+/// by `track_import_file`, under the provider each file was parsed as.
+pub fn track_import(provider: &str, duration: Duration, success: bool) {
     counter!(
         "llm_archive_imports_total",
         "provider" => provider.to_string(),
         "status" => if success { "success" } else { "failure" }.to_string(),
     ).increment(1);
     
-    if success {
-        counter!(
-            "llm_archive_import_conversations_total",
-            "provider" => provider.to_string(),
-        ).increment(conversations as u64);
-        
-        counter!(
-            "llm_archive_import_messages_total",
-            "provider" => provider.to_string(),
-        ).increment(messages as u64);
-    }
-    
     histogram!(
         "llm_archive_import_duration_seconds",
         "provider" => provider.to_string(),
     ).record(duration.as_secs_f64());
 }
 
+/// Track what parsing one export file produced, whether or not the file
+/// was imported completely
+pub fn track_import_file(provider: &str, conversations: usize, messages: usize, errors: usize, warnings: usize) {
+    counter!(
+        "llm_archive_import_conversations_total",
+        "provider" => provider.to_string(),
+    ).increment(conversations as u64);
+    
+    counter!(
+        "llm_archive_import_messages_total",
+        "provider" => provider.to_string(),
+    ).increment(messages as u64);
+    
+    counter!(
+        "llm_archive_import_parse_errors_total",
+        "provider" => provider.to_string(),
+    ).increment(errors as u64);
+    
+    counter!(
+        "llm_archive_import_parse_warnings_total",
+        "provider" => provider.to_string(),
+    ).increment(warnings as u64);
+}
+
 /// Update database statistics
 pub fn update_database_stats(size_bytes: u64, conversations: i64, messages: i64) {
     gauge!("llm_archive_database_size_bytes").set(size_bytes as f64);
@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 /// Core domain models - kept minimal and focused
 /// These match the simplified database schema
@@ -29,6 +31,16 @@ pub struct Conversation {
     pub temperature: Option<f32>,
     pub max_tokens: Option<i32>,
     pub user_id: Option<String>,
+    
+    /// Listed first when browsing; set through the API, never by imports
+    #[sqlx(default)]
+    #[serde(default)]
+    pub is_pinned: bool,
+    
+    /// Hidden from browsing, search, and stats unless asked for
+    #[sqlx(default)]
+    #[serde(default)]
+    pub is_archived: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -45,23 +57,142 @@ pub struct Message {
     pub finish_reason: Option<String>,
     pub tool_calls: Option<serde_json::Value>,
     pub attachments: Option<serde_json::Value>,
+    
+    /// Earlier versions of an edited message, oldest first; stored in
+    /// `message_revisions` rather than loaded with the message
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revisions: Vec<MessageRevision>,
+}
+
+/// Content a message had before it was edited
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageRevision {
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Per-conversation aggregates for auditing
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationSummary {
+    pub conversation_id: i64,
+    pub message_count: i64,
+    /// Messages per role, e.g. `{"assistant": 3, "user": 3}`
+    pub role_counts: BTreeMap<String, i64>,
+    /// Characters of message content
+    pub total_chars: i64,
+    /// Stored token counts, with the token counter standing in for
+    /// messages imported without one
+    pub estimated_tokens: i64,
+    pub first_message_at: Option<DateTime<Utc>>,
+    pub last_message_at: Option<DateTime<Utc>>,
+    /// Models that wrote messages, alphabetically
+    pub models: Vec<String>,
+    pub attachment_count: i64,
+}
+
+/// A filter value and how many rows have it
+#[derive(Debug, Clone, Serialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+/// Options for search filter dropdowns, drawn from unarchived conversations
+#[derive(Debug, Clone, Serialize)]
+pub struct Facets {
+    /// Conversations per provider, most first
+    pub providers: Vec<FacetCount>,
+    /// Conversations per conversation model, most first
+    pub models: Vec<FacetCount>,
+    /// Messages per role, most first
+    pub roles: Vec<FacetCount>,
+    /// Creation dates bounding the `after`/`before` filters; `None` for an
+    /// empty archive
+    pub first_created_at: Option<DateTime<Utc>>,
+    pub last_created_at: Option<DateTime<Utc>>,
+}
+
+/// A message matching a search within one conversation
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageMatch {
+    pub message_id: i64,
+    /// Zero-based index among the conversation's messages, oldest first
+    pub position: i64,
+    pub role: String,
+    pub snippet: String,
+}
+
+/// Which part of a conversation a search hit matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchField {
+    Title,
+    Message,
 }
 
 /// Search result with snippets
 #[derive(Debug, Serialize)]
 pub struct SearchResult {
     pub conversation: Conversation,
+    /// Best-matching message, for jumping to it in the conversation view;
+    /// `None` for title matches
+    pub message_id: Option<i64>,
+    /// Where the match was found: `title` or the message role
+    pub matched_in: String,
+    /// Whether the title or a message matched; `None` for browse results
+    pub match_field: Option<MatchField>,
     pub snippet: String,
+    /// `snippet` followed by excerpts from the conversation's other
+    /// best-matching messages, when more than one was requested
+    pub snippets: Vec<String>,
     pub rank: f32,
 }
 
 /// Import statistics
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct ImportStats {
     pub conversations: usize,
     pub messages: usize,
     pub errors: usize,
+    /// Problems worked around without losing a conversation, such as
+    /// messages cut to `import.max_message_chars`
+    pub warnings: usize,
     pub duration_ms: u64,
+    /// Export files found, whether or not they end up imported
+    pub files_total: usize,
+    pub files_processed: usize,
+    pub files_skipped: usize,
+    /// Conversations left out by the `since` cutoff
+    pub filtered: usize,
+    /// Files skipped by an auto-detected directory import because no
+    /// provider recognized them
+    pub unrecognized_files: Vec<PathBuf>,
+    /// External ids of conversations whose batch could not be stored
+    pub failed_conversations: Vec<String>,
+    /// Conversations stored for the first time
+    pub new: usize,
+    /// Conversations that replaced an earlier import of themselves
+    pub updated: usize,
+    /// Earlier copies of a conversation repeated within one batch; only
+    /// the last copy is stored
+    pub skipped_duplicates: usize,
+    /// Parsers that produced the import, e.g. `native-2.0.0`
+    pub parser_version: String,
+}
+
+/// A past import run from `import_events`
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportEvent {
+    pub id: i64,
+    pub provider: String,
+    pub file_path: Option<String>,
+    /// `in_progress`, `completed` or `failed`
+    pub status: String,
+    /// Counts written when the run finished; `None` while it is running
+    pub stats: Option<serde_json::Value>,
+    pub error: Option<String>,
+    pub created_at: String,
 }
 
 /// Supported providers
@@ -72,6 +203,8 @@ pub enum ProviderType {
     Gemini,
     XAI,
     Zed,
+    /// Markdown or plain text transcripts
+    Markdown,
     Unknown,
 }
 
@@ -83,6 +216,7 @@ impl ProviderType {
             "gemini" | "google" => Self::Gemini,
             "xai" | "grok" => Self::XAI,
             "zed" => Self::Zed,
+            "markdown" | "md" | "text" => Self::Markdown,
             _ => Self::Unknown,
         }
     }
@@ -94,7 +228,149 @@ impl ProviderType {
             Self::Gemini => "gemini",
             Self::XAI => "xai",
             Self::Zed => "zed",
+            Self::Markdown => "markdown",
             Self::Unknown => "unknown",
         }
     }
-}
\ No newline at end of file
+}
+
+/// How model slugs are grouped in stats
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelGrouping {
+    /// Every slug on its own, e.g. `gpt-4-0613`
+    #[default]
+    Exact,
+    /// Date- and snapshot-versioned slugs collapsed, e.g. `gpt-4`
+    Family,
+}
+
+impl ModelGrouping {
+    pub fn key(&self, slug: &str) -> String {
+        match self {
+            Self::Exact => slug.to_string(),
+            Self::Family => model_family(slug),
+        }
+    }
+}
+
+/// Model slug without its date or snapshot suffixes
+///
+/// `gpt-4-0613` → `gpt-4`, `gpt-4o-2024-05-13` → `gpt-4o`,
+/// `claude-3-5-sonnet@20240620` → `claude-3-5-sonnet`.
+pub fn model_family(slug: &str) -> String {
+    // Vertex AI pins versions with `@`
+    let mut family = slug.split('@').next().unwrap_or(slug);
+    while let Some(base) = strip_version_suffix(family) {
+        family = base;
+    }
+    family.to_string()
+}
+
+/// Alternate slugs for the same model, mapped to the name we store
+///
+/// Covers app-specific variants (mobile, custom GPTs), legacy internal
+/// names, and old API aliases. Versioned slugs are left alone; stats can
+/// collapse those with [`model_family`].
+const MODEL_ALIASES: &[(&str, &str)] = &[
+    // GPT
+    ("gpt-4-gizmo", "gpt-4"),
+    ("gpt-4-mobile", "gpt-4"),
+    ("text-davinci-002-render", "gpt-3.5-turbo"),
+    ("text-davinci-002-render-sha", "gpt-3.5-turbo"),
+    ("text-davinci-002-render-paid", "gpt-3.5-turbo"),
+    ("text-davinci-002-render-sha-mobile", "gpt-3.5-turbo"),
+    // Claude
+    ("claude-v1", "claude-1"),
+    ("claude-v2", "claude-2"),
+    ("claude-instant-v1", "claude-instant-1"),
+    // Gemini
+    ("bard", "gemini-pro"),
+    ("gemini-1.0-pro", "gemini-pro"),
+    // Grok
+    ("grok", "grok-1"),
+];
+
+/// Canonical model name for a provider's model slug
+///
+/// `gpt-4-mobile` → `gpt-4`, `models/gemini-1.5-pro` → `gemini-1.5-pro`.
+/// Slugs without a mapping pass through unchanged.
+pub fn normalize_model_name(provider: ProviderType, slug: &str) -> String {
+    let slug = slug.trim();
+    
+    // The Gemini API names models by resource path
+    let slug = match provider {
+        ProviderType::Gemini => slug.strip_prefix("models/").unwrap_or(slug),
+        _ => slug,
+    };
+    
+    MODEL_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(slug))
+        .map(|(_, canonical)| canonical.to_string())
+        .unwrap_or_else(|| slug.to_string())
+}
+
+fn strip_version_suffix(slug: &str) -> Option<&str> {
+    let digits = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+    
+    let (base, suffix) = slug.rsplit_once('-')?;
+    if base.is_empty() {
+        return None;
+    }
+    
+    // `-latest`, snapshots like `-0613`/`-001`, and compact dates like `-20240620`
+    if suffix == "latest" || [3, 4, 8].iter().any(|&len| digits(suffix, len)) {
+        return Some(base);
+    }
+    
+    // ISO dates: `-2024-05-13`
+    let (rest, month) = base.rsplit_once('-')?;
+    let (family, year) = rest.rsplit_once('-')?;
+    if digits(suffix, 2) && digits(month, 2) && digits(year, 4) && !family.is_empty() {
+        return Some(family);
+    }
+    
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_model_family_collapses_versioned_slugs() {
+        assert_eq!(model_family("gpt-4-0613"), "gpt-4");
+        assert_eq!(model_family("gpt-3.5-turbo-16k-0613"), "gpt-3.5-turbo-16k");
+        assert_eq!(model_family("gpt-4o-2024-05-13"), "gpt-4o");
+        assert_eq!(model_family("claude-3-5-sonnet-20240620"), "claude-3-5-sonnet");
+        assert_eq!(model_family("claude-3-5-sonnet@20240620"), "claude-3-5-sonnet");
+        assert_eq!(model_family("gemini-1.5-pro-001"), "gemini-1.5-pro");
+        assert_eq!(model_family("grok-2-latest"), "grok-2");
+        
+        // Nothing to strip
+        assert_eq!(model_family("gpt-4"), "gpt-4");
+        assert_eq!(model_family("gpt-4-1106-preview"), "gpt-4-1106-preview");
+        assert_eq!(model_family("2024"), "2024");
+    }
+    
+    #[test]
+    fn test_normalize_model_name() {
+        assert_eq!(normalize_model_name(ProviderType::ChatGPT, "gpt-4-mobile"), "gpt-4");
+        assert_eq!(normalize_model_name(ProviderType::ChatGPT, "text-davinci-002-render-sha"), "gpt-3.5-turbo");
+        assert_eq!(normalize_model_name(ProviderType::Claude, "claude-3.5-sonnet"), "claude-3.5-sonnet");
+        assert_eq!(normalize_model_name(ProviderType::Claude, "claude-v2"), "claude-2");
+        assert_eq!(normalize_model_name(ProviderType::Gemini, "models/gemini-1.5-pro"), "gemini-1.5-pro");
+        assert_eq!(normalize_model_name(ProviderType::XAI, "grok"), "grok-1");
+        
+        // Zed threads can use any provider's models
+        assert_eq!(normalize_model_name(ProviderType::Zed, "gpt-4-mobile"), "gpt-4");
+    }
+    
+    #[test]
+    fn test_normalize_model_name_passes_unknown_slugs_through() {
+        assert_eq!(normalize_model_name(ProviderType::ChatGPT, "o1-preview"), "o1-preview");
+        assert_eq!(normalize_model_name(ProviderType::Claude, "claude-3-5-sonnet-20240620"), "claude-3-5-sonnet-20240620");
+        assert_eq!(normalize_model_name(ProviderType::Unknown, "my-local-llama"), "my-local-llama");
+    }
+}
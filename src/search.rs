@@ -1,10 +1,17 @@
 use anyhow::{Context, Result};
-use sqlx::SqlitePool;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Row, SqlitePool};
+use std::collections::HashMap;
 use tracing::{debug, info};
 
-use crate::models::{Conversation, Message, SearchResult};
+use crate::db::terms;
+use crate::models::{
+    Conversation, ConversationSummary, FacetCount, Facets, MatchField, Message, MessageMatch, SearchResult,
+};
 
-/// Search conversations using FTS5
+/// Search conversations using FTS5, leaving out archived ones
 pub async fn search_conversations(
     pool: &SqlitePool,
     query: &str,
@@ -28,12 +35,14 @@ pub async fn search_conversations(
             c.system_prompt,
             c.temperature,
             c.max_tokens,
-            c.user_id
+            c.user_id,
+            c.is_pinned as "is_pinned: bool",
+            c.is_archived as "is_archived: bool"
         FROM conversations c
         JOIN messages m ON c.id = m.conversation_id
         JOIN messages_fts ON m.id = messages_fts.rowid
-        WHERE messages_fts MATCH $1
-        ORDER BY rank
+        WHERE messages_fts MATCH $1 AND c.is_archived = 0
+        ORDER BY rank, c.id DESC
         LIMIT $2
         "#,
         query,
@@ -48,186 +57,469 @@ pub async fn search_conversations(
     Ok(results)
 }
 
+/// Delimiters FTS5 puts around matched terms in snippets
+///
+/// The defaults are control characters that don't show up in chat text, so
+/// renderers can find the matches without mistaking brackets in code or
+/// citations for them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnippetMarkers {
+    pub start: String,
+    pub end: String,
+}
+
+impl Default for SnippetMarkers {
+    fn default() -> Self {
+        Self {
+            start: "\u{2}".to_string(),
+            end: "\u{3}".to_string(),
+        }
+    }
+}
+
+/// Message and title matches joined to their conversations
+///
+/// Title hits have their bm25 rank doubled (ranks are negative, lower is
+/// better) so a conversation named after the query outranks one that only
+/// mentions it in passing.
+///
+/// Binds `?1` to the FTS query, `?3` to the snippet length in tokens, and
+/// `?4`/`?5` to the snippet markers; callers append filters and hand the
+/// result to `best_hits`, or use `hit_number` themselves.
+const SNIPPET_HITS: &str = r#"
+        WITH hits AS (
+            SELECT m.conversation_id,
+                   m.id AS message_id,
+                   m.role AS matched_in,
+                   snippet(messages_fts, 0, ?4, ?5, '...', ?3) AS snippet,
+                   messages_fts.rank AS rank
+            FROM messages_fts
+            JOIN messages m ON m.id = messages_fts.rowid
+            WHERE messages_fts MATCH ?1
+            UNION ALL
+            SELECT conversations_fts.rowid,
+                   NULL,
+                   'title',
+                   snippet(conversations_fts, 0, ?4, ?5, '...', ?3),
+                   conversations_fts.rank * 2.0
+            FROM conversations_fts
+            WHERE conversations_fts MATCH ?1
+        )
+        SELECT c.id, c.provider, c.external_id, c.title, c.model,
+               c.created_at, c.updated_at, c.raw_json, c.system_prompt,
+               c.temperature, c.max_tokens, c.user_id, c.is_pinned, c.is_archived,
+               hits.message_id, hits.matched_in, hits.snippet, hits.rank,
+               ROW_NUMBER() OVER (PARTITION BY c.id ORDER BY hits.rank, hits.message_id) AS hit_number
+        FROM hits
+        JOIN conversations c ON c.id = hits.conversation_id
+        WHERE 1 = 1
+"#;
+
+/// `SNIPPET_HITS` narrowed by `conditions`, keeping each conversation's
+/// best hit so it takes one result slot, best first up to `LIMIT ?2`
+fn best_hits(conditions: &str) -> String {
+    format!(
+        "SELECT * FROM ({}{}) WHERE hit_number = 1 ORDER BY rank, id DESC LIMIT ?2",
+        SNIPPET_HITS, conditions
+    )
+}
+
+/// `best_hits` outside archived conversations, skipping the first `?6`
+static RANKED_HITS: Lazy<String> = Lazy::new(|| format!("{} OFFSET ?6", best_hits(" AND c.is_archived = 0")));
+
 /// Search with snippets and ranking
+///
+/// Matches come from message content and conversation titles, with title
+/// matches ranked higher; `match_field` on each result says which, and
+/// `matched_in` narrows message matches down to the message's role.
+/// Each result carries up to `snippet_count` snippets in `snippets`, with
+/// matched terms wrapped in `markers`.
+/// Archived conversations are left out; `advanced_search` can include them.
 pub async fn search_with_snippets(
     pool: &SqlitePool,
     query: &str,
     limit: usize,
     snippet_length: usize,
+    snippet_count: usize,
+    markers: &SnippetMarkers,
 ) -> Result<Vec<SearchResult>> {
     info!("Searching with snippets for: '{}' (limit: {})", query, limit);
     
-    let results = sqlx::query!(
-        r#"
-        SELECT 
-            c.id as conversation_id,
-            c.provider,
-            c.external_id,
-            c.title,
-            c.model,
-            c.created_at,
-            c.updated_at,
-            c.raw_json,
-            c.system_prompt,
-            c.temperature,
-            c.max_tokens,
-            c.user_id,
-            snippet(messages_fts, 0, '[', ']', '...', $3) as snippet,
-            rank
-        FROM conversations c
-        JOIN messages m ON c.id = m.conversation_id
-        JOIN messages_fts ON m.id = messages_fts.rowid
-        WHERE messages_fts MATCH $1
-        ORDER BY rank
-        LIMIT $2
-        "#,
-        query,
-        limit as i64,
-        snippet_length as i64 / 10 // Approximate token count
-    )
-    .fetch_all(pool)
-    .await
-    .context("Failed to search with snippets")?;
-    
-    let search_results: Vec<SearchResult> = results
-        .into_iter()
-        .map(|row| {
-            let conversation = Conversation {
-                id: row.conversation_id,
-                provider: row.provider,
-                external_id: row.external_id,
-                title: row.title,
-                model: row.model,
-                created_at: row.created_at,
-                updated_at: row.updated_at,
-                raw_json: row.raw_json.and_then(|s| serde_json::from_str(&s).ok()),
-                system_prompt: row.system_prompt,
-                temperature: row.temperature,
-                max_tokens: row.max_tokens,
-                user_id: row.user_id,
-            };
-            
-            SearchResult {
-                conversation,
-                snippet: row.snippet,
-                rank: row.rank,
-            }
-        })
-        .collect();
+    let mut search_results = search_page(pool, query, 0, limit, snippet_length, markers).await?;
+    add_snippets(pool, query, None, &mut search_results, snippet_length, snippet_count, markers).await?;
     
     debug!("Found {} results with snippets for '{}'", search_results.len(), query);
     
     Ok(search_results)
 }
 
-/// Advanced search with filters
-pub async fn advanced_search(
+/// `search_with_snippets` results `offset..offset + limit`, with a single
+/// snippet each
+///
+/// Lets long result lists be read a page at a time, each page its own
+/// short query.
+pub async fn search_page(
     pool: &SqlitePool,
     query: &str,
-    provider: Option<&str>,
-    model: Option<&str>,
-    user_id: Option<&str>,
-    date_from: Option<chrono::DateTime<chrono::Utc>>,
-    date_to: Option<chrono::DateTime<chrono::Utc>>,
+    offset: usize,
     limit: usize,
-) -> Result<Vec<Conversation>> {
-    let mut sql = String::from(
+    snippet_length: usize,
+    markers: &SnippetMarkers,
+) -> Result<Vec<SearchResult>> {
+    let rows = sqlx::query(RANKED_HITS.as_str())
+        .bind(query)
+        .bind(limit as i64)
+        .bind(snippet_length as i64 / 10) // Approximate token count
+        .bind(&markers.start)
+        .bind(&markers.end)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await
+        .context("Failed to search with snippets")?;
+    
+    rows.iter().map(search_result_from_row).collect()
+}
+
+/// Most recently updated conversations, shaped like search results
+///
+/// Backs the browse view for blank queries: pinned conversations come first,
+/// `matched_in` is `recent` and the snippet is the start of the first
+/// message. Archived conversations are listed only with `include_archived`;
+/// `language` keeps only conversations detected as that language.
+pub async fn recent_conversations(
+    pool: &SqlitePool,
+    limit: usize,
+    snippet_length: usize,
+    include_archived: bool,
+    language: Option<&str>,
+) -> Result<Vec<SearchResult>> {
+    let rows = sqlx::query(
         r#"
-        SELECT DISTINCT 
-            c.id,
-            c.provider,
-            c.external_id,
-            c.title,
-            c.model,
-            c.created_at,
-            c.updated_at,
-            c.raw_json,
-            c.system_prompt,
-            c.temperature,
-            c.max_tokens,
-            c.user_id
+        SELECT c.id, c.provider, c.external_id, c.title, c.model,
+               c.created_at, c.updated_at, c.raw_json, c.system_prompt,
+               c.temperature, c.max_tokens, c.user_id, c.is_pinned, c.is_archived,
+               NULL AS message_id,
+               'recent' AS matched_in,
+               COALESCE((
+                   SELECT substr(m.content, 1, ?2)
+                   FROM messages m
+                   WHERE m.conversation_id = c.id
+                   ORDER BY m.created_at, m.id
+                   LIMIT 1
+               ), '') AS snippet,
+               0.0 AS rank
         FROM conversations c
-        JOIN messages m ON c.id = m.conversation_id
-        JOIN messages_fts ON m.id = messages_fts.rowid
-        WHERE messages_fts MATCH ?
-        "#
-    );
+        WHERE (c.is_archived = 0 OR ?3)
+          AND (?4 IS NULL OR c.language = ?4)
+        ORDER BY c.is_pinned DESC, c.updated_at DESC, c.id DESC
+        LIMIT ?1
+        "#,
+    )
+    .bind(limit as i64)
+    .bind(snippet_length as i64)
+    .bind(include_archived)
+    .bind(language)
+    .fetch_all(pool)
+    .await
+    .context("Failed to load recent conversations")?;
+    
+    rows.iter().map(search_result_from_row).collect()
+}
+
+/// Build a `SearchResult` from a row of conversation columns plus match info
+fn search_result_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<SearchResult> {
+    let snippet: String = row.try_get("snippet")?;
+    let matched_in: String = row.try_get("matched_in")?;
+    let match_field = match matched_in.as_str() {
+        "recent" => None,
+        "title" => Some(MatchField::Title),
+        _ => Some(MatchField::Message),
+    };
     
-    let mut params: Vec<String> = vec![query.to_string()];
-    let mut param_count = 1;
+    Ok(SearchResult {
+        conversation: Conversation::from_row(row)?,
+        message_id: row.try_get("message_id")?,
+        matched_in,
+        match_field,
+        snippets: vec![snippet.clone()],
+        snippet,
+        rank: row.try_get::<f64, _>("rank")? as f32,
+    })
+}
+
+/// Top up each result's `snippets` to `count` with excerpts from the other
+/// best-ranked matching messages of its conversation, only from messages
+/// with `role` if given
+///
+/// One query covers every result, passing the conversation and message ids
+/// as JSON arrays.
+async fn add_snippets(
+    pool: &SqlitePool,
+    query: &str,
+    role: Option<&str>,
+    results: &mut [SearchResult],
+    snippet_length: usize,
+    count: usize,
+    markers: &SnippetMarkers,
+) -> Result<()> {
+    if count <= 1 {
+        return Ok(());
+    }
     
-    // Add filters
-    if let Some(p) = provider {
-        param_count += 1;
-        sql.push_str(&format!(" AND c.provider = ?{}", param_count));
-        params.push(p.to_string());
+    if results.is_empty() {
+        return Ok(());
     }
     
-    if let Some(m) = model {
-        param_count += 1;
-        sql.push_str(&format!(" AND c.model = ?{}", param_count));
-        params.push(m.to_string());
+    let conversation_ids: Vec<i64> = results.iter().map(|result| result.conversation.id).collect();
+    let shown_ids: Vec<i64> = results.iter().filter_map(|result| result.message_id).collect();
+    
+    let rows: Vec<(i64, String)> = sqlx::query_as(
+        r#"
+        WITH hits AS (
+            SELECT m.conversation_id,
+                   m.id AS message_id,
+                   snippet(messages_fts, 0, ?6, ?7, '...', ?3) AS snippet,
+                   messages_fts.rank AS rank
+            FROM messages_fts
+            JOIN messages m ON m.id = messages_fts.rowid
+            WHERE messages_fts MATCH ?1
+              AND m.conversation_id IN (SELECT value FROM json_each(?2))
+              AND m.id NOT IN (SELECT value FROM json_each(?4))
+              AND (?8 IS NULL OR m.role = ?8)
+        ),
+        numbered AS (
+            SELECT conversation_id, snippet,
+                   ROW_NUMBER() OVER (PARTITION BY conversation_id ORDER BY rank, message_id) AS n
+            FROM hits
+        )
+        SELECT conversation_id, snippet
+        FROM numbered
+        WHERE n <= ?5
+        ORDER BY conversation_id, n
+        "#,
+    )
+    .bind(query)
+    .bind(serde_json::to_string(&conversation_ids)?)
+    .bind(snippet_length as i64 / 10)
+    .bind(serde_json::to_string(&shown_ids)?)
+    .bind((count - 1) as i64)
+    .bind(&markers.start)
+    .bind(&markers.end)
+    .bind(role)
+    .fetch_all(pool)
+    .await
+    .context("Failed to load additional snippets")?;
+    
+    let mut extra: HashMap<i64, Vec<String>> = HashMap::new();
+    for (conversation_id, snippet) in rows {
+        extra.entry(conversation_id).or_default().push(snippet);
+    }
+    for result in results.iter_mut() {
+        result.snippets.extend(extra.remove(&result.conversation.id).unwrap_or_default());
     }
     
-    if let Some(u) = user_id {
-        param_count += 1;
-        sql.push_str(&format!(" AND c.user_id = ?{}", param_count));
-        params.push(u.to_string());
+    Ok(())
+}
+
+/// Conversation-level filters for `advanced_search`
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilters {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub user_id: Option<String>,
+    /// Conversations created at or after this instant
+    pub after: Option<chrono::DateTime<chrono::Utc>>,
+    /// Conversations created at or before this instant
+    pub before: Option<chrono::DateTime<chrono::Utc>>,
+    /// ISO 639-1 code detected at import, e.g. `en`
+    pub language: Option<String>,
+    /// Only match messages with this role; title matches are left out
+    pub role: Option<String>,
+    /// Match archived conversations too
+    pub include_archived: bool,
+}
+
+impl SearchFilters {
+    pub fn is_empty(&self) -> bool {
+        self.provider.is_none()
+            && self.model.is_none()
+            && self.user_id.is_none()
+            && self.after.is_none()
+            && self.before.is_none()
+            && self.language.is_none()
+            && self.role.is_none()
+            && !self.include_archived
     }
     
-    if let Some(from) = date_from {
-        param_count += 1;
-        sql.push_str(&format!(" AND c.created_at >= ?{}", param_count));
-        params.push(from.to_rfc3339());
+    /// `AND` conditions to append to `SNIPPET_HITS`, with their bind values
+    /// numbered from `?6`
+    fn sql_conditions(&self) -> (String, Vec<String>) {
+        let mut sql = String::new();
+        let mut params: Vec<String> = Vec::new();
+        let mut param_count = 5;
+        
+        let conditions = [
+            ("c.provider =", self.provider.clone()),
+            ("c.model =", self.model.clone()),
+            ("c.user_id =", self.user_id.clone()),
+            ("c.created_at >=", self.after.map(|from| from.to_rfc3339())),
+            ("c.created_at <=", self.before.map(|to| to.to_rfc3339())),
+            ("c.language =", self.language.clone()),
+            ("hits.matched_in =", self.role.clone()),
+        ];
+        
+        for (condition, value) in conditions {
+            if let Some(value) = value {
+                param_count += 1;
+                sql.push_str(&format!(" AND {} ?{}", condition, param_count));
+                params.push(value);
+            }
+        }
+        
+        if !self.include_archived {
+            sql.push_str(" AND c.is_archived = 0");
+        }
+        
+        (sql, params)
     }
+}
+
+/// Search with snippets, restricted by conversation filters
+pub async fn advanced_search(
+    pool: &SqlitePool,
+    query: &str,
+    filters: &SearchFilters,
+    limit: usize,
+    snippet_length: usize,
+    snippet_count: usize,
+    markers: &SnippetMarkers,
+) -> Result<Vec<SearchResult>> {
+    let (clause, params) = filters.sql_conditions();
+    let sql = best_hits(&clause);
     
-    if let Some(to) = date_to {
-        param_count += 1;
-        sql.push_str(&format!(" AND c.created_at <= ?{}", param_count));
-        params.push(to.to_rfc3339());
+    // Execute dynamic query
+    let mut statement = sqlx::query(&sql)
+        .bind(query)
+        .bind(limit as i64)
+        .bind(snippet_length as i64 / 10)
+        .bind(&markers.start)
+        .bind(&markers.end);
+    for param in params {
+        statement = statement.bind(param);
     }
     
-    sql.push_str(&format!(" ORDER BY rank LIMIT {}", limit));
+    let rows = statement
+        .fetch_all(pool)
+        .await
+        .context("Failed to execute advanced search")?;
+    
+    let mut results = rows
+        .iter()
+        .map(search_result_from_row)
+        .collect::<Result<Vec<_>>>()?;
+    add_snippets(pool, query, filters.role.as_deref(), &mut results, snippet_length, snippet_count, markers).await?;
     
-    // Execute dynamic query
-    let mut query = sqlx::query_as::<_, Conversation>(&sql);
+    Ok(results)
+}
+
+/// Ids of the conversations a filtered search matches, at most `limit`
+pub async fn matching_conversation_ids(
+    pool: &SqlitePool,
+    query: &str,
+    filters: &SearchFilters,
+    limit: usize,
+) -> Result<Vec<i64>> {
+    let (clause, params) = filters.sql_conditions();
+    let sql = format!("SELECT DISTINCT id FROM ({}{}) ORDER BY id LIMIT ?2", SNIPPET_HITS, clause);
+    
+    let mut query = sqlx::query_scalar(&sql)
+        .bind(query)
+        .bind(limit as i64)
+        .bind(1_i64)
+        .bind("")
+        .bind("");
     for param in params {
         query = query.bind(param);
     }
     
-    let results = query
+    query
         .fetch_all(pool)
         .await
-        .context("Failed to execute advanced search")?;
+        .context("Failed to find matching conversations")
+}
+
+/// Conversations with at least one attachment, most recently updated first
+///
+/// `mime` is either a full type (`application/pdf`) or a bare top-level type
+/// (`image`) matching any subtype; `None` matches any attachment.
+pub async fn conversations_with_attachment(
+    pool: &SqlitePool,
+    mime: Option<&str>,
+    limit: usize,
+) -> Result<Vec<Conversation>> {
+    let pattern = mime.map(|mime| match mime.contains('/') {
+        true => mime.to_ascii_lowercase(),
+        false => format!("{}/%", mime.to_ascii_lowercase()),
+    });
+    
+    let results = sqlx::query_as::<_, Conversation>(
+        r#"
+        SELECT c.id, c.provider, c.external_id, c.title, c.model,
+               c.created_at, c.updated_at, c.raw_json, c.system_prompt,
+               c.temperature, c.max_tokens, c.user_id, c.is_pinned, c.is_archived
+        FROM conversations c
+        WHERE EXISTS (
+            SELECT 1
+            FROM messages m
+            JOIN message_attachments a ON a.message_id = m.id
+            WHERE m.conversation_id = c.id
+              AND (?1 IS NULL OR a.mime LIKE ?1)
+        )
+        ORDER BY c.updated_at DESC, c.id DESC
+        LIMIT ?2
+        "#,
+    )
+    .bind(pattern)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await
+    .context("Failed to find conversations with attachments")?;
     
     Ok(results)
 }
 
+/// Which slice of a conversation's messages to load
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessagePage {
+    /// Maximum number of messages; `None` loads all of them
+    pub limit: Option<usize>,
+    pub offset: usize,
+    /// Newest first instead of oldest first
+    pub descending: bool,
+}
+
 /// Get conversation messages for display
 pub async fn get_conversation_messages(
     pool: &SqlitePool,
     conversation_id: i64,
+    page: &MessagePage,
 ) -> Result<Vec<Message>> {
-    let messages = sqlx::query_as!(
-        Message,
+    let order = if page.descending { "DESC" } else { "ASC" };
+    
+    // SQLite treats a negative LIMIT as no limit
+    let messages = sqlx::query_as::<_, Message>(&format!(
         r#"
-        SELECT 
-            id as "id!",
-            conversation_id as "conversation_id!",
-            role as "role!",
-            content as "content!",
-            model,
-            created_at as "created_at!",
-            tokens,
-            finish_reason,
-            tool_calls,
-            attachments
+        SELECT id, conversation_id, role, content, model, created_at,
+               tokens, finish_reason, tool_calls, attachments
         FROM messages
-        WHERE conversation_id = $1
-        ORDER BY created_at ASC
-        "#,
-        conversation_id
-    )
+        WHERE conversation_id = ?1
+        ORDER BY created_at {order}, id {order}
+        LIMIT ?2 OFFSET ?3
+        "#
+    ))
+    .bind(conversation_id)
+    .bind(page.limit.map_or(-1, |limit| limit as i64))
+    .bind(page.offset as i64)
     .fetch_all(pool)
     .await
     .context("Failed to fetch conversation messages")?;
@@ -235,18 +527,195 @@ pub async fn get_conversation_messages(
     Ok(messages)
 }
 
+/// Messages of one conversation matching an FTS query, in conversation order
+///
+/// Positions count every message of the conversation, so they line up with
+/// `get_conversation_messages` offsets.
+pub async fn search_in_conversation(
+    pool: &SqlitePool,
+    conversation_id: i64,
+    query: &str,
+    markers: &SnippetMarkers,
+) -> Result<Vec<MessageMatch>> {
+    let rows: Vec<(i64, i64, String, String)> = sqlx::query_as(
+        r#"
+        WITH ordered AS (
+            SELECT id, ROW_NUMBER() OVER (ORDER BY created_at, id) - 1 AS position
+            FROM messages
+            WHERE conversation_id = ?2
+        )
+        SELECT m.id, ordered.position, m.role,
+               snippet(messages_fts, 0, ?3, ?4, '...', 16)
+        FROM messages_fts
+        JOIN messages m ON m.id = messages_fts.rowid
+        JOIN ordered ON ordered.id = m.id
+        WHERE messages_fts MATCH ?1 AND messages_fts.conversation_id = ?2
+        ORDER BY ordered.position
+        "#,
+    )
+    .bind(query)
+    .bind(conversation_id)
+    .bind(&markers.start)
+    .bind(&markers.end)
+    .fetch_all(pool)
+    .await
+    .context("Failed to search conversation")?;
+    
+    debug!("Found {} messages matching '{}' in conversation {}", rows.len(), query, conversation_id);
+    
+    Ok(rows
+        .into_iter()
+        .map(|(message_id, position, role, snippet)| MessageMatch { message_id, position, role, snippet })
+        .collect())
+}
+
+/// Number of messages in a conversation
+pub async fn count_conversation_messages(pool: &SqlitePool, conversation_id: i64) -> Result<i64> {
+    let total = sqlx::query_scalar("SELECT COUNT(*) FROM messages WHERE conversation_id = ?1")
+        .bind(conversation_id)
+        .fetch_one(pool)
+        .await
+        .context("Failed to count conversation messages")?;
+    
+    Ok(total)
+}
+
+/// Message, character, token, model and attachment totals for a
+/// conversation, or `None` if it does not exist
+pub async fn conversation_summary(pool: &SqlitePool, conversation_id: i64) -> Result<Option<ConversationSummary>> {
+    let Some((models, attachment_count)): Option<(String, i64)> = sqlx::query_as(
+        r#"
+        SELECT
+            (SELECT json_group_array(DISTINCT model) FROM messages
+             WHERE conversation_id = c.id AND model IS NOT NULL),
+            (SELECT COUNT(*) FROM message_attachments a
+             JOIN messages m ON m.id = a.message_id
+             WHERE m.conversation_id = c.id)
+        FROM conversations c
+        WHERE c.id = ?1
+        "#,
+    )
+    .bind(conversation_id)
+    .fetch_optional(pool)
+    .await
+    .context("Failed to summarize conversation")? else {
+        return Ok(None);
+    };
+    
+    let roles: Vec<(String, i64, i64, i64, i64, Option<DateTime<Utc>>, Option<DateTime<Utc>>)> = sqlx::query_as(
+        r#"
+        SELECT role, COUNT(*), COALESCE(SUM(LENGTH(content)), 0), COALESCE(SUM(tokens), 0),
+               COUNT(*) - COUNT(tokens), MIN(created_at), MAX(created_at)
+        FROM messages
+        WHERE conversation_id = ?1
+        GROUP BY role
+        "#,
+    )
+    .bind(conversation_id)
+    .fetch_all(pool)
+    .await
+    .context("Failed to summarize conversation messages")?;
+    
+    let mut models: Vec<String> = serde_json::from_str(&models)?;
+    models.sort();
+    
+    let mut summary = ConversationSummary {
+        conversation_id,
+        message_count: 0,
+        role_counts: Default::default(),
+        total_chars: 0,
+        estimated_tokens: 0,
+        first_message_at: None,
+        last_message_at: None,
+        models,
+        attachment_count,
+    };
+    let mut uncounted = 0;
+    
+    for (role, count, chars, tokens, missing_tokens, first, last) in roles {
+        summary.message_count += count;
+        summary.role_counts.insert(role, count);
+        summary.total_chars += chars;
+        summary.estimated_tokens += tokens;
+        summary.first_message_at = summary.first_message_at.into_iter().chain(first).min();
+        summary.last_message_at = summary.last_message_at.into_iter().chain(last).max();
+        uncounted += missing_tokens;
+    }
+    
+    // Messages imported without `--count-tokens` are counted now
+    if uncounted > 0 {
+        let contents: Vec<String> =
+            sqlx::query_scalar("SELECT content FROM messages WHERE conversation_id = ?1 AND tokens IS NULL")
+                .bind(conversation_id)
+                .fetch_all(pool)
+                .await?;
+        summary.estimated_tokens += contents.iter()
+            .map(|content| crate::import::tokens::count_tokens(content) as i64)
+            .sum::<i64>();
+    }
+    
+    Ok(Some(summary))
+}
+
+/// Providers, models and roles with their counts, and the range of
+/// creation dates, each from one grouped query
+pub async fn facets(pool: &SqlitePool) -> Result<Facets> {
+    let (first_created_at, last_created_at): (Option<DateTime<Utc>>, Option<DateTime<Utc>>) =
+        sqlx::query_as("SELECT MIN(created_at), MAX(created_at) FROM conversations WHERE is_archived = 0")
+            .fetch_one(pool)
+            .await
+            .context("Failed to load the date range")?;
+    
+    Ok(Facets {
+        providers: facet_counts(
+            pool,
+            "SELECT provider, COUNT(*) FROM conversations WHERE is_archived = 0 GROUP BY provider",
+        )
+        .await?,
+        models: facet_counts(
+            pool,
+            "SELECT model, COUNT(*) FROM conversations WHERE is_archived = 0 AND model IS NOT NULL GROUP BY model",
+        )
+        .await?,
+        roles: facet_counts(
+            pool,
+            r#"
+            SELECT m.role, COUNT(*)
+            FROM messages m
+            JOIN conversations c ON c.id = m.conversation_id
+            WHERE c.is_archived = 0
+            GROUP BY m.role
+            "#,
+        )
+        .await?,
+        first_created_at,
+        last_created_at,
+    })
+}
+
+/// Rows of a `value, count` query, most common first
+async fn facet_counts(pool: &SqlitePool, sql: &str) -> Result<Vec<FacetCount>> {
+    let sql = format!("{} ORDER BY 2 DESC, 1", sql.trim_end());
+    let rows: Vec<(String, i64)> = sqlx::query_as(&sql)
+        .fetch_all(pool)
+        .await
+        .context("Failed to count facet values")?;
+    
+    Ok(rows.into_iter().map(|(value, count)| FacetCount { value, count }).collect())
+}
+
 /// Get search suggestions based on existing data
+///
+/// Blends conversation titles starting with `prefix`, most recent first,
+/// with the most frequent message words starting with it. Words get at
+/// least half the slots when there are enough of them, so a handful of
+/// matching titles can't crowd out the content.
 pub async fn get_search_suggestions(
     pool: &SqlitePool,
     prefix: &str,
     limit: usize,
 ) -> Result<Vec<String>> {
-    // This is a simple implementation - could be enhanced with:
-    // - Frequent search terms tracking
-    // - Model name suggestions
-    // - Smart completions
-    
-    let suggestions = sqlx::query!(
+    let titles: Vec<String> = sqlx::query!(
         r#"
         SELECT DISTINCT title
         FROM conversations
@@ -264,5 +733,412 @@ pub async fn get_search_suggestions(
     .filter_map(|row| row.title)
     .collect();
     
+    // A range over the primary key rather than LIKE, so `%` and `_` in the
+    // prefix match literally and the index is used
+    let prefix = prefix.to_lowercase();
+    let words: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT term FROM term_frequencies
+        WHERE term >= ?1 AND term < ?1 || char(1114111)
+        ORDER BY count DESC, term
+        LIMIT ?2
+        "#,
+    )
+    .bind(&prefix)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await
+    .context("Failed to load term suggestions")?;
+    
+    let title_slots = limit - words.len().min(limit / 2);
+    let mut suggestions: Vec<String> = titles.into_iter().take(title_slots).collect();
+    for word in words {
+        if suggestions.len() >= limit {
+            break;
+        }
+        if !suggestions.iter().any(|s| s.to_lowercase() == word) {
+            suggestions.push(word);
+        }
+    }
+    
     Ok(suggestions)
-}
\ No newline at end of file
+}
+
+/// Extract the most frequent meaningful words from text, most frequent first
+fn extract_keywords(text: &str, max_keywords: usize) -> Vec<String> {
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    
+    for (position, word) in terms::words(text).enumerate() {
+        counts.entry(word).or_insert((0, position)).0 += 1;
+    }
+    
+    // Highest count first, earliest occurrence breaks ties
+    let mut keywords: Vec<_> = counts.into_iter().collect();
+    keywords.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.1 .1.cmp(&b.1 .1)));
+    
+    keywords
+        .into_iter()
+        .take(max_keywords)
+        .map(|(word, _)| word)
+        .collect()
+}
+
+/// Find conversations similar to the given one
+///
+/// Keywords from the title and first user message are matched against the
+/// message index; each other conversation is ranked by its best message.
+/// Archived conversations are never suggested.
+pub async fn find_related(
+    pool: &SqlitePool,
+    conversation_id: i64,
+    limit: usize,
+    markers: &SnippetMarkers,
+) -> Result<Vec<SearchResult>> {
+    let source: Option<(Option<String>, Option<String>)> = sqlx::query_as(
+        r#"
+        SELECT c.title,
+               (SELECT m.content FROM messages m
+                WHERE m.conversation_id = c.id AND m.role = 'user'
+                ORDER BY m.created_at, m.id
+                LIMIT 1)
+        FROM conversations c
+        WHERE c.id = ?1
+        "#,
+    )
+    .bind(conversation_id)
+    .fetch_optional(pool)
+    .await
+    .context("Failed to load conversation for related search")?;
+    
+    let Some((title, first_message)) = source else {
+        return Ok(Vec::new());
+    };
+    
+    let text = format!("{} {}", title.unwrap_or_default(), first_message.unwrap_or_default());
+    let keywords = extract_keywords(&text, 8);
+    
+    if keywords.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    let fts_query = keywords
+        .iter()
+        .map(|k| format!("\"{}\"", k))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    
+    debug!("Related search for conversation {}: {}", conversation_id, fts_query);
+    
+    let rows = sqlx::query(
+        r#"
+        WITH hits AS (
+            SELECT m.conversation_id,
+                   m.id AS message_id,
+                   m.role AS matched_in,
+                   rank,
+                   snippet(messages_fts, 0, ?4, ?5, '...', 16) AS snippet
+            FROM messages_fts
+            JOIN messages m ON m.id = messages_fts.rowid
+            WHERE messages_fts MATCH ?1 AND m.conversation_id != ?2
+        ),
+        best AS (
+            SELECT conversation_id, message_id, matched_in, rank, snippet,
+                   ROW_NUMBER() OVER (PARTITION BY conversation_id ORDER BY rank) AS n
+            FROM hits
+        )
+        SELECT c.id, c.provider, c.external_id, c.title, c.model,
+               c.created_at, c.updated_at, c.raw_json, c.system_prompt,
+               c.temperature, c.max_tokens, c.user_id, c.is_pinned, c.is_archived,
+               best.message_id, best.matched_in, best.snippet, best.rank
+        FROM best
+        JOIN conversations c ON c.id = best.conversation_id
+        WHERE best.n = 1 AND c.is_archived = 0
+        ORDER BY best.rank, c.id DESC
+        LIMIT ?3
+        "#,
+    )
+    .bind(fts_query)
+    .bind(conversation_id)
+    .bind(limit as i64)
+    .bind(&markers.start)
+    .bind(&markers.end)
+    .fetch_all(pool)
+    .await
+    .context("Failed to find related conversations")?;
+    
+    rows.iter().map(search_result_from_row).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::{conversation, message, test_pool};
+    use crate::import::process_conversation_batch;
+
+    #[tokio::test]
+    async fn test_tied_ranks_order_deterministically() {
+        let (_dir, pool) = test_pool().await;
+
+        // Identical content gives every match the same bm25 rank
+        let batch = (0..5)
+            .map(|i| {
+                (
+                    conversation("chatgpt", &format!("conv-{}", i), "Tied"),
+                    vec![message("user", "identical tie content")],
+                )
+            })
+            .collect();
+        process_conversation_batch(&pool, batch).await.unwrap();
+
+        let mut runs = Vec::new();
+        for _ in 0..3 {
+            let ids: Vec<i64> = search_with_snippets(&pool, "identical", 10, 200, 1, &SnippetMarkers::default())
+                .await
+                .unwrap()
+                .iter()
+                .map(|r| r.conversation.id)
+                .collect();
+            runs.push(ids);
+        }
+
+        assert_eq!(runs[0].len(), 5);
+        assert!(runs.iter().all(|ids| ids == &runs[0]));
+
+        // Ties fall back to newest conversation first
+        let mut expected = runs[0].clone();
+        expected.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(runs[0], expected);
+    }
+
+    #[tokio::test]
+    async fn test_search_result_points_at_matching_message() {
+        let (_dir, pool) = test_pool().await;
+
+        let batch = vec![(
+            conversation("claude", "anchors", "Anchors"),
+            vec![
+                message("user", "Tell me about ferns"),
+                message("assistant", "Ferns reproduce with spores"),
+                message("user", "What about mosses?"),
+            ],
+        )];
+        process_conversation_batch(&pool, batch).await.unwrap();
+
+        let expected: i64 = sqlx::query_scalar("SELECT id FROM messages WHERE content LIKE '%spores%'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        let results = search_with_snippets(&pool, "spores", 10, 200, 1, &SnippetMarkers::default()).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message_id, Some(expected));
+    }
+
+    #[tokio::test]
+    async fn test_matched_in_reports_title_or_role() {
+        let (_dir, pool) = test_pool().await;
+
+        let batch = vec![
+            (
+                conversation("claude", "titled", "Quantum entanglement notes"),
+                vec![message("user", "Summarize chapter three")],
+            ),
+            (
+                conversation("claude", "content", "Physics chat"),
+                vec![
+                    message("user", "Explain it simply"),
+                    message("assistant", "Entanglement links two particles"),
+                ],
+            ),
+        ];
+        process_conversation_batch(&pool, batch).await.unwrap();
+
+        let results = search_with_snippets(&pool, "entanglement", 10, 200, 1, &SnippetMarkers::default()).await.unwrap();
+        assert_eq!(results.len(), 2);
+
+        let by_title = |title: &str| {
+            results
+                .iter()
+                .find(|r| r.conversation.title.as_deref() == Some(title))
+                .unwrap()
+        };
+
+        let titled = by_title("Quantum entanglement notes");
+        assert_eq!(titled.matched_in, "title");
+        assert_eq!(titled.message_id, None);
+
+        assert_eq!(by_title("Physics chat").matched_in, "assistant");
+    }
+
+    #[tokio::test]
+    async fn test_search_in_conversation_returns_positions_of_matches() {
+        let (_dir, pool) = test_pool().await;
+        let batch = vec![
+            (
+                conversation("claude", "garden", "Garden"),
+                vec![
+                    message("user", "When should I plant tomatoes?"),
+                    message("assistant", "After the last frost."),
+                    message("user", "And how deep do tomatoes go?"),
+                    message("assistant", "About two inches, deeper for leggy tomato seedlings."),
+                ],
+            ),
+            (
+                conversation("claude", "salad", "Salad"),
+                vec![message("user", "A salad with tomatoes please")],
+            ),
+        ];
+        process_conversation_batch(&pool, batch).await.unwrap();
+        let markers = SnippetMarkers { start: "[".to_string(), end: "]".to_string() };
+
+        let matches = search_in_conversation(&pool, 1, "tomatoes", &markers).await.unwrap();
+
+        let found: Vec<(i64, &str)> = matches.iter().map(|m| (m.position, m.role.as_str())).collect();
+        // Stemming matches "tomato" too
+        assert_eq!(found, vec![(0, "user"), (2, "user"), (3, "assistant")]);
+        assert_eq!(matches[0].snippet, "When should I plant [tomatoes]?");
+        assert!(search_in_conversation(&pool, 1, "salad", &markers).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_title_only_match_is_returned_and_boosted() {
+        let (_dir, pool) = test_pool().await;
+
+        let batch = vec![
+            (
+                conversation("claude", "titled", "Notes on feeding a sourdough starter"),
+                vec![message("user", "My loaf came out flat and dense")],
+            ),
+            (
+                conversation("claude", "mention", "Weekend plans"),
+                vec![message("user", "Sourdough, then hiking")],
+            ),
+            (
+                conversation("claude", "unrelated", "Taxes"),
+                vec![message("user", "filing deadlines for this year")],
+            ),
+        ];
+        process_conversation_batch(&pool, batch).await.unwrap();
+
+        let results = search_with_snippets(&pool, "sourdough", 10, 200, 1, &SnippetMarkers::default()).await.unwrap();
+        let found: Vec<_> = results
+            .iter()
+            .map(|r| (r.conversation.title.as_deref().unwrap(), r.match_field))
+            .collect();
+
+        // The short message alone would outrank the longer title without the boost
+        assert_eq!(
+            found,
+            vec![
+                ("Notes on feeding a sourdough starter", Some(MatchField::Title)),
+                ("Weekend plans", Some(MatchField::Message)),
+            ]
+        );
+
+        let json = serde_json::to_value(&results[0]).unwrap();
+        assert_eq!(json["match_field"], "title");
+    }
+
+    #[test]
+    fn test_extract_keywords_drops_stopwords_and_short_tokens() {
+        let keywords = extract_keywords("How do I fix the Rust borrow checker? The borrow is an error", 3);
+        assert_eq!(keywords, vec!["borrow", "fix", "rust"]);
+    }
+
+    #[tokio::test]
+    async fn test_find_related_ranks_shared_vocabulary_first() {
+        let (_dir, pool) = test_pool().await;
+
+        let batch = vec![
+            (
+                conversation("claude", "source", "Rust ownership and borrowing"),
+                vec![
+                    message("user", "How does ownership interact with the borrow checker in Rust?"),
+                    message("assistant", "Each value has a single owner."),
+                ],
+            ),
+            (
+                conversation("claude", "related", "Fighting the borrow checker"),
+                vec![
+                    message("user", "The Rust borrow checker rejects my code about ownership of a vector"),
+                    message("assistant", "Clone the vector or borrow it immutably."),
+                ],
+            ),
+            (
+                conversation("claude", "unrelated", "Sourdough starter"),
+                vec![
+                    message("user", "My sourdough starter smells like acetone"),
+                    message("assistant", "Feed it more often."),
+                ],
+            ),
+        ];
+        process_conversation_batch(&pool, batch).await.unwrap();
+
+        let related = find_related(&pool, 1, 5, &SnippetMarkers::default()).await.unwrap();
+
+        assert!(!related.is_empty());
+        assert_eq!(related[0].conversation.external_id.as_deref(), Some("related"));
+        assert!(related.iter().all(|r| r.conversation.id != 1));
+        assert!(related.iter().all(|r| r.conversation.external_id.as_deref() != Some("unrelated")));
+    }
+
+    #[tokio::test]
+    async fn test_archived_conversations_are_not_searched_or_related() {
+        let (_dir, pool) = test_pool().await;
+
+        let batch = vec![
+            (conversation("claude", "keep", "Origami cranes"), vec![message("user", "Folding origami cranes")]),
+            (conversation("claude", "hide", "Origami frogs"), vec![message("user", "Folding origami frogs")]),
+        ];
+        process_conversation_batch(&pool, batch).await.unwrap();
+        sqlx::query("UPDATE conversations SET is_archived = 1 WHERE id = 2").execute(&pool).await.unwrap();
+
+        let found = search_conversations(&pool, "origami", 10).await.unwrap();
+        assert_eq!(found.iter().map(|c| c.id).collect::<Vec<_>>(), vec![1]);
+        assert!(!found[0].is_archived);
+
+        assert!(find_related(&pool, 1, 5, &SnippetMarkers::default()).await.unwrap().is_empty());
+
+        // The archived conversation still finds the live one
+        let related = find_related(&pool, 2, 5, &SnippetMarkers::default()).await.unwrap();
+        assert_eq!(related.iter().map(|r| r.conversation.id).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_suggestions_include_frequent_content_words() {
+        let (_dir, pool) = test_pool().await;
+
+        let batch = vec![
+            (
+                conversation("claude", "quokka", "Animal trivia"),
+                vec![
+                    message("user", "Tell me about the quokka"),
+                    message("assistant", "The quokka is a small marsupial; quokkas live on Rottnest Island."),
+                ],
+            ),
+            (
+                conversation("claude", "quote", "Quotes for a toast"),
+                vec![message("user", "Any quotable lines about quokka selfies?")],
+            ),
+        ];
+        process_conversation_batch(&pool, batch).await.unwrap();
+
+        let suggestions = get_search_suggestions(&pool, "quo", 5).await.unwrap();
+
+        // The title first, then content words by how often they occur
+        assert_eq!(suggestions, vec!["Quotes for a toast", "quokka", "quokkas", "quotable"]);
+
+        // Re-importing a conversation doesn't count its words twice
+        let batch = vec![(
+            conversation("claude", "quote", "Quotes for a toast"),
+            vec![message("user", "Any quotable lines?")],
+        )];
+        process_conversation_batch(&pool, batch).await.unwrap();
+
+        let count: i64 = sqlx::query_scalar("SELECT count FROM term_frequencies WHERE term = 'quokka'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+}
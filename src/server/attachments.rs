@@ -0,0 +1,146 @@
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::header,
+    response::IntoResponse,
+    Json,
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{instrument, warn};
+
+use super::{get_conversation, AppState};
+use crate::config::Config;
+use crate::db::attachments::{self, StoredAttachment};
+use crate::errors::{AppError, AppResult};
+
+/// One attachment in a conversation listing
+#[derive(Debug, Serialize)]
+pub struct AttachmentInfo {
+    pub id: i64,
+    pub message_id: i64,
+    pub filename: Option<String>,
+    pub mime_type: Option<String>,
+    pub size: Option<i64>,
+    pub download_url: String,
+}
+
+impl From<StoredAttachment> for AttachmentInfo {
+    fn from(attachment: StoredAttachment) -> Self {
+        Self {
+            id: attachment.id,
+            message_id: attachment.message_id,
+            filename: attachment.filename,
+            mime_type: attachment.mime,
+            size: attachment.size,
+            download_url: format!("/api/attachments/{}/raw", attachment.id),
+        }
+    }
+}
+
+/// Attachments of every message in a conversation
+#[instrument(skip(state))]
+pub async fn list(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<i64>,
+) -> AppResult<Json<Vec<AttachmentInfo>>> {
+    get_conversation(&state.pool, id).await?;
+    
+    let attachments = attachments::for_conversation(&state.pool, id).await?;
+    Ok(Json(attachments.into_iter().map(AttachmentInfo::from).collect()))
+}
+
+/// Subdirectories of the data directory importers store media files in;
+/// attachment paths anywhere else are never served
+const MEDIA_DIRS: &[&str] = &["claude_attachments"];
+
+/// The bytes of one attachment
+///
+/// Files are read from the importers' media directories; attachments whose
+/// file isn't there but which have extracted text serve that text instead,
+/// as `text/plain`.
+#[instrument(skip(state))]
+pub async fn raw(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<i64>,
+) -> AppResult<impl IntoResponse> {
+    let not_found = || AppError::NotFound(format!("Attachment {} has no stored content", id));
+    
+    let attachment = attachments::get(&state.pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Attachment {} not found", id)))?;
+    
+    let file = match attachment.path.as_deref().and_then(|stored| resolve_media_path(&state.config, stored)) {
+        Some(path) => tokio::fs::read(&path).await.ok(),
+        None => None,
+    };
+    let (body, mime) = match file {
+        Some(bytes) => {
+            let mime = attachment
+                .mime
+                .clone()
+                .unwrap_or_else(|| "application/octet-stream".to_string());
+            (bytes, mime)
+        }
+        // Extracted text is plain text whatever the original file was
+        None => {
+            let text = attachment.extracted_content.clone().ok_or_else(not_found)?;
+            (text.into_bytes(), "text/plain; charset=utf-8".to_string())
+        }
+    };
+    let filename = attachment
+        .filename
+        .as_deref()
+        .map(sanitize_filename)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("attachment-{}", id));
+    
+    Ok((
+        [
+            (header::CONTENT_TYPE, mime),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        body,
+    ))
+}
+
+/// The file a stored attachment path refers to, if it lies inside one of
+/// the media directories
+///
+/// Stored paths come from imported JSON, so one naming any other file in
+/// the data directory is refused. `Config::media_path` refuses paths that
+/// could climb out; the canonical path is then checked again so symlinks
+/// can't point outside.
+fn resolve_media_path(config: &Config, stored: &str) -> Option<PathBuf> {
+    let Some(media_dir) = MEDIA_DIRS.iter().find(|dir| Path::new(stored).starts_with(dir)) else {
+        warn!("Refusing attachment path outside the media directories: {}", stored);
+        return None;
+    };
+    
+    let path = match config.media_path(stored) {
+        Ok(path) => path,
+        Err(e) => {
+            warn!("Refusing attachment path: {}", e);
+            return None;
+        }
+    };
+    
+    let root = config.data_dir().join(media_dir).canonicalize().ok()?;
+    let path = path.canonicalize().ok()?;
+    if !path.starts_with(&root) {
+        warn!("Refusing attachment path outside the media directories: {}", stored);
+        return None;
+    }
+    
+    Some(path)
+}
+
+/// Keep a filename safe to quote in a `Content-Disposition` header
+fn sanitize_filename(name: &str) -> String {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    name.chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
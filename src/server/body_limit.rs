@@ -0,0 +1,33 @@
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::errors::AppError;
+
+/// Answer oversized bodies with the same JSON error shape as every other
+/// failure, instead of the plain text axum's body limit rejection uses
+pub async fn json_rejection(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        return AppError::PayloadTooLarge("Request body is too large".to_string()).into_response();
+    }
+    
+    response
+}
+
+/// The error for a body over `limit` bytes
+pub fn too_large(limit: usize) -> AppError {
+    AppError::PayloadTooLarge(format!(
+        "Request body is too large; imports are limited to {} bytes",
+        limit
+    ))
+}
@@ -0,0 +1,161 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::header,
+    response::IntoResponse,
+    Json,
+};
+use bytes::Bytes;
+use futures::{channel::mpsc, SinkExt};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use tracing::{instrument, warn};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use super::{get_conversation, AppState};
+use crate::errors::{AppError, AppResult};
+use crate::export::{self, ExportFormat, ExportOptions};
+use crate::models::Conversation;
+use crate::search;
+
+/// Most conversations one bulk export may include
+const MAX_IDS: usize = 500;
+
+/// Bulk export request body
+#[derive(Deserialize)]
+pub struct BulkExportRequest {
+    ids: Vec<i64>,
+    /// Format of each file in the archive; defaults to markdown
+    format: Option<String>,
+    /// Mask `export.redaction_patterns` matches in every file
+    #[serde(default)]
+    redact: bool,
+}
+
+/// Selected conversations as a zip archive with one file per conversation
+///
+/// Every id is looked up before the response starts, so unknown ids are a
+/// 404; the archive itself is written entry by entry as the body streams.
+#[instrument(skip_all, fields(count = request.ids.len(), format = request.format.as_deref()))]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<BulkExportRequest>,
+) -> AppResult<impl IntoResponse> {
+    if request.ids.is_empty() {
+        return Err(AppError::BadRequest("No conversation ids given".into()));
+    }
+    if request.ids.len() > MAX_IDS {
+        return Err(AppError::BadRequest(format!(
+            "At most {} conversations can be exported at once, got {}",
+            MAX_IDS,
+            request.ids.len()
+        )));
+    }
+    
+    let options = request
+        .format
+        .as_deref()
+        .unwrap_or("markdown")
+        .parse::<ExportFormat>()
+        .and_then(|format| ExportOptions::new(format, false))
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let redactor = if request.redact { Some(state.config.export.redactor()?) } else { None };
+    let options = ExportOptions { redactor, ..options };
+    
+    let mut seen = HashSet::new();
+    let mut conversations = Vec::new();
+    for id in request.ids {
+        if seen.insert(id) {
+            conversations.push(get_conversation(&state.pool, id).await?);
+        }
+    }
+    
+    let (tx, rx) = mpsc::channel(4);
+    tokio::spawn(write_archive(state.pool.clone(), conversations, options, tx));
+    
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"conversations.zip\""),
+        ],
+        Body::from_stream(rx),
+    ))
+}
+
+type Chunks = mpsc::Sender<io::Result<Bytes>>;
+
+/// Stream the archive into `tx`, ending the body with an error if it fails
+async fn write_archive(pool: SqlitePool, conversations: Vec<Conversation>, options: ExportOptions, mut tx: Chunks) {
+    if let Err(e) = stream_archive(&pool, &conversations, &options, &mut tx).await {
+        warn!("Bulk export failed: {:#}", e);
+        // The client may already be gone, in which case nobody needs telling
+        let _ = tx.send(Err(io::Error::other(e.to_string()))).await;
+    }
+}
+
+async fn stream_archive(
+    pool: &SqlitePool,
+    conversations: &[Conversation],
+    options: &ExportOptions,
+    tx: &mut Chunks,
+) -> anyhow::Result<()> {
+    let buffer = ChunkBuffer::default();
+    let mut zip = ZipWriter::new_stream(buffer.clone());
+    let file_options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut names = HashSet::new();
+    
+    for conversation in conversations {
+        let messages =
+            search::get_conversation_messages(pool, conversation.id, &search::MessagePage::default()).await?;
+        let body = export::render_conversation(conversation, &messages, options)?;
+        
+        let name = unique_name(&mut names, options.file_name(conversation));
+        zip.start_file(name, file_options)?;
+        zip.write_all(&body)?;
+        
+        tx.send(Ok(buffer.take())).await?;
+    }
+    
+    zip.finish()?;
+    tx.send(Ok(buffer.take())).await?;
+    Ok(())
+}
+
+/// `name`, or `name` with `-2`, `-3`, ... before its extension if an earlier
+/// conversation already took it
+fn unique_name(taken: &mut HashSet<String>, name: String) -> String {
+    if taken.insert(name.clone()) {
+        return name;
+    }
+    
+    let (stem, extension) = name.rsplit_once('.').unwrap_or((name.as_str(), ""));
+    (2..)
+        .map(|n| format!("{}-{}.{}", stem, n, extension))
+        .find(|candidate| taken.insert(candidate.clone()))
+        .expect("some suffix is free")
+}
+
+/// Collects what the zip writer produces until it is sent as one chunk
+#[derive(Clone, Default)]
+struct ChunkBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl ChunkBuffer {
+    fn take(&self) -> Bytes {
+        Bytes::from(std::mem::take(&mut *self.0.lock().expect("chunk buffer poisoned")))
+    }
+}
+
+impl Write for ChunkBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().expect("chunk buffer poisoned").extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+/// Weak validator for a response, derived from the values it depends on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ETag(String);
+
+impl ETag {
+    /// Tag hashing `parts`; equal parts give the same tag across restarts
+    pub fn weak<T: AsRef<[u8]>>(parts: &[T]) -> Self {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part.as_ref());
+            // Keep ("ab", "c") and ("a", "bc") apart
+            hasher.update([0]);
+        }
+        let digest = format!("{:x}", hasher.finalize());
+        
+        Self(format!("W/\"{}\"", &digest[..16]))
+    }
+    
+    /// Whether the client's `If-None-Match` is `*` or lists this tag
+    ///
+    /// Tags are compared weakly, so a `W/` prefix on either side is ignored.
+    pub fn matches(&self, headers: &HeaderMap) -> bool {
+        let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+        let own = opaque(&self.0);
+        
+        headers
+            .get_all(header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|tag| tag.trim() == "*" || opaque(tag) == own)
+    }
+    
+    /// `body` carrying this tag, or an empty `304 Not Modified` when the
+    /// client already has it
+    pub fn respond(&self, headers: &HeaderMap, body: impl IntoResponse) -> Response {
+        let mut response = if self.matches(headers) {
+            StatusCode::NOT_MODIFIED.into_response()
+        } else {
+            body.into_response()
+        };
+        
+        if let Ok(value) = HeaderValue::from_str(&self.0) {
+            response.headers_mut().insert(header::ETAG, value);
+        }
+        
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_if_none_match_is_compared_weakly() {
+        let etag = ETag::weak(&["42", "2024-05-01T10:00:00Z", "3"]);
+        assert_ne!(etag, ETag::weak(&["42", "2024-05-01T10:00:00Z", "4"]));
+        assert_ne!(ETag::weak(&["ab", "c"]), ETag::weak(&["a", "bc"]));
+        
+        let strong = etag.0.trim_start_matches("W/").to_string();
+        for (sent, expected) in [
+            (etag.0.clone(), true),
+            (strong, true),
+            (format!("\"other\", {}", etag.0), true),
+            ("*".to_string(), true),
+            ("\"other\"".to_string(), false),
+        ] {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(&sent).unwrap());
+            assert_eq!(etag.matches(&headers), expected, "{}", sent);
+        }
+        assert!(!etag.matches(&HeaderMap::new()));
+    }
+}
@@ -0,0 +1,179 @@
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::db::lock::WriteLock;
+use crate::import::{self, ImportOptions, ImportProgress};
+use crate::models::ImportStats;
+
+pub type JobId = u64;
+
+/// Progress of every import started through the API, by job id
+pub type Jobs = Arc<RwLock<HashMap<JobId, JobProgress>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// What a client polling an import job sees
+#[derive(Debug, Clone, Serialize)]
+pub struct JobProgress {
+    pub status: JobStatus,
+    pub processed_files: usize,
+    pub total_conversations: usize,
+    /// Final counts once the import completed
+    pub stats: Option<ImportStats>,
+    pub error: Option<String>,
+}
+
+/// Finished jobs kept for polling; older ones are dropped as new jobs start
+const KEPT_FINISHED_JOBS: usize = 100;
+
+/// Run an import on a background task, returning the job id to poll
+///
+/// The task waits up to `lock_timeout` for the database's write lock
+/// before importing.
+pub fn spawn_import(
+    pool: SqlitePool,
+    jobs: Jobs,
+    provider: String,
+    path: PathBuf,
+    options: ImportOptions,
+    lock_timeout: Duration,
+) -> JobId {
+    let id = {
+        let mut jobs = jobs.write().expect("jobs lock poisoned");
+        evict_finished(&mut jobs);
+        // The newest job is never evicted, so ids keep counting up
+        let id = jobs.keys().max().map_or(1, |max| max + 1);
+        jobs.insert(id, JobProgress {
+            status: JobStatus::Running,
+            processed_files: 0,
+            total_conversations: 0,
+            stats: None,
+            error: None,
+        });
+        id
+    };
+    
+    let progress_jobs = jobs.clone();
+    let options = ImportOptions {
+        progress: Some(ImportProgress::new(move |stats| {
+            update(&progress_jobs, id, |job| {
+                job.processed_files = stats.files_processed;
+                job.total_conversations = stats.conversations;
+            });
+        })),
+        ..options
+    };
+    
+    tokio::spawn(async move {
+        info!("Import job {} started for {:?}", id, path);
+        let result = match WriteLock::for_pool(&pool, lock_timeout).await {
+            Ok(_lock) => import::import_conversations(&pool, &provider, &path, &options).await,
+            Err(e) => Err(e),
+        };
+        
+        update(&jobs, id, |job| match result {
+            Ok(stats) => {
+                job.status = JobStatus::Completed;
+                job.processed_files = stats.files_processed;
+                job.total_conversations = stats.conversations;
+                job.stats = Some(stats);
+            }
+            Err(e) => {
+                error!("Import job {} failed: {:#}", id, e);
+                job.status = JobStatus::Failed;
+                job.error = Some(format!("{:#}", e));
+            }
+        });
+    });
+    
+    id
+}
+
+/// Wait up to `timeout` for running imports to finish, returning how many
+/// are still running
+pub async fn wait_for_running(jobs: &Jobs, timeout: Duration) -> usize {
+    let running = || {
+        jobs.read()
+            .expect("jobs lock poisoned")
+            .values()
+            .filter(|job| job.status == JobStatus::Running)
+            .count()
+    };
+    
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut count = running();
+    if count > 0 {
+        info!("Waiting for {} running imports to finish", count);
+    }
+    while count > 0 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        count = running();
+    }
+    
+    count
+}
+
+/// Drop the oldest finished jobs beyond `KEPT_FINISHED_JOBS`
+fn evict_finished(jobs: &mut HashMap<JobId, JobProgress>) {
+    let mut finished: Vec<JobId> = jobs
+        .iter()
+        .filter(|(_, job)| job.status != JobStatus::Running)
+        .map(|(id, _)| *id)
+        .collect();
+    if finished.len() <= KEPT_FINISHED_JOBS {
+        return;
+    }
+    
+    finished.sort_unstable();
+    for id in &finished[..finished.len() - KEPT_FINISHED_JOBS] {
+        jobs.remove(id);
+    }
+}
+
+fn update(jobs: &Jobs, id: JobId, apply: impl FnOnce(&mut JobProgress)) {
+    if let Some(job) = jobs.write().expect("jobs lock poisoned").get_mut(&id) {
+        apply(job);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn job(status: JobStatus) -> JobProgress {
+        JobProgress {
+            status,
+            processed_files: 0,
+            total_conversations: 0,
+            stats: None,
+            error: None,
+        }
+    }
+    
+    #[test]
+    fn test_only_the_oldest_finished_jobs_are_evicted() {
+        let mut jobs: HashMap<JobId, JobProgress> = HashMap::new();
+        jobs.insert(1, job(JobStatus::Running));
+        for id in 2..=KEPT_FINISHED_JOBS as JobId + 6 {
+            jobs.insert(id, job(if id % 2 == 0 { JobStatus::Completed } else { JobStatus::Failed }));
+        }
+        
+        evict_finished(&mut jobs);
+        
+        assert_eq!(jobs.len(), KEPT_FINISHED_JOBS + 1);
+        assert!(jobs.contains_key(&1));
+        assert!((2..=6).all(|id| !jobs.contains_key(&id)));
+        assert!(jobs.contains_key(&(KEPT_FINISHED_JOBS as JobId + 6)));
+    }
+}
@@ -0,0 +1,115 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::debug;
+
+use super::AppState;
+use crate::models::SearchResult;
+use crate::search;
+
+/// Keystrokes closer together than this are searched as one query
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Most results sent per query
+const MAX_RESULTS: usize = 20;
+
+/// One batch of results, tagged with the query that produced it
+#[derive(Serialize)]
+struct LiveResults {
+    query: String,
+    results: Vec<SearchResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+type SearchTask = JoinHandle<(String, anyhow::Result<Vec<SearchResult>>)>;
+
+/// Search-as-you-type: the client sends each partial query as a text frame
+/// and gets back results for the latest one
+pub async fn handler(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> Response {
+    ws.on_upgrade(move |socket| run(socket, state))
+}
+
+/// Per-connection loop
+///
+/// A query waits out `DEBOUNCE` before it runs, and a newer query replaces
+/// it while waiting or aborts it while running, so only results for the
+/// latest keystroke reach the client.
+async fn run(mut socket: WebSocket, state: Arc<AppState>) {
+    let mut pending: Option<String> = None;
+    let mut in_flight: Option<SearchTask> = None;
+    let debounce = tokio::time::sleep(DEBOUNCE);
+    tokio::pin!(debounce);
+    
+    loop {
+        tokio::select! {
+            frame = socket.recv() => match frame {
+                Some(Ok(Message::Text(query))) => {
+                    if let Some(task) = in_flight.take() {
+                        task.abort();
+                    }
+                    pending = Some(query);
+                    debounce.as_mut().reset(Instant::now() + DEBOUNCE);
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            () = &mut debounce, if pending.is_some() => {
+                let query = pending.take().expect("guarded by pending.is_some()");
+                let state = state.clone();
+                in_flight = Some(tokio::spawn(async move {
+                    let results = run_query(&state, &query).await;
+                    (query, results)
+                }));
+            }
+            finished = async { in_flight.as_mut().expect("guarded by in_flight.is_some()").await },
+                if in_flight.is_some() =>
+            {
+                in_flight = None;
+                // Aborted tasks were superseded by a newer query
+                let Ok((query, results)) = finished else { continue };
+                
+                let batch = match results {
+                    Ok(results) => LiveResults { query, results, error: None },
+                    Err(e) => LiveResults { query, results: Vec::new(), error: Some(e.to_string()) },
+                };
+                let text = serde_json::to_string(&batch).expect("search results serialize");
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    
+    if let Some(task) = in_flight {
+        task.abort();
+    }
+    debug!("Live search connection closed");
+}
+
+/// Results for a partial query; blank queries match nothing
+async fn run_query(state: &AppState, query: &str) -> anyhow::Result<Vec<SearchResult>> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    let search_config = &state.config.search;
+    search::search_with_snippets(
+        &state.pool,
+        query,
+        MAX_RESULTS,
+        search_config.snippet_length,
+        1,
+        &search_config.snippet_markers,
+    )
+    .await
+}
@@ -1,28 +1,46 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use axum::{
-    extract::{Path as AxumPath, Query, State},
-    http::StatusCode,
+    extract::{DefaultBodyLimit, Path as AxumPath, Query, State},
+    http::{header, HeaderMap, StatusCode},
     middleware,
-    response::{Html, IntoResponse, Json},
+    response::{Html, IntoResponse, Json, Response},
     routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
-use std::path::PathBuf;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
 use tower_http::services::ServeDir;
-use tracing::info;
+use tracing::{field, info, instrument, warn, Span};
 
 use crate::{
     config::Config,
+    db,
     errors::{AppError, AppResult},
-    models::{Conversation, Message, SearchResult},
+    export::{self, ExportFormat, ExportOptions},
+    models::{Conversation, ConversationSummary, Facets, Message, MessageMatch, ModelGrouping, SearchResult},
     search,
 };
 
+mod attachments;
+mod body_limit;
+mod bulk_export;
+mod etag;
+mod jobs;
+mod live_search;
+mod rate_limit;
+mod request_id;
+mod search_stream;
+mod shutdown;
+mod stream_import;
 mod templates;
+use etag::ETag;
+use rate_limit::RateLimiter;
 use templates::*;
 
 /// Application state
@@ -30,38 +48,194 @@ use templates::*;
 struct AppState {
     pool: SqlitePool,
     config: Config,
+    rate_limiter: Arc<RateLimiter>,
+    import_jobs: jobs::Jobs,
+    in_flight: shutdown::InFlight,
+    /// Last `/api/facets` answer and when it was computed
+    facets: Arc<std::sync::Mutex<Option<(Instant, Facets)>>>,
 }
 
+impl AppState {
+    fn new(pool: SqlitePool, config: Config) -> Self {
+        let rate_limiter = Arc::new(RateLimiter::new(config.server.requests_per_minute));
+        Self {
+            pool,
+            config,
+            rate_limiter,
+            import_jobs: jobs::Jobs::default(),
+            in_flight: shutdown::InFlight::default(),
+            facets: Arc::default(),
+        }
+    }
+    
+    /// The database's write lock; every server write holds it so writes
+    /// queue behind CLI imports instead of racing them
+    ///
+    /// Requests wait only `server.write_wait_secs` and then get a 503, so
+    /// a long import doesn't tie up every writer's connection.
+    async fn write_lock(&self) -> AppResult<db::lock::WriteLock> {
+        let timeout = Duration::from_secs(self.config.server.write_wait_secs);
+        db::lock::WriteLock::for_pool(&self.pool, timeout).await.map_err(|e| {
+            if e.is::<db::lock::LockTimeout>() {
+                AppError::Busy("Another import is writing to the archive; retry shortly".into())
+            } else {
+                AppError::Internal(e)
+            }
+        })
+    }
+}
+
+/// Longest wait for background imports once the server stops taking requests
+const IMPORT_DRAIN_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// Run the web server
 pub async fn run(port: u16, database: PathBuf, config: Config) -> Result<()> {
+    let addr = bind_address(&config.server.host, port)?;
+    
     // Initialize metrics
     crate::metrics::init_metrics()?;
     
     // Create database pool
     let pool = crate::db::create_pool(&database).await?;
-    crate::db::run_migrations(&pool).await?;
+    crate::db::run_migrations(&pool, &config.search.tokenizer).await?;
+    
+    // Media defaults to living next to the database being served
+    let mut config = config;
+    config.database.path = database.to_string_lossy().into_owned();
     
+    let state = Arc::new(AppState::new(pool, config));
+    
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind {}", addr))?;
+    
+    let addr = listener.local_addr()?;
+    if !addr.ip().is_loopback() {
+        warn!(
+            "Listening on {}, reachable from other machines; the API has no authentication and /api/import reads server paths",
+            addr
+        );
+    }
+    
+    // Lets CLI imports against the same database know a server is live
+    let _marker = crate::db::lock::ServerMarker::create(&database, addr)?;
+    
+    info!("Server running at http://{}", addr);
+    
+    serve(listener, state, shutdown::signal()).await
+}
+
+/// Address to listen on for a `server.host` IP and port
+pub fn bind_address(host: &str, port: u16) -> Result<SocketAddr> {
+    let ip: IpAddr = host
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid server.host {:?}; expected an IP address like 0.0.0.0", host))?;
+    Ok(SocketAddr::new(ip, port))
+}
+
+/// Serve requests until `shutdown` resolves
+///
+/// In-flight requests are drained, imports started through the API get up
+/// to `IMPORT_DRAIN_TIMEOUT` to finish, background tasks are stopped, and
+/// the WAL is checkpointed into the database file before returning.
+async fn serve(
+    listener: TcpListener,
+    state: Arc<AppState>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
     // Start background stats updater
-    let stats_pool = pool.clone();
-    tokio::spawn(async move {
+    let stats_pool = state.pool.clone();
+    let stats_task = tokio::spawn(async move {
         crate::metrics::update_stats_task(stats_pool).await;
     });
     
-    let state = Arc::new(AppState { pool, config });
+    // Forget clients that have gone quiet
+    let limiter = state.rate_limiter.clone();
+    let prune_task = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            limiter.prune(Duration::from_secs(300));
+        }
+    });
+    
+    let in_flight = state.in_flight.clone();
+    let shutdown = async move {
+        shutdown.await;
+        info!("Shutting down; draining {} in-flight requests", in_flight.count());
+    };
+    
+    let app = router(state.clone());
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+        .with_graceful_shutdown(shutdown)
+        .await?;
+    
+    let unfinished = jobs::wait_for_running(&state.import_jobs, IMPORT_DRAIN_TIMEOUT).await;
+    if unfinished > 0 {
+        warn!("Stopping with {} imports still running", unfinished);
+    }
+    
+    for task in [stats_task, prune_task] {
+        task.abort();
+        // Only cancellation is expected here; neither task ends on its own
+        let _ = task.await;
+    }
+    
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(&state.pool)
+        .await?;
+    state.pool.close().await;
+    
+    info!("Server stopped");
+    Ok(())
+}
+
+/// Build the application router
+fn router(state: Arc<AppState>) -> Router {
+    let max_import_bytes = state.config.server.max_import_bytes;
     
-    // Build router
-    let app = Router::new()
+    Router::new()
         // Pages
         .route("/", get(index_page))
         .route("/search", get(search_page))
         .route("/conversation/:id", get(conversation_page))
         
         // API endpoints
+        .route("/api/openapi.json", get(openapi_api))
         .route("/api/search", get(search_api))
+        .route("/api/search/tag", post(search_tag_api))
+        .route("/api/search/stream", get(search_stream::handler))
         .route("/api/conversation/:id", get(conversation_api))
         .route("/api/conversation/:id/messages", get(messages_api))
+        .route("/api/conversation/:id/summary", get(summary_api))
+        .route("/api/conversation/:id/related", get(related_api))
+        .route("/api/conversation/:id/search", get(conversation_search_api))
+        .route("/api/conversation/:id/branches", get(branches_api))
+        .route("/api/conversation/:id/branch", post(select_branch_api))
+        .route("/api/conversation/:id/export", get(export_api))
+        .route("/api/conversation/:id/archive", post(archive_api))
+        .route("/api/conversation/:id/unarchive", post(unarchive_api))
+        .route("/api/conversation/:id/pin", post(pin_api))
+        .route("/api/conversation/:id/unpin", post(unpin_api))
+        .route("/api/conversation/:id/attachments", get(attachments::list))
+        .route("/api/attachments/:id/raw", get(attachments::raw))
+        .route("/api/message/:id/revisions", get(revisions_api))
+        .route("/api/export", get(export_all_api))
+        .route("/api/export/bulk", post(bulk_export::handler))
+        .route("/api/export.csv", get(export_csv_api))
         .route("/api/suggestions", get(suggestions_api))
         .route("/api/stats", get(stats_api))
+        .route("/api/facets", get(facets_api))
+        .route("/api/models", get(models_api))
+        .route("/api/admin/index-status", get(index_status_api))
+        .route("/api/import", post(import_api).layer(DefaultBodyLimit::max(max_import_bytes)))
+        .route("/api/import/jobs/:id", get(import_job_api))
+        .route("/api/import/history", get(import_history_api))
+        .route("/api/import/stream", post(stream_import::handler))
+        
+        // Search-as-you-type
+        .route("/ws/search", get(live_search::handler))
         
         // Static files
         .nest_service("/static", ServeDir::new("static"))
@@ -72,107 +246,590 @@ pub async fn run(port: u16, database: PathBuf, config: Config) -> Result<()> {
         // Metrics endpoint
         .route("/metrics", get(metrics_endpoint))
         
+        // JSON errors for bodies over the import or default size limit
+        .layer(middleware::from_fn(body_limit::json_rejection))
+        
+        // Per-client rate limiting (skips /health and /metrics)
+        .layer(middleware::from_fn_with_state(state.clone(), rate_limit::limit))
+        
         // Add metrics middleware
         .layer(middleware::from_fn(crate::metrics::middleware::track_metrics))
         
-        .with_state(state);
-    
-    let listener = tokio::net::TcpListener::bind(format!("127.0.0.1:{}", port))
-        .await?;
-    
-    info!("Server running at http://127.0.0.1:{}", port);
-    
-    axum::serve(listener, app).await?;
-    
-    Ok(())
+        // Counted so shutdown can report how many requests it waits for
+        .layer(middleware::from_fn_with_state(state.in_flight.clone(), shutdown::track))
+        
+        // Outermost, so every log line and response carries the request id
+        .layer(middleware::from_fn(request_id::assign))
+        
+        .with_state(state)
 }
 
 /// Index page
 async fn index_page(State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
-    let stats = get_stats(&state.pool).await?;
+    let stats = get_stats(&state.pool, state.config.server.model_grouping, StatsFilters::default()).await?;
     let html = render_index(&stats)?;
     Ok(Html(html))
 }
 
 /// Search page
+#[instrument(skip_all, fields(query = params.q.as_deref(), result_count = field::Empty))]
 async fn search_page(
     State(state): State<Arc<AppState>>,
     Query(params): Query<SearchParams>,
 ) -> AppResult<Html<String>> {
-    let results = if let Some(query) = &params.q {
-        search::search_with_snippets(
-            &state.pool,
-            query,
-            params.limit.unwrap_or(20),
-            state.config.search.snippet_length,
-        )
-        .await?
+    let limit = search_limit(&state, params.limit)?;
+    let query = params.q.unwrap_or_default();
+    
+    let markers = &state.config.search.snippet_markers;
+    let results = if !query.trim().is_empty() {
+        search::search_with_snippets(&state.pool, &query, limit, state.config.search.snippet_length, 1, markers).await?
+    } else if state.config.search.browse_on_empty_query {
+        search::recent_conversations(&state.pool, limit, state.config.search.snippet_length, false, None).await?
     } else {
         Vec::new()
     };
+    Span::current().record("result_count", results.len());
     
-    let html = render_search_results(&params.q.unwrap_or_default(), &results)?;
+    let html = render_search_results(&query, &results, markers)?;
     Ok(Html(html))
 }
 
 /// Conversation page
+#[instrument(skip_all, fields(conversation_id = id))]
 async fn conversation_page(
     State(state): State<Arc<AppState>>,
     AxumPath(id): AxumPath<i64>,
 ) -> AppResult<Html<String>> {
     let conversation = get_conversation(&state.pool, id).await?;
-    let messages = search::get_conversation_messages(&state.pool, id).await?;
+    let messages = search::get_conversation_messages(&state.pool, id, &search::MessagePage::default()).await?;
     
     let html = render_conversation(&conversation, &messages)?;
     Ok(Html(html))
 }
 
+/// Most snippets the search API returns per result
+const MAX_SNIPPETS: usize = 10;
+
 /// Search API endpoint
 #[derive(Deserialize)]
 struct SearchParams {
     q: Option<String>,
-    limit: Option<usize>,
+    /// Results wanted, up to `search.max_limit`
+    limit: Option<i64>,
     provider: Option<String>,
     model: Option<String>,
+    /// Only conversations created on or after this date (YYYY-MM-DD or RFC 3339)
+    after: Option<String>,
+    /// Only conversations created on or before this date (YYYY-MM-DD or RFC 3339)
+    before: Option<String>,
+    /// Snippets per result, from the conversation's best-matching messages
+    snippets: Option<usize>,
+    /// Only conversations detected as this language (ISO 639-1, e.g. `en`)
+    language: Option<String>,
+    /// Only match messages with this role: `user`, `assistant`, `system` or `tool`
+    role: Option<String>,
+    /// Show archived conversations too
+    #[serde(default)]
+    include_archived: bool,
+}
+
+/// Message roles the search API filters on
+const SEARCH_ROLES: &[&str] = &["user", "assistant", "system", "tool"];
+
+impl SearchParams {
+    fn filters(&self) -> AppResult<search::SearchFilters> {
+        Ok(search::SearchFilters {
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+            user_id: None,
+            after: self.after.as_deref().map(|v| parse_date_param("after", v)).transpose()?,
+            before: self.before.as_deref().map(|v| parse_end_date_param("before", v)).transpose()?,
+            language: self.language.clone(),
+            role: self.role.as_deref().map(parse_role_param).transpose()?,
+            include_archived: self.include_archived,
+        })
+    }
+}
+
+/// A `role` query parameter, lowercased
+fn parse_role_param(value: &str) -> AppResult<String> {
+    let role = value.to_ascii_lowercase();
+    if !SEARCH_ROLES.contains(&role.as_str()) {
+        return Err(AppError::BadRequest(format!(
+            "Invalid role '{}'; expected one of {}",
+            value,
+            SEARCH_ROLES.join(", ")
+        )));
+    }
+    Ok(role)
+}
+
+/// The configured result count for a requested search limit, or a 400
+fn search_limit(state: &AppState, requested: Option<i64>) -> AppResult<usize> {
+    state
+        .config
+        .search
+        .limit(requested)
+        .map_err(|e| AppError::BadRequest(e.to_string()))
+}
+
+/// Parse a date query parameter; bare dates mean midnight UTC
+fn parse_date_param(name: &str, value: &str) -> AppResult<chrono::DateTime<chrono::Utc>> {
+    parse_date_at(name, value, chrono::NaiveTime::MIN)
+}
+
+/// Parse an inclusive upper bound; bare dates cover that whole day (UTC)
+fn parse_end_date_param(name: &str, value: &str) -> AppResult<chrono::DateTime<chrono::Utc>> {
+    let end_of_day = chrono::NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999).expect("end of day is valid");
+    parse_date_at(name, value, end_of_day)
 }
 
+fn parse_date_at(name: &str, value: &str, time: chrono::NaiveTime) -> AppResult<chrono::DateTime<chrono::Utc>> {
+    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&chrono::Utc));
+    }
+    
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(time).and_utc())
+        .map_err(|_| AppError::BadRequest(format!("Invalid {} date '{}'; expected YYYY-MM-DD", name, value)))
+}
+
+#[instrument(
+    skip_all,
+    fields(query = params.q.as_deref(), provider = params.provider.as_deref(), result_count = field::Empty)
+)]
 async fn search_api(
     State(state): State<Arc<AppState>>,
     Query(params): Query<SearchParams>,
 ) -> AppResult<Json<Vec<SearchResult>>> {
-    let query = params.q.ok_or_else(|| AppError::BadRequest("Missing query parameter".into()))?;
+    let filters = params.filters()?;
+    let limit = search_limit(&state, params.limit)?;
+    let query = params.q.unwrap_or_default();
+    
+    // Blank queries browse recent conversations so one endpoint serves both views
+    if query.trim().is_empty() {
+        if !state.config.search.browse_on_empty_query {
+            return Err(AppError::BadRequest("Missing query parameter".into()));
+        }
+        let results = search::recent_conversations(
+            &state.pool,
+            limit,
+            state.config.search.snippet_length,
+            filters.include_archived,
+            filters.language.as_deref(),
+        )
+        .await?;
+        Span::current().record("result_count", results.len());
+        return Ok(Json(results));
+    }
     
     let start = Instant::now();
-    let results = search::search_with_snippets(
-        &state.pool,
-        &query,
-        params.limit.unwrap_or(20),
-        state.config.search.snippet_length,
-    )
-    .await?;
+    let snippet_length = state.config.search.snippet_length;
+    let snippet_count = params.snippets.unwrap_or(1).clamp(1, MAX_SNIPPETS);
+    let markers = &state.config.search.snippet_markers;
+    let results = if filters.is_empty() {
+        search::search_with_snippets(&state.pool, &query, limit, snippet_length, snippet_count, markers).await?
+    } else {
+        search::advanced_search(&state.pool, &query, &filters, limit, snippet_length, snippet_count, markers).await?
+    };
     
     let duration = start.elapsed();
+    Span::current().record("result_count", results.len());
     crate::metrics::track_search(params.provider.as_deref(), results.len(), duration);
     
     Ok(Json(results))
 }
 
+/// Bulk tagging above this many matches must be confirmed
+const BULK_TAG_CONFIRM_THRESHOLD: usize = 100;
+
+/// Most conversations one bulk tag request can touch
+const BULK_TAG_LIMIT: usize = 10_000;
+
+/// Filters of a bulk tag request, as in the search API
+#[derive(Deserialize, Default)]
+struct TagFilters {
+    provider: Option<String>,
+    model: Option<String>,
+    after: Option<String>,
+    before: Option<String>,
+    language: Option<String>,
+    #[serde(default)]
+    include_archived: bool,
+}
+
+/// Bulk tag request body
+#[derive(Deserialize)]
+struct SearchTagRequest {
+    query: String,
+    #[serde(default)]
+    filters: TagFilters,
+    tag: String,
+    /// Required when more than `BULK_TAG_CONFIRM_THRESHOLD` conversations match
+    #[serde(default)]
+    confirm: bool,
+}
+
+#[derive(Serialize)]
+struct SearchTagResponse {
+    matched: usize,
+    tagged: u64,
+}
+
+/// Tag every conversation matching a search
+#[instrument(skip_all, fields(query = %request.query, tag = %request.tag, result_count = field::Empty))]
+async fn search_tag_api(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SearchTagRequest>,
+) -> AppResult<Json<SearchTagResponse>> {
+    let tag = request.tag.trim();
+    if tag.is_empty() {
+        return Err(AppError::BadRequest("Tag must not be empty".into()));
+    }
+    if request.query.trim().is_empty() {
+        return Err(AppError::BadRequest("Missing query".into()));
+    }
+    
+    let filters = search::SearchFilters {
+        provider: request.filters.provider,
+        model: request.filters.model,
+        user_id: None,
+        after: request.filters.after.as_deref().map(|v| parse_date_param("after", v)).transpose()?,
+        before: request.filters.before.as_deref().map(|v| parse_end_date_param("before", v)).transpose()?,
+        language: request.filters.language,
+        role: None,
+        include_archived: request.filters.include_archived,
+    };
+    
+    let ids = search::matching_conversation_ids(&state.pool, &request.query, &filters, BULK_TAG_LIMIT + 1).await?;
+    Span::current().record("result_count", ids.len());
+    if ids.len() > BULK_TAG_LIMIT {
+        return Err(AppError::BadRequest(format!(
+            "More than {} conversations match; narrow the search",
+            BULK_TAG_LIMIT
+        )));
+    }
+    if ids.len() > BULK_TAG_CONFIRM_THRESHOLD && !request.confirm {
+        return Err(AppError::BadRequest(format!(
+            "{} conversations match; resend with \"confirm\": true to tag them all",
+            ids.len()
+        )));
+    }
+    
+    let _lock = state.write_lock().await?;
+    let tagged = db::tags::add(&state.pool, &ids, tag).await?;
+    
+    Ok(Json(SearchTagResponse { matched: ids.len(), tagged }))
+}
+
+/// Conversation query parameters
+#[derive(Deserialize)]
+struct ConversationParams {
+    /// `messages` embeds the ordered messages in the response
+    include: Option<String>,
+}
+
+/// Conversation, optionally with its messages embedded
+#[derive(Serialize)]
+struct ConversationDetail {
+    #[serde(flatten)]
+    conversation: Conversation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    messages: Option<Vec<Message>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_message_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    assistant_message_count: Option<usize>,
+}
+
 /// Get single conversation
+///
+/// Tagged with an ETag over the response body, and answered with 304 when
+/// the client's `If-None-Match` still matches.
+#[instrument(skip_all, fields(conversation_id = id))]
 async fn conversation_api(
     State(state): State<Arc<AppState>>,
     AxumPath(id): AxumPath<i64>,
-) -> AppResult<Json<Conversation>> {
+    Query(params): Query<ConversationParams>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
     let conversation = get_conversation(&state.pool, id).await?;
-    Ok(Json(conversation))
+    
+    let mut detail = ConversationDetail {
+        conversation,
+        messages: None,
+        message_count: None,
+        user_message_count: None,
+        assistant_message_count: None,
+    };
+    
+    if params.include.as_deref() == Some("messages") {
+        let messages = search::get_conversation_messages(&state.pool, id, &search::MessagePage::default()).await?;
+        let count_role = |role: &str| messages.iter().filter(|m| m.role == role).count();
+        
+        detail.message_count = Some(messages.len());
+        detail.user_message_count = Some(count_role("user"));
+        detail.assistant_message_count = Some(count_role("assistant"));
+        detail.messages = Some(messages);
+    }
+    
+    // Pinning, archiving and branch switches change the body without
+    // touching `updated_at`, so the tag covers all of it
+    let body = serde_json::to_vec(&detail).map_err(anyhow::Error::from)?;
+    let etag = ETag::weak(&[&body]);
+    Ok(etag.respond(&headers, ([(header::CONTENT_TYPE, "application/json")], body)))
+}
+
+/// Message pagination parameters
+#[derive(Deserialize)]
+struct MessagesParams {
+    limit: Option<usize>,
+    offset: Option<usize>,
+    /// `asc` (default) or `desc`
+    order: Option<String>,
+}
+
+/// A page of messages and the conversation's full message count
+#[derive(Serialize)]
+struct MessagesPage {
+    messages: Vec<Message>,
+    total: i64,
 }
 
 /// Get conversation messages
+#[instrument(skip_all, fields(conversation_id = id))]
 async fn messages_api(
     State(state): State<Arc<AppState>>,
     AxumPath(id): AxumPath<i64>,
-) -> AppResult<Json<Vec<Message>>> {
-    let messages = search::get_conversation_messages(&state.pool, id).await?;
-    Ok(Json(messages))
+    Query(params): Query<MessagesParams>,
+) -> AppResult<Json<MessagesPage>> {
+    let descending = match params.order.as_deref() {
+        None | Some("asc") => false,
+        Some("desc") => true,
+        Some(other) => {
+            return Err(AppError::BadRequest(format!("Invalid order '{}'; expected asc or desc", other)));
+        }
+    };
+    let page = search::MessagePage {
+        limit: params.limit,
+        offset: params.offset.unwrap_or(0),
+        descending,
+    };
+    
+    let messages = search::get_conversation_messages(&state.pool, id, &page).await?;
+    let total = search::count_conversation_messages(&state.pool, id).await?;
+    
+    Ok(Json(MessagesPage { messages, total }))
+}
+
+/// Message, character, token, model and attachment totals for auditing
+#[instrument(skip_all, fields(conversation_id = id))]
+async fn summary_api(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<i64>,
+) -> AppResult<Json<ConversationSummary>> {
+    let summary = search::conversation_summary(&state.pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Conversation {} not found", id)))?;
+    
+    Ok(Json(summary))
+}
+
+/// Earlier versions of an edited message, oldest first
+#[instrument(skip_all, fields(message_id = id))]
+async fn revisions_api(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<i64>,
+) -> AppResult<Json<Vec<db::revisions::StoredRevision>>> {
+    let exists: Option<i64> = sqlx::query_scalar("SELECT 1 FROM messages WHERE id = ?1")
+        .bind(id)
+        .fetch_optional(&state.pool)
+        .await?;
+    if exists.is_none() {
+        return Err(AppError::NotFound(format!("Message {} not found", id)));
+    }
+    
+    Ok(Json(db::revisions::for_message(&state.pool, id).await?))
+}
+
+/// Branches of a conversation's message tree and the one being shown
+#[instrument(skip_all, fields(conversation_id = id))]
+async fn branches_api(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<i64>,
+) -> AppResult<Json<crate::import::branches::BranchList>> {
+    let branches = crate::import::branches::list(&state.pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Conversation {} not found", id)))?;
+    
+    Ok(Json(branches))
+}
+
+/// Branch selection request body
+#[derive(Deserialize)]
+struct SelectBranchRequest {
+    index: usize,
+}
+
+/// Show another branch of a conversation, replacing its messages
+#[instrument(skip_all, fields(conversation_id = id, branch = request.index))]
+async fn select_branch_api(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<i64>,
+    Json(request): Json<SelectBranchRequest>,
+) -> AppResult<Json<crate::import::branches::BranchList>> {
+    let branches = crate::import::branches::list(&state.pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Conversation {} not found", id)))?;
+    if request.index >= branches.branches.len() {
+        return Err(AppError::BadRequest(format!(
+            "Conversation {} has no branch {}",
+            id, request.index
+        )));
+    }
+    
+    let options = crate::import::ImportOptions::from_config(&state.config.import)?;
+    let _lock = state.write_lock().await?;
+    crate::import::branches::select(&state.pool, id, request.index, &options).await?;
+    
+    let branches = crate::import::branches::list(&state.pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Conversation {} not found", id)))?;
+    
+    Ok(Json(branches))
+}
+
+/// Related conversations
+#[derive(Deserialize)]
+struct RelatedParams {
+    limit: Option<usize>,
+}
+
+#[instrument(skip_all, fields(conversation_id = id, result_count = field::Empty))]
+async fn related_api(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<i64>,
+    Query(params): Query<RelatedParams>,
+) -> AppResult<Json<Vec<SearchResult>>> {
+    // 404 for unknown conversations rather than an empty list
+    get_conversation(&state.pool, id).await?;
+    
+    let markers = &state.config.search.snippet_markers;
+    let related = search::find_related(&state.pool, id, params.limit.unwrap_or(5), markers).await?;
+    Span::current().record("result_count", related.len());
+    Ok(Json(related))
+}
+
+/// In-conversation search parameters
+#[derive(Deserialize)]
+struct ConversationSearchParams {
+    q: Option<String>,
+}
+
+/// Messages of one conversation matching a query, in conversation order
+#[instrument(skip_all, fields(conversation_id = id, result_count = field::Empty))]
+async fn conversation_search_api(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<i64>,
+    Query(params): Query<ConversationSearchParams>,
+) -> AppResult<Json<Vec<MessageMatch>>> {
+    let query = params.q.filter(|q| !q.trim().is_empty())
+        .ok_or_else(|| AppError::BadRequest("Missing query parameter".into()))?;
+    
+    // 404 for unknown conversations rather than an empty list
+    get_conversation(&state.pool, id).await?;
+    
+    let markers = &state.config.search.snippet_markers;
+    let matches = search::search_in_conversation(&state.pool, id, &query, markers).await?;
+    Span::current().record("result_count", matches.len());
+    Ok(Json(matches))
+}
+
+/// Conversation export
+#[derive(Deserialize)]
+struct ExportParams {
+    format: Option<String>,
+    #[serde(default)]
+    flatten: bool,
+    /// Transcript layout for markdown and html
+    template: Option<String>,
+    /// Combine markdown transcripts into one document with a table of contents
+    #[serde(default)]
+    combine: bool,
+    /// Mask `export.redaction_patterns` matches before exporting
+    #[serde(default)]
+    redact: bool,
+}
+
+impl ExportParams {
+    fn options(&self, config: &Config) -> AppResult<ExportOptions> {
+        let parse_options = || -> anyhow::Result<ExportOptions> {
+            let format = self.format.as_deref().unwrap_or("jsonl").parse::<ExportFormat>()?;
+            ExportOptions {
+                template: self.template.as_deref().unwrap_or("default").parse()?,
+                ..ExportOptions::new(format, self.flatten)?
+            }
+            .with_combine(self.combine)
+        };
+        let options = parse_options().map_err(|e| AppError::BadRequest(e.to_string()))?;
+        
+        let redactor = if self.redact { Some(config.export.redactor()?) } else { None };
+        Ok(ExportOptions { redactor, ..options })
+    }
+}
+
+/// One conversation as a download named after its title
+#[instrument(skip_all, fields(conversation_id = id, format = params.format.as_deref()))]
+async fn export_api(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<i64>,
+    Query(params): Query<ExportParams>,
+) -> AppResult<impl IntoResponse> {
+    let options = params.options(&state.config)?;
+    
+    let conversation = get_conversation(&state.pool, id).await?;
+    let messages = search::get_conversation_messages(&state.pool, id, &search::MessagePage::default()).await?;
+    
+    let body = export::render_conversation(&conversation, &messages, &options)?;
+    let disposition = format!("attachment; filename=\"{}\"", options.file_name(&conversation));
+    
+    Ok((
+        [
+            (header::CONTENT_TYPE, options.format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    ))
+}
+
+/// Every conversation in one export
+async fn export_all_api(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ExportParams>,
+) -> AppResult<impl IntoResponse> {
+    let options = params.options(&state.config)?;
+    
+    let mut body = Vec::new();
+    let content_type = options.format.content_type();
+    export::export_all(&state.pool, options, &mut body).await?;
+    
+    Ok(([(header::CONTENT_TYPE, content_type)], body))
+}
+
+/// Metadata and message counts of every conversation as csv
+async fn export_csv_api(State(state): State<Arc<AppState>>) -> AppResult<impl IntoResponse> {
+    let mut body = Vec::new();
+    export::export_csv(&state.pool, &mut body).await?;
+    
+    Ok((
+        [
+            (header::CONTENT_TYPE, ExportFormat::Csv.content_type()),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"conversations.csv\""),
+        ],
+        body,
+    ))
 }
 
 /// Search suggestions
@@ -201,7 +858,46 @@ async fn suggestions_api(
 struct Stats {
     total_conversations: i64,
     total_messages: i64,
+    /// Sum of known message token counts; see `import --count-tokens`
+    total_tokens: i64,
+    /// `total_tokens` averaged over conversations
+    tokens_per_conversation: f64,
     providers: Vec<ProviderStats>,
+    models: Vec<ModelStats>,
+    /// Conversations per detected language; undetected ones are left out
+    languages: Vec<LanguageStats>,
+    /// Filters the counts were restricted by
+    filters: StatsFilters,
+}
+
+/// Which conversations `/api/stats` counts
+#[derive(Debug, Clone, Default, Serialize)]
+struct StatsFilters {
+    provider: Option<String>,
+    /// Conversations created at or after this instant
+    after: Option<chrono::DateTime<chrono::Utc>>,
+    /// Conversations created at or before this instant
+    before: Option<chrono::DateTime<chrono::Utc>>,
+    include_archived: bool,
+}
+
+/// `StatsFilters` as a condition on conversations aliased `c`, bound to
+/// `?1`-`?4` by `bind_stats_filters`
+const STATS_FILTER: &str = "(c.is_archived = 0 OR ?1) \
+    AND (?2 IS NULL OR c.provider = ?2) \
+    AND (?3 IS NULL OR c.created_at >= ?3) \
+    AND (?4 IS NULL OR c.created_at <= ?4)";
+
+fn bind_stats_filters<'q, O>(
+    query: sqlx::query::QueryAs<'q, sqlx::Sqlite, O, sqlx::sqlite::SqliteArguments<'q>>,
+    filters: &'q StatsFilters,
+) -> sqlx::query::QueryAs<'q, sqlx::Sqlite, O, sqlx::sqlite::SqliteArguments<'q>> {
+    // Compared as text, like the search filters
+    query
+        .bind(filters.include_archived)
+        .bind(filters.provider.as_deref())
+        .bind(filters.after.map(|after| after.to_rfc3339()))
+        .bind(filters.before.map(|before| before.to_rfc3339()))
 }
 
 #[derive(Serialize)]
@@ -210,88 +906,2329 @@ struct ProviderStats {
     count: i64,
 }
 
-async fn stats_api(State(state): State<Arc<AppState>>) -> AppResult<Json<Stats>> {
-    let stats = get_stats(&state.pool).await?;
-    Ok(Json(stats))
+#[derive(Serialize)]
+struct LanguageStats {
+    language: String,
+    count: i64,
+}
+
+/// Assistant messages per model slug or family
+#[derive(Debug, Serialize, PartialEq)]
+struct ModelStats {
+    model: String,
+    messages: i64,
+    conversations: usize,
 }
 
-/// Health check
-async fn health_check() -> impl IntoResponse {
-    StatusCode::OK
+/// Model grouping parameters
+#[derive(Deserialize)]
+struct ModelParams {
+    /// `exact` or `family`; defaults to `server.model_grouping`
+    group: Option<ModelGrouping>,
+    /// Count archived conversations too
+    #[serde(default)]
+    include_archived: bool,
 }
 
-/// Metrics endpoint
-async fn metrics_endpoint() -> impl IntoResponse {
-    let encoder = metrics_exporter_prometheus::Encoder::new();
-    let metric_families = encoder.encode();
-    let mut buffer = String::new();
+/// Statistics parameters
+#[derive(Deserialize)]
+struct StatsParams {
+    /// `exact` or `family`; defaults to `server.model_grouping`
+    group: Option<ModelGrouping>,
+    provider: Option<String>,
+    /// Only conversations created on or after this date (YYYY-MM-DD or RFC 3339)
+    after: Option<String>,
+    /// Only conversations created on or before this date (YYYY-MM-DD or RFC 3339)
+    before: Option<String>,
+    /// Count archived conversations too
+    #[serde(default)]
+    include_archived: bool,
+}
+
+/// Archive statistics, tagged with an ETag over the counts so unchanged
+/// stats are answered with 304
+async fn stats_api(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<StatsParams>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let grouping = params.group.unwrap_or(state.config.server.model_grouping);
+    let filters = StatsFilters {
+        provider: params.provider,
+        after: params.after.as_deref().map(|v| parse_date_param("after", v)).transpose()?,
+        before: params.before.as_deref().map(|v| parse_end_date_param("before", v)).transpose()?,
+        include_archived: params.include_archived,
+    };
     
-    if let Err(e) = encoder.encode_fmt(&metric_families, &mut buffer) {
-        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to encode metrics: {}", e));
+    let stats = get_stats(&state.pool, grouping, filters).await?;
+    let etag = ETag::weak(&[serde_json::to_vec(&stats).map_err(anyhow::Error::from)?]);
+    Ok(etag.respond(&headers, Json(stats)))
+}
+
+/// How long `/api/facets` reuses its last answer
+const FACETS_TTL: Duration = Duration::from_secs(30);
+
+/// Filter options for a faceted search UI, recomputed at most every `FACETS_TTL`
+async fn facets_api(State(state): State<Arc<AppState>>) -> AppResult<Json<Facets>> {
+    if let Some((computed_at, facets)) = &*state.facets.lock().expect("facets lock poisoned") {
+        if computed_at.elapsed() < FACETS_TTL {
+            return Ok(Json(facets.clone()));
+        }
     }
     
-    (StatusCode::OK, buffer)
+    let facets = search::facets(&state.pool).await?;
+    *state.facets.lock().expect("facets lock poisoned") = Some((Instant::now(), facets.clone()));
+    Ok(Json(facets))
 }
 
-/// Helper functions
-async fn get_conversation(pool: &SqlitePool, id: i64) -> AppResult<Conversation> {
-    sqlx::query_as!(
-        Conversation,
-        r#"
-        SELECT 
-            id as "id!",
-            provider as "provider!",
-            external_id,
-            title,
-            model,
-            created_at as "created_at!",
-            updated_at as "updated_at!",
-            raw_json,
-            system_prompt,
-            temperature,
-            max_tokens,
-            user_id
-        FROM conversations
-        WHERE id = $1
-        "#,
-        id
-    )
-    .fetch_optional(pool)
-    .await?
-    .ok_or_else(|| AppError::NotFound(format!("Conversation {} not found", id)))
+async fn models_api(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ModelParams>,
+) -> AppResult<Json<Vec<ModelStats>>> {
+    let grouping = params.group.unwrap_or(state.config.server.model_grouping);
+    let filters = StatsFilters { include_archived: params.include_archived, ..StatsFilters::default() };
+    let models = get_model_stats(&state.pool, grouping, &filters).await?;
+    Ok(Json(models))
 }
 
-async fn get_stats(pool: &SqlitePool) -> AppResult<Stats> {
-    let total_conversations = sqlx::query!("SELECT COUNT(*) as count FROM conversations")
-        .fetch_one(pool)
-        .await?
-        .count;
+/// Hide a conversation from browsing, search, and stats
+#[instrument(skip_all, fields(conversation_id = id))]
+async fn archive_api(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<i64>,
+) -> AppResult<Json<ArchiveResponse>> {
+    set_archived(&state, id, true).await
+}
+
+/// Undo `archive_api`
+#[instrument(skip_all, fields(conversation_id = id))]
+async fn unarchive_api(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<i64>,
+) -> AppResult<Json<ArchiveResponse>> {
+    set_archived(&state, id, false).await
+}
+
+#[derive(Serialize)]
+struct ArchiveResponse {
+    id: i64,
+    is_archived: bool,
+}
+
+async fn set_archived(state: &AppState, id: i64, archived: bool) -> AppResult<Json<ArchiveResponse>> {
+    let _lock = state.write_lock().await?;
+    let result = sqlx::query("UPDATE conversations SET is_archived = ?2 WHERE id = ?1")
+        .bind(id)
+        .bind(archived)
+        .execute(&state.pool)
+        .await?;
     
-    let total_messages = sqlx::query!("SELECT COUNT(*) as count FROM messages")
-        .fetch_one(pool)
-        .await?
-        .count;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Conversation {} not found", id)));
+    }
+    
+    Ok(Json(ArchiveResponse { id, is_archived: archived }))
+}
+
+/// List a conversation ahead of the rest when browsing
+#[instrument(skip_all, fields(conversation_id = id))]
+async fn pin_api(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<i64>,
+) -> AppResult<Json<PinResponse>> {
+    set_pinned(&state, id, true).await
+}
+
+/// Undo `pin_api`
+#[instrument(skip_all, fields(conversation_id = id))]
+async fn unpin_api(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<i64>,
+) -> AppResult<Json<PinResponse>> {
+    set_pinned(&state, id, false).await
+}
+
+#[derive(Serialize)]
+struct PinResponse {
+    id: i64,
+    is_pinned: bool,
+}
+
+async fn set_pinned(state: &AppState, id: i64, pinned: bool) -> AppResult<Json<PinResponse>> {
+    let _lock = state.write_lock().await?;
+    let result = sqlx::query("UPDATE conversations SET is_pinned = ?2 WHERE id = ?1")
+        .bind(id)
+        .bind(pinned)
+        .execute(&state.pool)
+        .await?;
+    
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Conversation {} not found", id)));
+    }
+    
+    Ok(Json(PinResponse { id, is_pinned: pinned }))
+}
+
+/// Import request body
+#[derive(Deserialize)]
+struct ImportRequest {
+    /// Provider name, or `auto` (the default) to detect it
+    provider: Option<String>,
+    /// Export file or directory under `server.import_root`, or an https://
+    /// or s3:// URL on a host in `server.import_hosts`
+    path: PathBuf,
+    #[serde(default)]
+    force: bool,
+}
+
+#[derive(Serialize)]
+struct ImportAccepted {
+    job_id: jobs::JobId,
+}
+
+/// Start an import in the background; poll `/api/import/jobs/:id` for progress
+async fn import_api(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ImportRequest>,
+) -> AppResult<(StatusCode, Json<ImportAccepted>)> {
+    // The API has no authentication, so it only reads what the config opens up
+    let source = request.path.to_str().filter(|source| crate::import::remote::is_remote(source));
+    let path = match source {
+        Some(source) => {
+            crate::import::remote::check_allowed(source, &state.config.server.import_hosts)
+                .map_err(|e| AppError::BadRequest(e.to_string()))?;
+            request.path
+        }
+        None => import_path(&state.config, &request.path)?,
+    };
+    
+    let mut options = crate::import::ImportOptions {
+        force: request.force,
+        ..crate::import::ImportOptions::from_config(&state.config.import)?
+    };
+    // Redirects get the same host check as the requested URL
+    options.download_limits.allowed_hosts = Some(state.config.server.import_hosts.clone());
+    
+    let job_id = jobs::spawn_import(
+        state.pool.clone(),
+        state.import_jobs.clone(),
+        request.provider.unwrap_or_else(|| "auto".to_string()),
+        path,
+        options,
+        Duration::from_secs(state.config.database.lock_timeout_secs),
+    );
+    
+    Ok((StatusCode::ACCEPTED, Json(ImportAccepted { job_id })))
+}
+
+/// `path` resolved inside `server.import_root`, or a 400
+///
+/// Relative paths are taken from the root, and symlinks are resolved before
+/// comparing so a link can't lead out of it.
+fn import_path(config: &Config, path: &Path) -> AppResult<PathBuf> {
+    let Some(root) = &config.server.import_root else {
+        return Err(AppError::BadRequest(
+            "Importing server paths is disabled; set server.import_root to allow it".into(),
+        ));
+    };
+    let root = Path::new(shellexpand::tilde(root).as_ref())
+        .canonicalize()
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("server.import_root {:?}: {}", root, e)))?;
+    
+    let resolved = root
+        .join(path)
+        .canonicalize()
+        .map_err(|_| AppError::BadRequest(format!("Import path {:?} does not exist", path)))?;
+    if !resolved.starts_with(&root) {
+        return Err(AppError::BadRequest(format!("Import path {:?} is outside server.import_root", path)));
+    }
+    
+    Ok(resolved)
+}
+
+async fn import_job_api(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<jobs::JobId>,
+) -> AppResult<Json<jobs::JobProgress>> {
+    let jobs = state.import_jobs.read().expect("jobs lock poisoned");
+    let job = jobs.get(&id)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("Import job {} not found", id)))?;
     
-    let providers = sqlx::query!(
+    Ok(Json(job))
+}
+
+/// Import history
+#[derive(Deserialize)]
+struct ImportHistoryParams {
+    limit: Option<usize>,
+}
+
+/// Recent import runs with their new, updated and skipped counts
+async fn import_history_api(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ImportHistoryParams>,
+) -> AppResult<Json<Vec<crate::models::ImportEvent>>> {
+    let events = crate::import::history(&state.pool, params.limit.unwrap_or(20)).await?;
+    Ok(Json(events))
+}
+
+/// Messages and titles missing from the search index
+async fn index_status_api(State(state): State<Arc<AppState>>) -> AppResult<Json<db::fts::IndexStatus>> {
+    let status = db::fts::index_status(&state.pool).await?;
+    Ok(Json(status))
+}
+
+/// OpenAPI 3.0 description of the JSON API, kept by hand next to the handlers
+const OPENAPI: &str = include_str!("openapi.json");
+
+/// Machine-readable API description for client generators
+async fn openapi_api() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/json")], OPENAPI)
+}
+
+/// Result of a `/health` probe
+#[derive(Debug, Serialize)]
+struct Health {
+    /// `healthy`, or `unhealthy` when the database can't be queried
+    status: &'static str,
+    database: bool,
+    /// Round trip of the probe query
+    latency_ms: f64,
+    version: &'static str,
+}
+
+/// Health check that runs a trivial query, answering 503 if it fails
+async fn health_check(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let start = Instant::now();
+    let database = sqlx::query("SELECT 1").execute(&state.pool).await;
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    
+    if let Err(e) = &database {
+        warn!("Health check query failed: {}", e);
+    }
+    let (status_code, status) = match database {
+        Ok(_) => (StatusCode::OK, "healthy"),
+        Err(_) => (StatusCode::SERVICE_UNAVAILABLE, "unhealthy"),
+    };
+    
+    (
+        status_code,
+        Json(Health {
+            status,
+            database: status_code == StatusCode::OK,
+            latency_ms,
+            version: env!("CARGO_PKG_VERSION"),
+        }),
+    )
+}
+
+/// Metrics endpoint
+async fn metrics_endpoint() -> impl IntoResponse {
+    let encoder = metrics_exporter_prometheus::Encoder::new();
+    let metric_families = encoder.encode();
+    let mut buffer = String::new();
+    
+    if let Err(e) = encoder.encode_fmt(&metric_families, &mut buffer) {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to encode metrics: {}", e));
+    }
+    
+    (StatusCode::OK, buffer)
+}
+
+/// Helper functions
+async fn get_conversation(pool: &SqlitePool, id: i64) -> AppResult<Conversation> {
+    sqlx::query_as!(
+        Conversation,
         r#"
-        SELECT provider, COUNT(*) as count
+        SELECT 
+            id as "id!",
+            provider as "provider!",
+            external_id,
+            title,
+            model,
+            created_at as "created_at!",
+            updated_at as "updated_at!",
+            raw_json,
+            system_prompt,
+            temperature,
+            max_tokens,
+            user_id,
+            is_pinned as "is_pinned: bool",
+            is_archived as "is_archived: bool"
         FROM conversations
-        GROUP BY provider
-        ORDER BY count DESC
-        "#
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Conversation {} not found", id)))
+}
+
+/// Archive-wide counts; archived conversations only with `include_archived`
+async fn get_stats(pool: &SqlitePool, grouping: ModelGrouping, filters: StatsFilters) -> AppResult<Stats> {
+    let (total_conversations,): (i64,) = bind_stats_filters(
+        sqlx::query_as(&format!("SELECT COUNT(*) FROM conversations c WHERE {}", STATS_FILTER)),
+        &filters,
+    )
+    .fetch_one(pool)
+    .await?;
+    
+    let (total_messages, total_tokens): (i64, i64) = bind_stats_filters(
+        sqlx::query_as(&format!(
+            r#"
+            SELECT COUNT(*), COALESCE(SUM(m.tokens), 0)
+            FROM messages m
+            JOIN conversations c ON c.id = m.conversation_id
+            WHERE {}
+            "#,
+            STATS_FILTER
+        )),
+        &filters,
+    )
+    .fetch_one(pool)
+    .await?;
+    
+    let providers = bind_stats_filters(
+        sqlx::query_as::<_, (String, i64)>(&format!(
+            r#"
+            SELECT c.provider, COUNT(*) as count
+            FROM conversations c
+            WHERE {}
+            GROUP BY c.provider
+            ORDER BY count DESC
+            "#,
+            STATS_FILTER
+        )),
+        &filters,
     )
     .fetch_all(pool)
     .await?
     .into_iter()
-    .map(|row| ProviderStats {
-        name: row.provider,
-        count: row.count,
-    })
+    .map(|(name, count)| ProviderStats { name, count })
     .collect();
     
+    let languages = bind_stats_filters(
+        sqlx::query_as::<_, (String, i64)>(&format!(
+            r#"
+            SELECT c.language, COUNT(*) as count
+            FROM conversations c
+            WHERE c.language IS NOT NULL AND {}
+            GROUP BY c.language
+            ORDER BY count DESC, c.language
+            "#,
+            STATS_FILTER
+        )),
+        &filters,
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(language, count)| LanguageStats { language, count })
+    .collect();
+    
+    let tokens_per_conversation = if total_conversations > 0 {
+        total_tokens as f64 / total_conversations as f64
+    } else {
+        0.0
+    };
+    
     Ok(Stats {
         total_conversations,
         total_messages,
+        total_tokens,
+        tokens_per_conversation,
         providers,
+        models: get_model_stats(pool, grouping, &filters).await?,
+        languages,
+        filters,
     })
-}
\ No newline at end of file
+}
+
+/// Assistant message counts per model, most used first
+async fn get_model_stats(pool: &SqlitePool, grouping: ModelGrouping, filters: &StatsFilters) -> AppResult<Vec<ModelStats>> {
+    let rows: Vec<(String, i64, i64)> = bind_stats_filters(
+        sqlx::query_as(&format!(
+            r#"
+            SELECT m.model, m.conversation_id, COUNT(*)
+            FROM messages m
+            JOIN conversations c ON c.id = m.conversation_id
+            WHERE m.role = 'assistant' AND m.model IS NOT NULL
+              AND {}
+            GROUP BY m.model, m.conversation_id
+            "#,
+            STATS_FILTER
+        )),
+        filters,
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    // Grouped here rather than in SQL so a conversation spanning two
+    // versions of one family is counted once
+    let mut groups: std::collections::HashMap<String, (i64, std::collections::HashSet<i64>)> =
+        std::collections::HashMap::new();
+    for (model, conversation_id, messages) in rows {
+        let group = groups.entry(grouping.key(&model)).or_default();
+        group.0 += messages;
+        group.1.insert(conversation_id);
+    }
+    
+    let mut models: Vec<ModelStats> = groups
+        .into_iter()
+        .map(|(model, (messages, conversations))| ModelStats {
+            model,
+            messages,
+            conversations: conversations.len(),
+        })
+        .collect();
+    models.sort_by(|a, b| b.messages.cmp(&a.messages).then_with(|| a.model.cmp(&b.model)));
+    
+    Ok(models)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::{conversation, message, test_pool};
+    use crate::import::process_conversation_batch;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+    
+    async fn get_json(app: &Router, uri: &str) -> serde_json::Value {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+    
+    #[test]
+    fn test_bind_address_combines_host_and_port() {
+        assert_eq!(bind_address("127.0.0.1", 8080).unwrap(), SocketAddr::from(([127, 0, 0, 1], 8080)));
+        assert_eq!(bind_address("0.0.0.0", 3000).unwrap(), SocketAddr::from(([0, 0, 0, 0], 3000)));
+        assert_eq!(bind_address("::1", 8080).unwrap().to_string(), "[::1]:8080");
+        assert_eq!(bind_address(&Config::default().server.host, 80).unwrap().ip().to_string(), "127.0.0.1");
+        
+        for host in ["", "localhost", "192.168.1", "127.0.0.1:8080"] {
+            assert!(bind_address(host, 8080).is_err(), "{:?}", host);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_health_reports_database_status() {
+        let (_dir, pool) = test_pool().await;
+        let app = router(Arc::new(AppState::new(pool.clone(), Config::default())));
+    
+        let health = get_json(&app, "/health").await;
+        assert_eq!(health["status"], "healthy");
+        assert_eq!(health["database"], true);
+        assert!(health["latency_ms"].as_f64().unwrap() >= 0.0);
+        assert_eq!(health["version"], env!("CARGO_PKG_VERSION"));
+    
+        pool.close().await;
+        let response = app
+            .oneshot(Request::builder().uri("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let health: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(health["status"], "unhealthy");
+        assert_eq!(health["database"], false);
+    }
+    
+    #[tokio::test]
+    async fn test_conditional_get_answers_not_modified() {
+        let (_dir, pool) = test_pool().await;
+        let batch = |replies: &[&str]| {
+            let mut messages = vec![message("user", "cache this")];
+            messages.extend(replies.iter().map(|reply| message("assistant", reply)));
+            vec![(conversation("claude", "conv-1", "Cached"), messages)]
+        };
+        process_conversation_batch(&pool, batch(&[])).await.unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool.clone(), Config::default())));
+        let get = |uri: &str, if_none_match: Option<&str>| {
+            let mut request = Request::builder().uri(uri);
+            if let Some(etag) = if_none_match {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+        
+        let mut etags = Vec::new();
+        for uri in ["/api/conversation/1", "/api/stats"] {
+            let first = get(uri, None).await.unwrap();
+            assert_eq!(first.status(), StatusCode::OK);
+            let etag = first.headers()[header::ETAG].to_str().unwrap().to_string();
+            assert!(etag.starts_with("W/\""), "{}", etag);
+            
+            let cached = get(uri, Some(&etag)).await.unwrap();
+            assert_eq!(cached.status(), StatusCode::NOT_MODIFIED, "{}", uri);
+            assert_eq!(cached.headers()[header::ETAG], etag.as_str());
+            let body = axum::body::to_bytes(cached.into_body(), usize::MAX).await.unwrap();
+            assert!(body.is_empty());
+            
+            etags.push((uri, etag));
+        }
+        
+        // A new message changes both tags
+        process_conversation_batch(&pool, batch(&["fresh reply"])).await.unwrap();
+        for (uri, etag) in etags {
+            let changed = get(uri, Some(&etag)).await.unwrap();
+            assert_eq!(changed.status(), StatusCode::OK, "{}", uri);
+            assert_ne!(changed.headers()[header::ETAG], etag.as_str());
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_pinning_changes_the_conversation_etag() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(&pool, vec![(conversation("claude", "conv-1", "Cached"), vec![message("user", "hi")])])
+            .await
+            .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        let send = |method: &str, uri: &str, if_none_match: Option<&str>| {
+            let mut request = Request::builder().method(method).uri(uri);
+            if let Some(etag) = if_none_match {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+        
+        for uri in ["/api/conversation/1", "/api/conversation/1?include=messages"] {
+            let first = send("GET", uri, None).await.unwrap();
+            let etag = first.headers()[header::ETAG].to_str().unwrap().to_string();
+            assert_eq!(first.headers()[header::CONTENT_TYPE], "application/json");
+            assert_eq!(send("GET", uri, Some(&etag)).await.unwrap().status(), StatusCode::NOT_MODIFIED);
+            
+            send("POST", "/api/conversation/1/pin", None).await.unwrap();
+            let pinned = send("GET", uri, Some(&etag)).await.unwrap();
+            assert_eq!(pinned.status(), StatusCode::OK, "{}", uri);
+            let body = axum::body::to_bytes(pinned.into_body(), usize::MAX).await.unwrap();
+            let detail: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(detail["is_pinned"], true);
+            
+            send("POST", "/api/conversation/1/unpin", None).await.unwrap();
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_search_stream_sends_batches_then_done() {
+        let (_dir, pool) = test_pool().await;
+        // Two matching messages each, still one result per conversation
+        let batch = (0..45)
+            .map(|i| {
+                (
+                    conversation("claude", &format!("conv-{}", i), &format!("Conversation {}", i)),
+                    vec![message("user", "a streamable question"), message("assistant", "a streamable answer")],
+                )
+            })
+            .collect();
+        process_conversation_batch(&pool, batch).await.unwrap();
+        
+        let stream = |config: Config, uri: &'static str| {
+            let app = router(Arc::new(AppState::new(pool.clone(), config)));
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+                
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                String::from_utf8(body.to_vec())
+                    .unwrap()
+                    .split("\n\n")
+                    .filter_map(|event| {
+                        let field = |name: &str| {
+                            event.lines().find_map(|line| line.strip_prefix(name)).map(|value| value.trim_start())
+                        };
+                        Some((field("event:")?.to_string(), serde_json::from_str(field("data:")?).unwrap()))
+                    })
+                    .collect::<Vec<(String, serde_json::Value)>>()
+            }
+        };
+        
+        let events = stream(Config::default(), "/api/search/stream?q=streamable&limit=45").await;
+        let names: Vec<&str> = events.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["results", "results", "results", "done"]);
+        let sizes: Vec<usize> = events[..3].iter().map(|(_, data)| data.as_array().unwrap().len()).collect();
+        assert_eq!(sizes, vec![20, 20, 5]);
+        let ids: std::collections::HashSet<i64> = events[..3]
+            .iter()
+            .flat_map(|(_, data)| data.as_array().unwrap().iter().map(|r| r["conversation"]["id"].as_i64().unwrap()))
+            .collect();
+        assert_eq!(ids.len(), 45);
+        assert_eq!(events[3].1, serde_json::json!({ "total": 45 }));
+        
+        // Without a limit the default applies, and no more than `search.max_limit` is sent
+        let events = stream(Config::default(), "/api/search/stream?q=streamable").await;
+        assert_eq!(events.last().unwrap().1, serde_json::json!({ "total": 20 }));
+        let mut config = Config::default();
+        config.search.max_limit = 30;
+        let events = stream(config, "/api/search/stream?q=streamable&limit=1000").await;
+        assert_eq!(events.last().unwrap().1, serde_json::json!({ "total": 30 }));
+    }
+    
+    #[tokio::test]
+    async fn test_responses_carry_request_id() {
+        let (_dir, pool) = test_pool().await;
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        
+        let request_id = |response: &axum::response::Response| {
+            response.headers()["x-request-id"].to_str().unwrap().to_string()
+        };
+        
+        let first = app
+            .clone()
+            .oneshot(Request::builder().uri("/api/search?q=anything").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let second = app
+            .clone()
+            .oneshot(Request::builder().uri("/api/stats").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(!request_id(&first).is_empty());
+        assert_ne!(request_id(&first), request_id(&second));
+        
+        // A caller's own id is kept
+        let traced = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/stats")
+                    .header("x-request-id", "upstream-42")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(request_id(&traced), "upstream-42");
+    }
+    
+    #[tokio::test]
+    async fn test_conversation_with_messages_matches_separate_endpoints() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![(
+                conversation("claude", "conv-1", "Inline messages"),
+                vec![
+                    message("user", "first question"),
+                    message("assistant", "first answer"),
+                    message("user", "follow-up"),
+                ],
+            )],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        
+        let plain = get_json(&app, "/api/conversation/1").await;
+        let messages = get_json(&app, "/api/conversation/1/messages").await;
+        let mut combined = get_json(&app, "/api/conversation/1?include=messages").await;
+        
+        assert!(plain.get("messages").is_none());
+        assert_eq!(combined["messages"], messages["messages"]);
+        assert_eq!(combined["message_count"], 3);
+        assert_eq!(combined["user_message_count"], 2);
+        assert_eq!(combined["assistant_message_count"], 1);
+        
+        let combined = combined.as_object_mut().unwrap();
+        for key in ["messages", "message_count", "user_message_count", "assistant_message_count"] {
+            combined.remove(key);
+        }
+        assert_eq!(serde_json::Value::Object(combined.clone()), plain);
+    }
+    
+    #[tokio::test]
+    async fn test_combined_markdown_export_has_table_of_contents() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![
+                (conversation("claude", "book-1", "Sourdough starter"), vec![message("user", "Feed it daily?")]),
+                (conversation("chatgpt", "book-2", "Bread [part 2]"), vec![message("user", "Bake at 250C?")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/export?format=markdown&combine=true")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/markdown; charset=utf-8");
+        
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let book = std::str::from_utf8(&body).unwrap();
+        assert!(book.starts_with("# Conversation Export\n\n## Contents\n"));
+        assert!(book.contains("1. [Sourdough starter](#conversation-1)\n"));
+        assert!(book.contains("2. [Bread \\[part 2\\]](#conversation-2)\n"));
+        for id in [1, 2] {
+            let anchor = format!("<a id=\"conversation-{}\"></a>", id);
+            assert_eq!(book.matches(&anchor).count(), 1);
+        }
+        assert!(book.find("# Sourdough starter").unwrap() < book.find("# Bread [part 2]").unwrap());
+        
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/export?format=jsonl&combine=true")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    
+    #[tokio::test]
+    async fn test_conversation_export_formats_are_downloads() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![(
+                conversation("claude", "download", "Trip to Lisbon"),
+                vec![message("user", "Where should I eat?"), message("assistant", "Try the pastéis.")],
+            )],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        
+        for (format, content_type, file_name) in [
+            ("json", "application/json", "Trip-to-Lisbon.json"),
+            ("markdown", "text/markdown; charset=utf-8", "Trip-to-Lisbon.md"),
+            ("html", "text/html; charset=utf-8", "Trip-to-Lisbon.html"),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(format!("/api/conversation/1/export?format={}", format))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()[header::CONTENT_TYPE], content_type);
+            assert_eq!(
+                response.headers()[header::CONTENT_DISPOSITION],
+                format!("attachment; filename=\"{}\"", file_name).as_str()
+            );
+            
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let body = std::str::from_utf8(&body).unwrap();
+            assert!(body.contains("Trip to Lisbon"), "{} export is missing the title", format);
+            
+            if format == "json" {
+                let document: serde_json::Value = serde_json::from_str(body).unwrap();
+                assert_eq!(document["messages"].as_array().unwrap().len(), 2);
+            }
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_streamed_ndjson_import_stores_batches_as_lines_arrive() {
+        let (_dir, pool) = test_pool().await;
+        let app = router(Arc::new(AppState::new(pool.clone(), Config::default())));
+        
+        const LINES: usize = 1000;
+        let line = |i: usize| {
+            let record = serde_json::json!({
+                "conversation": conversation("claude", &format!("streamed-{}", i), &format!("Streamed {}", i)),
+                "messages": [message("user", &format!("line {}", i))],
+            });
+            format!("{}\n", record)
+        };
+        
+        // Each line arrives in two chunks split mid-record, followed by one
+        // bad line; before handing over a line, check the earlier ones were
+        // already stored rather than buffered
+        let chunks = futures::stream::unfold(0, move |i| {
+            let pool = pool.clone();
+            async move {
+                if i > 2 * LINES {
+                    return None;
+                }
+                if i == 2 * LINES {
+                    return Some((Ok::<_, std::io::Error>(bytes::Bytes::from("not json\n")), i + 1));
+                }
+                
+                let n = i / 2;
+                if i % 2 == 0 {
+                    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM conversations")
+                        .fetch_one(&pool)
+                        .await
+                        .unwrap();
+                    assert!(stored as usize + 100 >= n, "{} stored before line {}", stored, n);
+                }
+                
+                let text = line(n);
+                let (head, tail) = text.split_at(text.len() / 2);
+                let chunk = if i % 2 == 0 { head } else { tail };
+                Some((Ok(bytes::Bytes::from(chunk.to_string())), i + 1))
+            }
+        });
+        
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/import/stream")
+                    .header(header::CONTENT_TYPE, "application/x-ndjson")
+                    .body(Body::from_stream(chunks))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["imported"], LINES);
+        assert_eq!(report["messages"], LINES);
+        assert_eq!(report["errors"], 1);
+        assert!(report["error_messages"][0].as_str().unwrap().starts_with("line 1001:"));
+        
+        assert_eq!(get_json(&app, "/api/stats").await["total_conversations"], LINES);
+    }
+    
+    #[tokio::test]
+    async fn test_bulk_export_zips_one_file_per_conversation() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![
+                (conversation("claude", "a", "Trip to Lisbon"), vec![message("user", "Where should I eat?")]),
+                (conversation("claude", "b", "Trip to Lisbon"), vec![message("user", "What should I see?")]),
+                (conversation("chatgpt", "c", "Tax forms"), vec![message("user", "Which form do I need?")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        let bulk_export = |body: serde_json::Value| {
+            app.clone().oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/export/bulk")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+        };
+        
+        let response = bulk_export(serde_json::json!({ "ids": [1, 2, 3], "format": "markdown" })).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/zip");
+        
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(body)).unwrap();
+        assert_eq!(archive.len(), 3);
+        
+        let mut names: Vec<_> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(names, vec!["Tax-forms.md", "Trip-to-Lisbon-2.md", "Trip-to-Lisbon.md"]);
+        
+        let mut transcript = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("Tax-forms.md").unwrap(), &mut transcript).unwrap();
+        assert!(transcript.contains("Which form do I need?"));
+        
+        let too_many: Vec<i64> = (1..=501).collect();
+        let response = bulk_export(serde_json::json!({ "ids": too_many })).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        
+        let response = bulk_export(serde_json::json!({ "ids": [1, 99] })).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    
+    #[tokio::test]
+    async fn test_flattened_jsonl_export_has_one_line_per_message() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![(
+                conversation("chatgpt", "rag", "Chunk me"),
+                vec![
+                    message("user", "What is a vector store?"),
+                    message("assistant", "A database for embeddings."),
+                    message("user", "Thanks"),
+                ],
+            )],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/conversation/1/export?format=jsonl&flatten=true")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/jsonl");
+        
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let lines: Vec<serde_json::Value> = std::str::from_utf8(&body)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        
+        assert_eq!(lines.len(), 3);
+        for (position, line) in lines.iter().enumerate() {
+            let keys: Vec<_> = line.as_object().unwrap().keys().map(String::as_str).collect();
+            for key in ["conversation_id", "position", "role", "content", "title", "provider", "model", "timestamp"] {
+                assert!(keys.contains(&key), "missing {}", key);
+            }
+            assert_eq!(line["conversation_id"], 1);
+            assert_eq!(line["position"], position);
+            assert_eq!(line["title"], "Chunk me");
+            assert_eq!(line["provider"], "chatgpt");
+        }
+        assert_eq!(lines[1]["role"], "assistant");
+        assert_eq!(lines[1]["content"], "A database for embeddings.");
+    }
+    
+    #[tokio::test]
+    async fn test_rate_limit_rejects_request_over_limit() {
+        let (_dir, pool) = test_pool().await;
+        
+        let mut config = Config::default();
+        config.server.requests_per_minute = 3;
+        let app = router(Arc::new(AppState::new(pool, config)));
+        
+        let get = |uri: &'static str| {
+            app.clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        };
+        
+        for _ in 0..3 {
+            let response = get("/api/conversation/1").await.unwrap();
+            assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        }
+        
+        let response = get("/api/conversation/1").await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.headers()[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+        assert!(retry_after >= 1);
+        
+        // Health checks stay reachable
+        assert_eq!(get("/health").await.unwrap().status(), StatusCode::OK);
+    }
+    
+    #[tokio::test]
+    async fn test_empty_search_returns_recent_conversations() {
+        let (_dir, pool) = test_pool().await;
+        
+        let mut older = conversation("claude", "older", "Older chat");
+        older.updated_at = older.updated_at - chrono::Duration::days(3);
+        let newer = conversation("chatgpt", "newer", "Newer chat");
+        process_conversation_batch(
+            &pool,
+            vec![
+                (older, vec![message("user", "from last week")]),
+                (newer, vec![message("user", "from today")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        
+        let empty = get_json(&app, "/api/search?q=").await;
+        let titles: Vec<_> = empty
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["conversation"]["title"].as_str().unwrap())
+            .collect();
+        assert_eq!(titles, vec!["Newer chat", "Older chat"]);
+        assert_eq!(empty[0]["matched_in"], "recent");
+        assert_eq!(empty[0]["snippet"], "from today");
+        
+        assert_eq!(get_json(&app, "/api/search?q=%20%20").await, empty);
+        assert_eq!(get_json(&app, "/api/search").await, empty);
+    }
+    
+    #[tokio::test]
+    async fn test_language_filter_and_stats() {
+        use crate::import::{import_batch, ImportOptions};
+        use crate::models::ImportStats;
+        
+        let (_dir, pool) = test_pool().await;
+        let batch = vec![
+            (
+                conversation("claude", "english", "English"),
+                vec![message(
+                    "user",
+                    "Can you explain how ownership works in Rust and why the borrow checker rejects my code?",
+                )],
+            ),
+            (
+                conversation("claude", "french", "French"),
+                vec![message(
+                    "user",
+                    "Peux-tu m'expliquer comment fonctionne la propriété en Rust et pourquoi le compilateur refuse mon code ?",
+                )],
+            ),
+            (conversation("claude", "short", "Short"), vec![message("user", "Rust?")]),
+        ];
+        let options = ImportOptions { detect_language: true, ..ImportOptions::default() };
+        import_batch(&pool, batch, &options, &mut ImportStats::default()).await.unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        let titles = |results: serde_json::Value| -> Vec<String> {
+            results
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["conversation"]["title"].as_str().unwrap().to_string())
+                .collect()
+        };
+        
+        assert_eq!(titles(get_json(&app, "/api/search?q=rust&language=en").await), vec!["English"]);
+        assert_eq!(titles(get_json(&app, "/api/search?q=rust&language=fr").await), vec!["French"]);
+        assert_eq!(titles(get_json(&app, "/api/search?language=fr").await), vec!["French"]);
+        assert_eq!(titles(get_json(&app, "/api/search?q=rust").await).len(), 3);
+        
+        // Too little text to guess from leaves the language unset
+        let languages = get_json(&app, "/api/stats").await["languages"].clone();
+        assert_eq!(
+            languages,
+            serde_json::json!([{ "language": "en", "count": 1 }, { "language": "fr", "count": 1 }])
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_search_filters_by_provider_and_date() {
+        let (_dir, pool) = test_pool().await;
+        
+        let mut old_claude = conversation("claude", "c-old", "Old Claude");
+        old_claude.created_at = "2023-06-01T12:00:00Z".parse().unwrap();
+        let mut new_claude = conversation("claude", "c-new", "New Claude");
+        new_claude.created_at = "2024-06-01T12:00:00Z".parse().unwrap();
+        let mut chatgpt = conversation("chatgpt", "g-new", "New ChatGPT");
+        chatgpt.created_at = "2024-06-01T12:00:00Z".parse().unwrap();
+        
+        process_conversation_batch(
+            &pool,
+            vec![
+                (old_claude, vec![message("user", "kubernetes networking")]),
+                (new_claude, vec![message("user", "kubernetes storage")]),
+                (chatgpt, vec![message("user", "kubernetes ingress")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        let titles = |results: serde_json::Value| {
+            let mut titles: Vec<String> = results
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["conversation"]["title"].as_str().unwrap().to_string())
+                .collect();
+            titles.sort();
+            titles
+        };
+        
+        let all = get_json(&app, "/api/search?q=kubernetes").await;
+        assert_eq!(titles(all), vec!["New ChatGPT", "New Claude", "Old Claude"]);
+        
+        let claude = get_json(&app, "/api/search?q=kubernetes&provider=claude").await;
+        assert!(claude.as_array().unwrap().iter().all(|r| r["snippet"].as_str().unwrap().contains("\u{2}kubernetes\u{3}")));
+        assert_eq!(titles(claude), vec!["New Claude", "Old Claude"]);
+        
+        let recent_claude = get_json(&app, "/api/search?q=kubernetes&provider=claude&after=2024-01-01").await;
+        assert_eq!(titles(recent_claude), vec!["New Claude"]);
+        
+        let older = get_json(&app, "/api/search?q=kubernetes&before=2024-01-01T00:00:00Z").await;
+        assert_eq!(titles(older), vec!["Old Claude"]);
+        
+        let response = app
+            .oneshot(Request::builder().uri("/api/search?q=kubernetes&after=June").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    
+    #[tokio::test]
+    async fn test_search_returns_multiple_snippets() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![
+                (
+                    conversation("claude", "gardening", "Gardening"),
+                    vec![
+                        message("user", "How deep should tomato seedlings be planted?"),
+                        message("assistant", "Plant tomato seedlings deep, up to the first leaves"),
+                        message("user", "Do tomato plants need a cage?"),
+                    ],
+                ),
+                (conversation("claude", "salad", "Salad"), vec![message("user", "Slice the tomato thinly")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        
+        // Each conversation takes one result however many of its messages match
+        let single = get_json(&app, "/api/search?q=tomato&limit=10").await;
+        let titles: Vec<&str> = single
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["conversation"]["title"].as_str().unwrap())
+            .collect();
+        assert_eq!(titles.len(), 2);
+        assert!(titles.contains(&"Gardening") && titles.contains(&"Salad"));
+        assert!(single.as_array().unwrap().iter().all(|r| r["snippets"].as_array().unwrap().len() == 1));
+        
+        let results = get_json(&app, "/api/search?q=tomato&limit=10&snippets=2").await;
+        assert_eq!(results.as_array().unwrap().len(), 2);
+        let gardening = results
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["conversation"]["title"] == "Gardening")
+            .unwrap();
+        let salad = results.as_array().unwrap().iter().find(|r| r["conversation"]["title"] == "Salad").unwrap();
+        assert_eq!(salad["snippets"].as_array().unwrap().len(), 1);
+        
+        let snippets: Vec<&str> = gardening["snippets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| s.as_str().unwrap())
+            .collect();
+        assert_eq!(snippets.len(), 2);
+        assert_ne!(snippets[0], snippets[1]);
+        assert_eq!(snippets[0], gardening["snippet"]);
+        assert!(snippets.iter().all(|s| s.contains("\u{2}tomato\u{3}")));
+    }
+    
+    #[tokio::test]
+    async fn test_search_role_filter_matches_only_that_role() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![(
+                conversation("claude", "plants", "Plants"),
+                vec![
+                    message("user", "Why are leaves green?"),
+                    message("assistant", "Chlorophyll drives photosynthesis and reflects green light"),
+                    message("user", "Does chlorophyll break down in autumn?"),
+                    message("assistant", "Yes, chlorophyll fades first, revealing other pigments"),
+                ],
+            )],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        
+        // Only an assistant message mentions photosynthesis
+        assert_eq!(get_json(&app, "/api/search?q=photosynthesis&role=user").await, serde_json::json!([]));
+        let results = get_json(&app, "/api/search?q=photosynthesis&role=assistant").await;
+        assert_eq!(results.as_array().unwrap().len(), 1);
+        assert_eq!(results[0]["matched_in"], "assistant");
+        
+        // Snippets come only from messages of the requested role
+        let results = get_json(&app, "/api/search?q=chlorophyll&role=user&snippets=3").await;
+        assert!(results.as_array().unwrap().iter().all(|r| r["matched_in"] == "user"));
+        let snippets = results[0]["snippets"].as_array().unwrap();
+        assert_eq!(snippets.len(), 1);
+        assert!(snippets[0].as_str().unwrap().contains("autumn"));
+        
+        let response = app
+            .oneshot(Request::builder().uri("/api/search?q=chlorophyll&role=robot").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    
+    #[tokio::test]
+    async fn test_openapi_document_describes_the_api() {
+        let (_dir, pool) = test_pool().await;
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        
+        let document = get_json(&app, "/api/openapi.json").await;
+        assert_eq!(document["openapi"], "3.0.3");
+        assert!(document["paths"]["/api/search"]["get"].is_object());
+        for schema in ["Conversation", "Message", "SearchResult"] {
+            assert!(document["components"]["schemas"][schema].is_object(), "{}", schema);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_search_limit_is_clamped_to_configured_max() {
+        let (_dir, pool) = test_pool().await;
+        let batch = (0..5)
+            .map(|i| {
+                (
+                    conversation("claude", &format!("soup-{}", i), &format!("Soup {}", i)),
+                    vec![message("user", "a soup recipe")],
+                )
+            })
+            .collect();
+        process_conversation_batch(&pool, batch).await.unwrap();
+        
+        let mut config = Config::default();
+        config.search.default_limit = 2;
+        config.search.max_limit = 3;
+        let app = router(Arc::new(AppState::new(pool, config)));
+        
+        let count = |results: serde_json::Value| results.as_array().unwrap().len();
+        assert_eq!(count(get_json(&app, "/api/search?q=soup").await), 2);
+        assert_eq!(count(get_json(&app, "/api/search?q=soup&limit=9999").await), 3);
+        assert_eq!(count(get_json(&app, "/api/search?q=&limit=9999").await), 3);
+        
+        for limit in ["0", "-5"] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(format!("/api/search?q=soup&limit={}", limit))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "limit={}", limit);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_stats_filter_by_provider_and_date_range() {
+        let (_dir, pool) = test_pool().await;
+        
+        // Four conversations per provider in each of 2023 and 2024, the
+        // n-th with n + 1 messages
+        let mut batch = Vec::new();
+        for year in [2023, 2024] {
+            for provider in ["claude", "chatgpt"] {
+                for n in 0..4u32 {
+                    let created_at = chrono::NaiveDate::from_ymd_opt(year, 3 * n + 1, 15)
+                        .unwrap()
+                        .and_hms_opt(12, 0, 0)
+                        .unwrap()
+                        .and_utc();
+                    let conv = Conversation {
+                        created_at,
+                        updated_at: created_at,
+                        ..conversation(provider, &format!("{}-{}-{}", provider, year, n), "Chat")
+                    };
+                    let messages = (0..=n).map(|i| message("user", &format!("message {}", i))).collect();
+                    batch.push((conv, messages));
+                }
+            }
+        }
+        process_conversation_batch(&pool, batch.clone()).await.unwrap();
+        
+        let in_claude_2024 = |conv: &Conversation| {
+            conv.provider == "claude" && conv.created_at.format("%Y").to_string() == "2024"
+        };
+        let expected_conversations = batch.iter().filter(|(conv, _)| in_claude_2024(conv)).count();
+        let expected_messages: usize = batch
+            .iter()
+            .filter(|(conv, _)| in_claude_2024(conv))
+            .map(|(_, messages)| messages.len())
+            .sum();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        
+        let stats = get_json(&app, "/api/stats?provider=claude&after=2024-01-01&before=2024-12-31").await;
+        assert_eq!(stats["total_conversations"], expected_conversations);
+        assert_eq!(stats["total_messages"], expected_messages);
+        assert_eq!(stats["providers"], serde_json::json!([{ "name": "claude", "count": 4 }]));
+        assert_eq!(stats["filters"]["provider"], "claude");
+        assert_eq!(stats["filters"]["after"], "2024-01-01T00:00:00Z");
+        
+        // Dates alone cover every provider
+        let stats = get_json(&app, "/api/stats?before=2023-12-31").await;
+        assert_eq!(stats["total_conversations"], 8);
+        assert_eq!(stats["total_messages"], 20);
+        
+        // A bare `before` date includes the whole of that day
+        let stats = get_json(&app, "/api/stats?after=2024-10-15&before=2024-10-15").await;
+        assert_eq!(stats["total_conversations"], 2);
+        assert_eq!(stats["filters"]["before"], "2024-10-15T23:59:59.999999999Z");
+        let stats = get_json(&app, "/api/stats?before=2024-10-15T12:00:00Z").await;
+        assert_eq!(stats["total_conversations"], 16);
+        let stats = get_json(&app, "/api/stats?before=2024-10-15T11:59:59Z").await;
+        assert_eq!(stats["total_conversations"], 14);
+        
+        // No filters count everything
+        let stats = get_json(&app, "/api/stats").await;
+        assert_eq!(stats["total_conversations"], 16);
+        assert_eq!(stats["filters"]["provider"], serde_json::Value::Null);
+        
+        let response = app
+            .oneshot(Request::builder().uri("/api/stats?after=last-year").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    
+    #[tokio::test]
+    async fn test_search_page_highlights_only_matched_terms() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![(
+                conversation("chatgpt", "tides", "Tides"),
+                vec![message("assistant", "Tides follow the moon [1] and the sun [2] <sup>")],
+            )],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        let response = app
+            .oneshot(Request::builder().uri("/search?q=moon").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        
+        assert!(html.contains("Tides follow the <mark>moon</mark> [1] and the sun [2] &lt;sup&gt;"));
+        assert_eq!(html.matches("<mark>").count(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_archived_conversations_hidden_unless_requested() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![
+                (conversation("claude", "keep", "Keep me"), vec![message("user", "origami cranes")]),
+                (conversation("claude", "hide", "Hide me"), vec![message("user", "origami frogs")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        let post = |uri: &'static str| {
+            app.clone()
+                .oneshot(Request::builder().method("POST").uri(uri).body(Body::empty()).unwrap())
+        };
+        let titles = |results: serde_json::Value| {
+            let mut titles: Vec<String> = results
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["conversation"]["title"].as_str().unwrap().to_string())
+                .collect();
+            titles.sort();
+            titles
+        };
+        
+        let response = post("/api/conversation/2/archive").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(post("/api/conversation/99/archive").await.unwrap().status(), StatusCode::NOT_FOUND);
+        
+        assert_eq!(titles(get_json(&app, "/api/search?q=origami").await), vec!["Keep me"]);
+        assert_eq!(titles(get_json(&app, "/api/search?q=").await), vec!["Keep me"]);
+        assert_eq!(get_json(&app, "/api/stats").await["total_conversations"], 1);
+        assert_eq!(get_json(&app, "/api/stats").await["total_messages"], 1);
+        
+        assert_eq!(
+            titles(get_json(&app, "/api/search?q=origami&include_archived=true").await),
+            vec!["Hide me", "Keep me"]
+        );
+        assert_eq!(titles(get_json(&app, "/api/search?q=&include_archived=true").await), vec!["Hide me", "Keep me"]);
+        assert_eq!(get_json(&app, "/api/stats?include_archived=true").await["total_conversations"], 2);
+        
+        // Archived conversations stay reachable directly, and say so
+        let detail = get_json(&app, "/api/conversation/2").await;
+        assert_eq!(detail["title"], "Hide me");
+        assert_eq!(detail["is_archived"], true);
+        assert_eq!(get_json(&app, "/api/conversation/1").await["is_archived"], false);
+        
+        post("/api/conversation/2/unarchive").await.unwrap();
+        assert_eq!(titles(get_json(&app, "/api/search?q=origami").await), vec!["Hide me", "Keep me"]);
+    }
+    
+    #[tokio::test]
+    async fn test_pinned_conversations_are_listed_first() {
+        let (_dir, pool) = test_pool().await;
+        let now = chrono::Utc::now();
+        process_conversation_batch(
+            &pool,
+            vec![
+                (
+                    Conversation { updated_at: now - chrono::Duration::days(30), ..conversation("claude", "old", "Old") },
+                    vec![message("user", "from last month")],
+                ),
+                (conversation("claude", "new", "New"), vec![message("user", "from today")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool.clone(), Config::default())));
+        let post = |uri: &'static str| {
+            app.clone()
+                .oneshot(Request::builder().method("POST").uri(uri).body(Body::empty()).unwrap())
+        };
+        let titles = |results: serde_json::Value| -> Vec<String> {
+            results
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["conversation"]["title"].as_str().unwrap().to_string())
+                .collect()
+        };
+        
+        assert_eq!(titles(get_json(&app, "/api/search?q=").await), vec!["New", "Old"]);
+        
+        let response = post("/api/conversation/1/pin").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(post("/api/conversation/99/pin").await.unwrap().status(), StatusCode::NOT_FOUND);
+        
+        let results = get_json(&app, "/api/search?q=").await;
+        assert_eq!(titles(results.clone()), vec!["Old", "New"]);
+        assert_eq!(results[0]["conversation"]["is_pinned"], true);
+        assert_eq!(get_json(&app, "/api/conversation/1").await["is_pinned"], true);
+        
+        // Re-importing a pinned conversation keeps it pinned
+        process_conversation_batch(&pool, vec![(conversation("claude", "old", "Old"), vec![message("user", "again")])])
+            .await
+            .unwrap();
+        assert_eq!(get_json(&app, "/api/conversation/1").await["is_pinned"], true);
+        
+        post("/api/conversation/1/unpin").await.unwrap();
+        let results = get_json(&app, "/api/search?q=").await;
+        assert_eq!(results[0]["conversation"]["is_pinned"], false);
+    }
+    
+    #[tokio::test]
+    async fn test_server_writes_wait_for_cli_write_lock() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(&pool, vec![(conversation("claude", "conv-1", "Locked"), vec![message("user", "hello")])])
+            .await
+            .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool.clone(), Config::default())));
+        let post = |uri: &'static str, body: String| {
+            tokio::spawn(app.clone().oneshot(Request::builder().method("POST").uri(uri).body(Body::from(body)).unwrap()))
+        };
+        let record = serde_json::json!({
+            "conversation": conversation("claude", "conv-2", "Streamed"),
+            "messages": [message("user", "streamed while locked")],
+        });
+        
+        // A CLI import holds the lock while both requests arrive
+        let cli = db::lock::WriteLock::for_pool(&pool, Duration::from_secs(1)).await.unwrap();
+        let pin = post("/api/conversation/1/pin", String::new());
+        let stream = post("/api/import/stream", format!("{}\n", record));
+        
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(!pin.is_finished());
+        assert!(!stream.is_finished());
+        let (pinned, conversations): (bool, i64) = sqlx::query_as(
+            "SELECT (SELECT is_pinned FROM conversations WHERE id = 1), (SELECT COUNT(*) FROM conversations)",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(!pinned);
+        assert_eq!(conversations, 1);
+        
+        drop(cli);
+        assert_eq!(pin.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert_eq!(stream.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert_eq!(get_json(&app, "/api/conversation/1").await["is_pinned"], true);
+        assert_eq!(get_json(&app, "/api/conversation/2").await["title"], "Streamed");
+    }
+    
+    #[tokio::test]
+    async fn test_server_writes_answer_503_when_the_lock_stays_held() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(&pool, vec![(conversation("claude", "conv-1", "Locked"), vec![message("user", "hello")])])
+            .await
+            .unwrap();
+        
+        let mut config = Config::default();
+        config.server.write_wait_secs = 0;
+        let app = router(Arc::new(AppState::new(pool.clone(), config)));
+        let pin = || {
+            app.clone()
+                .oneshot(Request::builder().method("POST").uri("/api/conversation/1/pin").body(Body::empty()).unwrap())
+        };
+        
+        let cli = db::lock::WriteLock::for_pool(&pool, Duration::from_secs(1)).await.unwrap();
+        let response = pin().await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "5");
+        
+        drop(cli);
+        assert_eq!(pin().await.unwrap().status(), StatusCode::OK);
+    }
+    
+    #[tokio::test]
+    async fn test_stream_import_does_not_hold_the_lock_while_reading() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(&pool, vec![(conversation("claude", "conv-1", "Pinned"), vec![message("user", "hello")])])
+            .await
+            .unwrap();
+        
+        let mut config = Config::default();
+        config.server.write_wait_secs = 0;
+        let app = router(Arc::new(AppState::new(pool, config)));
+        
+        // An upload that has sent one line and then stalls
+        let (mut body, chunks) = futures::channel::mpsc::channel::<Result<bytes::Bytes, std::io::Error>>(1);
+        let record = serde_json::json!({
+            "conversation": conversation("claude", "conv-2", "Streamed"),
+            "messages": [message("user", "slow upload")],
+        });
+        body.try_send(Ok(format!("{}\n", record).into())).unwrap();
+        let upload = tokio::spawn(app.clone().oneshot(
+            Request::builder().method("POST").uri("/api/import/stream").body(Body::from_stream(chunks)).unwrap(),
+        ));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        
+        let pin = app
+            .clone()
+            .oneshot(Request::builder().method("POST").uri("/api/conversation/1/pin").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(pin.status(), StatusCode::OK);
+        
+        drop(body);
+        assert_eq!(upload.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert_eq!(get_json(&app, "/api/conversation/2").await["title"], "Streamed");
+    }
+    
+    #[tokio::test]
+    async fn test_messages_endpoint_paginates_long_conversations() {
+        let (_dir, pool) = test_pool().await;
+        let start = chrono::Utc::now();
+        let messages = (0..200)
+            .map(|i| Message {
+                created_at: start + chrono::Duration::seconds(i),
+                ..message(if i % 2 == 0 { "user" } else { "assistant" }, &format!("message {}", i))
+            })
+            .collect();
+        process_conversation_batch(&pool, vec![(conversation("claude", "long", "Long"), messages)])
+            .await
+            .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        let contents = |page: &serde_json::Value| {
+            page["messages"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|m| m["content"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        
+        let all = get_json(&app, "/api/conversation/1/messages").await;
+        assert_eq!(all["total"], 200);
+        assert_eq!(contents(&all).len(), 200);
+        assert_eq!(contents(&all)[0], "message 0");
+        
+        let page = get_json(&app, "/api/conversation/1/messages?limit=50&offset=100").await;
+        assert_eq!(page["total"], 200);
+        let page = contents(&page);
+        assert_eq!(page.len(), 50);
+        assert_eq!(page.first().unwrap(), "message 100");
+        assert_eq!(page.last().unwrap(), "message 149");
+        
+        let tail = get_json(&app, "/api/conversation/1/messages?limit=10&offset=195").await;
+        assert_eq!(contents(&tail), (195..200).map(|i| format!("message {}", i)).collect::<Vec<_>>());
+        
+        let newest = get_json(&app, "/api/conversation/1/messages?limit=3&order=desc").await;
+        assert_eq!(contents(&newest), vec!["message 199", "message 198", "message 197"]);
+    }
+    
+    #[tokio::test]
+    async fn test_chatgpt_citations_appear_in_message_json() {
+        let (_dir, pool) = test_pool().await;
+        let export_dir = tempfile::tempdir().unwrap();
+        let export = serde_json::json!({ "conversations": [{
+            "id": "research",
+            "title": "Research",
+            "create_time": 1700000000.0,
+            "update_time": 1700000100.0,
+            "current_node": "a1",
+            "mapping": {
+                "u1": {
+                    "id": "u1",
+                    "parent": null,
+                    "children": ["a1"],
+                    "message": {
+                        "id": "u1",
+                        "author": { "role": "user" },
+                        "content": { "content_type": "text", "parts": ["When was Rust 1.0 released?"] }
+                    }
+                },
+                "a1": {
+                    "id": "a1",
+                    "parent": "u1",
+                    "children": [],
+                    "message": {
+                        "id": "a1",
+                        "author": { "role": "assistant" },
+                        "content": { "content_type": "text", "parts": ["May 2015."] },
+                        "recipient": "all",
+                        "metadata": {
+                            "model_slug": "gpt-4o",
+                            "citations": [
+                                { "metadata": { "title": "Announcing Rust 1.0", "url": "https://blog.rust-lang.org/2015/05/15/Rust-1.0.html" } },
+                                { "metadata": { "title": "Rust releases", "url": "https://github.com/rust-lang/rust/blob/master/RELEASES.md" } }
+                            ]
+                        }
+                    }
+                }
+            }
+        }] });
+        let path = export_dir.path().join("conversations.json");
+        std::fs::write(&path, export.to_string()).unwrap();
+        crate::import::import_conversations(&pool, "chatgpt", &path, &Default::default())
+            .await
+            .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        let detail = get_json(&app, "/api/conversation/1?include=messages").await;
+        let answer = &detail["messages"][1];
+        assert_eq!(answer["role"], "assistant");
+        assert_eq!(
+            answer["tool_calls"]["citations"],
+            serde_json::json!([
+                { "title": "Announcing Rust 1.0", "url": "https://blog.rust-lang.org/2015/05/15/Rust-1.0.html" },
+                { "title": "Rust releases", "url": "https://github.com/rust-lang/rust/blob/master/RELEASES.md" }
+            ])
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_serve_returns_ok_after_shutdown_signal() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        
+        let (_dir, pool) = test_pool().await;
+        let state = Arc::new(AppState::new(pool.clone(), Config::default()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(serve(listener, state, async {
+            let _ = stopped.await;
+        }));
+        
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        
+        stop.send(()).unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("serve did not return after shutdown")
+            .unwrap();
+        assert!(result.is_ok(), "{:?}", result);
+        assert!(pool.is_closed());
+    }
+    
+    #[tokio::test]
+    async fn test_live_search_delivers_only_latest_query() {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message as Frame;
+        
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![
+                (conversation("claude", "ml-1", "Intro to ML"), vec![message("user", "what is machine learning")]),
+                (conversation("claude", "ml-2", "Lathes"), vec![message("user", "machine shop safety")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+                .await
+                .unwrap()
+        });
+        
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws/search", addr))
+            .await
+            .unwrap();
+        for query in ["ma", "mach", "machine"] {
+            socket.send(Frame::Text(query.to_string())).await.unwrap();
+        }
+        
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+            .await
+            .expect("results arrive")
+            .unwrap()
+            .unwrap();
+        let batch: serde_json::Value = serde_json::from_str(frame.to_text().unwrap()).unwrap();
+        assert_eq!(batch["query"], "machine");
+        assert_eq!(batch["results"].as_array().unwrap().len(), 2);
+        
+        // Earlier keystrokes were coalesced away
+        let extra = tokio::time::timeout(std::time::Duration::from_millis(300), socket.next()).await;
+        assert!(extra.is_err(), "unexpected frame: {:?}", extra);
+    }
+    
+    #[tokio::test]
+    async fn test_import_job_runs_in_background() {
+        let (_dir, pool) = test_pool().await;
+        let export_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(export_dir.path().join("exports")).unwrap();
+        std::fs::copy("test-data/claude-mixed-dates.json", export_dir.path().join("exports/claude.json")).unwrap();
+        std::fs::write(export_dir.path().join("private.json"), "{}").unwrap();
+        
+        let mut config = Config::default();
+        config.server.import_root = Some(export_dir.path().join("exports").to_string_lossy().into_owned());
+        config.server.import_hosts = vec!["exports.example.com".to_string()];
+        let app = router(Arc::new(AppState::new(pool, config)));
+        let start = |path: serde_json::Value| {
+            let request = Request::builder()
+                .method("POST")
+                .uri("/api/import")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::json!({ "provider": "claude", "path": path }).to_string()))
+                .unwrap();
+            app.clone().oneshot(request)
+        };
+        
+        // Outside the import root, or from hosts nobody allowed
+        for path in [
+            serde_json::json!(export_dir.path().join("private.json")),
+            serde_json::json!("../private.json"),
+            serde_json::json!("http://exports.example.com/claude.json"),
+            serde_json::json!("https://169.254.169.254/latest/meta-data"),
+        ] {
+            let response = start(path.clone()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", path);
+        }
+        
+        let response = start(serde_json::json!("claude.json")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let job_id = serde_json::from_slice::<serde_json::Value>(&body).unwrap()["job_id"].as_u64().unwrap();
+        
+        let mut job = get_json(&app, &format!("/api/import/jobs/{}", job_id)).await;
+        for _ in 0..100 {
+            if job["status"] != "running" {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            job = get_json(&app, &format!("/api/import/jobs/{}", job_id)).await;
+        }
+        
+        assert_eq!(job["status"], "completed");
+        assert_eq!(job["processed_files"], 1);
+        assert_eq!(job["total_conversations"], 4);
+        assert_eq!(job["stats"]["conversations"], 4);
+        
+        let response = app
+            .oneshot(Request::builder().uri("/api/import/jobs/999").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    
+    #[tokio::test]
+    async fn test_import_history_tells_new_from_updated() {
+        let (_dir, pool) = test_pool().await;
+        let export_dir = tempfile::tempdir().unwrap();
+        std::fs::copy("test-data/claude-mixed-dates.json", export_dir.path().join("claude.json")).unwrap();
+        
+        // Forced, since the manifest would skip the unchanged file
+        let options = crate::import::ImportOptions { force: true, ..Default::default() };
+        for _ in 0..2 {
+            crate::import::import_conversations(&pool, "claude", export_dir.path(), &options)
+                .await
+                .unwrap();
+        }
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        let history = get_json(&app, "/api/import/history").await;
+        let events = history.as_array().unwrap();
+        assert_eq!(events.len(), 2);
+        
+        let (second, first) = (&events[0]["stats"], &events[1]["stats"]);
+        assert_eq!(events[0]["status"], "completed");
+        assert_eq!(first["new"], 4);
+        assert_eq!(first["updated"], 0);
+        assert_eq!(second["new"], 0);
+        assert_eq!(second["updated"], 4);
+        assert_eq!(second["skipped_duplicates"], 0);
+        assert_eq!(second["parser_version"], format!("native-{}", env!("CARGO_PKG_VERSION")));
+        
+        let latest = get_json(&app, "/api/import/history?limit=1").await;
+        assert_eq!(latest.as_array().unwrap().len(), 1);
+        assert_eq!(latest[0]["id"], events[0]["id"]);
+    }
+    
+    #[tokio::test]
+    async fn test_facets_list_seeded_models() {
+        let (_dir, pool) = test_pool().await;
+        let with_model = |external_id: &str, model: Option<&str>| Conversation {
+            model: model.map(str::to_string),
+            ..conversation(if external_id == "c" { "claude" } else { "chatgpt" }, external_id, "Chat")
+        };
+        process_conversation_batch(
+            &pool,
+            vec![
+                (with_model("a", Some("gpt-4o")), vec![message("user", "q"), message("assistant", "a")]),
+                (with_model("b", Some("gpt-4o")), vec![message("user", "q")]),
+                (with_model("c", Some("claude-3-opus")), vec![message("user", "q")]),
+                (with_model("d", None), vec![message("user", "q")]),
+            ],
+        )
+        .await
+        .unwrap();
+        let app = router(Arc::new(AppState::new(pool.clone(), Config::default())));
+        
+        let facets = get_json(&app, "/api/facets").await;
+        assert_eq!(
+            facets["models"],
+            serde_json::json!([
+                { "value": "gpt-4o", "count": 2 },
+                { "value": "claude-3-opus", "count": 1 },
+            ])
+        );
+        assert_eq!(
+            facets["providers"],
+            serde_json::json!([{ "value": "chatgpt", "count": 3 }, { "value": "claude", "count": 1 }])
+        );
+        assert_eq!(
+            facets["roles"],
+            serde_json::json!([{ "value": "user", "count": 4 }, { "value": "assistant", "count": 1 }])
+        );
+        assert!(facets["first_created_at"].is_string());
+        
+        // Answered from the cache until it expires
+        process_conversation_batch(&pool, vec![(with_model("e", Some("o1")), vec![message("user", "q")])])
+            .await
+            .unwrap();
+        assert_eq!(get_json(&app, "/api/facets").await, facets);
+    }
+    
+    #[tokio::test]
+    async fn test_model_stats_group_by_family() {
+        let (_dir, pool) = test_pool().await;
+        let reply = |model: &str| Message {
+            model: Some(model.to_string()),
+            ..message("assistant", "answer")
+        };
+        process_conversation_batch(
+            &pool,
+            vec![
+                (conversation("chatgpt", "a", "A"), vec![message("user", "q"), reply("gpt-4-0613"), reply("gpt-4-0314")]),
+                (conversation("chatgpt", "b", "B"), vec![reply("gpt-4")]),
+                (conversation("claude", "c", "C"), vec![reply("claude-3-5-sonnet-20240620")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        
+        let exact = get_json(&app, "/api/models?group=exact").await;
+        let slugs: Vec<&str> = exact.as_array().unwrap().iter().map(|m| m["model"].as_str().unwrap()).collect();
+        assert_eq!(slugs, vec!["claude-3-5-sonnet-20240620", "gpt-4", "gpt-4-0314", "gpt-4-0613"]);
+        
+        let family = get_json(&app, "/api/models?group=family").await;
+        assert_eq!(
+            family,
+            serde_json::json!([
+                { "model": "gpt-4", "messages": 3, "conversations": 2 },
+                { "model": "claude-3-5-sonnet", "messages": 1, "conversations": 1 },
+            ])
+        );
+        
+        let stats = get_json(&app, "/api/stats?group=family").await;
+        assert_eq!(stats["models"], family);
+    }
+    
+    #[tokio::test]
+    async fn test_bulk_tag_search_results() {
+        let (_dir, pool) = test_pool().await;
+        process_conversation_batch(
+            &pool,
+            vec![
+                (conversation("claude", "a", "Sourdough"), vec![message("user", "my sourdough starter is flat")]),
+                (conversation("chatgpt", "b", "Bread"), vec![message("user", "sourdough hydration levels")]),
+                (conversation("claude", "c", "Taxes"), vec![message("user", "filing deadlines")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let app = router(Arc::new(AppState::new(pool.clone(), Config::default())));
+        let tag = |body: serde_json::Value| {
+            let app = app.clone();
+            async move {
+                let request = Request::builder()
+                    .method("POST")
+                    .uri("/api/search/tag")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap();
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+            }
+        };
+        
+        let (status, body) = tag(serde_json::json!({ "query": "sourdough", "tag": "baking" })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "matched": 2, "tagged": 2 }));
+        
+        let (_, body) = tag(serde_json::json!({
+            "query": "sourdough",
+            "filters": { "provider": "claude" },
+            "tag": "claude-baking",
+        }))
+        .await;
+        assert_eq!(body["matched"], 1);
+        
+        assert_eq!(db::tags::for_conversation(&pool, 1).await.unwrap(), vec!["baking", "claude-baking"]);
+        assert_eq!(db::tags::for_conversation(&pool, 2).await.unwrap(), vec!["baking"]);
+        assert!(db::tags::for_conversation(&pool, 3).await.unwrap().is_empty());
+        
+        // Re-tagging is idempotent
+        let (_, body) = tag(serde_json::json!({ "query": "sourdough", "tag": "baking" })).await;
+        assert_eq!(body, serde_json::json!({ "matched": 2, "tagged": 0 }));
+        
+        let (status, _) = tag(serde_json::json!({ "query": "sourdough", "tag": "  " })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+    
+    #[tokio::test]
+    async fn test_conversation_attachments_are_listed_and_downloadable() {
+        let (_dir, pool) = test_pool().await;
+        let root = tempfile::tempdir().unwrap();
+        let media_dir = root.path().join("media");
+        std::fs::create_dir_all(media_dir.join("claude_attachments/files")).unwrap();
+        std::fs::write(media_dir.join("claude_attachments/files/diagram.png"), b"\x89PNG").unwrap();
+        std::fs::write(media_dir.join("llm_archive.db"), b"SQLite format 3").unwrap();
+        std::fs::write(root.path().join("secret.txt"), b"do not serve").unwrap();
+        
+        let mut with_files = message("user", "see attached");
+        with_files.attachments = Some(serde_json::json!([
+            { "file_name": "diagram.png", "path": "claude_attachments/files/diagram.png", "file_size": 4 },
+            { "file_name": "secret.txt", "path": "../secret.txt" },
+            { "file_name": "report.pdf", "path": "claude_attachments/files/report.pdf", "extracted_content": "Quarterly numbers" },
+            { "file_name": "archive.db", "path": "llm_archive.db" },
+        ]));
+        process_conversation_batch(
+            &pool,
+            vec![
+                (conversation("claude", "files", "With files"), vec![with_files]),
+                (conversation("claude", "plain", "Plain"), vec![message("user", "no files")]),
+            ],
+        )
+        .await
+        .unwrap();
+        
+        let config = Config {
+            data_dir: Some(media_dir.to_string_lossy().into_owned()),
+            ..Config::default()
+        };
+        let app = router(Arc::new(AppState::new(pool, config)));
+        
+        let listed = get_json(&app, "/api/conversation/1/attachments").await;
+        let listed = listed.as_array().unwrap();
+        assert_eq!(listed.len(), 4);
+        assert_eq!(listed[0]["filename"], "diagram.png");
+        assert_eq!(listed[0]["mime_type"], "image/png");
+        assert_eq!(listed[0]["size"], 4);
+        assert_eq!(get_json(&app, "/api/conversation/2/attachments").await, serde_json::json!([]));
+        
+        let download = |uri: String| {
+            let app = app.clone();
+            async move {
+                app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+            }
+        };
+        
+        let response = download(listed[0]["download_url"].as_str().unwrap().to_string()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"diagram.png\""
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"\x89PNG");
+        
+        // A stored path that climbs out of the data directory is never read
+        let response = download(listed[1]["download_url"].as_str().unwrap().to_string()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        
+        // Without its file a PDF falls back to its text, served as text
+        let response = download(listed[2]["download_url"].as_str().unwrap().to_string()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain; charset=utf-8");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"Quarterly numbers");
+        
+        // An imported path naming a data directory file outside the media
+        // directories is refused even though the file exists
+        let response = download(listed[3]["download_url"].as_str().unwrap().to_string()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        
+        let response = download("/api/conversation/99/attachments".to_string()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    
+    #[tokio::test]
+    async fn test_conversation_summary_aggregates_messages() {
+        use chrono::TimeZone;
+        
+        let (_dir, pool) = test_pool().await;
+        let at = |hour: u32| chrono::Utc.with_ymd_and_hms(2024, 3, 1, hour, 0, 0).unwrap();
+        
+        let mut system = message("system", "Be brief.");
+        system.created_at = at(9);
+        let mut question = message("user", "What is in this picture?");
+        question.created_at = at(10);
+        question.tokens = Some(7);
+        question.attachments = Some(serde_json::json!([{ "file_name": "cat.png" }, { "file_name": "dog.png" }]));
+        let mut answer = message("assistant", "A cat and a dog.");
+        answer.created_at = at(11);
+        answer.model = Some("gpt-4o".to_string());
+        answer.tokens = Some(6);
+        let mut follow_up = message("assistant", "Both are asleep.");
+        follow_up.created_at = at(12);
+        follow_up.model = Some("gpt-4".to_string());
+        follow_up.tokens = Some(5);
+        
+        process_conversation_batch(
+            &pool,
+            vec![(conversation("chatgpt", "summary", "Summary"), vec![system, question, answer, follow_up])],
+        )
+        .await
+        .unwrap();
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        
+        let summary = get_json(&app, "/api/conversation/1/summary").await;
+        assert_eq!(summary["message_count"], 4);
+        assert_eq!(summary["role_counts"], serde_json::json!({ "assistant": 2, "system": 1, "user": 1 }));
+        assert_eq!(summary["total_chars"], 9 + 24 + 16 + 16);
+        assert_eq!(summary["estimated_tokens"], 7 + 6 + 5 + crate::import::tokens::count_tokens("Be brief."));
+        assert_eq!(summary["models"], serde_json::json!(["gpt-4", "gpt-4o"]));
+        assert_eq!(summary["attachment_count"], 2);
+        
+        let timestamp = |field: &str| summary[field].as_str().unwrap().parse::<chrono::DateTime<chrono::Utc>>().unwrap();
+        assert_eq!(timestamp("first_message_at"), at(9));
+        assert_eq!(timestamp("last_message_at"), at(12));
+        
+        let response = app
+            .oneshot(Request::builder().uri("/api/conversation/99/summary").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    
+    #[tokio::test]
+    async fn test_message_revisions_endpoint() {
+        let (_dir, pool) = test_pool().await;
+        let mut edited = message("user", "final wording");
+        edited.revisions = vec![crate::models::MessageRevision {
+            content: "first wording".to_string(),
+            created_at: edited.created_at,
+        }];
+        process_conversation_batch(
+            &pool,
+            vec![(conversation("claude", "edited", "Edited"), vec![edited, message("assistant", "noted")])],
+        )
+        .await
+        .unwrap();
+        let app = router(Arc::new(AppState::new(pool, Config::default())));
+        
+        let revisions = get_json(&app, "/api/message/1/revisions").await;
+        assert_eq!(revisions[0]["revision"], 1);
+        assert_eq!(revisions[0]["content"], "first wording");
+        assert_eq!(get_json(&app, "/api/message/2/revisions").await, serde_json::json!([]));
+        
+        let response = app
+            .oneshot(Request::builder().uri("/api/message/99/revisions").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    
+    #[tokio::test]
+    async fn test_selecting_a_branch_replaces_the_messages() {
+        use crate::import::ImportOptions;
+        use crate::models::ImportStats;
+        
+        let (dir, pool) = test_pool().await;
+        let node = |id: &str, parent: Option<&str>, children: &[&str], role: &str, text: &str| {
+            serde_json::json!({
+                "id": id,
+                "parent": parent,
+                "children": children,
+                "message": {
+                    "id": id,
+                    "author": { "role": role },
+                    "content": { "content_type": "text", "parts": [text] },
+                },
+            })
+        };
+        let export = serde_json::json!([{
+            "id": "conv-branches",
+            "title": "Branches",
+            "current_node": "a1",
+            "mapping": {
+                "root": { "id": "root", "parent": null, "children": ["u1"] },
+                "u1": node("u1", Some("root"), &["a1", "a2"], "user", "tell me a joke"),
+                "a1": node("a1", Some("u1"), &[], "assistant", "first answer"),
+                "a2": node("a2", Some("u1"), &[], "assistant", "regenerated punchline"),
+            },
+        }]);
+        let path = dir.path().join("conversations.json");
+        std::fs::write(&path, export.to_string()).unwrap();
+        let (pool_ref, path_ref) = (&pool, &path);
+        let import = move || async move {
+            crate::import::parsers::chatgpt::import(pool_ref, path_ref, &mut ImportStats::default(), &ImportOptions::default())
+                .await
+                .unwrap()
+        };
+        import().await;
+        let app = router(Arc::new(AppState::new(pool.clone(), Config::default())));
+        
+        let branches = get_json(&app, "/api/conversation/1/branches").await;
+        assert_eq!(branches["branch_count"], 2);
+        assert_eq!(branches["selected_branch"], 0);
+        assert_eq!(branches["branches"][0]["first_message"], "first answer");
+        assert_eq!(branches["branches"][1]["first_message"], "regenerated punchline");
+        assert_eq!(get_json(&app, "/api/search?q=punchline").await, serde_json::json!([]));
+        
+        let select = |body: serde_json::Value| {
+            let request = Request::builder()
+                .method("POST")
+                .uri("/api/conversation/1/branch")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            app.clone().oneshot(request)
+        };
+        let response = select(serde_json::json!({ "index": 1 })).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        
+        let messages = get_json(&app, "/api/conversation/1/messages").await;
+        let contents: Vec<_> = messages["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["content"].as_str().unwrap())
+            .collect();
+        assert_eq!(contents, vec!["tell me a joke", "regenerated punchline"]);
+        assert_eq!(get_json(&app, "/api/conversation/1/branches").await["selected_branch"], 1);
+        assert_eq!(get_json(&app, "/api/search?q=punchline").await.as_array().unwrap().len(), 1);
+        
+        let response = select(serde_json::json!({ "index": 2 })).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        
+        // Importing the same export again keeps the chosen branch
+        import().await;
+        let messages = get_json(&app, "/api/conversation/1/messages").await;
+        assert_eq!(messages["messages"][1]["content"], "regenerated punchline");
+        assert_eq!(get_json(&app, "/api/conversation/1/branches").await["selected_branch"], 1);
+        assert_eq!(get_json(&app, "/api/search?q=punchline").await.as_array().unwrap().len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_import_bodies_over_the_limit_are_rejected() {
+        let (_dir, pool) = test_pool().await;
+        let mut config = Config::default();
+        config.server.max_import_bytes = 64;
+        let app = router(Arc::new(AppState::new(pool, config)));
+        
+        let post = |uri: &str, body: String| {
+            let app = app.clone();
+            let request = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&body).ok())
+            }
+        };
+        
+        // Just over the limit, as a JSON request and as a stream without a length
+        let request = serde_json::json!({ "path": "/does/not/exist" }).to_string();
+        let oversized = format!("{:<65}", request);
+        for uri in ["/api/import", "/api/import/stream"] {
+            let (status, error) = post(uri, oversized.clone()).await;
+            assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE, "{}", uri);
+            assert!(error.unwrap()["error"].as_str().unwrap().contains("too large"));
+        }
+        
+        let (status, _) = post("/api/import", request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        // Other routes keep the default limit, whatever the import limit is
+        let tag = serde_json::json!({ "query": "x".repeat(100), "tag": "t" }).to_string();
+        let (status, _) = post("/api/search/tag", tag).await;
+        assert_ne!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        
+        let huge = format!("{{\"ids\": [1], \"format\": \"{}\"}}", "x".repeat(3 * 1024 * 1024));
+        let (status, error) = post("/api/export/bulk", huge).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(error.is_some());
+    }
+}
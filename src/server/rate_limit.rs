@@ -0,0 +1,130 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::AppState;
+
+/// Paths that are never rate limited
+const EXEMPT_PATHS: &[&str] = &["/health", "/metrics"];
+
+/// Per-client token bucket rate limiter
+#[derive(Debug)]
+pub struct RateLimiter {
+    requests_per_minute: u32,
+    buckets: DashMap<IpAddr, Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// A limit of 0 disables rate limiting
+    pub fn new(requests_per_minute: u32) -> Self {
+        Self {
+            requests_per_minute,
+            buckets: DashMap::new(),
+        }
+    }
+    
+    /// Take a token for `ip`, or return how long until one is available
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        if self.requests_per_minute == 0 {
+            return Ok(());
+        }
+        
+        let capacity = self.requests_per_minute as f64;
+        let per_second = capacity / 60.0;
+        let now = Instant::now();
+        
+        let mut bucket = self.buckets.entry(ip).or_insert_with(|| Bucket {
+            tokens: capacity,
+            last_refill: now,
+        });
+        
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.last_refill = now;
+        
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+    
+    /// Drop buckets untouched for `idle`; they would have refilled anyway
+    pub fn prune(&self, idle: Duration) {
+        let now = Instant::now();
+        self.buckets.retain(|_, bucket| now.duration_since(bucket.last_refill) < idle);
+    }
+}
+
+/// Reject clients that exceed `server.requests_per_minute` with 429
+pub async fn limit(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    if EXEMPT_PATHS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+    
+    // Requests without connection info (e.g. in-process tests) share one bucket
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    
+    match state.rate_limiter.check(ip) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            let seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, seconds.to_string())],
+                "Too many requests",
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_bucket_empties_then_reports_retry_after() {
+        let limiter = RateLimiter::new(2);
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        
+        assert!(limiter.check(ip).is_ok());
+        assert!(limiter.check(ip).is_ok());
+        
+        let retry_after = limiter.check(ip).unwrap_err();
+        assert!(retry_after > Duration::from_secs(25) && retry_after <= Duration::from_secs(30));
+        
+        // Other clients have their own bucket
+        assert!(limiter.check(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2))).is_ok());
+    }
+    
+    #[test]
+    fn test_prune_drops_idle_buckets() {
+        let limiter = RateLimiter::new(60);
+        limiter.check(IpAddr::V4(Ipv4Addr::LOCALHOST)).unwrap();
+        
+        limiter.prune(Duration::from_secs(60));
+        assert_eq!(limiter.buckets.len(), 1);
+        
+        limiter.prune(Duration::ZERO);
+        assert!(limiter.buckets.is_empty());
+    }
+}
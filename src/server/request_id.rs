@@ -0,0 +1,59 @@
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info_span, Instrument};
+
+/// Header carrying the request id, on the way in and on the way out
+pub static REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied id that is reused rather than replaced
+const MAX_CLIENT_ID_LEN: usize = 128;
+
+/// Distinguishes ids from different server runs
+static RUN_PREFIX: Lazy<u32> = Lazy::new(|| {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u32)
+        .unwrap_or_default()
+});
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Give every request an id, run it inside a span carrying that id, and
+/// echo the id in the `x-request-id` response header
+///
+/// An id the client already sent is kept so logs line up across services.
+pub async fn assign(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(&REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= MAX_CLIENT_ID_LEN)
+        .map(str::to_string)
+        .unwrap_or_else(generate);
+    
+    let span = info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    
+    let mut response = next.run(request).instrument(span).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID.clone(), value);
+    }
+    
+    response
+}
+
+/// A fresh id: the run prefix and a sequence number, in hex
+fn generate() -> String {
+    format!("{:08x}-{:08x}", *RUN_PREFIX, NEXT_ID.fetch_add(1, Ordering::Relaxed))
+}
@@ -0,0 +1,102 @@
+use axum::{
+    extract::{Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::{channel::mpsc, SinkExt};
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tracing::{debug, instrument, warn};
+
+use super::AppState;
+use crate::errors::{AppError, AppResult};
+use crate::search;
+
+/// Results per `results` event, each fetched with its own query
+const BATCH_SIZE: usize = 20;
+
+/// Events buffered ahead of a slow client before the query task waits
+const CHANNEL_CAPACITY: usize = 4;
+
+/// Streaming search parameters
+#[derive(Deserialize)]
+pub struct StreamParams {
+    q: Option<String>,
+    /// Results wanted, up to `search.max_limit`
+    limit: Option<i64>,
+}
+
+type Events = mpsc::Sender<Result<Event, Infallible>>;
+
+/// Search results as Server-Sent Events
+///
+/// Matches arrive as `results` events, each a JSON array of up to
+/// `BATCH_SIZE` results, one per conversation, in rank order. The stream
+/// ends with a `done` event carrying `{"total": n}`, or an `error` event if
+/// a query fails part way.
+///
+/// Each batch is a separate query, so a slow reader holds no database
+/// connection while the next batch waits to be sent.
+#[instrument(skip_all, fields(query = params.q.as_deref()))]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<StreamParams>,
+) -> AppResult<Sse<mpsc::Receiver<Result<Event, Infallible>>>> {
+    let query = params.q.filter(|q| !q.trim().is_empty())
+        .ok_or_else(|| AppError::BadRequest("Missing query parameter".into()))?;
+    let limit = super::search_limit(&state, params.limit)?;
+    
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    tokio::spawn(send_results(state, query, limit, tx));
+    
+    Ok(Sse::new(rx).keep_alive(KeepAlive::default()))
+}
+
+/// Query task: forward matches a batch at a time, then the closing `done`
+/// or `error` event
+async fn send_results(state: Arc<AppState>, query: String, limit: usize, mut tx: Events) {
+    let closing = match stream_batches(&state, &query, limit, &mut tx).await {
+        Ok(Some(total)) => Event::default().event("done").data(serde_json::json!({ "total": total }).to_string()),
+        // The client went away
+        Ok(None) => return,
+        Err(e) => {
+            warn!("Streaming search for '{}' failed: {:#}", query, e);
+            Event::default().event("error").data(serde_json::json!({ "error": e.to_string() }).to_string())
+        }
+    };
+    
+    let _ = tx.send(Ok(closing)).await;
+}
+
+/// Send `results` events until the matches run out; the total sent, or
+/// `None` once the client stops listening
+async fn stream_batches(
+    state: &AppState,
+    query: &str,
+    limit: usize,
+    tx: &mut Events,
+) -> anyhow::Result<Option<usize>> {
+    let markers = &state.config.search.snippet_markers;
+    let snippet_length = state.config.search.snippet_length;
+    let mut total = 0;
+    
+    while total < limit {
+        let size = BATCH_SIZE.min(limit - total);
+        let batch = search::search_page(&state.pool, query, total, size, snippet_length, markers).await?;
+        if batch.is_empty() {
+            break;
+        }
+        total += batch.len();
+        
+        let event = Event::default().event("results").data(serde_json::to_string(&batch)?);
+        if tx.send(Ok(event)).await.is_err() {
+            debug!("Search stream for '{}' closed after {} results", query, total);
+            return Ok(None);
+        }
+        if batch.len() < size {
+            break;
+        }
+    }
+    
+    Ok(Some(total))
+}
@@ -0,0 +1,179 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderMap},
+    Json,
+};
+use futures::{future, Stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::sync::Arc;
+use tokio_util::codec::{FramedRead, LinesCodec, LinesCodecError};
+use tokio_util::io::StreamReader;
+use tracing::{info, instrument, warn};
+
+use super::{body_limit, AppState};
+use crate::errors::{AppError, AppResult};
+use crate::import::{self, ImportOptions};
+use crate::models::{Conversation, ImportStats, Message};
+
+/// Conversations parsed before they are stored as one batch
+const BATCH_SIZE: usize = 100;
+
+/// Longest line accepted; longer ones are skipped and counted as errors
+const MAX_LINE_BYTES: usize = 32 * 1024 * 1024;
+
+/// Error messages included in the response; the rest are only counted
+const MAX_REPORTED_ERRORS: usize = 20;
+
+/// Marks the read error raised once the body passes `server.max_import_bytes`
+#[derive(Debug)]
+struct BodyTooLarge;
+
+impl std::fmt::Display for BodyTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("request body too large")
+    }
+}
+
+impl std::error::Error for BodyTooLarge {}
+
+/// One NDJSON line, in the shape `export --format jsonl` writes
+#[derive(Deserialize)]
+struct Record {
+    conversation: Conversation,
+    #[serde(default)]
+    messages: Vec<Message>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct StreamImportResponse {
+    /// Conversations stored
+    pub imported: usize,
+    pub messages: usize,
+    /// Lines that could not be parsed
+    pub errors: usize,
+    /// The first few parse errors, with their line numbers
+    pub error_messages: Vec<String>,
+}
+
+/// Import an NDJSON request body with one conversation per line
+///
+/// Lines are parsed as they arrive and stored in batches, so only one
+/// batch and one line are held in memory however large the upload is.
+/// Unparseable lines are skipped and counted; ids in the records are
+/// ignored and conversations are matched on `(provider, external_id)`
+/// like any other import.
+///
+/// Bodies over `server.max_import_bytes` are rejected with a 413, up front
+/// when `Content-Length` says so and otherwise once that many bytes have
+/// arrived; batches stored before then stay imported.
+#[instrument(skip_all)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Body,
+) -> AppResult<Json<StreamImportResponse>> {
+    let options = ImportOptions::from_config(&state.config.import)?;
+    
+    // A raw body stream isn't covered by `DefaultBodyLimit`, so it is counted here
+    let limit = state.config.server.max_import_bytes;
+    let declared = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if declared.is_some_and(|length| length > limit) {
+        return Err(body_limit::too_large(limit));
+    }
+    
+    let mut received = 0;
+    let data = body
+        .into_data_stream()
+        .map_err(io::Error::other)
+        .and_then(move |chunk| {
+            received += chunk.len();
+            future::ready(if received > limit { Err(io::Error::other(BodyTooLarge)) } else { Ok(chunk) })
+        });
+    
+    let reader = StreamReader::new(data);
+    let lines = FramedRead::new(reader, LinesCodec::new_with_max_length(MAX_LINE_BYTES));
+    
+    let response = import_lines(&state, lines, &options)
+        .await
+        .map_err(|e| match e {
+            AppError::PayloadTooLarge(_) => body_limit::too_large(limit),
+            other => other,
+        })?;
+    info!(
+        "Streamed import stored {} conversations, skipped {} bad lines",
+        response.imported, response.errors
+    );
+    
+    Ok(Json(response))
+}
+
+async fn import_lines(
+    state: &AppState,
+    lines: impl Stream<Item = Result<String, LinesCodecError>>,
+    options: &ImportOptions,
+) -> AppResult<StreamImportResponse> {
+    let mut response = StreamImportResponse::default();
+    let mut stats = ImportStats::default();
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    
+    let mut lines = std::pin::pin!(lines);
+    let mut line_number = 0;
+    while let Some(line) = lines.next().await {
+        line_number += 1;
+        
+        let parsed = match line {
+            Ok(line) if line.trim().is_empty() => continue,
+            Ok(line) => serde_json::from_str::<Record>(&line).map_err(|e| e.to_string()),
+            Err(LinesCodecError::MaxLineLengthExceeded) => {
+                Err(format!("longer than {} bytes", MAX_LINE_BYTES))
+            }
+            Err(LinesCodecError::Io(e)) if e.get_ref().is_some_and(|inner| inner.is::<BodyTooLarge>()) => {
+                return Err(AppError::PayloadTooLarge(e.to_string()));
+            }
+            Err(LinesCodecError::Io(e)) => {
+                return Err(AppError::BadRequest(format!("Failed to read request body: {}", e)));
+            }
+        };
+        
+        match parsed {
+            Ok(record) => batch.push((record.conversation, record.messages)),
+            Err(e) => {
+                warn!("Skipping line {} of streamed import: {}", line_number, e);
+                response.errors += 1;
+                if response.error_messages.len() < MAX_REPORTED_ERRORS {
+                    response.error_messages.push(format!("line {}: {}", line_number, e));
+                }
+            }
+        }
+        
+        if batch.len() >= BATCH_SIZE {
+            store_batch(state, std::mem::take(&mut batch), options, &mut stats).await?;
+        }
+    }
+    
+    if !batch.is_empty() {
+        store_batch(state, batch, options, &mut stats).await?;
+    }
+    
+    response.imported = stats.conversations;
+    response.messages = stats.messages;
+    Ok(response)
+}
+
+/// Store one batch under the write lock, which is held only while storing
+/// so a slow upload doesn't block other writers between batches
+async fn store_batch(
+    state: &AppState,
+    batch: Vec<(Conversation, Vec<Message>)>,
+    options: &ImportOptions,
+    stats: &mut ImportStats,
+) -> AppResult<()> {
+    let _lock = state.write_lock().await?;
+    import::import_batch(&state.pool, batch, options, stats).await?;
+    Ok(())
+}
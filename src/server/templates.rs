@@ -1,5 +1,7 @@
 use askama::Template;
+use crate::export::escape_html;
 use crate::models::{Conversation, Message, SearchResult};
+use crate::search::SnippetMarkers;
 use super::Stats;
 
 /// Index page template
@@ -15,6 +17,14 @@ pub struct IndexTemplate<'a> {
 pub struct SearchTemplate<'a> {
     pub query: &'a str,
     pub results: &'a [SearchResult],
+    pub markers: &'a SnippetMarkers,
+}
+
+impl SearchTemplate<'_> {
+    /// Snippet as HTML with its matched terms in `<mark>`
+    fn snippet_html(&self, snippet: &str) -> String {
+        highlight_snippet(snippet, self.markers)
+    }
 }
 
 /// Conversation view template
@@ -32,11 +42,29 @@ pub fn render_index(stats: &Stats) -> anyhow::Result<String> {
 }
 
 /// Render search results
-pub fn render_search_results(query: &str, results: &[SearchResult]) -> anyhow::Result<String> {
-    let template = SearchTemplate { query, results };
+pub fn render_search_results(
+    query: &str,
+    results: &[SearchResult],
+    markers: &SnippetMarkers,
+) -> anyhow::Result<String> {
+    let template = SearchTemplate { query, results, markers };
     Ok(template.render()?)
 }
 
+/// Escape a snippet for HTML, then turn exactly its `markers` into `<mark>`
+/// tags, so brackets or markup in the message text stay literal
+fn highlight_snippet(snippet: &str, markers: &SnippetMarkers) -> String {
+    let start = escape_html(&markers.start);
+    let end = escape_html(&markers.end);
+    if start.is_empty() || end.is_empty() {
+        return escape_html(snippet);
+    }
+    
+    escape_html(snippet)
+        .replace(&start, "<mark>")
+        .replace(&end, "</mark>")
+}
+
 /// Render conversation view
 pub fn render_conversation(conversation: &Conversation, messages: &[Message]) -> anyhow::Result<String> {
     let template = ConversationTemplate { conversation, messages };